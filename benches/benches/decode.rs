@@ -0,0 +1,36 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! `Contract::decode` benchmarks
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pact::types::Contract;
+use pact_bench::encoded_contract;
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    for clauses in [1, 16, 256, 4096].iter() {
+        let blob = encoded_contract(*clauses);
+        group.throughput(Throughput::Bytes(blob.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(clauses), &blob, |b, blob| {
+            b.iter(|| Contract::decode(black_box(blob)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
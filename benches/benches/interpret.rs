@@ -0,0 +1,119 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! `interpreter::interpret` benchmarks
+//! Each group measures one cost term of the weight model, see `benches/README.md`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pact::interpreter;
+use pact::testing::*;
+use pact::types::{Numeric, PactType, StringLike};
+use pact_bench::{clauses, conjunctions, numerics, string};
+
+const SIZES: [usize; 4] = [1, 64, 1024, 16384];
+
+/// Cost per clause
+fn many_clauses(c: &mut Criterion) {
+    let mut group = c.benchmark_group("interpret/clauses");
+    let data = [PactType::Numeric(Numeric(1))];
+    for n in SIZES.iter() {
+        let code = clauses(*n);
+        group.throughput(Throughput::Elements(*n as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &code, |b, code| {
+            b.iter(|| interpreter::interpret(&data, &data, black_box(code)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Cost per conjunction and comparator pair
+fn many_conjunctions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("interpret/conjunctions");
+    let data = [PactType::Numeric(Numeric(1))];
+    for n in SIZES.iter() {
+        let code = conjunctions(*n);
+        group.throughput(Throughput::Elements(*n as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &code, |b, code| {
+            b.iter(|| interpreter::interpret(&data, &data, black_box(code)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Cost per compared string byte
+fn long_strings(c: &mut Criterion) {
+    let mut group = c.benchmark_group("interpret/string_eq");
+    let code = comp_eq(input(0), user(0));
+    for n in SIZES.iter() {
+        let lhs = string(*n);
+        let rhs = string(*n);
+        let input_data = [PactType::StringLike(StringLike(&lhs))];
+        let user_data = [PactType::StringLike(StringLike(&rhs))];
+        group.throughput(Throughput::Bytes(*n as u64));
+        group.bench_function(BenchmarkId::from_parameter(n), |b| {
+            b.iter(|| interpreter::interpret(&input_data, &user_data, black_box(&code)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Cost per scanned list element, the needle is missing so every element is compared
+fn large_lists(c: &mut Criterion) {
+    let mut group = c.benchmark_group("interpret/list_in");
+    let code = comp_nin(input(0), user(0));
+    for n in SIZES.iter() {
+        let input_data = [PactType::Numeric(Numeric(*n as u64))];
+        let user_data = [numerics(*n)];
+        group.throughput(Throughput::Elements(*n as u64));
+        group.bench_function(BenchmarkId::from_parameter(n), |b| {
+            b.iter(|| interpreter::interpret(&input_data, &user_data, black_box(&code)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Cost per input set of a batch, against interpreting each set separately
+fn batches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("interpret/batch");
+    let data = [PactType::Numeric(Numeric(1))];
+    let code = conjunctions(64);
+    for n in SIZES.iter() {
+        let inputs: Vec<&[PactType]> = vec![&data; *n];
+        group.throughput(Throughput::Elements(*n as u64));
+        group.bench_with_input(BenchmarkId::new("batch", n), &inputs, |b, inputs| {
+            b.iter(|| interpreter::interpret_batch(inputs, &data, black_box(&code)))
+        });
+        group.bench_with_input(BenchmarkId::new("loop", n), &inputs, |b, inputs| {
+            b.iter(|| {
+                inputs
+                    .iter()
+                    .map(|input_data| interpreter::interpret(input_data, &data, black_box(&code)))
+                    .collect::<Vec<_>>()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    many_clauses,
+    many_conjunctions,
+    long_strings,
+    large_lists,
+    batches
+);
+criterion_main!(benches);
@@ -0,0 +1,67 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Contracts and data tables for the pact benchmarks
+//! Each constructor returns a program sized by `n`, so criterion can plot cost against size.
+//!
+use pact::testing::*;
+use pact::types::{Contract, DataTable, Numeric, PactType, StringLike};
+
+/// `n` clauses of `INPUT(0) == USER(0)`, each of which holds
+pub fn clauses(n: usize) -> Vec<u8> {
+    bytecode(&vec![comp_eq(input(0), user(0)); n])
+}
+
+/// One clause of `n` `INPUT(0) == USER(0)` comparators joined by `AND`
+pub fn conjunctions(n: usize) -> Vec<u8> {
+    let mut ops = vec![comp_eq(input(0), user(0))];
+    for _ in 1..n {
+        ops.push(conj_and());
+        ops.push(comp_eq(input(0), user(0)));
+    }
+    bytecode(&ops)
+}
+
+/// A string of `n` bytes
+pub fn string(n: usize) -> Vec<u8> {
+    vec![b'x'; n]
+}
+
+/// A list of the numerics `0..n`
+pub fn numerics(n: usize) -> PactType<'static> {
+    PactType::List(
+        (0..n as u64)
+            .map(|i| PactType::Numeric(Numeric(i)))
+            .collect(),
+    )
+}
+
+/// An encoded contract with `n` clauses, cycling through a data table of 16 strings
+pub fn encoded_contract(n: usize) -> Vec<u8> {
+    let values: Vec<PactType> = (0..16)
+        .map(|_| PactType::StringLike(StringLike(b"benchmark")))
+        .collect();
+    let mut ops = Vec::with_capacity(n);
+    for i in 0..n {
+        ops.push(comp_eq(input(0), user((i % 16) as u8)));
+    }
+    let mut buf = Vec::new();
+    Contract::from_parts_unchecked(DataTable::new(values), bytecode(&ops))
+        .encode(&mut buf)
+        .expect("benchmark contracts are encodable");
+    buf
+}
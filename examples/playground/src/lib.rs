@@ -0,0 +1,216 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! The pact playground, compiled to WASM and driven by `index.html`
+//! Compiles pact source, disassembles it, and runs it against JSON inputs.
+//!
+//! The module exports a minimal string passing ABI rather than depending on a bindings generator:
+//! - `playground_alloc(len) -> ptr` reserves `len` bytes for the page to write UTF-8 into
+//! - `playground_free(ptr, len)` releases them
+//! - `playground_run(source_ptr, source_len, inputs_ptr, inputs_len) -> ptr` runs the playground,
+//!   returning the report as UTF-8 sections separated by NUL bytes, see `Report::to_sections`
+//! - `playground_output_len() -> len` returns the length of the last report
+//!
+// `const` thread local initializers are not available on our minimum supported Rust
+#![allow(clippy::missing_const_for_thread_local)]
+use pact::decompiler::{disassemble, format_op, format_value};
+use pact::interpreter::{self, OpCode, Outcome};
+use pact::types::PactType;
+use pact::{compiler, errors, json, parser};
+use std::cell::RefCell;
+use std::slice;
+
+/// What the playground shows for some source and inputs
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    /// A parse, compile, or inputs error
+    pub error: Option<String>,
+    /// The disassembled bytecode, one line per opcode
+    pub bytecode: Vec<String>,
+    /// The data table, one line per entry
+    pub data_table: Vec<String>,
+    /// Whether the contract accepted the inputs, if it compiled
+    pub verdict: Option<String>,
+}
+
+impl Report {
+    /// Join the error, bytecode, data table, and verdict, separated by NUL bytes
+    pub fn to_sections(&self) -> String {
+        [
+            self.error.clone().unwrap_or_default(),
+            self.bytecode.join("\n"),
+            self.data_table.join("\n"),
+            self.verdict.clone().unwrap_or_default(),
+        ]
+        .join("\0")
+    }
+}
+
+/// Compile pact `source` and run it against `inputs`, a JSON array of input values
+pub fn run(source: &str, inputs: &str) -> Report {
+    let mut report = Report::default();
+    let ast = match parser::parse(source) {
+        Ok(ast) => ast,
+        Err(err) => {
+            report.error = Some(errors::Report::parse(source, &err).to_string());
+            return report;
+        }
+    };
+    let contract = match compiler::compile(&ast) {
+        Ok(contract) => contract,
+        Err(err) => {
+            report.error = Some(errors::Report::compile(source, &err).to_string());
+            return report;
+        }
+    };
+    report.bytecode = disassemble(contract.bytecode());
+    report.data_table = contract
+        .data_table()
+        .as_ref()
+        .iter()
+        .enumerate()
+        .map(|(index, value)| format!("user[{}] = {}", index, format_value(value)))
+        .collect();
+
+    let inputs = match json::parse_inputs(inputs) {
+        Ok(inputs) => inputs,
+        Err(err) => {
+            report.error = Some(format!("inputs: {:?}", err));
+            return report;
+        }
+    };
+    let input_table: Vec<PactType> = inputs.iter().map(json::Value::as_pact).collect();
+    if let Err(err) = contract.check_inputs(&input_table) {
+        report.verdict = Some(format!("ERROR ({:?})", err));
+        return report;
+    }
+    let result = interpreter::interpret_verbose(
+        &input_table,
+        contract.data_table().as_ref(),
+        contract.bytecode(),
+    );
+    report.verdict = Some(match result {
+        Ok(Outcome::Upheld) => "ACCEPTED".into(),
+        Ok(Outcome::Rejected {
+            opcode_offset,
+            comparator,
+            lhs,
+            rhs,
+        }) => format!(
+            "REJECTED at {:04x}  {} ({} vs {})",
+            opcode_offset,
+            format_op(OpCode::COMP(comparator)),
+            format_value(lhs),
+            format_value(rhs)
+        ),
+        Err(err) => format!("ERROR ({:?})", err),
+    });
+    report
+}
+
+thread_local! {
+    static OUTPUT: RefCell<Vec<u8>> = RefCell::new(Vec::new());
+}
+
+/// Reserve `len` bytes for the page to write into
+#[no_mangle]
+pub extern "C" fn playground_alloc(len: usize) -> *mut u8 {
+    let mut buf = Vec::<u8>::with_capacity(len);
+    let ptr = buf.as_mut_ptr();
+    std::mem::forget(buf);
+    ptr
+}
+
+/// Release bytes reserved by `playground_alloc`
+///
+/// # Safety
+/// `ptr` and `len` must be from one `playground_alloc` call, and not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn playground_free(ptr: *mut u8, len: usize) {
+    drop(Vec::from_raw_parts(ptr, 0, len));
+}
+
+/// Run the playground, returning a pointer to the report, see `playground_output_len`
+/// The report is valid until the next call. Invalid UTF-8 is replaced, not rejected.
+///
+/// # Safety
+/// The pointers must be valid for reads of their lengths.
+#[no_mangle]
+pub unsafe extern "C" fn playground_run(
+    source: *const u8,
+    source_len: usize,
+    inputs: *const u8,
+    inputs_len: usize,
+) -> *const u8 {
+    let source = String::from_utf8_lossy(slice::from_raw_parts(source, source_len));
+    let inputs = String::from_utf8_lossy(slice::from_raw_parts(inputs, inputs_len));
+    let report = run(&source, &inputs).to_sections();
+    OUTPUT.with(|output| {
+        let mut output = output.borrow_mut();
+        *output = report.into_bytes();
+        output.as_ptr()
+    })
+}
+
+/// Return the length in bytes of the last report
+#[no_mangle]
+pub extern "C" fn playground_output_len() -> usize {
+    OUTPUT.with(|output| output.borrow().len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_runs_a_contract() {
+        let source = "given parameters $a\n$a must be less than 10";
+        let report = run(source, "[5]");
+        assert_eq!(report.error, None);
+        assert_eq!(report.data_table, vec!["user[0] = 10"]);
+        assert_eq!(report.bytecode.len(), 1);
+        assert_eq!(report.verdict, Some("ACCEPTED".into()));
+        let report = run(source, "[15]");
+        assert!(report.verdict.unwrap().starts_with("REJECTED at 0000"));
+    }
+
+    #[test]
+    fn it_reports_errors() {
+        let report = run("given parameters $a\n$a must be", "[]");
+        assert!(report.error.is_some());
+        assert!(report.bytecode.is_empty());
+        assert_eq!(report.verdict, None);
+        let report = run("given parameters $a\n$a must be equal to 1", "[1");
+        assert!(report.error.unwrap().starts_with("inputs: "));
+        assert_eq!(report.bytecode.len(), 1);
+    }
+
+    #[test]
+    fn it_passes_strings_through_memory() {
+        let source = "given parameters $a\n$a must be equal to \"hi\"";
+        let inputs = "[\"hi\"]";
+        unsafe {
+            let ptr = playground_run(source.as_ptr(), source.len(), inputs.as_ptr(), inputs.len());
+            let output = slice::from_raw_parts(ptr, playground_output_len());
+            let sections: Vec<&str> = std::str::from_utf8(output).unwrap().split('\0').collect();
+            assert_eq!(sections.len(), 4);
+            assert_eq!(sections[3], "ACCEPTED");
+            let buf = playground_alloc(16);
+            playground_free(buf, 16);
+        }
+    }
+}
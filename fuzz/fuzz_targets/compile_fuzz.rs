@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use pact::{compiler, parser};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(source) = std::str::from_utf8(data) {
+        if let Ok(ast) = parser::parse(source) {
+            if let Ok(contract) = compiler::compile(&ast) {
+                let mut buf = Vec::new();
+                // Oversize literals are reported rather than truncated
+                let _ = contract.encode(&mut buf);
+            }
+        }
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use pact::interpreter;
+use pact::types::PactType;
+use pact_fuzz::{FuzzPactType, InterpretInput};
+
+fuzz_target!(|input: InterpretInput| {
+    let input_data: Vec<PactType> = input.input_data.iter().map(FuzzPactType::as_pact).collect();
+    let user_data: Vec<PactType> = input.user_data.iter().map(FuzzPactType::as_pact).collect();
+    let _ = interpreter::interpret(&input_data, &user_data, &input.bytecode);
+});
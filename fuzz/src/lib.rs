@@ -0,0 +1,61 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Structured fuzz inputs shared by the fuzz targets
+//!
+use arbitrary::Arbitrary;
+use pact::types::{Numeric, PactType, StringLike};
+
+/// An owned `PactType` which can be generated by the fuzzer
+/// `PactType` borrows its data so it can't implement `Arbitrary` directly
+#[derive(Arbitrary, Clone, Debug)]
+pub enum FuzzPactType {
+    StringLike(Vec<u8>),
+    Numeric(u64),
+    List(Vec<FuzzPactType>),
+    Bool(bool),
+    SortedList(Vec<FuzzPactType>),
+    FixedPoint(u128, u8),
+}
+
+impl FuzzPactType {
+    /// Borrow this value as a `PactType`
+    pub fn as_pact(&self) -> PactType<'_> {
+        match self {
+            FuzzPactType::StringLike(s) => PactType::StringLike(StringLike(s)),
+            FuzzPactType::Numeric(n) => PactType::Numeric(Numeric(*n)),
+            FuzzPactType::List(l) => PactType::List(l.iter().map(Self::as_pact).collect()),
+            FuzzPactType::Bool(b) => PactType::Bool(*b),
+            // Not necessarily sorted, binary searches must not panic regardless
+            FuzzPactType::SortedList(l) => {
+                PactType::SortedList(l.iter().map(Self::as_pact).collect())
+            }
+            FuzzPactType::FixedPoint(value, decimals) => PactType::FixedPoint {
+                value: *value,
+                decimals: *decimals,
+            },
+        }
+    }
+}
+
+/// Input and user data registers with arbitrary bytecode
+#[derive(Arbitrary, Debug)]
+pub struct InterpretInput {
+    pub input_data: Vec<FuzzPactType>,
+    pub user_data: Vec<FuzzPactType>,
+    pub bytecode: Vec<u8>,
+}
@@ -0,0 +1,61 @@
+/* Copyright 2019 Centrality Investments Limited
+ * This file is part of Pact.
+ *
+ * Licensed under the LGPL, Version 3.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Pact. If not, see:
+ *   <https://centrality.ai/licenses/gplv3.txt>
+ *   <https://centrality.ai/licenses/lgplv3.txt>
+ */
+
+/* Generated with cbindgen from src/ffi/mod.rs, do not edit by hand */
+
+#ifndef PACT_H
+#define PACT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/* The call succeeded */
+#define PACT_OK 0
+
+/* The contract rejected the inputs */
+#define PACT_REJECTED 1
+
+/* The call failed, see `pact_last_error` */
+#define PACT_ERROR -1
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Compile pact `source` into the v0 binary format, written to `out`
+ * On success `*out_len` is set to the encoded length and `PACT_OK` is returned.
+ * If `out_cap` is too small `*out_len` is set to the required length and `PACT_ERROR` is returned.
+ */
+int32_t pact_compile(const char *source, uint8_t *out, size_t out_cap, size_t *out_len);
+
+/* Interpret a binary `contract` against `inputs`, a JSON array of input registers
+ * e.g. `[5, "alice", ["bob"]]`
+ * Returns `PACT_OK` if the contract was upheld, `PACT_REJECTED` if not, or `PACT_ERROR`.
+ * Inputs which don't match the contract's input schema are an error.
+ */
+int32_t pact_interpret(const uint8_t *contract, size_t contract_len, const char *inputs);
+
+/* Return the calling thread's last error message, or null if the last call succeeded
+ * The string is owned by the library and valid until the next `pact_*` call on this thread.
+ */
+const char *pact_last_error(void);
+
+#ifdef __cplusplus
+} /* extern "C" */
+#endif
+
+#endif /* PACT_H */
@@ -0,0 +1,184 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! `#[derive(ToPactInputs)]`, converting fields into input registers in declaration order
+//! Use it through pact's `derive` feature, which re-exports it as
+//! `pact::types::traits::ToPactInputs`.
+//!
+//! Fields are converted with `IntoPact`, borrowing string-likes from the value. On enums, each
+//! variant's fields are converted in order.
+//! Attributes:
+//! - `#[pact(skip)]` on a field leaves it out of the inputs
+//! - `#[pact(schema = "PATH")]` on a struct or enum variant checks at compile time that the
+//!   constant slice or array at `PATH` e.g. a contract's `&[InputType]` has one element per
+//!   converted field
+//!
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, Lit, Meta, NestedMeta, Path,
+};
+
+#[proc_macro_derive(ToPactInputs, attributes(pact))]
+pub fn derive_to_pact_inputs(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// The `#[pact(...)]` attributes of an item
+#[derive(Default)]
+struct Options {
+    skip: bool,
+    schema: Option<Path>,
+}
+
+/// Parse the `#[pact(...)]` attributes in `attrs`
+fn options(attrs: &[Attribute]) -> Result<Options, Error> {
+    let mut options = Options::default();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("pact")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new(meta.span(), "expected `#[pact(...)]`")),
+        };
+        for nested in list.nested.iter() {
+            match nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => options.skip = true,
+                NestedMeta::Meta(Meta::NameValue(pair)) if pair.path.is_ident("schema") => {
+                    match &pair.lit {
+                        Lit::Str(path) => options.schema = Some(path.parse()?),
+                        lit => return Err(Error::new(lit.span(), "expected a string path")),
+                    }
+                }
+                _ => {
+                    return Err(Error::new(
+                        nested.span(),
+                        "expected `skip` or `schema = \"PATH\"`",
+                    ))
+                }
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// Return the bindings of `fields`' converted fields, and a pattern binding them
+fn bind(fields: &Fields) -> Result<(Vec<TokenStream2>, TokenStream2), Error> {
+    let mut converted = Vec::new();
+    let mut patterns = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let binding = format_ident!("field_{}", index);
+        if options(&field.attrs)?.skip {
+            patterns.push(match &field.ident {
+                Some(name) => quote!(#name: _),
+                None => quote!(_),
+            });
+            continue;
+        }
+        patterns.push(match &field.ident {
+            Some(name) => quote!(#name: ref #binding),
+            None => quote!(ref #binding),
+        });
+        // A method call so `&u64` fields auto-deref to `u64: IntoPact`
+        converted.push(quote_spanned! {field.ty.span()=> {
+            use ::pact::types::traits::IntoPact as _;
+            #binding.into_pact()
+        }});
+    }
+    let pattern = match fields {
+        Fields::Named(_) => quote!({ #(#patterns),* }),
+        Fields::Unnamed(_) => quote!(( #(#patterns),* )),
+        Fields::Unit => quote!(),
+    };
+    Ok((converted, pattern))
+}
+
+/// Return a compile time check that `schema` has `arity` elements
+fn check_arity(schema: &Option<Path>, arity: usize) -> TokenStream2 {
+    match schema {
+        Some(schema) => quote_spanned! {schema.span()=>
+            const _: [(); #arity] = [(); #schema.len()];
+        },
+        None => quote!(),
+    }
+}
+
+/// Return the conversion of `fields` bound by `pattern` after `prefix` e.g. `Self::Variant`
+fn arm(
+    prefix: TokenStream2,
+    fields: &Fields,
+    schema: &Option<Path>,
+) -> Result<TokenStream2, Error> {
+    let (converted, pattern) = bind(fields)?;
+    let check = check_arity(schema, converted.len());
+    Ok(quote! {
+        #prefix #pattern => {
+            #check
+            ::pact::__derive::Ok(::pact::__derive::vec![#(#converted?),*])
+        }
+    })
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let options = options(&input.attrs)?;
+    let arms = match &input.data {
+        Data::Struct(data) => vec![arm(quote!(Self), &data.fields, &options.schema)?],
+        Data::Enum(data) => {
+            if options.schema.is_some() {
+                return Err(Error::new(
+                    input.ident.span(),
+                    "put `#[pact(schema = \"PATH\")]` on enum variants",
+                ));
+            }
+            data.variants
+                .iter()
+                .map(|variant| {
+                    let name = &variant.ident;
+                    arm(
+                        quote!(Self::#name),
+                        &variant.fields,
+                        &self::options(&variant.attrs)?.schema,
+                    )
+                })
+                .collect::<Result<_, _>>()?
+        }
+        Data::Union(_) => {
+            return Err(Error::new(
+                Span::call_site(),
+                "ToPactInputs can't be derived for unions",
+            ))
+        }
+    };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::pact::types::traits::ToPactInputs for #name #ty_generics #where_clause {
+            fn to_pact_inputs(
+                &self,
+            ) -> ::pact::__derive::Result<::pact::__derive::Vec<::pact::types::PactType<'_>>, ()> {
+                match *self {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,122 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! `#[derive(ToPactInputs)]` tests
+
+#![cfg(test)]
+use pact::types::traits::ToPactInputs;
+use pact::types::{Address, InputType, Numeric, PactType, StringLike};
+
+const TRANSFER_SCHEMA: &[InputType] = &[InputType::StringLike, InputType::Numeric];
+
+#[derive(ToPactInputs)]
+#[pact(schema = "TRANSFER_SCHEMA")]
+struct Transfer {
+    dest: Vec<u8>,
+    #[pact(skip)]
+    #[allow(dead_code)]
+    memo: String,
+    amount: u128,
+}
+
+#[derive(ToPactInputs)]
+struct Flags(bool, &'static str);
+
+#[derive(ToPactInputs)]
+struct Nothing;
+
+#[derive(ToPactInputs)]
+struct Account<'a> {
+    address: Address<'a>,
+    nonce: u32,
+}
+
+#[derive(ToPactInputs)]
+enum Call {
+    #[pact(schema = "TRANSFER_SCHEMA")]
+    Transfer {
+        dest: String,
+        amount: u64,
+    },
+    Remark([u8; 2]),
+    Kill,
+}
+
+#[test]
+fn it_converts_struct_fields_in_order() {
+    let transfer = Transfer {
+        dest: b"alice".to_vec(),
+        memo: "rent".into(),
+        amount: 100,
+    };
+    assert_eq!(
+        transfer.to_pact_inputs(),
+        Ok(vec![
+            PactType::StringLike(StringLike(b"alice")),
+            PactType::Numeric(Numeric(100)),
+        ])
+    );
+    assert_eq!(
+        Flags(true, "x").to_pact_inputs(),
+        Ok(vec![
+            PactType::Bool(true),
+            PactType::StringLike(StringLike(b"x"))
+        ])
+    );
+    assert_eq!(Nothing.to_pact_inputs(), Ok(vec![]));
+    let bytes = [1_u8; 20];
+    assert_eq!(
+        Account {
+            address: Address::from(&bytes),
+            nonce: 7
+        }
+        .to_pact_inputs(),
+        Ok(vec![
+            PactType::StringLike(StringLike(&bytes)),
+            PactType::Numeric(Numeric(7))
+        ])
+    );
+}
+
+#[test]
+fn it_converts_enum_variant_fields_in_order() {
+    let call = Call::Transfer {
+        dest: "bob".into(),
+        amount: 5,
+    };
+    assert_eq!(
+        call.to_pact_inputs(),
+        Ok(vec![
+            PactType::StringLike(StringLike(b"bob")),
+            PactType::Numeric(Numeric(5)),
+        ])
+    );
+    assert_eq!(
+        Call::Remark([1, 2]).to_pact_inputs(),
+        Ok(vec![PactType::StringLike(StringLike(&[1, 2]))])
+    );
+    assert_eq!(Call::Kill.to_pact_inputs(), Ok(vec![]));
+}
+
+#[test]
+fn it_fails_on_unconvertible_fields() {
+    let transfer = Transfer {
+        dest: vec![],
+        memo: String::new(),
+        amount: u128::MAX,
+    };
+    assert_eq!(transfer.to_pact_inputs(), Err(()));
+}
@@ -0,0 +1,582 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Error tolerant analysis of pact source for editor tooling e.g. a language server
+//! Reports every malformed statement rather than only the first, where each identifier is
+//! declared and used, and completion candidates.
+//!
+use crate::compiler::{self, Warning};
+use crate::errors::Report;
+use crate::parser::{self, ast, ast::Location, PactParser, Rule};
+use pest::iterators::Pair;
+use pest::Parser;
+
+/// The keywords and phrases of the pact language, offered as completions
+pub const KEYWORDS: &[&str] = &[
+    "given parameters",
+    "define",
+    "as",
+    "placeholder",
+    "clause",
+    "include",
+    "meta",
+    "must be",
+    "must not be",
+    "equal to",
+    "less than",
+    "less than or equal to",
+    "greater than",
+    "greater than or equal to",
+    "one of",
+    "a subset of",
+    "intersecting with",
+    "percent of",
+    "and",
+    "or",
+    "true",
+    "false",
+    "the number of parameters",
+    "every parameter",
+    "any parameter",
+    "numeric",
+    "string",
+    "bool",
+    "list",
+    "decimal",
+];
+
+/// A range of the pact source, from `start` up to but excluding `end`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location,
+}
+
+impl Span {
+    /// Return the span of a pest input pair
+    fn of(pair: &Pair<Rule>) -> Self {
+        let span = pair.as_span();
+        let (line, column) = span.start_pos().line_col();
+        let (end_line, end_column) = span.end_pos().line_col();
+        Span {
+            start: Location { line, column },
+            end: Location {
+                line: end_line,
+                column: end_column,
+            },
+        }
+    }
+
+    /// Whether `location` is within the span
+    pub fn contains(&self, location: Location) -> bool {
+        let at = (location.line, location.column);
+        (self.start.line, self.start.column) <= at && at < (self.end.line, self.end.column)
+    }
+}
+
+/// How serious a diagnostic is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The source does not compile
+    Error,
+    /// The source compiles but is likely a mistake
+    Warning,
+}
+
+/// A parse or compile error, or a compiler warning
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Where in the source, if known
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    fn from_report(severity: Severity, report: &Report) -> Self {
+        let span = report.location().map(|start| Span {
+            start,
+            end: Location {
+                line: start.line,
+                column: start.column + report.width(),
+            },
+        });
+        Diagnostic {
+            severity,
+            message: report.message().into(),
+            span,
+        }
+    }
+}
+
+/// What a symbol names
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// An input parameter e.g. `given parameters $a`
+    Parameter,
+    /// A definition of a value e.g. `define $limit as 100`
+    Definition,
+    /// A definition bound after compilation e.g. `define $limit as placeholder`
+    Placeholder,
+    /// A definition of another identifier e.g. `define $max as $limit`
+    Alias,
+    /// A named clause e.g. `clause limit: ...`
+    Clause,
+    /// An identifier which is used but never declared
+    Undeclared,
+}
+
+/// A named parameter, definition, or clause, and where it is declared and used
+#[derive(Clone, Debug, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Where the symbol is declared, `None` if it is undeclared
+    pub definition: Option<Span>,
+    /// Where the symbol is used, or declared again, in source order
+    pub references: Vec<Span>,
+}
+
+/// What a completion candidate is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompletionKind {
+    Keyword,
+    Symbol(SymbolKind),
+}
+
+/// A completion candidate
+#[derive(Clone, Debug, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+}
+
+/// The diagnostics and symbols of some pact source, see `analyze`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Analysis {
+    /// Errors and warnings in source order of discovery
+    pub diagnostics: Vec<Diagnostic>,
+    /// Symbols in order of first appearance
+    pub symbols: Vec<Symbol>,
+}
+
+impl Analysis {
+    /// Whether any diagnostic is an error
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// Return the symbol declared or used at `location`, if any
+    pub fn symbol_at(&self, location: Location) -> Option<&Symbol> {
+        self.symbols.iter().find(|symbol| {
+            symbol
+                .definition
+                .iter()
+                .chain(&symbol.references)
+                .any(|span| span.contains(location))
+        })
+    }
+
+    /// Return the declared identifiers, then the keywords, which start with `prefix`
+    pub fn completions(&self, prefix: &str) -> Vec<Completion> {
+        let symbols = self
+            .symbols
+            .iter()
+            .filter(|symbol| match symbol.kind {
+                SymbolKind::Clause | SymbolKind::Undeclared => false,
+                _ => symbol.name.starts_with(prefix),
+            })
+            .map(|symbol| Completion {
+                label: symbol.name.clone(),
+                kind: CompletionKind::Symbol(symbol.kind),
+            });
+        let keywords = KEYWORDS
+            .iter()
+            .filter(|keyword| keyword.starts_with(prefix))
+            .map(|keyword| Completion {
+                label: (*keyword).into(),
+                kind: CompletionKind::Keyword,
+            });
+        symbols.chain(keywords).collect()
+    }
+
+    fn push_parse_error(&mut self, source: &str, err: &pest::error::Error<Rule>) {
+        self.diagnostics.push(Diagnostic::from_report(
+            Severity::Error,
+            &Report::parse(source, err),
+        ));
+    }
+}
+
+/// Analyze pact `source`, tolerating errors
+///
+/// When the source does not parse, each statement after the input declaration is parsed on its
+/// own so that every malformed statement is reported, and symbols are collected from the rest.
+/// Statements are told apart by line, see `statements`. Compile errors and warnings are only
+/// reported for source which parses, as the malformed statements may declare or use identifiers.
+pub fn analyze(source: &str) -> Analysis {
+    let mut analysis = Analysis::default();
+    let err = match parser::parse(source) {
+        Ok(ast) => {
+            collect_symbols(source, &mut analysis.symbols);
+            check(source, &ast, &mut analysis);
+            return analysis;
+        }
+        Err(err) => err,
+    };
+
+    let lines: Vec<&str> = source.split('\n').collect();
+    let statements = statements(&lines);
+    let declaration = statements
+        .iter()
+        .position(|(start, _)| lines[*start].trim_start().starts_with("given"));
+    let declaration = match declaration {
+        Some(declaration) => declaration,
+        // Nothing can be parsed without the input declaration
+        None => {
+            analysis.push_parse_error(source, &err);
+            return analysis;
+        }
+    };
+    let header = mask(&lines, &statements, |i| i <= declaration);
+    if let Err(err) = parser::parse(&header) {
+        analysis.push_parse_error(&header, &err);
+        return analysis;
+    }
+
+    let mut parsed: Vec<bool> = (0..statements.len()).map(|i| i <= declaration).collect();
+    for (i, (_, end)) in statements.iter().enumerate().skip(declaration + 1) {
+        // Truncate the source after the statement, so an unfinished one fails where it ends
+        let text = mask(&lines[..=*end], &statements, |j| j <= declaration || j == i);
+        match parser::parse(&text) {
+            Ok(_) => parsed[i] = true,
+            Err(err) => analysis.push_parse_error(&text, &err),
+        }
+    }
+    if !analysis.has_errors() {
+        // Every statement parses on its own, so they were told apart wrongly
+        analysis.push_parse_error(source, &err);
+    }
+    let text = mask(&lines, &statements, |i| parsed[i]);
+    collect_symbols(&text, &mut analysis.symbols);
+    analysis
+}
+
+/// Return the first and last line index of each statement in `lines`
+///
+/// A statement starts on a line which is not indented or starts with a statement keyword,
+/// unless it continues an assertion i.e. it starts with a conjunction or imperative, or the
+/// line before ends with a conjunction. Blank and comment lines belong to the statement before.
+fn statements(lines: &[&str]) -> Vec<(usize, usize)> {
+    let mut statements: Vec<(usize, usize)> = Vec::new();
+    let mut continued = false;
+    for (i, line) in lines.iter().enumerate() {
+        let code = line.split("//").next().unwrap_or("").trim_end();
+        let trimmed = code.trim_start();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let first = trimmed.split_whitespace().next().unwrap_or("");
+        let last = trimmed.split_whitespace().last().unwrap_or("");
+        let starts = code.len() == trimmed.len()
+            || ["given", "define", "clause", "include", "meta"].contains(&first);
+        if starts && !continued && !["and", "or", "must"].contains(&first) {
+            statements.push((i, i));
+        }
+        if let Some((_, end)) = statements.last_mut() {
+            *end = i;
+        }
+        continued = last == "and" || last == "or";
+    }
+    // Trailing blank and comment lines belong to the last statement
+    if let Some((_, end)) = statements.last_mut() {
+        *end = lines.len() - 1;
+    }
+    // And those between statements to the statement before
+    for i in 1..statements.len() {
+        statements[i - 1].1 = statements[i].0 - 1;
+    }
+    statements
+}
+
+/// Join `lines`, blanking those of the statements which aren't kept
+/// Lines keep their length so locations in the result match those in the source.
+fn mask(lines: &[&str], statements: &[(usize, usize)], keep: impl Fn(usize) -> bool) -> String {
+    let mut masked: Vec<String> = lines.iter().map(|line| (*line).into()).collect();
+    for (i, (start, end)) in statements.iter().enumerate() {
+        if keep(i) {
+            continue;
+        }
+        for line in masked.iter_mut().take(*end + 1).skip(*start) {
+            *line = line
+                .chars()
+                .map(|c| if c == '\r' { c } else { ' ' })
+                .collect();
+        }
+    }
+    masked.join("\n")
+}
+
+/// Collect the symbols of `source`, which must parse
+fn collect_symbols(source: &str, symbols: &mut Vec<Symbol>) {
+    if let Ok(pairs) = PactParser::parse(Rule::contract, source) {
+        for pair in pairs {
+            collect(pair, symbols);
+        }
+    }
+}
+
+/// Collect the symbols declared and used within a pest input pair
+fn collect(pair: Pair<Rule>, symbols: &mut Vec<Symbol>) {
+    match pair.as_rule() {
+        Rule::parameter => {
+            let identifier = pair.into_inner().next().unwrap();
+            declare(symbols, &identifier, SymbolKind::Parameter);
+        }
+        Rule::definition => {
+            let mut inner = pair.into_inner();
+            let identifier = inner.next().unwrap();
+            let value = inner.next().unwrap();
+            let kind = match value.as_rule() {
+                Rule::placeholder => SymbolKind::Placeholder,
+                Rule::identifier => SymbolKind::Alias,
+                _ => SymbolKind::Definition,
+            };
+            declare(symbols, &identifier, kind);
+            collect(value, symbols);
+        }
+        Rule::clause_name => declare(symbols, &pair, SymbolKind::Clause),
+        Rule::identifier => {
+            let span = Span::of(&pair);
+            match symbols
+                .iter_mut()
+                .find(|symbol| symbol.name == pair.as_str())
+            {
+                Some(symbol) => symbol.references.push(span),
+                None => symbols.push(Symbol {
+                    name: pair.as_str().into(),
+                    kind: SymbolKind::Undeclared,
+                    definition: None,
+                    references: vec![span],
+                }),
+            }
+        }
+        _ => {
+            for inner in pair.into_inner() {
+                collect(inner, symbols);
+            }
+        }
+    }
+}
+
+/// Record the declaration of a symbol at a pest input pair
+fn declare(symbols: &mut Vec<Symbol>, pair: &Pair<Rule>, kind: SymbolKind) {
+    let span = Span::of(pair);
+    match symbols
+        .iter_mut()
+        .find(|symbol| symbol.name == pair.as_str())
+    {
+        // Used before it was declared
+        Some(symbol) if symbol.kind == SymbolKind::Undeclared => {
+            symbol.kind = kind;
+            symbol.definition = Some(span);
+        }
+        // Declared again, which the compiler rejects
+        Some(symbol) => symbol.references.push(span),
+        None => symbols.push(Symbol {
+            name: pair.as_str().into(),
+            kind,
+            definition: Some(span),
+            references: Vec::new(),
+        }),
+    }
+}
+
+/// Report every use of an undeclared identifier, then the compile error or warnings of `ast`
+fn check(source: &str, ast: &[ast::Node], analysis: &mut Analysis) {
+    for symbol in &analysis.symbols {
+        if symbol.kind != SymbolKind::Undeclared {
+            continue;
+        }
+        for span in &symbol.references {
+            analysis.diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                message: format!("undeclared variable `{}`", symbol.name),
+                span: Some(*span),
+            });
+        }
+    }
+    match compiler::compile_with_warnings(ast) {
+        Ok((_, warnings)) => {
+            for warning in warnings {
+                let mut diagnostic =
+                    Diagnostic::from_report(Severity::Warning, &Report::warning(source, &warning));
+                if let Warning::UnusedParameter(identifier) = &warning {
+                    diagnostic.span = analysis
+                        .symbols
+                        .iter()
+                        .find(|symbol| &symbol.name == identifier)
+                        .and_then(|symbol| symbol.definition);
+                }
+                analysis.diagnostics.push(diagnostic);
+            }
+        }
+        Err(err) => {
+            let diagnostic =
+                Diagnostic::from_report(Severity::Error, &Report::compile(source, &err));
+            // An undeclared identifier is already reported
+            if !analysis.diagnostics.contains(&diagnostic) {
+                analysis.diagnostics.push(diagnostic);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(line: usize, column: usize) -> Location {
+        Location { line, column }
+    }
+
+    fn span(line: usize, column: usize, end_column: usize) -> Option<Span> {
+        Some(Span {
+            start: at(line, column),
+            end: at(line, end_column),
+        })
+    }
+
+    #[test]
+    fn it_reports_every_malformed_statement() {
+        let source = "given parameters $a, $b
+define $limit as 100
+$a must be less than
+$b must be equal to 5 and
+    $b must be one of
+$a must be greater than $limit";
+        let analysis = analyze(source);
+        assert_eq!(
+            analysis
+                .diagnostics
+                .iter()
+                .map(|diagnostic| (diagnostic.severity, diagnostic.span.unwrap().start))
+                .collect::<Vec<_>>(),
+            vec![(Severity::Error, at(3, 21)), (Severity::Error, at(5, 22))]
+        );
+        // Symbols are collected from the statements which parse
+        let limit = analysis.symbol_at(at(6, 26)).unwrap();
+        assert_eq!(limit.name, "$limit");
+        assert_eq!(limit.kind, SymbolKind::Definition);
+        assert_eq!(limit.definition, span(2, 8, 14));
+        assert_eq!(limit.references, vec![span(6, 25, 31).unwrap()]);
+    }
+
+    #[test]
+    fn it_stops_at_a_malformed_input_declaration() {
+        let analysis = analyze("given parameters a\n$a must be equal to 1");
+        assert_eq!(analysis.diagnostics.len(), 1);
+        assert_eq!(analysis.diagnostics[0].span.unwrap().start, at(1, 18));
+        assert!(analysis.symbols.is_empty());
+    }
+
+    #[test]
+    fn it_collects_symbols() {
+        let source = "given parameters $a: numeric, $b
+define $limit as placeholder numeric
+define $max as $limit
+clause cap: $a must be less than $max and $b must be equal to $c";
+        let analysis = analyze(source);
+        let symbols: Vec<(&str, SymbolKind, usize)> = analysis
+            .symbols
+            .iter()
+            .map(|symbol| (symbol.name.as_str(), symbol.kind, symbol.references.len()))
+            .collect();
+        assert_eq!(
+            symbols,
+            vec![
+                ("$a", SymbolKind::Parameter, 1),
+                ("$b", SymbolKind::Parameter, 1),
+                ("$limit", SymbolKind::Placeholder, 1),
+                ("$max", SymbolKind::Alias, 1),
+                ("cap", SymbolKind::Clause, 0),
+                ("$c", SymbolKind::Undeclared, 1),
+            ]
+        );
+        assert_eq!(
+            analysis.diagnostics,
+            vec![Diagnostic {
+                severity: Severity::Error,
+                message: "undeclared variable `$c`".into(),
+                span: span(4, 63, 65),
+            }]
+        );
+    }
+
+    #[test]
+    fn it_reports_compile_errors_and_warnings() {
+        let analysis = analyze("given parameters $a, $b\n$a must be equal to 1");
+        assert_eq!(
+            analysis.diagnostics,
+            vec![Diagnostic {
+                severity: Severity::Warning,
+                message: "unused parameter `$b`".into(),
+                span: span(1, 22, 24),
+            }]
+        );
+        assert!(!analysis.has_errors());
+        let analysis = analyze("given parameters $a, $a\n$a must be equal to 1");
+        assert!(analysis.has_errors());
+    }
+
+    #[test]
+    fn it_completes_identifiers_and_keywords() {
+        let analysis = analyze(
+            "given parameters $amount\ndefine $limit as 5\n$amount must be less than $limit",
+        );
+        assert_eq!(
+            analysis.completions("$l"),
+            vec![Completion {
+                label: "$limit".into(),
+                kind: CompletionKind::Symbol(SymbolKind::Definition),
+            }]
+        );
+        let labels: Vec<String> = analysis
+            .completions("less")
+            .into_iter()
+            .map(|completion| completion.label)
+            .collect();
+        assert_eq!(labels, vec!["less than", "less than or equal to"]);
+        assert_eq!(
+            analysis.completions("perc"),
+            vec![Completion {
+                label: "percent of".into(),
+                kind: CompletionKind::Keyword,
+            }]
+        );
+        assert_eq!(
+            analysis.completions("dec"),
+            vec![Completion {
+                label: "decimal".into(),
+                kind: CompletionKind::Keyword,
+            }]
+        );
+        assert_eq!(analysis.completions("").len(), 2 + KEYWORDS.len());
+    }
+}
@@ -0,0 +1,387 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! The pact command line toolchain
+//!
+//! pactc compile <contract.pact> -o <contract.bin>
+//! pactc simulate <contract.bin> --inputs <inputs.json>
+//! pactc fixture <contract.bin> --inputs <inputs.json>
+//! pactc grammar [-o <grammar.pest>]
+//! pactc vectors [-o <vectors.json>]
+//! pactc repl
+//!
+use pact::decompiler::{disassemble, format_op, format_value};
+use pact::errors::Report;
+use pact::interpreter::{self, OpCode, Outcome};
+use pact::json::Fixture;
+use pact::types::{Contract, PactType};
+use pact::{compiler, json, parser};
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process;
+
+const USAGE: &str = "usage:
+  pactc compile <contract.pact> -o <contract.bin>
+  pactc simulate <contract.bin> --inputs <inputs.json>
+  pactc fixture <contract.bin> --inputs <inputs.json>
+  pactc grammar [-o <grammar.pest>]
+  pactc vectors [-o <vectors.json>]
+  pactc repl";
+
+/// Exit code when the contract rejected the inputs
+const EXIT_REJECTED: i32 = 1;
+/// Exit code for usage, I/O or contract errors
+const EXIT_ERROR: i32 = 2;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("compile") => compile(&args[1..]),
+        Some("simulate") => simulate(&args[1..]),
+        Some("fixture") => fixture(&args[1..]),
+        Some("grammar") => grammar(&args[1..]),
+        Some("vectors") => vectors(&args[1..]),
+        Some("repl") => repl(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(code) => process::exit(code),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(EXIT_ERROR);
+        }
+    }
+}
+
+/// Split `args` into a positional path and the value of `flag`
+fn path_and_flag<'a>(args: &'a [String], flag: &str) -> Result<(&'a str, &'a str), String> {
+    match args {
+        [path, f, value] if f == flag => Ok((path, value)),
+        [f, value, path] if f == flag => Ok((path, value)),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn read(path: &str) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|err| format!("{}: {}", path, err))
+}
+
+/// Compile pact source into a binary contract file
+fn compile(args: &[String]) -> Result<i32, String> {
+    let (source_path, out_path) = path_and_flag(args, "-o")?;
+    let source = String::from_utf8(read(source_path)?)
+        .map_err(|_| format!("{}: source is not valid UTF-8", source_path))?;
+    let ast = parser::parse(&source).map_err(|err| {
+        Report::parse(&source, &err)
+            .with_path(source_path)
+            .to_string()
+    })?;
+    let (contract, warnings) = compiler::compile_with_warnings(&ast).map_err(|err| {
+        Report::compile(&source, &err)
+            .with_path(source_path)
+            .to_string()
+    })?;
+    for warning in &warnings {
+        eprintln!(
+            "warning: {}",
+            Report::warning(&source, warning).with_path(source_path)
+        );
+    }
+
+    let mut buf = Vec::<u8>::default();
+    contract
+        .encode(&mut buf)
+        .map_err(|err| format!("{}: {:?}", source_path, err))?;
+    fs::write(out_path, &buf).map_err(|err| format!("{}: {}", out_path, err))?;
+    println!("wrote {} bytes to {}", buf.len(), out_path);
+    Ok(0)
+}
+
+/// Export the pact grammar for tooling in other languages, to stdout or a file
+fn grammar(args: &[String]) -> Result<i32, String> {
+    match args {
+        [] => print!("{}", parser::grammar()),
+        [flag, out_path] if flag == "-o" => fs::write(out_path, parser::grammar())
+            .map_err(|err| format!("{}: {}", out_path, err))?,
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(0)
+}
+
+/// Export the interpreter conformance vectors, see `json::vectors`, to stdout or a file
+fn vectors(args: &[String]) -> Result<i32, String> {
+    let json = json::fixtures_to_json(&json::vectors::conformance())
+        .map_err(|err| format!("vectors: {:?}", err))?;
+    match args {
+        [] => print!("{}", json),
+        [flag, out_path] if flag == "-o" => {
+            fs::write(out_path, json).map_err(|err| format!("{}: {}", out_path, err))?
+        }
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(0)
+}
+
+/// Run a binary contract against input registers and report the verdict
+fn simulate(args: &[String]) -> Result<i32, String> {
+    let (contract_path, inputs_path) = path_and_flag(args, "--inputs")?;
+    let blob = read(contract_path)?;
+    let contract =
+        Contract::decode(&blob).map_err(|err| format!("{}: {:?}", contract_path, err))?;
+    let inputs_source = String::from_utf8(read(inputs_path)?)
+        .map_err(|_| format!("{}: inputs are not valid UTF-8", inputs_path))?;
+    let inputs =
+        json::parse_inputs(&inputs_source).map_err(|err| format!("{}: {:?}", inputs_path, err))?;
+    let input_table: Vec<PactType> = inputs.iter().map(json::Value::as_pact).collect();
+
+    println!("contract: {} ({} bytes)", contract_path, blob.len());
+    if let Some(metadata) = contract.metadata() {
+        println!("metadata:");
+        for (key, value) in metadata.iter() {
+            println!("  {}: {}", key, value);
+        }
+    }
+    println!("data table:");
+    for (index, value) in contract.data_table().as_ref().iter().enumerate() {
+        println!("  user[{}] = {}", index, format_value(value));
+    }
+    println!("inputs:");
+    for (index, value) in input_table.iter().enumerate() {
+        println!("  input[{}] = {}", index, format_value(value));
+    }
+    println!("bytecode:");
+    for line in disassemble(contract.bytecode()) {
+        println!("  {}", line);
+    }
+
+    if let Err(err) = contract.check_inputs(&input_table) {
+        println!("verdict: ERROR ({:?})", err);
+        return Ok(EXIT_ERROR);
+    }
+    // Named clauses are also evaluated alone, as hosts may only check one of them
+    if contract.clause_names().is_some() {
+        if let Ok(clauses) = interpreter::interpret_clauses(&contract, &input_table) {
+            println!("clauses:");
+            for (index, (name, holds)) in clauses.iter().enumerate() {
+                let verdict = if *holds { "holds" } else { "fails" };
+                match name {
+                    Some(name) => println!("  {}: {}", name, verdict),
+                    None => println!("  #{}: {}", index, verdict),
+                }
+            }
+        }
+    }
+    let result = interpreter::interpret_verbose(
+        &input_table,
+        contract.data_table().as_ref(),
+        contract.bytecode(),
+    );
+    match result {
+        Ok(Outcome::Upheld) => {
+            println!("verdict: ACCEPTED");
+            Ok(0)
+        }
+        Ok(Outcome::Rejected {
+            opcode_offset,
+            comparator,
+            lhs,
+            rhs,
+        }) => {
+            println!("verdict: REJECTED");
+            println!(
+                "  at {:04x}  {} ({} vs {})",
+                opcode_offset,
+                format_op(OpCode::COMP(comparator)),
+                format_value(lhs),
+                format_value(rhs)
+            );
+            Ok(EXIT_REJECTED)
+        }
+        Err(err) => {
+            println!("verdict: ERROR ({:?})", err);
+            Ok(EXIT_ERROR)
+        }
+    }
+}
+
+/// Print a verdict fixture file for a binary contract and input registers
+/// Other pact implementations replay it to check they agree with this one
+fn fixture(args: &[String]) -> Result<i32, String> {
+    let (contract_path, inputs_path) = path_and_flag(args, "--inputs")?;
+    let blob = read(contract_path)?;
+    let inputs_source = String::from_utf8(read(inputs_path)?)
+        .map_err(|_| format!("{}: inputs are not valid UTF-8", inputs_path))?;
+    let inputs =
+        json::parse_inputs(&inputs_source).map_err(|err| format!("{}: {:?}", inputs_path, err))?;
+
+    // Undecodable contracts are exported too, implementations must agree on rejecting them
+    let fixture = Fixture::evaluate(blob, inputs);
+    let json = fixture
+        .to_json()
+        .map_err(|err| format!("{}: {:?}", inputs_path, err))?;
+    println!("[{}]", json);
+    Ok(0)
+}
+
+const REPL_HELP: &str =
+    "Enter pact statements one line at a time, starting with `given parameters`.
+Each statement is compiled with those before it, and kept if the contract still compiles.
+commands:
+  :data              print the data table
+  :bytecode          print the disassembled bytecode
+  :source            print the statements entered so far
+  :inputs <json>     set the sample inputs e.g. :inputs [100, \"alice\"]
+  :run               run the contract against the sample inputs
+  :try <assertion>   run an assertion against the sample inputs, without keeping it
+  :reset             forget every statement and the sample inputs
+  :help              print this message
+  :quit              exit";
+
+/// Develop a contract interactively, one statement at a time
+fn repl(args: &[String]) -> Result<i32, String> {
+    if !args.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    println!("pact repl, enter :help for commands");
+    let mut session = Session::default();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("pact> ");
+        io::stdout().flush().map_err(|err| err.to_string())?;
+        let line = match lines.next() {
+            Some(line) => line.map_err(|err| err.to_string())?,
+            None => break,
+        };
+        match session.eval(line.trim()) {
+            Some(output) => print!("{}", output),
+            None => break,
+        }
+    }
+    println!();
+    Ok(0)
+}
+
+/// The statements and sample inputs of a `repl`
+#[derive(Default)]
+struct Session {
+    /// The statements entered so far, one per line
+    source: String,
+    inputs: Vec<json::Value>,
+}
+
+impl Session {
+    /// Evaluate a line of input, returning the output to print or `None` to exit
+    fn eval(&mut self, line: &str) -> Option<String> {
+        let (command, arg) = match line.find(' ') {
+            Some(space) => (&line[..space], line[space..].trim()),
+            None => (line, ""),
+        };
+        let output = match command {
+            "" => Ok(String::new()),
+            ":quit" | ":q" => return None,
+            ":help" => Ok(format!("{}\n", REPL_HELP)),
+            ":source" => Ok(self.source.clone()),
+            ":reset" => {
+                *self = Session::default();
+                Ok(String::new())
+            }
+            ":inputs" => json::parse_inputs(arg)
+                .map(|inputs| {
+                    self.inputs = inputs;
+                    String::new()
+                })
+                .map_err(|err| format!("invalid inputs: {:?}", err)),
+            ":data" => self.with_contract(&self.source, |contract| {
+                Ok(contract
+                    .data_table()
+                    .as_ref()
+                    .iter()
+                    .enumerate()
+                    .map(|(index, value)| format!("user[{}] = {}\n", index, format_value(value)))
+                    .collect())
+            }),
+            ":bytecode" => self.with_contract(&self.source, |contract| {
+                Ok(disassemble(contract.bytecode())
+                    .iter()
+                    .map(|line| format!("{}\n", line))
+                    .collect())
+            }),
+            ":run" => self.with_contract(&self.source, |contract| {
+                let input_table: Vec<PactType> =
+                    self.inputs.iter().map(json::Value::as_pact).collect();
+                match interpreter::interpret(
+                    &input_table,
+                    contract.data_table().as_ref(),
+                    contract.bytecode(),
+                ) {
+                    Ok(true) => Ok("ACCEPTED\n".to_string()),
+                    Ok(false) => Ok("REJECTED\n".to_string()),
+                    Err(err) => Err(format!("{:?}", err)),
+                }
+            }),
+            ":try" => {
+                let source = format!("{}{}\n", self.source, arg);
+                self.with_contract(&source, |contract| {
+                    let input_table: Vec<PactType> =
+                        self.inputs.iter().map(json::Value::as_pact).collect();
+                    // The tried assertion is the last clause
+                    match interpreter::interpret_clauses(contract, &input_table) {
+                        Ok(clauses) => match clauses.last() {
+                            Some((_, true)) => Ok("holds\n".to_string()),
+                            Some((_, false)) => Ok("fails\n".to_string()),
+                            None => Err("expected an assertion".to_string()),
+                        },
+                        Err(err) => Err(format!("{:?}", err)),
+                    }
+                })
+            }
+            _ if command.starts_with(':') => Err(format!(
+                "unknown command `{}`, enter :help for commands",
+                command
+            )),
+            _ => {
+                let source = format!("{}{}\n", self.source, line);
+                let output = self.with_contract(&source, |contract| {
+                    Ok(format!(
+                        "ok, {} data table entries and {} bytes of bytecode\n",
+                        contract.data_table().as_ref().len(),
+                        contract.bytecode().len()
+                    ))
+                });
+                if output.is_ok() {
+                    self.source = source;
+                }
+                output
+            }
+        };
+        Some(output.unwrap_or_else(|err| format!("error: {}\n", err)))
+    }
+
+    /// Compile `source` and call `f` with the contract
+    fn with_contract(
+        &self,
+        source: &str,
+        f: impl FnOnce(&Contract) -> Result<String, String>,
+    ) -> Result<String, String> {
+        let ast = parser::parse(source).map_err(|err| Report::parse(source, &err).to_string())?;
+        let contract =
+            compiler::compile(&ast).map_err(|err| Report::compile(source, &err).to_string())?;
+        f(&contract)
+    }
+}
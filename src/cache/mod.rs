@@ -0,0 +1,394 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! A least recently used cache of decoded contracts, for runtimes which repeatedly decode the
+//! same contract blobs from storage
+//! Entries are keyed by the BLAKE2b-256 hash of the blob. `Contract` borrows its blob, so the
+//! cache stores an owned form decoded by the caller e.g. an `interpreter::Program`.
+//! `CachedInterpreter` caches verdicts instead, for validators which interpret the same
+//! contract and inputs repeatedly e.g. during gossip and again at block import.
+//!
+use crate::types::blake2b::blake2b_256;
+use crate::types::{LengthWidth, PactType};
+use crate::verifier::{Decision, PactErr, Verifier};
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+
+/// Callbacks for cache metrics e.g. hit rate gauges, which do nothing by default
+pub trait CacheMetrics {
+    /// A blob was found in the cache
+    fn hit(&mut self) {}
+    /// A blob was not found in the cache
+    fn miss(&mut self) {}
+    /// The least recently used entry was evicted to make room for another
+    fn eviction(&mut self) {}
+}
+
+/// No metrics
+impl CacheMetrics for () {}
+
+/// Counts of cache lookups and evictions since the cache was created
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CacheStats {
+    /// Lookups which found the blob cached
+    pub hits: u64,
+    /// Lookups which decoded the blob
+    pub misses: u64,
+    /// Entries evicted to make room for others
+    pub evictions: u64,
+}
+
+/// A cache entry and when it was last used
+struct Entry<T> {
+    value: T,
+    last_used: u64,
+}
+
+/// A least recently used cache of values decoded from contract blobs, holding at most
+/// `capacity` entries
+/// Eviction scans every entry, so this is meant for the small capacities of a runtime's
+/// working set of contracts.
+pub struct ContractCache<T, M: CacheMetrics = ()> {
+    capacity: NonZeroUsize,
+    entries: HashMap<[u8; 32], Entry<T>>,
+    /// Incremented on every use, orders entries by recency
+    clock: u64,
+    stats: CacheStats,
+    metrics: M,
+}
+
+impl<T> ContractCache<T> {
+    /// Return an empty cache holding at most `capacity` entries
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        ContractCache {
+            capacity,
+            entries: HashMap::with_capacity(capacity.get()),
+            clock: 0,
+            stats: CacheStats::default(),
+            metrics: (),
+        }
+    }
+}
+
+impl<T, M: CacheMetrics> ContractCache<T, M> {
+    /// Report hits, misses, and evictions to `metrics`
+    pub fn with_metrics<N: CacheMetrics>(self, metrics: N) -> ContractCache<T, N> {
+        ContractCache {
+            capacity: self.capacity,
+            entries: self.entries,
+            clock: self.clock,
+            stats: self.stats,
+            metrics,
+        }
+    }
+
+    /// Return the value cached for `blob`, or decode it with `decode` and cache it
+    /// Decoding errors are returned and nothing is cached, so a malformed blob is decoded again
+    /// on its next lookup.
+    pub fn get_or_decode<E>(
+        &mut self,
+        blob: &[u8],
+        decode: impl FnOnce(&[u8]) -> Result<T, E>,
+    ) -> Result<&T, E> {
+        let key = blake2b_256(blob);
+        self.clock += 1;
+        if self.entries.contains_key(&key) {
+            self.stats.hits += 1;
+            self.metrics.hit();
+        } else {
+            self.stats.misses += 1;
+            self.metrics.miss();
+            let value = decode(blob)?;
+            if self.entries.len() >= self.capacity.get() {
+                self.evict();
+            }
+            self.entries.insert(
+                key,
+                Entry {
+                    value,
+                    last_used: 0,
+                },
+            );
+        }
+        let entry = self
+            .entries
+            .get_mut(&key)
+            .expect("it was found or inserted");
+        entry.last_used = self.clock;
+        Ok(&entry.value)
+    }
+
+    /// Return the value cached for `blob`, if any, without counting a hit or miss
+    pub fn peek(&self, blob: &[u8]) -> Option<&T> {
+        self.entries
+            .get(&blake2b_256(blob))
+            .map(|entry| &entry.value)
+    }
+
+    /// Remove and return the value cached for `blob`, if any
+    pub fn remove(&mut self, blob: &[u8]) -> Option<T> {
+        self.entries
+            .remove(&blake2b_256(blob))
+            .map(|entry| entry.value)
+    }
+
+    /// Remove every entry, keeping the stats
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Return the number of cached entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Return the most entries the cache holds
+    pub fn capacity(&self) -> NonZeroUsize {
+        self.capacity
+    }
+
+    /// Return the hit, miss, and eviction counts
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Evict the least recently used entry
+    fn evict(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| *key);
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+            self.stats.evictions += 1;
+            self.metrics.eviction();
+        }
+    }
+}
+
+/// A least recently used cache of contract verdicts, holding at most `capacity` verdicts
+/// Verdicts are keyed by the BLAKE2b-256 hash of the contract blob's hash followed by the inputs
+/// encoded with 2 byte lengths. Errors are not cached, nor are verdicts for inputs too long to
+/// encode.
+pub struct CachedInterpreter<M: CacheMetrics = ()> {
+    verdicts: ContractCache<bool, M>,
+    verifier: Verifier,
+}
+
+impl CachedInterpreter {
+    /// Return an empty cache holding at most `capacity` verdicts, verifying with the default
+    /// `Verifier`
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        CachedInterpreter {
+            verdicts: ContractCache::new(capacity),
+            verifier: Verifier::default(),
+        }
+    }
+}
+
+impl<M: CacheMetrics> CachedInterpreter<M> {
+    /// Verify contracts with `verifier` e.g. to limit capabilities or contract length
+    /// Cached verdicts are cleared as they may differ under `verifier`.
+    pub fn with_verifier(mut self, verifier: Verifier) -> Self {
+        self.verifier = verifier;
+        self.verdicts.clear();
+        self
+    }
+
+    /// Report hits, misses, and evictions to `metrics`
+    pub fn with_metrics<N: CacheMetrics>(self, metrics: N) -> CachedInterpreter<N> {
+        CachedInterpreter {
+            verdicts: self.verdicts.with_metrics(metrics),
+            verifier: self.verifier,
+        }
+    }
+
+    /// Return whether the contract `blob` holds for `inputs`, interpreting it unless the verdict
+    /// is cached, see `Verifier::verify`
+    pub fn interpret(&mut self, blob: &[u8], inputs: &[PactType]) -> Result<bool, PactErr> {
+        let verifier = self.verifier;
+        let verify = |blob: &[u8]| {
+            verifier
+                .verify(blob, inputs)
+                .map(|decision| decision == Decision::Upheld)
+        };
+        let mut key = blake2b_256(blob).to_vec();
+        for input in inputs {
+            if input.encode_as(&mut key, LengthWidth::U16).is_err() {
+                return verify(blob);
+            }
+        }
+        self.verdicts.get_or_decode(&key, |_| verify(blob)).copied()
+    }
+
+    /// Remove every verdict, keeping the stats
+    pub fn clear(&mut self) {
+        self.verdicts.clear();
+    }
+
+    /// Return the number of cached verdicts
+    pub fn len(&self) -> usize {
+        self.verdicts.len()
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.verdicts.is_empty()
+    }
+
+    /// Return the hit, miss, and eviction counts
+    pub fn stats(&self) -> CacheStats {
+        self.verdicts.stats()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::*;
+    use crate::types::{Contract, DataTable, Numeric, StringLike};
+
+    fn cache(capacity: usize) -> ContractCache<usize> {
+        ContractCache::new(NonZeroUsize::new(capacity).unwrap())
+    }
+
+    fn decode(blob: &[u8]) -> Result<usize, ()> {
+        Ok(blob.len())
+    }
+
+    #[test]
+    fn it_caches_decoded_values() {
+        let mut cache = cache(2);
+        assert_eq!(cache.get_or_decode(b"abc", decode), Ok(&3));
+        assert_eq!(
+            cache.get_or_decode(b"abc", |_| -> Result<usize, ()> {
+                panic!("decoded a cached blob")
+            }),
+            Ok(&3)
+        );
+        assert_eq!(cache.peek(b"abc"), Some(&3));
+        assert_eq!(cache.peek(b"abcd"), None);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                evictions: 0
+            }
+        );
+        assert_eq!(cache.remove(b"abc"), Some(3));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn it_evicts_the_least_recently_used_entry() {
+        let mut cache = cache(2);
+        cache.get_or_decode(b"a", decode).unwrap();
+        cache.get_or_decode(b"bb", decode).unwrap();
+        // `a` is now more recently used than `bb`
+        cache.get_or_decode(b"a", decode).unwrap();
+        cache.get_or_decode(b"ccc", decode).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.peek(b"a"), Some(&1));
+        assert_eq!(cache.peek(b"bb"), None);
+        assert_eq!(cache.peek(b"ccc"), Some(&3));
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn it_does_not_cache_decoding_errors() {
+        let mut cache = cache(1);
+        assert_eq!(
+            cache.get_or_decode(b"bad", |_| Err("malformed")),
+            Err("malformed")
+        );
+        assert!(cache.is_empty());
+        assert_eq!(cache.stats().misses, 1);
+    }
+
+    #[test]
+    fn it_reports_metrics() {
+        #[derive(Default)]
+        struct Counts(Vec<&'static str>);
+        impl CacheMetrics for &mut Counts {
+            fn hit(&mut self) {
+                self.0.push("hit");
+            }
+            fn miss(&mut self) {
+                self.0.push("miss");
+            }
+            fn eviction(&mut self) {
+                self.0.push("eviction");
+            }
+        }
+        let mut counts = Counts::default();
+        let mut cache = cache(1).with_metrics(&mut counts);
+        cache.get_or_decode(b"a", decode).unwrap();
+        cache.get_or_decode(b"a", decode).unwrap();
+        cache.get_or_decode(b"bb", decode).unwrap();
+        drop(cache);
+        assert_eq!(counts.0, vec!["miss", "hit", "miss", "eviction"]);
+    }
+
+    #[test]
+    fn it_caches_verdicts() {
+        let mut blob = Vec::new();
+        Contract::from_parts_unchecked(
+            DataTable::new(vec![PactType::Numeric(Numeric(10))]),
+            comp_lt(input(0), user(0)),
+        )
+        .encode(&mut blob)
+        .unwrap();
+        let mut interpreter = CachedInterpreter::new(NonZeroUsize::new(2).unwrap());
+        let five = [PactType::Numeric(Numeric(5))];
+        let fifty = [PactType::Numeric(Numeric(50))];
+        assert_eq!(interpreter.interpret(&blob, &five), Ok(true));
+        assert_eq!(interpreter.interpret(&blob, &fifty), Ok(false));
+        assert_eq!(interpreter.interpret(&blob, &five), Ok(true));
+        assert_eq!(interpreter.len(), 2);
+        assert_eq!(
+            interpreter.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 2,
+                evictions: 0
+            }
+        );
+
+        // Errors aren't cached
+        let text = [PactType::StringLike(StringLike(b"5"))];
+        assert!(interpreter.interpret(&blob, &text).is_err());
+        assert!(interpreter.interpret(&[0], &five).is_err());
+        assert_eq!(interpreter.len(), 2);
+
+        // Nor are inputs too long to encode
+        let long = vec![0_u8; 0x1_0000];
+        let long = [PactType::StringLike(StringLike(&long))];
+        assert!(interpreter.interpret(&blob, &long).is_err());
+        assert_eq!(interpreter.stats().misses, 4);
+
+        let mut interpreter = interpreter.with_verifier(Verifier::new().with_max_contract_bytes(1));
+        assert!(interpreter.is_empty());
+        assert!(interpreter.interpret(&blob, &five).is_err());
+    }
+}
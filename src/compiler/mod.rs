@@ -14,32 +14,613 @@
 //   <https://centrality.ai/licenses/gplv3.txt>
 //   <https://centrality.ai/licenses/lgplv3.txt>
 
-use crate::parser::ast;
-use crate::types::opcode::{Comparator, Conjunction, LoadSource, OpCode, SubjectSource};
-use crate::types::{Contract, DataTable, Numeric, PactType, StringLike};
+use crate::parser::{self, ast, Rule};
+use crate::types::opcode::{
+    Comparator, Conjunction, LoadSource, OpCode, OpCodeErr, OpCodes, OpComp, OpConj, OpLoad,
+    OpQuant, SubjectSource, ARITY_INDEX, MAX_INDEX,
+};
+use crate::types::{
+    rescale, Contract, DataTable, EncodeErr, InputType, LengthWidth, Metadata, Numeric, PactType,
+    StringLike, MAX_ENTRIES,
+};
 
-use hashbrown::HashMap;
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::convert::TryFrom;
+use core::str::FromStr;
 
-const MAX_ENTRIES: usize = 16;
+/// The maximum number of elements in a list literal, bounds interpreter scan time
+const MAX_LIST_ELEMENTS: usize = 64;
+/// The maximum encoded size of a list literal's elements, bounded by its 2 byte length prefix
+const MAX_LIST_BYTES: usize = 0xffff;
+/// The most decimals of a percentage, so its ratio's denominator fits in a `Numeric`
+pub const MAX_PERCENTAGE_DECIMALS: u8 = 16;
+/// The minimum number of elements in a list literal compiled to a `SortedList`
+/// Shorter lists are scanned about as quickly and keep the v0.1 encoding.
+const SORTED_LIST_MIN_ELEMENTS: usize = 16;
 
 /// Compilation error
 #[derive(Debug, PartialEq)]
 pub enum CompileErr {
     /// The identifier used is not declared
-    UndeclaredVar(ast::Identifier),
-    /// A parameter with the same identifier has already been declared
-    Redeclared,
-    InvalidListElement,
+    UndeclaredVar(ast::Identifier, ast::Location),
+    /// A parameter or definition with the same identifier has already been declared
+    /// Input parameters have no source location.
+    Redeclared(ast::Identifier, Option<ast::Location>),
+    InvalidListElement(ast::Location),
     /// Comparing user data table entries is not valid
-    InvalidCompare,
+    InvalidCompare(ast::Location),
     /// Data table is full
     DataTableFull,
     /// Too Many Input arguments
     TooManyInputs,
+    /// Input parameters are declared more than once, a contract has one `given parameters`
+    DuplicateInputDeclaration,
+    /// A list literal has more elements than the interpreter allows
+    ListTooLong(ast::Location),
+    /// A list literal's encoding is larger than the binary format allows
+    ListTooLarge(ast::Location),
+    /// An input parameter is compared with itself so the outcome is fixed
+    SelfComparison(ast::Location),
+    /// An assertion's operands can never be compared e.g. a parameter is checked to be one of
+    /// itself
+    InvalidOperands(ast::Location),
+    /// A user defined value is checked to be a subset of a parameter, this can't be encoded
+    /// as `SUBSET` is not symmetric
+    UserDefinedSubset(ast::Location),
+    /// An alias names an identifier which is not an earlier definition
+    UndefinedReference(ast::Identifier, ast::Location),
+    /// An alias names itself
+    CyclicDefinition(ast::Identifier, ast::Location),
+    /// An included library can't be resolved, or `compile` was given an unresolved include
+    UnresolvedInclude(String, ast::Location),
+    /// An included library has statements other than definitions
+    InvalidInclude(String, ast::Location),
+    /// A clause with the same name has already been declared
+    DuplicateClause(ast::Location),
+    /// A metadata field with the same key has already been declared
+    DuplicateMetadata(String, ast::Location),
+    /// A quantified subject e.g. `every parameter` is on the RHS of an assertion
+    MisplacedQuantifier(ast::Location),
+    /// A percentage is compared with a constant, or is not the RHS of an assertion
+    MisplacedPercentage(ast::Location),
+    /// A percentage doesn't fit in 64 bits or has more than `MAX_PERCENTAGE_DECIMALS` decimals
+    PercentageOutOfRange(ast::Location),
+    /// The subject at `location` is of a type the assertion's comparator can't compare e.g. a
+    /// string which must be greater than a number
+    TypeError {
+        expected: ast::InputType,
+        found: ast::InputType,
+        location: ast::Location,
+    },
+    /// The number at `location` can't be compared at a common scale with the other subject,
+    /// which has `decimals` e.g. `1.125` with a `decimal(2)` parameter
+    ScaleMismatch {
+        decimals: u8,
+        location: ast::Location,
+    },
+    /// Recompiled clauses or input parameters differ from the previous contract's
+    StructureChanged,
+    /// An OpCode can't be assembled into bytecode
+    Unassemblable(OpCodeErr),
+}
+
+impl CompileErr {
+    /// Return the source location of the error, if it has one
+    pub fn location(&self) -> Option<ast::Location> {
+        match self {
+            CompileErr::UndeclaredVar(_, location)
+            | CompileErr::UndefinedReference(_, location)
+            | CompileErr::CyclicDefinition(_, location)
+            | CompileErr::UnresolvedInclude(_, location)
+            | CompileErr::InvalidInclude(_, location)
+            | CompileErr::DuplicateMetadata(_, location)
+            | CompileErr::InvalidListElement(location)
+            | CompileErr::InvalidCompare(location)
+            | CompileErr::ListTooLong(location)
+            | CompileErr::ListTooLarge(location)
+            | CompileErr::SelfComparison(location)
+            | CompileErr::InvalidOperands(location)
+            | CompileErr::UserDefinedSubset(location)
+            | CompileErr::DuplicateClause(location)
+            | CompileErr::MisplacedQuantifier(location)
+            | CompileErr::MisplacedPercentage(location)
+            | CompileErr::PercentageOutOfRange(location)
+            | CompileErr::TypeError { location, .. }
+            | CompileErr::ScaleMismatch { location, .. } => Some(*location),
+            CompileErr::Redeclared(_, location) => *location,
+            CompileErr::DataTableFull
+            | CompileErr::TooManyInputs
+            | CompileErr::DuplicateInputDeclaration
+            | CompileErr::StructureChanged
+            | CompileErr::Unassemblable(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for CompileErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CompileErr::UndeclaredVar(ident, _) => write!(f, "undeclared variable `{}`", ident),
+            CompileErr::Redeclared(ident, _) => {
+                write!(f, "variable `{}` is already declared", ident)
+            }
+            CompileErr::InvalidListElement(_) => {
+                write!(f, "lists may only contain numbers or strings")
+            }
+            CompileErr::InvalidCompare(_) => {
+                write!(f, "cannot compare two user defined values")
+            }
+            CompileErr::DataTableFull => {
+                write!(f, "data table is full (max {} entries)", MAX_ENTRIES)
+            }
+            CompileErr::TooManyInputs => {
+                write!(f, "too many input parameters (max {})", MAX_ENTRIES - 1)
+            }
+            CompileErr::ListTooLong(_) => {
+                write!(f, "list has too many elements (max {})", MAX_LIST_ELEMENTS)
+            }
+            CompileErr::ListTooLarge(_) => {
+                write!(
+                    f,
+                    "list is too large to encode (max {} bytes)",
+                    MAX_LIST_BYTES
+                )
+            }
+            CompileErr::DuplicateInputDeclaration => {
+                write!(f, "input parameters are already declared")
+            }
+            CompileErr::StructureChanged => {
+                write!(f, "clauses or parameters differ from the previous contract")
+            }
+            CompileErr::Unassemblable(OpCodeErr::IndexOutOfRange(index)) => {
+                write!(f, "index {} is out of range (max {})", index, MAX_INDEX)
+            }
+            CompileErr::Unassemblable(OpCodeErr::BufferTooShort { .. }) => {
+                write!(f, "bytecode buffer is too short")
+            }
+            CompileErr::SelfComparison(_) => {
+                write!(
+                    f,
+                    "parameter is compared with itself so the outcome is fixed"
+                )
+            }
+            CompileErr::InvalidOperands(_) => {
+                write!(
+                    f,
+                    "operands can never be compared, a parameter is never one of itself"
+                )
+            }
+            CompileErr::UserDefinedSubset(_) => {
+                write!(f, "a user defined list cannot be a subset of a parameter")
+            }
+            CompileErr::UndefinedReference(ident, _) => {
+                write!(f, "`{}` does not name an earlier definition", ident)
+            }
+            CompileErr::CyclicDefinition(ident, _) => {
+                write!(f, "`{}` is defined as itself", ident)
+            }
+            CompileErr::UnresolvedInclude(name, _) => {
+                write!(f, "library \"{}\" can't be resolved", name)
+            }
+            CompileErr::InvalidInclude(name, _) => {
+                write!(f, "library \"{}\" may only contain definitions", name)
+            }
+            CompileErr::DuplicateClause(_) => write!(f, "clause name is already declared"),
+            CompileErr::DuplicateMetadata(key, _) => {
+                write!(f, "metadata `{}` is already declared", key)
+            }
+            CompileErr::MisplacedQuantifier(_) => {
+                write!(
+                    f,
+                    "a quantified parameter must be on the left of an assertion"
+                )
+            }
+            CompileErr::MisplacedPercentage(_) => {
+                write!(
+                    f,
+                    "a percentage must be on the right of an assertion about a parameter"
+                )
+            }
+            CompileErr::PercentageOutOfRange(_) => write!(
+                f,
+                "percentage is too large or has too many decimals (max {})",
+                MAX_PERCENTAGE_DECIMALS
+            ),
+            CompileErr::TypeError {
+                expected, found, ..
+            } => write!(
+                f,
+                "expected {}, found {}",
+                type_name(*expected),
+                type_name(*found)
+            ),
+            CompileErr::ScaleMismatch { decimals, .. } => write!(
+                f,
+                "expected a number representable with {} decimals",
+                decimals
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CompileErr {}
+
+/// Describe a type in compiler messages
+#[cfg(feature = "std")]
+fn type_name(input_type: ast::InputType) -> &'static str {
+    match input_type {
+        ast::InputType::Any => "any value",
+        ast::InputType::Numeric => "a number",
+        ast::InputType::StringLike => "a string",
+        ast::InputType::List => "a list",
+        ast::InputType::Bool => "a bool",
+        ast::InputType::FixedPoint(_) => "a decimal",
+    }
+}
+
+/// Return the type of a literal `value`
+fn value_type(value: &ast::Value) -> ast::InputType {
+    match value {
+        ast::Value::StringLike(_) => ast::InputType::StringLike,
+        ast::Value::Numeric(_) => ast::InputType::Numeric,
+        ast::Value::List(_) => ast::InputType::List,
+        ast::Value::Bool(_) => ast::InputType::Bool,
+        ast::Value::FixedPoint(_, decimals) => ast::InputType::FixedPoint(*decimals),
+    }
+}
+
+/// Return the decimals of a numeric type, numerics have none
+fn decimals(input_type: ast::InputType) -> Option<u8> {
+    match input_type {
+        ast::InputType::Numeric => Some(0),
+        ast::InputType::FixedPoint(decimals) => Some(decimals),
+        _ => None,
+    }
+}
+
+/// A non-fatal compiler diagnostic
+/// The contract compiles as written but likely doesn't mean what its author intended.
+/// Comparisons between two literals or of a parameter with itself have fixed outcomes and are
+/// rejected outright as `CompileErr::InvalidCompare` and `CompileErr::SelfComparison`.
+#[derive(Debug, PartialEq)]
+pub enum Warning {
+    /// An input parameter is declared but no clause reads it
+    UnusedParameter(ast::Identifier),
+    /// A definition is declared but no clause reads it
+    UnusedDefinition(ast::Identifier, ast::Location),
+    /// A literal repeats the value of a definition, which should be used by name instead
+    ShadowedLiteral(ast::Identifier, ast::Location),
+}
+
+impl Warning {
+    /// Return the source location of the warning, if it has one
+    pub fn location(&self) -> Option<ast::Location> {
+        match self {
+            Warning::UnusedDefinition(_, location) | Warning::ShadowedLiteral(_, location) => {
+                Some(*location)
+            }
+            Warning::UnusedParameter(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Warning::UnusedParameter(ident) => write!(f, "unused parameter `{}`", ident),
+            Warning::UnusedDefinition(ident, _) => write!(f, "unused definition `{}`", ident),
+            Warning::ShadowedLiteral(ident, _) => {
+                write!(f, "literal has the same value as `{}`", ident)
+            }
+        }
+    }
+}
+
+/// Resolves the libraries named by `include` statements into AST nodes
+/// Implementors typically load and parse library source with `parser::parse_library`.
+pub trait Resolver {
+    /// Return the nodes of the library named `name`, or `None` if there is no such library
+    fn resolve(&self, name: &str) -> Option<Vec<ast::Node>>;
+}
+
+impl<F: Fn(&str) -> Option<Vec<ast::Node>>> Resolver for F {
+    fn resolve(&self, name: &str) -> Option<Vec<ast::Node>> {
+        self(name)
+    }
+}
+
+/// Replace each `include` statement in a pact contract AST with the definitions of the library
+/// it names, so they precede the statements after it
+/// Libraries may only contain definitions, aliases, and placeholders, so they can't include other libraries.
+pub fn resolve_includes(
+    ir: Vec<ast::Node>,
+    resolver: &dyn Resolver,
+) -> Result<Vec<ast::Node>, CompileErr> {
+    let mut resolved = Vec::<ast::Node>::with_capacity(ir.len());
+    for node in ir {
+        match node {
+            ast::Node::Include(name, location) => {
+                let library = match resolver.resolve(&name) {
+                    Some(library) => library,
+                    None => return Err(CompileErr::UnresolvedInclude(name, location)),
+                };
+                if !library.iter().all(|n| {
+                    matches!(
+                        n,
+                        ast::Node::Definition(..)
+                            | ast::Node::Alias(..)
+                            | ast::Node::Placeholder(..)
+                    )
+                }) {
+                    return Err(CompileErr::InvalidInclude(name, location));
+                }
+                trace_event!(library = %name, nodes = library.len(), "included library");
+                resolved.extend(library);
+            }
+            node => resolved.push(node),
+        }
+    }
+    Ok(resolved)
+}
+
+/// Compile a pact contract AST with `include` statements resolved by `resolver`
+/// `ir` is replaced by the resolved AST the contract borrows from, or left empty on error.
+pub fn compile_with_resolver<'a>(
+    ir: &'a mut Vec<ast::Node>,
+    resolver: &dyn Resolver,
+) -> Result<Contract<'a>, CompileErr> {
+    *ir = resolve_includes(core::mem::take(ir), resolver)?;
+    compile(ir)
 }
 
 /// Compile a pact contract AST into bytecode
-pub fn compile(ir: &[ast::Node]) -> Result<Contract, CompileErr> {
+/// Warnings are discarded, see `compile_with_warnings`.
+/// Compilation is deterministic: the same AST compiles to the same bytes on every run, target,
+/// and crate version with the same binary format. Data table entries are ordered by their first
+/// appearance in the source, so hashes of compiled contracts can be stored and compared.
+pub fn compile(ir: &[ast::Node]) -> Result<Contract<'_>, CompileErr> {
+    compile_with_warnings(ir).map(|(contract, _)| contract)
+}
+
+/// How the compiler optimises a contract's bytecode, see `compile_with_opts`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OptLevel {
+    /// Clauses are emitted in source order, as by `compile`
+    None,
+    /// Clauses, and comparators conjoined by `AND` within a clause, are reordered so cheaper ones
+    /// are interpreted first, when it's safe
+    ReorderClauses,
+}
+
+/// Compile a pact contract AST into bytecode optimised at `level`
+/// The interpreter stops at the first clause which doesn't hold, so with
+/// `OptLevel::ReorderClauses` clauses with cheap comparisons e.g. of numerics are moved before
+/// costly ones e.g. scans of long lists, and rejections are found sooner on average.
+/// Within a clause, comparators `AND`ed onto the result so far are reordered the same way, so
+/// with `Limits::short_circuit` the costly ones are skipped once a cheap one is false. `OR`,
+/// `XOR` and inverted conjunctions, and the comparators they join, keep their place.
+/// Clauses are only reordered when every input parameter has a declared type and there are no
+/// placeholders, so no comparator fails on inputs conforming to the input schema and the verdict
+/// of `interpret` can't depend on the clause order. The rejecting comparator may differ. Clause
+/// names move with their clauses.
+/// Under `interpret_with_limits` the verdict may change: a clause failing with
+/// `InterpErr::StringTooLong`, or an OpCode reaching `Limits::max_opcodes`, may now be preceded
+/// by a rejecting clause so the contract is rejected instead of failing, and vice versa. Hosts
+/// which distinguish errors from rejections should use `OptLevel::None` with limits.
+pub fn compile_with_opts(ir: &[ast::Node], level: OptLevel) -> Result<Contract<'_>, CompileErr> {
+    let contract = compile(ir)?;
+    let typed = match contract.input_types() {
+        Some(types) => types.iter().all(|t| *t != InputType::Any),
+        None => false,
+    };
+    let placeholders = contract
+        .data_table()
+        .as_ref()
+        .iter()
+        .any(|value| matches!(value, PactType::Placeholder(..)));
+    if level == OptLevel::None || !typed || placeholders {
+        return Ok(contract);
+    }
+    let costs: Vec<usize> = contract
+        .clauses()
+        .iter()
+        .map(|(_, bytecode)| clause_cost(bytecode, contract.data_table().as_ref()))
+        .collect();
+    // A stable sort keeps clauses of equal cost in source order
+    let mut order: Vec<usize> = (0..costs.len()).collect();
+    order.sort_by_key(|clause| costs[*clause]);
+    Ok(contract
+        .reorder_clauses(&order)
+        .map_clauses(reorder_conjuncts))
+}
+
+/// Reorder a clause's comparators which are conjoined by `AND` so cheaper ones come first
+/// The interpreter folds a clause left to right, so comparators each `AND`ed onto the result so
+/// far commute with each other, and with the first comparator if they directly follow it.
+fn reorder_conjuncts(clause: &[u8], data_table: &[PactType]) -> Vec<u8> {
+    let and = Conjunction::new(OpConj::AND);
+    let mut bytecode = Vec::with_capacity(clause.len());
+    // Comparators `AND`ed onto the result so far, in source order
+    let mut run: Vec<Comparator> = Vec::new();
+    let flush = |run: &mut Vec<Comparator>, bytecode: &mut Vec<u8>| {
+        // A stable sort keeps comparators of equal cost in source order
+        run.sort_by_key(|comparator| comparator_cost(*comparator, data_table));
+        for comparator in run.drain(..) {
+            if !bytecode.is_empty() {
+                emit(OpCode::CONJ(and), bytecode);
+            }
+            emit(OpCode::COMP(comparator), bytecode);
+        }
+    };
+    let mut conjunction = None;
+    // Compiled bytecode parses
+    for (_, op) in OpCodes::new(clause).flatten() {
+        match (op, conjunction.take()) {
+            (OpCode::CONJ(next), _) => conjunction = Some(next),
+            (OpCode::COMP(comparator), None) => run.push(comparator),
+            (OpCode::COMP(comparator), Some(previous)) if previous == and => run.push(comparator),
+            (OpCode::COMP(comparator), Some(previous)) => {
+                flush(&mut run, &mut bytecode);
+                emit(OpCode::CONJ(previous), &mut bytecode);
+                emit(OpCode::COMP(comparator), &mut bytecode);
+            }
+        }
+    }
+    flush(&mut run, &mut bytecode);
+    bytecode
+}
+
+/// Append the compiled `op` to `bytecode`
+fn emit(op: OpCode, bytecode: &mut Vec<u8>) {
+    op.compile(bytecode)
+        .unwrap_or_else(|_| unreachable!("parsed OpCodes compile"));
+}
+
+/// Estimate the relative cost of interpreting a clause's `bytecode`
+/// Each comparator costs about one unit per 8 bytes or list element compared. Values from input
+/// registers are of unknown length, and quantified comparators range over an unknown number of
+/// registers, so they're assumed to cost a few units.
+fn clause_cost(bytecode: &[u8], data_table: &[PactType]) -> usize {
+    let mut cost = 0;
+    let mut scanner = bytecode.iter();
+    // Compiled bytecode parses
+    while let Ok(Some(op)) = OpCode::parse(&mut scanner) {
+        if let OpCode::COMP(comparator) = op {
+            cost += comparator_cost(comparator, data_table);
+        }
+    }
+    cost
+}
+
+/// Estimate the relative cost of interpreting `comparator`, see `clause_cost`
+fn comparator_cost(comparator: Comparator, data_table: &[PactType]) -> usize {
+    const INPUT_COST: usize = 4;
+    const QUANTIFIED_REGISTERS: usize = 4;
+    let operand = match comparator.load {
+        OpLoad::INPUT_VS_USER => data_table
+            .get(comparator.indices.rhs as usize)
+            .map_or(INPUT_COST, |value| value_cost(value, comparator.op)),
+        OpLoad::INPUT_VS_INPUT => INPUT_COST,
+    };
+    match comparator.quant {
+        OpQuant::ONE => operand,
+        _ => operand * QUANTIFIED_REGISTERS,
+    }
+}
+
+/// Estimate the cost of comparing an input with `value` by `op`
+fn value_cost(value: &PactType, op: OpComp) -> usize {
+    match value {
+        PactType::StringLike(StringLike(bytes)) => 1 + bytes.len() / 8,
+        // `IN` binary searches sorted lists
+        PactType::SortedList(values) if op == OpComp::IN => {
+            let steps = (64 - (values.len() as u64).leading_zeros()) as usize;
+            steps * values.first().map_or(1, |value| value_cost(value, op))
+        }
+        PactType::List(values) | PactType::SortedList(values) => {
+            1 + values
+                .iter()
+                .map(|value| value_cost(value, op))
+                .sum::<usize>()
+        }
+        _ => 1,
+    }
+}
+
+/// An error compiling pact source in one step, see `CompiledContract::from_source`
+#[derive(Debug, PartialEq)]
+pub enum SourceErr {
+    /// The source doesn't parse
+    Parse(pest::error::Error<Rule>),
+    /// The source doesn't compile
+    Compile(CompileErr),
+    /// The compiled contract can't be encoded
+    Encode(EncodeErr),
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for SourceErr {
+    /// Wrapped errors are not repeated, see `source`
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SourceErr::Parse(_) => write!(f, "pact source doesn't parse"),
+            SourceErr::Compile(_) => write!(f, "pact source doesn't compile"),
+            SourceErr::Encode(_) => write!(f, "compiled contract can't be encoded"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SourceErr {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SourceErr::Parse(err) => Some(err),
+            SourceErr::Compile(err) => Some(err),
+            SourceErr::Encode(err) => Some(err),
+        }
+    }
+}
+
+/// An encoded contract compiled from pact source, for callers with source who want bytecode
+/// A `Contract` borrows its strings from the AST it was compiled from, so this owns the
+/// contract's encoding instead and decodes it on demand with `contract`.
+/// Create one with `from_source`, `str::parse`, or `TryFrom<&str>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompiledContract(Vec<u8>);
+
+impl CompiledContract {
+    /// Parse and compile pact `source`, then encode the contract
+    /// Warnings are discarded, see `compile_with_warnings`.
+    // The pest error is large, but boxing it would differ from `parser::parse`
+    #[allow(clippy::result_large_err)]
+    pub fn from_source(source: &str) -> Result<Self, SourceErr> {
+        let ast = parser::parse(source).map_err(SourceErr::Parse)?;
+        let contract = compile(&ast).map_err(SourceErr::Compile)?;
+        let mut blob = Vec::new();
+        contract.encode(&mut blob).map_err(SourceErr::Encode)?;
+        Ok(CompiledContract(blob))
+    }
+
+    /// Return the decoded contract
+    /// Compile time details which aren't encoded e.g. `Contract::origins` are not kept.
+    pub fn contract(&self) -> Contract<'_> {
+        Contract::decode(&self.0).expect("the contract was encoded by this crate")
+    }
+
+    /// Return the encoded contract
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Return the encoded contract, consuming it
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl FromStr for CompiledContract {
+    type Err = SourceErr;
+    #[allow(clippy::result_large_err)]
+    fn from_str(source: &str) -> Result<Self, SourceErr> {
+        Self::from_source(source)
+    }
+}
+
+impl TryFrom<&str> for CompiledContract {
+    type Error = SourceErr;
+    #[allow(clippy::result_large_err)]
+    fn try_from(source: &str) -> Result<Self, SourceErr> {
+        Self::from_source(source)
+    }
+}
+
+/// Compile a pact contract AST into bytecode, returning non-fatal diagnostics with the contract
+/// Warnings are ordered by kind: unused parameters, unused definitions, then shadowed literals,
+/// each in source order.
+pub fn compile_with_warnings(ir: &[ast::Node]) -> Result<(Contract<'_>, Vec<Warning>), CompileErr> {
     // 1. Semantically verify the AST
     //     - Duplicate var definition
     //     - Missing var definition
@@ -47,61 +628,192 @@ pub fn compile(ir: &[ast::Node]) -> Result<Contract, CompileErr> {
     // 2. Move user-defined vars into a data section
     // 3. Replace var identifiers with data indexes
     // 4. Replace input param identifiers with data indexes
+    trace_span!("compile", nodes = ir.len());
     let mut compiler = Compiler::new();
+    let mut parameters = Vec::<(&str, ast::InputType)>::default();
+    let mut clauses = 0;
+    let mut clause_names = Vec::<(usize, &str)>::default();
+    let mut metadata = Metadata::default();
+    let mut definitions = Vec::<(&ast::Identifier, ast::Location)>::default();
+    let mut declared = false;
 
     for node in ir.iter() {
         match node {
-            ast::Node::InputDeclaration(idents) => {
-                if idents.len() >= MAX_ENTRIES {
+            ast::Node::InputDeclaration(params) => {
+                // A second declaration would restart input indices at 0
+                if declared {
+                    return Err(CompileErr::DuplicateInputDeclaration);
+                }
+                declared = true;
+                if params.len() >= MAX_ENTRIES {
                     return Err(CompileErr::TooManyInputs);
                 }
-                for (index, ident) in idents.iter().enumerate() {
+                for (index, (ident, input_type)) in params.iter().enumerate() {
+                    if compiler.input_var_index.contains_key(ident)
+                        || compiler.user_var_index.contains_key(ident)
+                    {
+                        return Err(CompileErr::Redeclared(ident.to_string(), None));
+                    }
+                    trace_event!(
+                        identifier = %ident,
+                        index,
+                        input_type = ?input_type,
+                        "declared input parameter"
+                    );
                     compiler
                         .input_var_index
                         .insert(ident.to_string(), index as u8);
+                    compiler.input_types.push(*input_type);
+                    parameters.push((ident.trim_start_matches('$'), *input_type));
+                }
+            }
+            ast::Node::Include(name, location) => {
+                return Err(CompileErr::UnresolvedInclude(name.to_string(), *location));
+            }
+            ast::Node::Meta(key, value, location) => {
+                if !metadata.insert(key, value) {
+                    return Err(CompileErr::DuplicateMetadata(key.to_string(), *location));
                 }
             }
             ast::Node::Clause(assertion) => {
-                compiler.compile_assertion(&assertion)?;
+                trace_span!("clause", index = clauses);
+                compiler.compile_assertion(assertion)?;
+                clauses += 1;
             }
-            ast::Node::Definition(identifier, value) => {
-                if compiler.input_var_index.contains_key(identifier) {
-                    return Err(CompileErr::Redeclared);
+            ast::Node::NamedClause(name, assertion) => {
+                if clause_names.iter().any(|(_, n)| n == name) {
+                    return Err(CompileErr::DuplicateClause(assertion.location));
                 }
-                let previous = compiler
+                trace_span!("clause", index = clauses, name = %name);
+                clause_names.push((clauses, name));
+                compiler.compile_assertion(assertion)?;
+                clauses += 1;
+            }
+            ast::Node::Definition(identifier, value, location) => {
+                compiler.check_undeclared(identifier, *location)?;
+                // Literals compiled earlier precede the definition in the data table
+                compiler.push_to_datatable(compile_value(value, *location)?)?;
+                let index = (compiler.data_table.len() as u8) - 1;
+                compiler
+                    .user_var_index
+                    .insert(identifier.to_string(), index);
+                definitions.push((identifier, *location));
+                trace_event!(identifier = %identifier, index, "defined user variable");
+            }
+            ast::Node::Placeholder(identifier, input_type, location) => {
+                compiler.check_undeclared(identifier, *location)?;
+                compiler.push_to_datatable(PactType::Placeholder(
+                    identifier.trim_start_matches('$'),
+                    *input_type,
+                ))?;
+                let index = (compiler.data_table.len() as u8) - 1;
+                compiler
                     .user_var_index
-                    .insert(identifier.to_string(), compiler.user_var_index.len() as u8);
-                if previous.is_some() {
-                    return Err(CompileErr::Redeclared);
+                    .insert(identifier.to_string(), index);
+                definitions.push((identifier, *location));
+                trace_event!(identifier = %identifier, index, "declared placeholder");
+            }
+            ast::Node::Alias(identifier, aliased, location) => {
+                if identifier == aliased {
+                    return Err(CompileErr::CyclicDefinition(
+                        identifier.to_string(),
+                        *location,
+                    ));
                 }
+                compiler.check_undeclared(identifier, *location)?;
+                // Definitions are resolved in order so an alias can't form a cycle through
+                // later definitions, and aliases share the aliased data table entry
+                let index = *compiler.user_var_index.get(aliased).ok_or_else(|| {
+                    CompileErr::UndefinedReference(aliased.to_string(), *location)
+                })?;
+                compiler.used.insert(aliased.to_string());
+                compiler
+                    .user_var_index
+                    .insert(identifier.to_string(), index);
+                definitions.push((identifier, *location));
+                trace_event!(identifier = %identifier, index, "aliased user variable");
+            }
+        }
+    }
+
+    let warnings = compiler.warnings(&parameters, &definitions);
 
-                // convert ast::Value to PactType
-                let v = match value {
+    // The compiler only emits indices it has allocated
+    let mut contract =
+        Contract::from_parts_unchecked(DataTable::new(compiler.data_table), compiler.bytecode);
+    if !clause_names.is_empty() {
+        contract = contract.with_clause_names(clause_names);
+    }
+    if !metadata.is_empty() {
+        contract = contract.with_metadata(metadata);
+    }
+    // Only contracts with declared types carry an input schema
+    let contract = if parameters.iter().all(|(_, t)| *t == ast::InputType::Any) {
+        contract.with_input_names(parameters.into_iter().map(|(name, _)| name).collect())
+    } else {
+        contract.with_input_schema(parameters)
+    };
+    Ok((contract, warnings))
+}
+
+/// Recompile a pact contract AST whose `define` values changed since `previous` was compiled
+/// e.g. rotating a whitelist. Fails unless the clauses, their names, and input parameters are
+/// untouched, so the new contract's bytecode is byte-identical to `previous` and only its data
+/// table differs.
+pub fn recompile_data<'a>(
+    ir: &'a [ast::Node],
+    previous: &Contract,
+) -> Result<Contract<'a>, CompileErr> {
+    let contract = compile(ir)?;
+    if contract.bytecode() != previous.bytecode()
+        || contract.input_names() != previous.input_names()
+        || contract.input_types() != previous.input_types()
+        || contract.clause_names() != previous.clause_names()
+    {
+        return Err(CompileErr::StructureChanged);
+    }
+    Ok(contract)
+}
+
+/// Convert an `ast::Value` literal at `location` into a `PactType`
+fn compile_value(value: &ast::Value, location: ast::Location) -> Result<PactType<'_>, CompileErr> {
+    match value {
+        ast::Value::Numeric(n) => Ok(PactType::Numeric(Numeric(*n))),
+        ast::Value::StringLike(s) => Ok(PactType::StringLike(StringLike(s.as_bytes()))),
+        ast::Value::Bool(b) => Ok(PactType::Bool(*b)),
+        ast::Value::FixedPoint(value, decimals) => Ok(PactType::FixedPoint {
+            value: *value,
+            decimals: *decimals,
+        }),
+        ast::Value::List(l) => {
+            if l.len() > MAX_LIST_ELEMENTS {
+                return Err(CompileErr::ListTooLong(location));
+            }
+            let mut list = Vec::<PactType>::with_capacity(l.len());
+            for element in l {
+                list.push(match element {
                     ast::Value::Numeric(n) => PactType::Numeric(Numeric(*n)),
                     ast::Value::StringLike(s) => PactType::StringLike(StringLike(s.as_bytes())),
-                    ast::Value::List(l) => {
-                        let mut list = Vec::<PactType>::with_capacity(l.len());
-                        for element in l {
-                            list.push(match element {
-                                ast::Value::Numeric(n) => PactType::Numeric(Numeric(*n)),
-                                ast::Value::StringLike(s) => {
-                                    PactType::StringLike(StringLike(s.as_bytes()))
-                                }
-                                _ => return Err(CompileErr::InvalidListElement),
-                            })
-                        }
-                        PactType::List(list)
-                    }
-                };
-                compiler.push_to_datatable(v)?;
+                    _ => return Err(CompileErr::InvalidListElement(location)),
+                })
+            }
+            let mut encoded = Vec::<u8>::default();
+            for element in &list {
+                element
+                    .encode_as(&mut encoded, LengthWidth::U16)
+                    .map_err(|_| CompileErr::ListTooLarge(location))?;
+            }
+            if encoded.len() > MAX_LIST_BYTES {
+                return Err(CompileErr::ListTooLarge(location));
             }
+            if list.len() < SORTED_LIST_MIN_ELEMENTS {
+                return Ok(PactType::List(list));
+            }
+            // Lists of one element type are binary searched, mixed lists are scanned
+            let sorted = PactType::sorted_list(list.clone());
+            Ok(sorted.unwrap_or(PactType::List(list)))
         }
     }
-
-    Ok(Contract {
-        data_table: DataTable::new(compiler.data_table),
-        bytecode: compiler.bytecode,
-    })
 }
 
 /// A pact compiler
@@ -109,9 +821,17 @@ struct Compiler<'a> {
     data_table: Vec<PactType<'a>>,
     bytecode: Vec<u8>,
     // Intermediate store for user var definitions (identity, u8 ordered index)
-    input_var_index: HashMap<String, u8>,
+    input_var_index: BTreeMap<String, u8>,
     // Intermediate store for input var ordering (identity, u8 ordered index)
-    user_var_index: HashMap<String, u8>,
+    user_var_index: BTreeMap<String, u8>,
+    // Declared input parameter types, by input index
+    input_types: Vec<ast::InputType>,
+    // Identifiers read by a clause
+    used: BTreeSet<String>,
+    // Whether a clause reads every input register through a quantifier
+    quantified: bool,
+    // Data table indices of literal subjects and their source locations
+    literals: Vec<(u8, ast::Location)>,
 }
 
 impl<'a> Compiler<'a> {
@@ -122,13 +842,72 @@ impl<'a> Compiler<'a> {
             bytecode: Default::default(),
             input_var_index: Default::default(),
             user_var_index: Default::default(),
+            input_types: Default::default(),
+            used: Default::default(),
+            quantified: false,
+            literals: Default::default(),
+        }
+    }
+
+    /// Collect warnings for the compiled contract's declared `parameters` and `definitions`
+    fn warnings(
+        &self,
+        parameters: &[(&str, ast::InputType)],
+        definitions: &[(&ast::Identifier, ast::Location)],
+    ) -> Vec<Warning> {
+        let mut warnings = Vec::<Warning>::default();
+        if !self.quantified {
+            for (name, _) in parameters {
+                let ident = format!("${}", name);
+                if !self.used.contains(&ident) {
+                    warnings.push(Warning::UnusedParameter(ident));
+                }
+            }
+        }
+        for (ident, location) in definitions {
+            if !self.used.contains(*ident) {
+                warnings.push(Warning::UnusedDefinition(ident.to_string(), *location));
+            }
+        }
+        for (index, location) in &self.literals {
+            let value = &self.data_table[*index as usize];
+            if let Some((ident, _)) = definitions
+                .iter()
+                .find(|(ident, _)| &self.data_table[self.user_var_index[*ident] as usize] == value)
+            {
+                warnings.push(Warning::ShadowedLiteral(ident.to_string(), *location));
+            }
+        }
+        warnings
+    }
+
+    /// Fail if `identifier` defined at `location` is already an input parameter or definition
+    fn check_undeclared(
+        &self,
+        identifier: &str,
+        location: ast::Location,
+    ) -> Result<(), CompileErr> {
+        if self.input_var_index.contains_key(identifier)
+            || self.user_var_index.contains_key(identifier)
+        {
+            return Err(CompileErr::Redeclared(
+                identifier.to_string(),
+                Some(location),
+            ));
         }
+        Ok(())
     }
 
     fn push_to_datatable(&mut self, value: PactType<'a>) -> Result<(), CompileErr> {
         if self.data_table.len() >= MAX_ENTRIES {
+            trace_event!(entries = self.data_table.len(), "data table is full");
             Err(CompileErr::DataTableFull)
         } else {
+            trace_event!(
+                index = self.data_table.len(),
+                value = ?value,
+                "allocated data table entry"
+            );
             self.data_table.push(value);
             Ok(())
         }
@@ -136,66 +915,307 @@ impl<'a> Compiler<'a> {
 
     /// Compile an assertion AST node
     fn compile_assertion(&mut self, assertion: &'a ast::Assertion) -> Result<(), CompileErr> {
+        trace_span!(
+            "assertion",
+            line = assertion.location.line,
+            column = assertion.location.column
+        );
         let lhs_load = self.compile_subject(&assertion.lhs_subject)?;
-        let rhs_load = self.compile_subject(&assertion.rhs_subject)?;
+        // A percentage scales its subject by a ratio in the data table
+        let (rhs_subject, ratio) = match &assertion.rhs_subject {
+            ast::Subject::Percentage(percentage, subject, location) => {
+                if lhs_load.load_source == LoadSource::DataTable {
+                    return Err(CompileErr::MisplacedPercentage(*location));
+                }
+                let ratio = self.compile_ratio(percentage, *location)?;
+                (&**subject, Some(ratio))
+            }
+            subject => (subject, None),
+        };
+        let rhs_load = self.compile_subject(rhs_subject)?;
 
         if lhs_load.load_source == LoadSource::DataTable
             && rhs_load.load_source == LoadSource::DataTable
         {
-            return Err(CompileErr::InvalidCompare);
+            return Err(CompileErr::InvalidCompare(assertion.location));
+        }
+        if matches!(assertion.comparator, ast::Comparator::SubsetOf)
+            && lhs_load.load_source == LoadSource::DataTable
+        {
+            return Err(CompileErr::UserDefinedSubset(assertion.location));
+        }
+        // Quantifiers range over the LHS register so must be on the LHS
+        if let ast::Subject::Quantified(_, location) = rhs_subject {
+            return Err(CompileErr::MisplacedQuantifier(*location));
         }
+        self.check_types(assertion)?;
+        let quant = match &assertion.lhs_subject {
+            ast::Subject::Quantified(quantifier, _) => OpQuant::from(quantifier),
+            _ => OpQuant::ONE,
+        };
 
         // Build and compile comparator
-        let _ = OpCode::COMP(
-            Comparator::from(&assertion.comparator)
-                .apply_imperative(&assertion.imperative)
-                .loads_from_subjects(lhs_load, rhs_load),
-        )
-        .compile(&mut self.bytecode);
+        let mut comparator = Comparator::from(&assertion.comparator)
+            .apply_imperative(&assertion.imperative)
+            .loads_from_subjects(lhs_load, rhs_load)
+            .quantify(quant);
+        if let Some(ratio) = ratio {
+            comparator = comparator.scaled(ratio);
+        }
+        if comparator.is_self_comparison() {
+            return Err(CompileErr::SelfComparison(assertion.location));
+        }
+        if comparator.has_invalid_operands() {
+            return Err(CompileErr::InvalidOperands(assertion.location));
+        }
+        trace_event!(
+            offset = self.bytecode.len(),
+            opcode = u8::from(OpCode::COMP(comparator)),
+            lhs = comparator.indices.lhs,
+            rhs = comparator.indices.rhs,
+            "emitted comparator"
+        );
+        OpCode::COMP(comparator)
+            .compile(&mut self.bytecode)
+            .map_err(CompileErr::Unassemblable)?;
 
         // Handle conjunction if it exists
         if let Some((conjunctive, conjoined_assertion)) = &assertion.conjoined_assertion {
-            let _ = OpCode::CONJ(Conjunction::from(conjunctive)).compile(&mut self.bytecode);
-            self.compile_assertion(&*conjoined_assertion)?;
+            trace_event!(
+                offset = self.bytecode.len(),
+                opcode = u8::from(OpCode::CONJ(Conjunction::from(conjunctive))),
+                "emitted conjunction"
+            );
+            OpCode::CONJ(Conjunction::from(conjunctive))
+                .compile(&mut self.bytecode)
+                .map_err(CompileErr::Unassemblable)?;
+            self.compile_assertion(conjoined_assertion)?;
         }
 
         Ok(())
     }
 
+    /// Compile a `percentage` literal at `location` into a (numerator, denominator) ratio in
+    /// the data table, returning its index
+    fn compile_ratio(
+        &mut self,
+        percentage: &ast::Value,
+        location: ast::Location,
+    ) -> Result<u8, CompileErr> {
+        let (numerator, decimals) = match percentage {
+            ast::Value::Numeric(numerator) => (u128::from(*numerator), 0),
+            ast::Value::FixedPoint(numerator, decimals) => (*numerator, *decimals),
+            value => {
+                return Err(CompileErr::TypeError {
+                    expected: ast::InputType::Numeric,
+                    found: value_type(value),
+                    location,
+                })
+            }
+        };
+        let denominator = match decimals {
+            decimals if decimals <= MAX_PERCENTAGE_DECIMALS => 100 * 10_u64.pow(decimals.into()),
+            _ => return Err(CompileErr::PercentageOutOfRange(location)),
+        };
+        let numerator =
+            u64::try_from(numerator).map_err(|_| CompileErr::PercentageOutOfRange(location))?;
+        self.push_to_datatable(PactType::List(vec![
+            PactType::Numeric(Numeric(numerator)),
+            PactType::Numeric(Numeric(denominator)),
+        ]))?;
+        Ok((self.data_table.len() as u8) - 1)
+    }
+
+    /// Fail if the assertion's subjects are of types its comparator can't compare
+    /// Subjects of unknown type e.g. parameters without a declared type are checked at runtime.
+    fn check_types(&self, assertion: &ast::Assertion) -> Result<(), CompileErr> {
+        let lhs = self.subject_type(&assertion.lhs_subject);
+        let rhs = self.subject_type(&assertion.rhs_subject);
+        let expect = |subject: Option<(ast::InputType, ast::Location)>,
+                      expected: ast::InputType| match subject {
+            Some((found, location)) if found != expected => Err(CompileErr::TypeError {
+                expected,
+                found,
+                location,
+            }),
+            _ => Ok(()),
+        };
+        // Ratios scale whole numbers only
+        if let ast::Subject::Percentage(_, subject, _) = &assertion.rhs_subject {
+            expect(lhs, ast::InputType::Numeric)?;
+            expect(self.subject_type(subject), ast::InputType::Numeric)?;
+        }
+        // Numerics and fixed points of any decimals are compared by value
+        let expect_number = |subject: Option<(ast::InputType, ast::Location)>| match subject {
+            Some((found, location)) if decimals(found).is_none() => Err(CompileErr::TypeError {
+                expected: ast::InputType::Numeric,
+                found,
+                location,
+            }),
+            _ => Ok(()),
+        };
+        match assertion.comparator {
+            ast::Comparator::Equal => match (lhs, rhs) {
+                (Some((ast::InputType::List, location)), _)
+                | (_, Some((ast::InputType::List, location))) => Err(CompileErr::TypeError {
+                    // Lists are compared by membership, subset, or intersection instead
+                    expected: ast::InputType::Any,
+                    found: ast::InputType::List,
+                    location,
+                }),
+                (Some((expected, _)), rhs) if decimals(expected).is_some() => {
+                    expect_number(rhs)?;
+                    self.check_scales(assertion)
+                }
+                (Some((expected, _)), rhs) => expect(rhs, expected),
+                (None, _) => Ok(()),
+            },
+            ast::Comparator::GreaterThan
+            | ast::Comparator::GreaterThanOrEqual
+            | ast::Comparator::LessThan
+            | ast::Comparator::LessThanOrEqual => {
+                expect_number(lhs)?;
+                expect_number(rhs)?;
+                self.check_scales(assertion)
+            }
+            ast::Comparator::OneOf => expect(rhs, ast::InputType::List),
+            ast::Comparator::SubsetOf | ast::Comparator::IntersectingWith => {
+                expect(lhs, ast::InputType::List)?;
+                expect(rhs, ast::InputType::List)
+            }
+        }
+    }
+
+    /// Fail if the assertion's numeric subjects can't be compared at a common scale
+    /// A constant must be exactly representable with the decimals of the subject it's compared
+    /// with, subjects only known at runtime must have the same decimals.
+    fn check_scales(&self, assertion: &ast::Assertion) -> Result<(), CompileErr> {
+        let lhs = self.subject_scale(&assertion.lhs_subject);
+        let rhs = self.subject_scale(&assertion.rhs_subject);
+        let mismatch = match (lhs, rhs) {
+            (Some((decimals, None, _)), Some((found, None, location))) if decimals != found => {
+                Some((decimals, location))
+            }
+            (Some((decimals, None, _)), Some((found, Some(value), location)))
+            | (Some((found, Some(value), location)), Some((decimals, None, _)))
+                if rescale(value, found, decimals).is_none() =>
+            {
+                Some((decimals, location))
+            }
+            _ => None,
+        };
+        match mismatch {
+            Some((decimals, location)) => Err(CompileErr::ScaleMismatch { decimals, location }),
+            None => Ok(()),
+        }
+    }
+
+    /// Return the decimals of a numeric `subject`, its value if it's a constant, and its location
+    fn subject_scale(&self, subject: &ast::Subject) -> Option<(u8, Option<u128>, ast::Location)> {
+        let location = subject.location();
+        match subject {
+            ast::Subject::Value(ast::Value::Numeric(value), _) => {
+                Some((0, Some(u128::from(*value)), location))
+            }
+            ast::Subject::Value(ast::Value::FixedPoint(value, decimals), _) => {
+                Some((*decimals, Some(*value), location))
+            }
+            ast::Subject::Identifier(ident, _) => {
+                if let Some(index) = self.input_var_index.get(ident) {
+                    return decimals(self.input_types[*index as usize])
+                        .map(|d| (d, None, location));
+                }
+                match &self.data_table[*self.user_var_index.get(ident)? as usize] {
+                    PactType::Placeholder(_, input_type) => {
+                        decimals(*input_type).map(|d| (d, None, location))
+                    }
+                    value => value
+                        .as_fixed_point()
+                        .map(|(value, decimals)| (decimals, Some(value), location)),
+                }
+            }
+            ast::Subject::Arity(_) => Some((0, None, location)),
+            _ => None,
+        }
+    }
+
+    /// Return the type of `subject` and its location, if it's known at compile time
+    fn subject_type(&self, subject: &ast::Subject) -> Option<(ast::InputType, ast::Location)> {
+        let input_type = match subject {
+            ast::Subject::Value(value, _) => value_type(value),
+            ast::Subject::Identifier(ident, _) => {
+                if let Some(index) = self.input_var_index.get(ident) {
+                    self.input_types[*index as usize]
+                } else {
+                    match &self.data_table[*self.user_var_index.get(ident)? as usize] {
+                        PactType::StringLike(_) => ast::InputType::StringLike,
+                        PactType::Numeric(_) => ast::InputType::Numeric,
+                        PactType::List(_) | PactType::SortedList(_) => ast::InputType::List,
+                        PactType::Bool(_) => ast::InputType::Bool,
+                        PactType::Placeholder(_, input_type) => *input_type,
+                        PactType::FixedPoint { decimals, .. } => {
+                            ast::InputType::FixedPoint(*decimals)
+                        }
+                    }
+                }
+            }
+            ast::Subject::Arity(_) | ast::Subject::Percentage(..) => ast::InputType::Numeric,
+            ast::Subject::Quantified(..) => ast::InputType::Any,
+        };
+        Some((input_type, subject.location()))
+            .filter(|(input_type, _)| *input_type != ast::InputType::Any)
+    }
+
     /// Compile a subject AST node
     fn compile_subject(&mut self, subject: &'a ast::Subject) -> Result<SubjectSource, CompileErr> {
-        // `subject` could be a literal value or an identifier
+        // `subject` could be a literal value, an identifier, the arity, or a quantifier
         // A literal value should be stored in the user data table
         // An identifier should have been declared or it is an error
+        // The arity is loaded from its reserved input register
+        // A quantifier ranges over the input registers from the first
+        // A percentage is only valid on the RHS, where it's compiled by `compile_assertion`
         match subject {
-            ast::Subject::Value(value) => {
-                // convert ast::Value to PactType
-                let v = match value {
-                    ast::Value::Numeric(n) => PactType::Numeric(Numeric(*n)),
-                    ast::Value::StringLike(s) => PactType::StringLike(StringLike(s.as_bytes())),
-                    ast::Value::List(_) => panic!("Invalid subject"),
-                };
-                self.push_to_datatable(v)?;
+            ast::Subject::Percentage(_, _, location) => {
+                Err(CompileErr::MisplacedPercentage(*location))
+            }
+            ast::Subject::Arity(_) => Ok(SubjectSource {
+                load_source: LoadSource::Input,
+                index: ARITY_INDEX,
+            }),
+            ast::Subject::Quantified(_, _) => {
+                self.quantified = true;
+                Ok(SubjectSource {
+                    load_source: LoadSource::Input,
+                    index: 0,
+                })
+            }
+            ast::Subject::Value(value, location) => {
+                self.push_to_datatable(compile_value(value, *location)?)?;
+                let index = (self.data_table.len() as u8) - 1;
+                self.literals.push((index, *location));
                 Ok(SubjectSource {
                     load_source: LoadSource::DataTable,
-                    index: (self.data_table.len() as u8) - 1,
+                    index,
                 })
             }
-            ast::Subject::Identifier(ident) => {
+            ast::Subject::Identifier(ident, location) => {
+                self.used.insert(ident.to_string());
                 // Try lookup this var `ident` in the known input and user data tables
                 if let Some(index) = self.input_var_index.get(ident) {
+                    trace_event!(identifier = %ident, input = *index, "resolved identifier");
                     return Ok(SubjectSource {
                         load_source: LoadSource::Input,
                         index: *index,
                     });
                 }
                 if let Some(index) = self.user_var_index.get(ident) {
+                    trace_event!(identifier = %ident, user = *index, "resolved identifier");
                     return Ok(SubjectSource {
                         load_source: LoadSource::DataTable,
                         index: *index,
                     });
                 }
-                Err(CompileErr::UndeclaredVar(ident.to_string()))
+                Err(CompileErr::UndeclaredVar(ident.to_string(), *location))
             }
         }
     }
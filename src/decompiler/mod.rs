@@ -0,0 +1,503 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Decompiles a pact contract back to pact source for auditing
+//! Input parameters keep the contract's input names if it has usable ones, otherwise they are named `$input<N>`.
+//! Declared input types are kept.
+//! Data table entries are named `$user<N>`.
+//! Data table entries become definitions so recompiling the output yields the same contract.
+//! Ratio comparators are written as percentages and their ratios aren't defined, so they
+//! recompile to the same contract if the ratios follow the other entries, e.g. as compiled.
+//! Clause names are kept if they are distinct valid names.
+//! Metadata is kept, except free-form entries whose keys aren't valid names.
+//! Bytecode may also be disassembled one opcode per line, see `disassemble`.
+//!
+use crate::compiler::MAX_PERCENTAGE_DECIMALS;
+use crate::interpreter::{OpCode, OpComp, OpConj, OpInvert, OpLoad, OpQuant, ARITY_INDEX};
+use crate::types::{
+    Capabilities, Contract, InputType, PactType, StringLike, ValidationErr, MAX_DECIMALS,
+};
+
+/// Decompilation error
+#[derive(Debug, PartialEq)]
+pub enum DecompileErr {
+    /// The contract is invalid
+    Invalid(ValidationErr),
+    /// The data table entry at `index` can't be written as a pact literal
+    UnrepresentableValue(usize),
+    /// The comparator at `offset` has no pact source equivalent e.g. `CALL`
+    UnrepresentableComparator(usize),
+    /// The conjunction at `offset` has no pact source equivalent e.g. `XOR`
+    UnrepresentableConjunction(usize),
+}
+
+impl std::fmt::Display for DecompileErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DecompileErr::Invalid(_) => write!(f, "contract is invalid"),
+            DecompileErr::UnrepresentableValue(index) => write!(
+                f,
+                "data table entry {} can't be written as a pact literal",
+                index
+            ),
+            DecompileErr::UnrepresentableComparator(offset) => write!(
+                f,
+                "comparator at offset {} has no pact source equivalent",
+                offset
+            ),
+            DecompileErr::UnrepresentableConjunction(offset) => write!(
+                f,
+                "conjunction at offset {} has no pact source equivalent",
+                offset
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DecompileErr {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecompileErr::Invalid(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Reconstruct pact source from a compiled `contract`
+pub fn decompile(contract: &Contract) -> Result<String, DecompileErr> {
+    contract
+        .validate(Capabilities::all())
+        .map_err(DecompileErr::Invalid)?;
+
+    let mut source = String::new();
+
+    for (key, value) in contract.metadata().into_iter().flat_map(|m| m.iter()) {
+        let valid_key = key.starts_with(|c: char| c.is_ascii_alphabetic())
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if valid_key {
+            // Metadata values are UTF-8 so they are representable
+            if let Some(literal) =
+                decompile_value(&PactType::StringLike(StringLike(value.as_bytes())))
+            {
+                source.push_str(&format!("meta {} {}\n", key, literal));
+            }
+        }
+    }
+
+    // The grammar requires at least one parameter even if no input is loaded
+    let inputs = input_count(contract.bytecode()).max(1);
+    let params = param_names(contract, inputs);
+    let types = contract.input_types().unwrap_or(&[]);
+    let declarations: Vec<String> = params
+        .iter()
+        .enumerate()
+        .map(|(index, param)| match types.get(index) {
+            Some(InputType::Numeric) => format!("{}: numeric", param),
+            Some(InputType::StringLike) => format!("{}: string", param),
+            Some(InputType::List) => format!("{}: list", param),
+            Some(InputType::Bool) => format!("{}: bool", param),
+            Some(InputType::FixedPoint(decimals)) => format!("{}: decimal({})", param, decimals),
+            Some(InputType::Any) | None => param.clone(),
+        })
+        .collect();
+    source.push_str(&format!("given parameters {}\n", declarations.join(", ")));
+
+    let ratios = ratio_entries(contract.bytecode());
+    for (index, value) in contract.data_table().as_ref().iter().enumerate() {
+        if ratios.get(index) == Some(&true) {
+            continue;
+        }
+        let literal = decompile_value(value).ok_or(DecompileErr::UnrepresentableValue(index))?;
+        source.push_str(&format!("define $user{} as {}\n", index, literal));
+    }
+
+    let clause_names = clause_names(contract);
+    let mut scanner = contract.bytecode().iter();
+    let mut clause_started = false;
+    let mut clause = 0;
+    loop {
+        let offset = contract.bytecode().len() - scanner.len();
+        // The bytecode has been validated so it parses
+        let op = match OpCode::parse(&mut scanner) {
+            Ok(Some(op)) => op,
+            _ => break,
+        };
+        match op {
+            OpCode::COMP(comparator) => {
+                if clause_started {
+                    source.push('\n');
+                    clause += 1;
+                }
+                // The first OpCode of valid bytecode is a comparator
+                if clause_started || offset == 0 {
+                    if let Some((_, name)) = clause_names.iter().find(|(index, _)| *index == clause)
+                    {
+                        source.push_str(&format!("clause {}: ", name));
+                    }
+                }
+                // Ratio comparators are written as a percentage of their RHS
+                let (op, percentage) = match comparator.op {
+                    OpComp::RATIO(ratio) => {
+                        let percentage = format_percentage(
+                            &contract.data_table().as_ref()[ratio.index as usize],
+                        )
+                        .ok_or(DecompileErr::UnrepresentableComparator(offset))?;
+                        (OpComp::from(ratio.order), Some(percentage))
+                    }
+                    op => (op, None),
+                };
+                let phrase = match (op, comparator.invert) {
+                    (OpComp::EQ, OpInvert::NORMAL) => "must be equal to",
+                    (OpComp::EQ, OpInvert::NOT) => "must not be equal to",
+                    (OpComp::GT, OpInvert::NORMAL) => "must be greater than",
+                    (OpComp::GT, OpInvert::NOT) => "must be less than or equal to",
+                    (OpComp::GTE, OpInvert::NORMAL) => "must be greater than or equal to",
+                    (OpComp::GTE, OpInvert::NOT) => "must be less than",
+                    (OpComp::IN, OpInvert::NORMAL) => "must be one of",
+                    (OpComp::IN, OpInvert::NOT) => "must not be one of",
+                    (OpComp::SUBSET, OpInvert::NORMAL) => "must be a subset of",
+                    (OpComp::SUBSET, OpInvert::NOT) => "must not be a subset of",
+                    (OpComp::INTERSECTS, OpInvert::NORMAL) => "must be intersecting with",
+                    (OpComp::INTERSECTS, OpInvert::NOT) => "must not be intersecting with",
+                    (OpComp::CALL, _) | (OpComp::RATIO(_), _) => {
+                        return Err(DecompileErr::UnrepresentableComparator(offset))
+                    }
+                };
+                let input = |index: u8| {
+                    if index == ARITY_INDEX {
+                        "the number of parameters".to_string()
+                    } else {
+                        params[index as usize].clone()
+                    }
+                };
+                let rhs = match comparator.load {
+                    OpLoad::INPUT_VS_USER => format!("$user{}", comparator.indices.rhs),
+                    OpLoad::INPUT_VS_INPUT => input(comparator.indices.rhs),
+                };
+                let rhs = match percentage {
+                    Some(percentage) => format!("{} percent of {}", percentage, rhs),
+                    None => rhs,
+                };
+                // The compiler only emits quantifiers ranging over every input register
+                let lhs = match (comparator.quant, comparator.indices.lhs) {
+                    (OpQuant::ONE, lhs) => input(lhs),
+                    (OpQuant::ALL, 0) => "every parameter".to_string(),
+                    (OpQuant::ANY, 0) => "any parameter".to_string(),
+                    _ => return Err(DecompileErr::UnrepresentableComparator(offset)),
+                };
+                source.push_str(&format!("{} {} {}", lhs, phrase, rhs));
+                clause_started = true;
+            }
+            OpCode::CONJ(conjunction) => {
+                let word = match (conjunction.op, conjunction.invert) {
+                    (OpConj::AND, OpInvert::NORMAL) => "and",
+                    (OpConj::OR, OpInvert::NORMAL) => "or",
+                    _ => return Err(DecompileErr::UnrepresentableConjunction(offset)),
+                };
+                source.push_str(&format!(" {} ", word));
+                clause_started = false;
+            }
+        }
+    }
+    source.push('\n');
+
+    Ok(source)
+}
+
+/// Return the parameter identifiers for `contract`, at least `inputs` of them
+/// Input names are only used if they cover every loaded input, are distinct valid identifiers, and
+/// can't clash with the `$user<N>` definitions
+fn param_names(contract: &Contract, inputs: usize) -> Vec<String> {
+    let usable = |names: &[&str]| {
+        names.len() >= inputs
+            && names.iter().enumerate().all(|(i, name)| {
+                name.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && name.chars().all(|c| c.is_ascii_alphanumeric())
+                    && !(name.len() > 4
+                        && name.starts_with("user")
+                        && name[4..].chars().all(|c| c.is_ascii_digit()))
+                    && !names[..i].contains(name)
+            })
+    };
+    match contract.input_names() {
+        Some(names) if usable(names) => names.iter().map(|name| format!("${}", name)).collect(),
+        _ => (0..inputs).map(|i| format!("$input{}", i)).collect(),
+    }
+}
+
+/// Return the clause names of `contract` if they can all be written as pact clause names
+fn clause_names<'a>(contract: &'a Contract) -> &'a [(usize, &'a str)] {
+    let names = contract.clause_names().unwrap_or(&[]);
+    let usable = names.iter().enumerate().all(|(i, (_, name))| {
+        name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !names[..i].iter().any(|(_, n)| n == name)
+    });
+    if usable {
+        names
+    } else {
+        &[]
+    }
+}
+
+/// Return the number of input parameters referenced by `bytecode`, excluding the arity register
+fn input_count(bytecode: &[u8]) -> usize {
+    let mut scanner = bytecode.iter();
+    let mut count = 0;
+    let mut count_input = |index: u8| {
+        if index != ARITY_INDEX {
+            count = count.max(index as usize + 1);
+        }
+    };
+    while let Ok(Some(op)) = OpCode::parse(&mut scanner) {
+        if let OpCode::COMP(comparator) = op {
+            if comparator.quant == OpQuant::ONE {
+                count_input(comparator.indices.lhs);
+            }
+            if comparator.load == OpLoad::INPUT_VS_INPUT {
+                count_input(comparator.indices.rhs);
+            }
+        }
+    }
+    count
+}
+
+/// Return whether each data table entry is only loaded as the ratio of `RATIO` comparators
+fn ratio_entries(bytecode: &[u8]) -> Vec<bool> {
+    let mut scanner = bytecode.iter();
+    let mut entries = Vec::<Option<bool>>::new();
+    let mut mark = |index: u8, ratio: bool| {
+        let index = index as usize;
+        if entries.len() <= index {
+            entries.resize(index + 1, None);
+        }
+        entries[index] = Some(entries[index].unwrap_or(true) && ratio);
+    };
+    while let Ok(Some(op)) = OpCode::parse(&mut scanner) {
+        if let OpCode::COMP(comparator) = op {
+            if let OpComp::RATIO(ratio) = comparator.op {
+                mark(ratio.index, true);
+            }
+            if comparator.load == OpLoad::INPUT_VS_USER {
+                mark(comparator.indices.rhs, false);
+            }
+        }
+    }
+    entries
+        .into_iter()
+        .map(|entry| entry == Some(true))
+        .collect()
+}
+
+/// Write `value` as a pact literal, if the grammar can express it
+fn decompile_value(value: &PactType) -> Option<String> {
+    match value {
+        PactType::Numeric(n) => Some(n.0.to_string()),
+        PactType::Bool(b) => Some(b.to_string()),
+        PactType::StringLike(s) => {
+            // String literals are UTF-8, with quotes, backslashes and control characters escaped
+            let s = core::str::from_utf8(s.0).ok()?;
+            let mut literal = String::with_capacity(s.len() + 2);
+            literal.push('"');
+            for c in s.chars() {
+                match c {
+                    '"' => literal.push_str("\\\""),
+                    '\\' => literal.push_str("\\\\"),
+                    '\n' => literal.push_str("\\n"),
+                    '\r' => literal.push_str("\\r"),
+                    '\t' => literal.push_str("\\t"),
+                    c if c.is_control() => literal.push_str(&format!("\\u{{{:x}}}", c as u32)),
+                    c => literal.push(c),
+                }
+            }
+            literal.push('"');
+            Some(literal)
+        }
+        // Decompiled definitions are renamed so a placeholder couldn't be bound by its name
+        PactType::Placeholder(..) => None,
+        // Fixed point literals have at least one decimal
+        PactType::FixedPoint { value, decimals } if *decimals > 0 && *decimals <= MAX_DECIMALS => {
+            Some(format_fixed_point(*value, *decimals))
+        }
+        PactType::FixedPoint { .. } => None,
+        PactType::List(list) | PactType::SortedList(list) => {
+            // Lists must be non-empty and contain only strings or only integers
+            let first = list.first()?;
+            let homogeneous = list.iter().all(|v| {
+                matches!(
+                    (v, first),
+                    (PactType::Numeric(_), PactType::Numeric(_))
+                        | (PactType::StringLike(_), PactType::StringLike(_))
+                )
+            });
+            if !homogeneous {
+                return None;
+            }
+            let elements = list
+                .iter()
+                .map(decompile_value)
+                .collect::<Option<Vec<String>>>()?;
+            Some(format!("[{}]", elements.join(", ")))
+        }
+    }
+}
+
+/// Render bytecode as one line per opcode, stopping at the first malformed byte
+pub fn disassemble(bytecode: &[u8]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut scanner = bytecode.iter();
+    let mut offset = 0;
+    loop {
+        let remaining = scanner.len();
+        match OpCode::parse(&mut scanner) {
+            Ok(Some(op)) => lines.push(format!(
+                "{:04x}  {:<32}; {}",
+                offset,
+                format_op(op),
+                op.describe()
+            )),
+            Ok(None) => break,
+            Err(err) => {
+                lines.push(format!("{:04x}  <invalid: {:?}>", offset, err));
+                break;
+            }
+        }
+        offset += remaining - scanner.len();
+    }
+    lines
+}
+
+/// Render an opcode in assembly form e.g. `COMP LT input[0] user[0]`
+pub fn format_op(op: OpCode) -> String {
+    match op {
+        OpCode::COMP(comp) => {
+            let name = match comp.op {
+                OpComp::EQ => "EQ",
+                OpComp::GT => "GT",
+                OpComp::GTE => "GTE",
+                OpComp::IN => "IN",
+                OpComp::CALL => "CALL",
+                OpComp::SUBSET => "SUBSET",
+                OpComp::INTERSECTS => "INTERSECTS",
+                OpComp::RATIO(_) => "RATIO",
+            };
+            let input = |index: u8| {
+                if index == ARITY_INDEX {
+                    "ARITY".to_string()
+                } else {
+                    format!("input[{}]", index)
+                }
+            };
+            let rhs = match comp.load {
+                OpLoad::INPUT_VS_USER => format!("user[{}]", comp.indices.rhs),
+                OpLoad::INPUT_VS_INPUT => input(comp.indices.rhs),
+            };
+            let lhs = match comp.quant {
+                OpQuant::ONE => input(comp.indices.lhs),
+                OpQuant::ALL => format!("ALL input[{}..]", comp.indices.lhs),
+                OpQuant::ANY => format!("ANY input[{}..]", comp.indices.lhs),
+            };
+            format!(
+                "COMP {}{} {} {}",
+                if comp.invert == OpInvert::NOT {
+                    "NOT "
+                } else {
+                    ""
+                },
+                name,
+                lhs,
+                rhs
+            )
+        }
+        OpCode::CONJ(conj) => {
+            let name = match conj.op {
+                OpConj::AND => "AND",
+                OpConj::OR => "OR",
+                OpConj::XOR => "XOR",
+            };
+            format!(
+                "CONJ {}{}",
+                if conj.invert == OpInvert::NOT {
+                    "NOT "
+                } else {
+                    ""
+                },
+                name
+            )
+        }
+    }
+}
+
+/// Render a value for display, with strings quoted and invalid UTF-8 as hex
+pub fn format_value(value: &PactType) -> String {
+    match value {
+        PactType::Numeric(n) => n.0.to_string(),
+        PactType::Bool(b) => b.to_string(),
+        PactType::StringLike(s) => match core::str::from_utf8(s.0) {
+            Ok(s) => format!("{:?}", s),
+            Err(_) => {
+                s.0.iter()
+                    .fold("0x".to_string(), |acc, b| format!("{}{:02x}", acc, b))
+            }
+        },
+        PactType::List(l) | PactType::SortedList(l) => format!(
+            "[{}]",
+            l.iter().map(format_value).collect::<Vec<_>>().join(", ")
+        ),
+        PactType::Placeholder(name, _) => format!("placeholder ${}", name),
+        PactType::FixedPoint { value, decimals } => format_fixed_point(*value, *decimals),
+    }
+}
+
+/// Write a ratio data table entry as a pact percentage e.g. `[1, 200]` as `0.5`
+/// Percentages must be representable with at most `MAX_PERCENTAGE_DECIMALS` decimals.
+fn format_percentage(ratio: &PactType) -> Option<String> {
+    let (numerator, denominator) = match ratio {
+        PactType::List(ratio) | PactType::SortedList(ratio) => match ratio.as_slice() {
+            [PactType::Numeric(numerator), PactType::Numeric(denominator)]
+                if denominator.0 != 0 =>
+            {
+                (u128::from(numerator.0), u128::from(denominator.0))
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    (0..=MAX_PERCENTAGE_DECIMALS).find_map(|decimals| {
+        let scaled = numerator * 100 * 10_u128.pow(u32::from(decimals));
+        match (scaled / denominator, scaled % denominator) {
+            (percentage, 0) if percentage <= u128::from(u64::MAX) => {
+                Some(format_fixed_point(percentage, decimals))
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Render a fixed point as a decimal e.g. `1.50`
+fn format_fixed_point(value: u128, decimals: u8) -> String {
+    let decimals = usize::from(decimals);
+    let digits = format!("{:0>width$}", value, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! An adapter from doughnut permission domains to contract verdicts
+//! A `PactDomain` finds a contract blob in a doughnut's domain payload, converts the method
+//! arguments into input registers, and verifies the contract with a `Verifier`.
+//! The doughnut crate isn't a dependency, hosts implement `Doughnut` for their doughnut type.
+//!
+use crate::types::traits::IntoPactTable;
+use crate::verifier::{Decision, PactErr, Verifier};
+use core::fmt;
+
+/// A doughnut certificate's permission domains
+/// Implementations usually delegate to the doughnut crate's `DoughnutApi::get_domain`.
+pub trait Doughnut {
+    /// Return the payload of the domain called `domain`, if the doughnut has one
+    fn get_domain(&self, domain: &str) -> Option<&[u8]>;
+}
+
+/// A doughnut verification error
+#[cfg_attr(feature = "std", derive(Debug, PartialEq))]
+pub enum DoughnutErr {
+    /// The doughnut has no domain with the adapter's name
+    MissingDomain,
+    /// The domain payload has no contract blob
+    MissingContract,
+    /// A method argument can't be converted into a `PactType`
+    InvalidArguments,
+    /// The contract can't be verified
+    Verify(PactErr),
+}
+
+impl fmt::Display for DoughnutErr {
+    /// Wrapped errors are not repeated, see `source`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DoughnutErr::MissingDomain => write!(f, "doughnut has no pact domain"),
+            DoughnutErr::MissingContract => write!(f, "domain payload has no contract"),
+            DoughnutErr::InvalidArguments => {
+                write!(f, "method arguments can't be converted to pact types")
+            }
+            DoughnutErr::Verify(_) => write!(f, "contract can't be verified"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DoughnutErr {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DoughnutErr::Verify(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// A doughnut permission domain holding a pact contract
+/// By default the whole domain payload is the contract blob, see `with_extractor`.
+#[derive(Clone, Copy)]
+pub struct PactDomain<'n> {
+    name: &'n str,
+    extractor: fn(&[u8]) -> Option<&[u8]>,
+    verifier: Verifier,
+}
+
+impl<'n> PactDomain<'n> {
+    /// Return an adapter for the domain called `name`, verifying with the default `Verifier`
+    pub fn new(name: &'n str) -> Self {
+        PactDomain {
+            name,
+            extractor: |payload| Some(payload),
+            verifier: Verifier::default(),
+        }
+    }
+    /// Find the contract blob within the domain payload with `extractor`
+    /// e.g. for payloads which hold permissions alongside a contract
+    pub fn with_extractor(mut self, extractor: fn(&[u8]) -> Option<&[u8]>) -> Self {
+        self.extractor = extractor;
+        self
+    }
+    /// Verify contracts with `verifier`, e.g. to limit capabilities or contract length
+    pub fn with_verifier(mut self, verifier: Verifier) -> Self {
+        self.verifier = verifier;
+        self
+    }
+    /// Return the contract blob in `doughnut`'s domain
+    pub fn contract<'d, D: Doughnut>(&self, doughnut: &'d D) -> Result<&'d [u8], DoughnutErr> {
+        let payload = doughnut
+            .get_domain(self.name)
+            .ok_or(DoughnutErr::MissingDomain)?;
+        (self.extractor)(payload).ok_or(DoughnutErr::MissingContract)
+    }
+    /// Verify the contract in `doughnut`'s domain against a method's `arguments` e.g. a tuple,
+    /// which become the contract's input registers in order
+    pub fn verify<'i, D, A, I>(&self, doughnut: &D, arguments: A) -> Result<Decision, DoughnutErr>
+    where
+        D: Doughnut,
+        A: IntoPactTable<'i, I>,
+    {
+        let blob = self.contract(doughnut)?;
+        let inputs = arguments
+            .into_pact_table()
+            .map_err(|_| DoughnutErr::InvalidArguments)?;
+        self.verifier
+            .verify(blob, &inputs)
+            .map_err(DoughnutErr::Verify)
+    }
+}
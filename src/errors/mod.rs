@@ -0,0 +1,260 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Source annotated rendering of parse and compile errors
+//! Shows the offending line with a caret under the bad token, similar to rustc output
+//!
+use crate::compiler::{CompileErr, Warning};
+use crate::parser::{ast::Location, Rule};
+use pest::error::{Error, ErrorVariant, LineColLocation};
+use std::fmt;
+
+/// A parse or compile error rendered against the pact source it came from
+/// `Display` writes the message, then the location and source snippet when known
+pub struct Report<'a> {
+    source: &'a str,
+    path: Option<&'a str>,
+    message: String,
+    location: Option<Location>,
+    // Number of columns to underline from `location`
+    width: usize,
+}
+
+impl<'a> Report<'a> {
+    /// Create a report for a parser error in `source`
+    pub fn parse(source: &'a str, err: &Error<Rule>) -> Self {
+        let message = match &err.variant {
+            ErrorVariant::ParsingError {
+                positives,
+                negatives,
+            } => match (negatives.is_empty(), positives.is_empty()) {
+                (false, false) => format!(
+                    "unexpected {}; expected {}",
+                    enumerate(negatives),
+                    enumerate(positives)
+                ),
+                (false, true) => format!("unexpected {}", enumerate(negatives)),
+                (true, false) => format!("expected {}", enumerate(positives)),
+                (true, true) => "unknown parsing error".into(),
+            },
+            ErrorVariant::CustomError { message } => message.clone(),
+        };
+        let (location, width) = match err.line_col {
+            LineColLocation::Pos((line, column)) => (Location { line, column }, 1),
+            LineColLocation::Span((line, column), (end_line, end_column)) => {
+                let location = Location { line, column };
+                if end_line == line && end_column > column {
+                    (location, end_column - column)
+                } else {
+                    (location, token_width(source, location))
+                }
+            }
+        };
+        Report {
+            source,
+            path: None,
+            message,
+            location: Some(location),
+            width,
+        }
+    }
+
+    /// Create a report for a compiler error in `source`
+    pub fn compile(source: &'a str, err: &CompileErr) -> Self {
+        let location = err.location();
+        Report {
+            source,
+            path: None,
+            message: err.to_string(),
+            location,
+            width: location.map_or(0, |l| token_width(source, l)),
+        }
+    }
+
+    /// Create a report for a compiler warning in `source`
+    pub fn warning(source: &'a str, warning: &Warning) -> Self {
+        let location = warning.location();
+        Report {
+            source,
+            path: None,
+            message: warning.to_string(),
+            location,
+            width: location.map_or(0, |l| token_width(source, l)),
+        }
+    }
+
+    /// Name the source file in the rendered location
+    pub fn with_path(mut self, path: &'a str) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    /// Return the message, without the location or source snippet
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Return where the error starts in the source, if known
+    pub fn location(&self) -> Option<Location> {
+        self.location
+    }
+
+    /// Return the number of columns the error spans from its location
+    pub fn width(&self) -> usize {
+        self.width
+    }
+}
+
+impl<'a> fmt::Display for Report<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        let location = match self.location {
+            Some(location) => location,
+            None => {
+                if let Some(path) = self.path {
+                    write!(f, "\n --> {}", path)?;
+                }
+                return Ok(());
+            }
+        };
+        let line = self
+            .source
+            .lines()
+            .nth(location.line.saturating_sub(1))
+            .unwrap_or("");
+        let gutter = " ".repeat(location.line.to_string().len());
+        match self.path {
+            Some(path) => write!(
+                f,
+                "\n{}--> {}:{}:{}",
+                gutter, path, location.line, location.column
+            )?,
+            None => write!(f, "\n{}--> {}:{}", gutter, location.line, location.column)?,
+        }
+        write!(f, "\n{} |", gutter)?;
+        write!(f, "\n{} | {}", location.line, line)?;
+        write!(
+            f,
+            "\n{} | {}{}",
+            gutter,
+            " ".repeat(location.column.saturating_sub(1)),
+            "^".repeat(self.width.max(1))
+        )
+    }
+}
+
+/// Join rule names as a human readable list e.g. "a, b, or c"
+fn enumerate(rules: &[Rule]) -> String {
+    let names: Vec<String> = rules.iter().map(|r| format!("{:?}", r)).collect();
+    match names.len() {
+        1 => names[0].clone(),
+        2 => format!("{} or {}", names[0], names[1]),
+        l => format!("{}, or {}", names[..l - 1].join(", "), names[l - 1]),
+    }
+}
+
+/// Return the width of the token starting at `location` in `source`
+/// String and list literals are measured to their closing delimiter
+fn token_width(source: &str, location: Location) -> usize {
+    let token: Vec<char> = source
+        .lines()
+        .nth(location.line.saturating_sub(1))
+        .unwrap_or("")
+        .chars()
+        .skip(location.column.saturating_sub(1))
+        .collect();
+    let close = match token.first() {
+        Some('"') => '"',
+        Some('[') => ']',
+        Some(_) => return token.iter().take_while(|c| !c.is_whitespace()).count(),
+        None => return 1,
+    };
+    // Escaped characters in string literals can't close them
+    let mut escaped = false;
+    token
+        .iter()
+        .skip(1)
+        .position(|c| {
+            let closes = *c == close && !escaped;
+            escaped = !escaped && *c == '\\';
+            closes
+        })
+        .map_or(token.len(), |end| end + 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compiler, parser};
+
+    #[test]
+    fn it_renders_a_compile_error() {
+        let source = "given parameters $a\n$a must be equal to \"hello world\" and\n  $a must be less than $b";
+        let ast = parser::parse(source).unwrap();
+        let err = compiler::compile(&ast).unwrap_err();
+        assert_eq!(
+            Report::compile(source, &err)
+                .with_path("contract.pact")
+                .to_string(),
+            "undeclared variable `$b`
+ --> contract.pact:3:24
+  |
+3 |   $a must be less than $b
+  |                        ^^"
+        );
+    }
+
+    #[test]
+    fn it_renders_a_warning() {
+        let source = "given parameters $a\ndefine $max as 7\n$a must be less than 7";
+        let ast = parser::parse(source).unwrap();
+        let (_, warnings) = compiler::compile_with_warnings(&ast).unwrap();
+        assert_eq!(
+            Report::warning(source, &warnings[1]).to_string(),
+            "literal has the same value as `$max`
+ --> 3:22
+  |
+3 | $a must be less than 7
+  |                      ^"
+        );
+    }
+
+    #[test]
+    fn it_renders_a_parse_error() {
+        let source = "given parameters $a\n$a must be equal to 18446744073709551616";
+        let err = parser::parse(source).unwrap_err();
+        assert_eq!(
+            Report::parse(source, &err).to_string(),
+            "integer literal does not fit in 64 bits
+ --> 2:21
+  |
+2 | $a must be equal to 18446744073709551616
+  |                     ^^^^^^^^^^^^^^^^^^^^"
+        );
+    }
+
+    #[test]
+    fn it_measures_tokens() {
+        let source = "$a must be equal to \"hello world\" or [1, 2]";
+        let at = |column| Location { line: 1, column };
+        assert_eq!(token_width(source, at(1)), 2);
+        assert_eq!(token_width(source, at(21)), 13);
+        assert_eq!(token_width(source, at(38)), 6);
+        let source = r#"$a must be equal to "say \"hi\"\\" or 5"#;
+        assert_eq!(token_width(source, at(21)), 14);
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! A minimal C API for non-Rust hosts, declared in `include/pact.h`
+//! Build a shared library with: `cargo rustc --release --lib --features ffi --crate-type cdylib`
+//! Regenerate the header with: `cbindgen --config cbindgen.toml --output include/pact.h`
+//!
+//! Errors are reported through return codes, with a message available from `pact_last_error`.
+//! Panics are caught and reported as errors so they never unwind across the FFI boundary.
+//!
+// `const` thread local initializers are not available on our minimum supported Rust
+#![allow(clippy::missing_const_for_thread_local)]
+use crate::types::{Contract, PactType};
+use crate::{compiler, interpreter, json, parser};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice};
+
+/// The call succeeded
+pub const PACT_OK: i32 = 0;
+/// The contract rejected the inputs
+pub const PACT_REJECTED: i32 = 1;
+/// The call failed, see `pact_last_error`
+pub const PACT_ERROR: i32 = -1;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// Record `message` as this thread's last error and return `PACT_ERROR`
+fn fail(message: String) -> i32 {
+    // Interior NUL bytes can't be represented in a C string
+    let message = CString::new(message.replace('\0', "\\0")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+    PACT_ERROR
+}
+
+/// Run `f` clearing the last error first, converting any error or panic into `PACT_ERROR`
+fn guard<F: FnOnce() -> Result<i32, String>>(f: F) -> i32 {
+    LAST_ERROR.with(|e| *e.borrow_mut() = None);
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(code)) => code,
+        Ok(Err(message)) => fail(message),
+        Err(_) => fail("internal error: panicked".into()),
+    }
+}
+
+/// Read a NUL terminated UTF-8 string
+unsafe fn read_str<'a>(s: *const c_char, name: &str) -> Result<&'a str, String> {
+    if s.is_null() {
+        return Err(format!("{} is null", name));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| format!("{} is not valid UTF-8", name))
+}
+
+/// Compile pact `source` into the v0 binary format, written to `out`
+/// On success `*out_len` is set to the encoded length and `PACT_OK` is returned.
+/// If `out_cap` is too small `*out_len` is set to the required length and `PACT_ERROR` is returned.
+///
+/// # Safety
+/// `source` must be a NUL terminated string, `out` must be valid for `out_cap` bytes
+/// and `out_len` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn pact_compile(
+    source: *const c_char,
+    out: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> i32 {
+    guard(|| {
+        let source = read_str(source, "source")?;
+        if out_len.is_null() || (out.is_null() && out_cap > 0) {
+            return Err("output pointer is null".into());
+        }
+        let ast = parser::parse(source).map_err(|err| err.to_string())?;
+        let contract = compiler::compile(&ast).map_err(|err| err.to_string())?;
+        let mut buf = Vec::<u8>::default();
+        contract
+            .encode(&mut buf)
+            .map_err(|err| format!("{:?}", err))?;
+
+        *out_len = buf.len();
+        if buf.len() > out_cap {
+            return Err(format!(
+                "output buffer too small: {} bytes required",
+                buf.len()
+            ));
+        }
+        ptr::copy_nonoverlapping(buf.as_ptr(), out, buf.len());
+        Ok(PACT_OK)
+    })
+}
+
+/// Interpret a binary `contract` against `inputs`, a JSON array of input registers
+/// e.g. `[5, "alice", ["bob"]]`
+/// Returns `PACT_OK` if the contract was upheld, `PACT_REJECTED` if not, or `PACT_ERROR`.
+/// Inputs which don't match the contract's input schema are an error.
+///
+/// # Safety
+/// `contract` must be valid for `contract_len` bytes and `inputs` must be a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pact_interpret(
+    contract: *const u8,
+    contract_len: usize,
+    inputs: *const c_char,
+) -> i32 {
+    guard(|| {
+        if contract.is_null() {
+            return Err("contract is null".into());
+        }
+        let blob = slice::from_raw_parts(contract, contract_len);
+        let contract = Contract::decode(blob).map_err(|err| format!("{:?}", err))?;
+        let inputs =
+            json::parse_inputs(read_str(inputs, "inputs")?).map_err(|err| format!("{:?}", err))?;
+        let input_table: Vec<PactType> = inputs.iter().map(json::Value::as_pact).collect();
+        contract
+            .check_inputs(&input_table)
+            .map_err(|err| format!("{:?}", err))?;
+
+        match interpreter::interpret(
+            &input_table,
+            contract.data_table().as_ref(),
+            contract.bytecode(),
+        ) {
+            Ok(true) => Ok(PACT_OK),
+            Ok(false) => Ok(PACT_REJECTED),
+            Err(err) => Err(format!("{:?}", err)),
+        }
+    })
+}
+
+/// Return the calling thread's last error message, or null if the last call succeeded
+/// The string is owned by the library and valid until the next `pact_*` call on this thread.
+#[no_mangle]
+pub extern "C" fn pact_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| match &*e.borrow() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
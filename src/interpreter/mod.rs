@@ -17,12 +17,48 @@
 //!
 //! The pact bytecode interpreter
 //!
-use crate::types::PactType;
+use crate::types::{
+    compare_fixed_point, compare_sorted, Capabilities, Contract, Numeric, PactType,
+};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
 
 pub use crate::types::opcode::{
-    Comparator, Conjunction, OpCode, OpComp, OpConj, OpIndices, OpLoad,
+    Comparator, Conjunction, OpCode, OpCodeErr, OpCodes, OpComp, OpConj, OpIndices, OpInvert,
+    OpLoad, OpOrder, OpQuant, OpRatio, Visitor, ARITY_INDEX, MAX_INDEX,
 };
 
+/// The largest arity register value, loaded when there are this many input registers or more
+/// Contracts declare at most 15 input parameters so larger arities are never distinguished.
+const MAX_ARITY: usize = 16;
+
+/// The most input registers a quantified comparator ranges over
+/// This bounds the cost of a quantified comparator to that of as many unquantified ones.
+const MAX_QUANTIFIED: usize = ARITY_INDEX as usize;
+
+/// Arity register values, so loaded arities can be borrowed like other registers
+static ARITIES: [PactType<'static>; MAX_ARITY + 1] = [
+    PactType::Numeric(Numeric(0)),
+    PactType::Numeric(Numeric(1)),
+    PactType::Numeric(Numeric(2)),
+    PactType::Numeric(Numeric(3)),
+    PactType::Numeric(Numeric(4)),
+    PactType::Numeric(Numeric(5)),
+    PactType::Numeric(Numeric(6)),
+    PactType::Numeric(Numeric(7)),
+    PactType::Numeric(Numeric(8)),
+    PactType::Numeric(Numeric(9)),
+    PactType::Numeric(Numeric(10)),
+    PactType::Numeric(Numeric(11)),
+    PactType::Numeric(Numeric(12)),
+    PactType::Numeric(Numeric(13)),
+    PactType::Numeric(Numeric(14)),
+    PactType::Numeric(Numeric(15)),
+    PactType::Numeric(Numeric(16)),
+];
+
 /// Interpret some pact byte code (`source`) with input data registers (`input_data`) and
 /// user data registers (`user_data`).
 /// Returns a boolean indicating whether the pact contract was validated or not,
@@ -32,26 +68,629 @@ pub fn interpret(
     user_data: &[PactType],
     source: &[u8],
 ) -> Result<bool, InterpErr> {
-    let mut interpreter = Interpreter::new(input_data, user_data);
-    let mut scanner = source.iter();
-    while let Some(op) = OpCode::parse(&mut scanner)? {
-        match interpreter.interpret(op) {
-            Err(InterpErr::Refused) => break,
-            Err(err) => return Err(err),
-            Ok(_) => {}
+    match interpret_verbose(input_data, user_data, source)? {
+        Outcome::Upheld => Ok(true),
+        Outcome::Rejected { .. } => Ok(false),
+    }
+}
+
+/// Interpret some pact byte code (`source`) against each of many input data register sets
+/// (`inputs`) with the same user data registers (`user_data`), returning each verdict in order
+/// `source` is decoded once rather than per input set, so this is faster than calling `interpret`
+/// in a loop e.g. a block producer checking one pact against many candidate transactions.
+/// Each verdict is the same as `interpret` would return for its input set.
+pub fn interpret_batch(
+    inputs: &[&[PactType]],
+    user_data: &[PactType],
+    source: &[u8],
+) -> Vec<Result<bool, InterpErr>> {
+    let program = Program::decode(source);
+    inputs
+        .iter()
+        .map(|input_data| program.execute(input_data, user_data))
+        .collect()
+}
+
+/// Interpret some pact byte code against each of many input data register sets like
+/// `interpret_batch`, spreading the input sets across rayon's thread pool
+/// `source` is decoded once and shared by every thread e.g. an off-chain indexer checking a
+/// pact against historical transactions.
+#[cfg(feature = "parallel")]
+pub fn interpret_batch_par(
+    inputs: &[&[PactType]],
+    user_data: &[PactType],
+    source: &[u8],
+) -> Vec<Result<bool, InterpErr>> {
+    use rayon::prelude::*;
+    let program = Program::decode(source);
+    inputs
+        .par_iter()
+        .map(|input_data| program.execute(input_data, user_data))
+        .collect()
+}
+
+/// Interpret a `Program` with input data registers (`input_data`) and user data registers
+/// (`user_data`) like `interpret`
+/// The byte code was validated when the program was loaded, so this only fails on the inputs
+/// e.g. `InterpErr::MissingIndex`, and doesn't allocate.
+pub fn interpret_program(
+    program: &Program,
+    input_data: &[PactType],
+    user_data: &[PactType],
+) -> Result<bool, InterpErr> {
+    program.execute(input_data, user_data)
+}
+
+/// Interpret `contract` with input values given by parameter name rather than position
+/// Names are as declared by `given parameters`, without the `$` sigil e.g. `("amount", value)`.
+/// This guards against callers supplying inputs in a different order than the contract expects.
+pub fn interpret_named(
+    contract: &Contract,
+    inputs: &[(&str, PactType)],
+) -> Result<bool, InterpErr> {
+    check_features(contract, Capabilities::all())?;
+    let names = contract.input_names().ok_or(InterpErr::UnnamedInputs)?;
+    let mut positional: Vec<Option<PactType>> = names.iter().map(|_| None).collect();
+    for (index, (name, value)) in inputs.iter().enumerate() {
+        match names.iter().position(|n| n == name) {
+            Some(position) if positional[position].is_none() => {
+                positional[position] = Some(value.clone())
+            }
+            // An unknown or repeated name is most likely a caller mistake
+            _ => return Err(InterpErr::UnexpectedInput(index)),
         }
     }
+    let input_data = positional
+        .into_iter()
+        .enumerate()
+        .map(|(position, value)| value.ok_or(InterpErr::MissingInput(position as u8)))
+        .collect::<Result<Vec<PactType>, InterpErr>>()?;
+
+    interpret(
+        &input_data,
+        contract.data_table().as_ref(),
+        contract.bytecode(),
+    )
+}
+
+/// Interpret only the clause of `contract` named `name` with input data registers `input_data`
+/// Hosts use this to evaluate the rule relevant to an action, rather than every clause.
+pub fn interpret_clause(
+    contract: &Contract,
+    name: &str,
+    input_data: &[PactType],
+) -> Result<bool, InterpErr> {
+    check_features(contract, Capabilities::all())?;
+    let clause = contract.clause(name).ok_or(InterpErr::UnknownClause)?;
+    interpret(input_data, contract.data_table().as_ref(), clause)
+}
+
+/// Interpret every clause of `contract` separately with input data registers `input_data`
+/// Returns each clause's name, if it has one, and whether it holds, in bytecode order.
+/// The contract holds iff every clause holds.
+pub fn interpret_clauses<'a>(
+    contract: &Contract<'a>,
+    input_data: &[PactType],
+) -> Result<Vec<(Option<&'a str>, bool)>, InterpErr> {
+    check_features(contract, Capabilities::all())?;
+    contract
+        .clauses()
+        .into_iter()
+        .map(|(name, clause)| {
+            interpret(input_data, contract.data_table().as_ref(), clause).map(|holds| (name, holds))
+        })
+        .collect()
+}
+
+/// Interpret `contract` like an interpreter supporting only `features` would
+/// e.g. `Capabilities::supported_by(version)` for an older crate version.
+/// Fails with `InterpErr::UnsupportedFeature` before execution if the contract requires other
+/// features, including features declared by a later crate version, see
+/// `Contract::required_features`.
+pub fn interpret_with_features(
+    contract: &Contract,
+    input_data: &[PactType],
+    features: Capabilities,
+) -> Result<bool, InterpErr> {
+    check_features(contract, features)?;
+    interpret(
+        input_data,
+        contract.data_table().as_ref(),
+        contract.bytecode(),
+    )
+}
+
+/// Fail if `contract` requires features outside `features`
+fn check_features(contract: &Contract, features: Capabilities) -> Result<(), InterpErr> {
+    let missing = contract.required_features().difference(features);
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(InterpErr::UnsupportedFeature(missing))
+    }
+}
+
+/// Interpret some pact byte code like `interpret`, resolving `CALL` comparators with `context`
+pub fn interpret_with_context(
+    input_data: &[PactType],
+    user_data: &[PactType],
+    source: &[u8],
+    context: &dyn HostContext,
+) -> Result<bool, InterpErr> {
+    match run(
+        input_data,
+        user_data,
+        source,
+        context,
+        Limits::default(),
+        &mut (),
+    )? {
+        Outcome::Upheld => Ok(true),
+        Outcome::Rejected { .. } => Ok(false),
+    }
+}
 
-    match interpreter.state {
-        State::AssertionTrue => Ok(true),
-        State::Failed | State::AssertionFalse => Ok(false),
-        // Any other state is an Unexpected end of input
-        _invalid => Err(InterpErr::UnexpectedEOI("incomplete operation")),
+/// Interpret some pact byte code like `interpret`, failing byte code and comparisons which exceed
+/// `limits`
+/// Hosts which don't bound input sizes upstream use this to keep execution within priced costs.
+pub fn interpret_with_limits(
+    input_data: &[PactType],
+    user_data: &[PactType],
+    source: &[u8],
+    limits: Limits,
+) -> Result<bool, InterpErr> {
+    match run(input_data, user_data, source, &(), limits, &mut ())? {
+        Outcome::Upheld => Ok(true),
+        Outcome::Rejected { .. } => Ok(false),
     }
 }
 
+/// Interpret some pact byte code like `interpret`, reporting which assertion rejected the contract
+/// The rejecting assertion is the last comparator in the failed clause which evaluated false,
+/// or the clause's final comparator when none did e.g. `XOR` of two true assertions.
+pub fn interpret_verbose<'a>(
+    input_data: &'a [PactType<'a>],
+    user_data: &'a [PactType<'a>],
+    source: &[u8],
+) -> Result<Outcome<'a>, InterpErr> {
+    interpret_observed(input_data, user_data, source, &mut ())
+}
+
+/// Interpret some pact byte code like `interpret_verbose`, reporting each assertion and clause
+/// evaluation to `observer` as it happens
+pub fn interpret_observed<'a>(
+    input_data: &'a [PactType<'a>],
+    user_data: &'a [PactType<'a>],
+    source: &[u8],
+    observer: &mut dyn Observer<'a>,
+) -> Result<Outcome<'a>, InterpErr> {
+    run(
+        input_data,
+        user_data,
+        source,
+        &(),
+        Limits::default(),
+        observer,
+    )
+}
+
+/// Interpret `source` with host `context` within `limits`, reporting to `observer`
+pub(crate) fn run<'a>(
+    input_data: &'a [PactType<'a>],
+    user_data: &'a [PactType<'a>],
+    source: &[u8],
+    context: &'a dyn HostContext,
+    limits: Limits,
+    observer: &mut dyn Observer<'a>,
+) -> Result<Outcome<'a>, InterpErr> {
+    let mut executor =
+        Executor::with_context(input_data, user_data, source, context).with_limits(limits);
+    let mut clause_offset = 0;
+    // Candidate rejections within the current clause
+    let mut last_false: Option<Outcome<'a>> = None;
+    let mut last: Option<Outcome<'a>> = None;
+    // Assertions are traced within a span for their clause
+    #[cfg(feature = "trace")]
+    let mut clause_span = tracing::Span::none();
+    loop {
+        let state = executor.state();
+        // A comparator without a conjunction starts a new clause
+        let starts_clause = matches!(state, State::Initial | State::AssertionTrue);
+        let previous_upheld = matches!(state, State::AssertionTrue);
+        let (opcode_offset, op) = match executor.step()? {
+            Some(step) => step,
+            None => break,
+        };
+        if let (OpCode::COMP(_), true) = (op, starts_clause) {
+            if previous_upheld {
+                observer.clause(clause_offset, true);
+            }
+            clause_offset = opcode_offset;
+            last_false = None;
+            last = None;
+            #[cfg(feature = "trace")]
+            {
+                clause_span = tracing::debug_span!("clause", offset = clause_offset);
+            }
+        }
+        if let State::Failed = executor.state() {
+            observer.clause(clause_offset, false);
+        }
+        if let Some(assertion) = executor.interpreter.last_assertion.take() {
+            #[cfg(feature = "trace")]
+            let _entered = clause_span.enter();
+            trace_event!(
+                offset = opcode_offset,
+                opcode = u8::from(OpCode::COMP(assertion.comparator)),
+                lhs = assertion.comparator.indices.lhs,
+                rhs = assertion.comparator.indices.rhs,
+                result = assertion.result,
+                "evaluated assertion"
+            );
+            observer.assertion(&AssertionEvent {
+                opcode_offset,
+                comparator: assertion.comparator,
+                lhs: assertion.lhs,
+                rhs: assertion.rhs,
+                result: assertion.result,
+                list_scan: assertion.list_scan,
+            });
+            let rejection = Outcome::Rejected {
+                opcode_offset,
+                comparator: assertion.comparator,
+                lhs: assertion.lhs,
+                rhs: assertion.rhs,
+            };
+            if !assertion.result {
+                last_false = Some(rejection.clone());
+            }
+            last = Some(rejection);
+        }
+    }
+
+    match executor.verdict()? {
+        true => {
+            observer.clause(clause_offset, true);
+            Ok(Outcome::Upheld)
+        }
+        false => {
+            if let State::AssertionFalse = executor.state() {
+                observer.clause(clause_offset, false);
+            }
+            last_false
+                .or(last)
+                .ok_or(InterpErr::UnexpectedEOI("incomplete operation"))
+        }
+    }
+}
+
+/// Validated, decoded pact byte code for repeated execution, see `interpret_program`
+/// `Program::try_from(source)` fails with any malformed OpCode or incomplete operation in
+/// `source`, so executing a program only fails on its inputs e.g. a missing register.
+/// Byte code `interpret` would reject for some inputs but not others is rejected when loaded.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, PartialEq)]
+pub struct Program {
+    opcodes: Vec<OpCode>,
+    /// The error decoding the OpCode after `opcodes`, raised only when execution reaches it
+    error: Option<InterpErr>,
+}
+
+impl Program {
+    /// Decode `source` upto its first malformed OpCode, deferring its error to execution
+    fn decode(source: &[u8]) -> Self {
+        let mut opcodes = Vec::new();
+        for op in OpCodes::new(source) {
+            match op {
+                Ok((_offset, op)) => opcodes.push(op),
+                Err(err) => {
+                    return Program {
+                        opcodes,
+                        error: Some(err),
+                    }
+                }
+            }
+        }
+        Program {
+            opcodes,
+            error: None,
+        }
+    }
+
+    /// Return the program's OpCodes in execution order
+    pub fn opcodes(&self) -> &[OpCode] {
+        &self.opcodes
+    }
+
+    /// Execute the OpCodes like `Executor` would, returning the verdict
+    fn execute<'a>(
+        &self,
+        input_data: &'a [PactType<'a>],
+        user_data: &'a [PactType<'a>],
+    ) -> Result<bool, InterpErr> {
+        let mut interpreter = Interpreter::new(input_data, user_data);
+        for op in &self.opcodes {
+            match interpreter.interpret(*op) {
+                Ok(()) => {}
+                // A failed clause decides the verdict, later OpCodes are never reached
+                Err(InterpErr::Refused) => return Ok(false),
+                Err(err) => return Err(err),
+            }
+        }
+        if let Some(err) = &self.error {
+            return Err(err.clone());
+        }
+        match interpreter.state() {
+            State::AssertionTrue => Ok(true),
+            State::AssertionFalse | State::Failed => Ok(false),
+            _invalid => Err(InterpErr::UnexpectedEOI("incomplete operation")),
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for Program {
+    type Error = InterpErr;
+    /// Decode and validate the byte code `source`
+    fn try_from(source: &[u8]) -> Result<Self, Self::Error> {
+        let program = Program::decode(source);
+        if let Some(err) = program.error {
+            return Err(err);
+        }
+        // Comparators must alternate with conjunctions, starting and ending with a comparator
+        let mut expect_comparator = true;
+        for op in &program.opcodes {
+            match op {
+                OpCode::COMP(_) => expect_comparator = false,
+                OpCode::CONJ(_) if expect_comparator => {
+                    return Err(InterpErr::UnexpectedOpCode((*op).into()))
+                }
+                OpCode::CONJ(_) => expect_comparator = true,
+            }
+        }
+        if expect_comparator {
+            return Err(InterpErr::UnexpectedEOI("incomplete operation"));
+        }
+        Ok(program)
+    }
+}
+
+/// Executes pact byte code one OpCode at a time
+/// Embedders use this to interleave execution with their own accounting e.g. charging gas per
+/// OpCode, and can stop at any step. `interpret` runs an executor to completion.
+pub struct Executor<'a, 'b> {
+    interpreter: Interpreter<'a>,
+    opcodes: OpCodes<'b>,
+    /// The byte code length, checked against `Limits::max_bytecode_length`
+    source_length: usize,
+    /// The # of OpCodes executed, checked against `Limits::max_opcodes`
+    executed: usize,
+    /// Whether the byte code is exhausted or the verdict is decided
+    finished: bool,
+    /// The error which stopped execution, returned again by later calls
+    error: Option<InterpErr>,
+}
+
+impl<'a, 'b> Executor<'a, 'b> {
+    /// Return a new executor for `source` with input data registers (`input_data`) and user data
+    /// registers (`user_data`)
+    pub fn new(
+        input_data: &'a [PactType<'a>],
+        user_data: &'a [PactType<'a>],
+        source: &'b [u8],
+    ) -> Self {
+        Self::with_context(input_data, user_data, source, &())
+    }
+
+    /// Return a new executor which resolves `CALL` comparators with `host`
+    pub fn with_context(
+        input_data: &'a [PactType<'a>],
+        user_data: &'a [PactType<'a>],
+        source: &'b [u8],
+        host: &'a dyn HostContext,
+    ) -> Self {
+        Executor {
+            interpreter: Interpreter::with_context(input_data, user_data, host),
+            opcodes: OpCodes::new(source),
+            source_length: source.len(),
+            executed: 0,
+            finished: false,
+            error: None,
+        }
+    }
+
+    /// Fail byte code and comparisons which exceed `limits`
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.interpreter = self.interpreter.with_limits(limits);
+        self
+    }
+
+    /// Fail OpCodes which need features outside `features`, see `Interpreter::with_features`
+    pub fn with_features(mut self, features: Capabilities) -> Self {
+        self.interpreter = self.interpreter.with_features(features);
+        self
+    }
+
+    /// Parse and execute the next OpCode, returning its byte offset and the OpCode
+    /// Returns `None` once the byte code is exhausted, or a failed clause has decided the verdict
+    /// so later OpCodes are not executed. Errors are final, later steps return them again.
+    pub fn step(&mut self) -> Result<Option<(usize, OpCode)>, InterpErr> {
+        if let Some(err) = &self.error {
+            return Err(err.clone());
+        }
+        if self.finished {
+            return Ok(None);
+        }
+        let limits = self.interpreter.limits;
+        let result = match self.opcodes.next() {
+            _ if matches!(limits.max_bytecode_length, Some(max) if self.source_length > max) => {
+                Err(InterpErr::LimitExceeded("max_bytecode_length"))
+            }
+            Some(Ok((_, op)))
+                if matches!(limits.max_opcodes, Some(max) if self.executed >= max)
+                    && !self.is_decided_by(op) =>
+            {
+                Err(InterpErr::LimitExceeded("max_opcodes"))
+            }
+            Some(Ok((offset, op))) => {
+                self.executed += 1;
+                self.interpreter.interpret(op).map(|_| Some((offset, op)))
+            }
+            Some(Err(err)) => Err(err),
+            None => Ok(None),
+        };
+        match result {
+            Ok(Some(step)) => Ok(Some(step)),
+            Ok(None) | Err(InterpErr::Refused) => {
+                self.finished = true;
+                Ok(None)
+            }
+            Err(err) => {
+                self.error = Some(err.clone());
+                Err(err)
+            }
+        }
+    }
+
+    /// Return the interpreter state after the last executed OpCode
+    pub fn state(&self) -> &State {
+        self.interpreter.state()
+    }
+
+    /// Whether `op` follows a failed clause, so it decides the verdict rather than executing
+    fn is_decided_by(&self, op: OpCode) -> bool {
+        match self.interpreter.state() {
+            State::Failed => true,
+            State::AssertionFalse => matches!(op, OpCode::COMP(_)),
+            _ => false,
+        }
+    }
+
+    /// Whether `step` has nothing left to execute
+    pub fn is_finished(&self) -> bool {
+        self.finished || self.error.is_some()
+    }
+
+    /// Return the verdict of the OpCodes executed so far, `true` if the contract was upheld
+    /// Fails if execution failed, or stopped part way through an assertion.
+    pub fn verdict(&self) -> Result<bool, InterpErr> {
+        if let Some(err) = &self.error {
+            return Err(err.clone());
+        }
+        match self.interpreter.state() {
+            State::AssertionTrue => Ok(true),
+            State::AssertionFalse | State::Failed => Ok(false),
+            // Any other state is an Unexpected end of input
+            _invalid => Err(InterpErr::UnexpectedEOI("incomplete operation")),
+        }
+    }
+}
+
+/// Host predicates available to contracts through the `CALL` comparator
+/// `CALL input[i] user[j]` calls the predicate numbered by the numeric `user[j]` with `input[i]`
+/// e.g. "the signer must be a council member".
+/// Predicates must be deterministic so every node reaches the same verdict.
+pub trait HostContext {
+    /// Evaluate the predicate registered as `index` against `arg`
+    /// Unregistered predicates should return `InterpErr::UnknownHostFn`
+    fn call(&self, index: u64, arg: &PactType) -> Result<bool, InterpErr>;
+}
+
+/// A context without host predicates, every `CALL` fails
+impl HostContext for () {
+    fn call(&self, index: u64, _arg: &PactType) -> Result<bool, InterpErr> {
+        Err(InterpErr::UnknownHostFn(index))
+    }
+}
+
+/// Execution limits for hosts which don't bound input or byte code sizes upstream
+/// The default is unlimited, and evaluates every comparator.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Limits {
+    /// The maximum length in bytes of a string-like operand, including list elements
+    /// Comparing a longer string fails with `InterpErr::StringTooLong` rather than truncating it.
+    pub max_string_bytes: Option<usize>,
+    /// The maximum length in bytes of the byte code
+    /// Longer byte code fails with `InterpErr::LimitExceeded` before any OpCode is executed.
+    pub max_bytecode_length: Option<usize>,
+    /// The maximum # of OpCodes executed
+    /// Reaching another OpCode fails with `InterpErr::LimitExceeded`, OpCodes skipped after a
+    /// failed clause are not counted.
+    pub max_opcodes: Option<usize>,
+    /// Skip comparators whose conjunction is decided by its LHS e.g. `false AND ...`
+    /// Skipped comparators load no operands so they can't fail e.g. with a missing input, which
+    /// changes the verdict of such contracts from an error. Every node must agree on this setting.
+    pub short_circuit: bool,
+    /// Compare string-likes for equality without exiting early e.g. when contracts compare secrets
+    /// Equal length operands are compared byte for byte behind volatile reads, which is
+    /// best-effort: it prevents known optimizations but doesn't guarantee constant time on every
+    /// target. Lengths, list membership and numeric comparisons still take data dependent time.
+    pub constant_time_eq: bool,
+}
+
+/// Receives interpreter decisions as they are made
+/// Hosts implement this to forward decisions to their logging or tracing stack.
+pub trait Observer<'a> {
+    /// A comparator was evaluated. `result` is before any conjunction is applied
+    fn assertion(&mut self, _event: &AssertionEvent<'a>) {}
+    /// The clause starting at `opcode_offset` finished evaluating
+    fn clause(&mut self, _opcode_offset: usize, _upheld: bool) {}
+}
+
+/// An observer which ignores all events
+impl<'a> Observer<'a> for () {}
+
+/// A single comparator evaluation
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, PartialEq)]
+pub struct AssertionEvent<'a> {
+    /// Byte offset of the comparator OpCode
+    pub opcode_offset: usize,
+    /// The evaluated comparator
+    pub comparator: Comparator,
+    /// The comparator's LHS value
+    pub lhs: &'a PactType<'a>,
+    /// The comparator's RHS value
+    pub rhs: &'a PactType<'a>,
+    /// The comparator result
+    pub result: bool,
+    /// How far an `IN` comparator scanned its list, `None` for other comparators
+    /// Authors can use this to order list entries by likelihood and to spot near misses.
+    pub list_scan: Option<ListScan>,
+}
+
+/// The extent of an `IN` comparator's scan over its list
+/// Scans stop at the first matching element. `SortedList`s are binary searched instead.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum ListScan {
+    /// The element at this index matched
+    Matched(usize),
+    /// No element matched, this many were compared
+    Missed(usize),
+}
+
+/// The outcome of interpreting a pact contract
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, PartialEq)]
+pub enum Outcome<'a> {
+    /// The contract invariants were maintained
+    Upheld,
+    /// The contract was rejected by the comparator at `opcode_offset` in the bytecode
+    Rejected {
+        /// Byte offset of the rejecting comparator OpCode
+        opcode_offset: usize,
+        /// The rejecting comparator
+        comparator: Comparator,
+        /// The comparator's LHS value
+        lhs: &'a PactType<'a>,
+        /// The comparator's RHS value
+        rhs: &'a PactType<'a>,
+    },
+}
+
 /// An interpreter error
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum InterpErr {
     /// A comparison operator failed with incompatible types on LHS and RHS
     TypeMismatch,
@@ -65,44 +704,231 @@ pub enum InterpErr {
     UnsupportedOpCode(&'static str),
     /// Encountered an invalid OpCode
     InvalidOpCode(u8),
+    /// A comparator OpCode and indices byte load operands it can never compare e.g. `IN` with the
+    /// arity register as the list, see `Comparator::has_invalid_operands`
+    InvalidOperands(u8, u8),
     /// A referenced index in the data table does not exist
     MissingIndex(u8),
+    /// A `CALL` comparator referenced a host predicate which is not registered
+    UnknownHostFn(u64),
+    /// Named inputs were given for a contract without input names
+    UnnamedInputs,
+    /// No named input was given for the input parameter at this position
+    MissingInput(u8),
+    /// The named input at this index is not a parameter of the contract, or is repeated
+    UnexpectedInput(usize),
+    /// A compared string-like operand of this length exceeds `Limits::max_string_bytes`
+    StringTooLong(usize),
+    /// The byte code exceeds the `Limits` field with this name, `max_bytecode_length` or
+    /// `max_opcodes`
+    LimitExceeded(&'static str),
     /// Raised when trying to execute an OpCode from an interpreter which is in a failed state
     Refused,
+    /// The contract has no clause with the requested name
+    UnknownClause,
+    /// A quantified comparator was given this many input registers, more than it ranges over
+    TooManyInputs(usize),
+    /// A comparator loaded the placeholder at this data table index, which was never bound
+    UnboundPlaceholder(u8),
+    /// The contract or an OpCode requires these features, which the interpreter doesn't support
+    UnsupportedFeature(Capabilities),
 }
 
-/// Evaluate a comparator OpCode returning its result
+impl fmt::Display for InterpErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterpErr::TypeMismatch => write!(f, "operands are of incompatible types"),
+            InterpErr::BadTypeOperation => {
+                write!(f, "comparator does not support the operand types")
+            }
+            InterpErr::UnexpectedEOI(reason) => write!(f, "unexpected end of input: {}", reason),
+            InterpErr::UnexpectedOpCode(op) => write!(f, "unexpected OpCode 0x{:02x}", op),
+            InterpErr::UnsupportedOpCode(op) => write!(f, "unsupported OpCode {}", op),
+            InterpErr::InvalidOpCode(op) => write!(f, "invalid OpCode 0x{:02x}", op),
+            InterpErr::InvalidOperands(op, indices) => write!(
+                f,
+                "comparator 0x{:02x} with indices 0x{:02x} loads operands it can never compare",
+                op, indices
+            ),
+            InterpErr::MissingIndex(index) => write!(f, "register {} does not exist", index),
+            InterpErr::UnknownHostFn(index) => {
+                write!(f, "host predicate {} is not registered", index)
+            }
+            InterpErr::UnnamedInputs => {
+                write!(
+                    f,
+                    "named inputs were given for a contract without input names"
+                )
+            }
+            InterpErr::MissingInput(index) => {
+                write!(f, "no named input was given for parameter {}", index)
+            }
+            InterpErr::UnexpectedInput(index) => write!(
+                f,
+                "named input {} is not a parameter of the contract, or is repeated",
+                index
+            ),
+            InterpErr::StringTooLong(length) => write!(
+                f,
+                "compared string of {} bytes exceeds the string length limit",
+                length
+            ),
+            InterpErr::LimitExceeded(limit) => write!(f, "byte code exceeds {}", limit),
+            InterpErr::Refused => write!(f, "a clause has already failed"),
+            InterpErr::UnknownClause => write!(f, "contract has no clause with that name"),
+            InterpErr::TooManyInputs(count) => write!(
+                f,
+                "quantified comparator was given {} inputs, more than it ranges over",
+                count
+            ),
+            InterpErr::UnboundPlaceholder(index) => {
+                write!(f, "placeholder at data table index {} is not bound", index)
+            }
+            InterpErr::UnsupportedFeature(features) => write!(
+                f,
+                "contract requires features which are not supported (since {})",
+                features.min_version()
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InterpErr {}
+
+/// Evaluate a comparator OpCode returning its result, and the list scan for `IN` comparators
+/// `ratio` is the (numerator, denominator) a `RATIO` comparator scales its RHS by.
 fn eval_comparator(
     comparator: Comparator,
     lhs: &PactType,
     rhs: &PactType,
-) -> Result<bool, InterpErr> {
+    ratio: Option<(u64, u64)>,
+    host: &dyn HostContext,
+    limits: Limits,
+) -> Result<(bool, Option<ListScan>), InterpErr> {
+    if let Some(max) = limits.max_string_bytes {
+        // `CALL` arguments are compared by the host, not the interpreter
+        let length = longest_string(lhs).max(longest_string(rhs));
+        if comparator.op != OpComp::CALL && length > max {
+            return Err(InterpErr::StringTooLong(length));
+        }
+    }
+    let mut list_scan = None;
+    // Fixed points are compared at a common scale, numerics have no decimals
+    let fixed_point_order = match (lhs, rhs) {
+        (PactType::FixedPoint { .. }, _) | (_, PactType::FixedPoint { .. }) => {
+            match (lhs.as_fixed_point(), rhs.as_fixed_point()) {
+                (Some(l), Some(r)) => Some(compare_fixed_point(l, r)),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
     let value = match (lhs, rhs) {
-        (PactType::Numeric(l), PactType::Numeric(r)) => match comparator.op {
-            OpComp::EQ => Ok(l == r),
-            OpComp::GT => Ok(l > r),
-            OpComp::GTE => Ok(l >= r),
+        (_, PactType::Numeric(index)) if comparator.op == OpComp::CALL => host.call(index.0, lhs),
+        (_, _) if comparator.op == OpComp::CALL => Err(InterpErr::BadTypeOperation),
+        (PactType::Numeric(l), PactType::Numeric(r)) => match (comparator.op, ratio) {
+            (OpComp::EQ, _) => Ok(l == r),
+            (OpComp::GT, _) => Ok(l > r),
+            (OpComp::GTE, _) => Ok(l >= r),
+            // Products of u64s can't overflow a u128
+            (OpComp::RATIO(op), Some((numerator, denominator))) => {
+                let scaled_lhs = u128::from(l.0) * u128::from(denominator);
+                let scaled_rhs = u128::from(r.0) * u128::from(numerator);
+                Ok(op.order.holds(scaled_lhs.cmp(&scaled_rhs)))
+            }
+            _ => Err(InterpErr::BadTypeOperation),
+        },
+        _ if fixed_point_order.is_some() => match (fixed_point_order, comparator.op) {
+            (Some(order), OpComp::EQ) => Ok(order == Ordering::Equal),
+            (Some(order), OpComp::GT) => Ok(order == Ordering::Greater),
+            (Some(order), OpComp::GTE) => Ok(order != Ordering::Less),
             _ => Err(InterpErr::BadTypeOperation),
         },
         (PactType::StringLike(l), PactType::StringLike(r)) => match comparator.op {
+            OpComp::EQ if limits.constant_time_eq => Ok(constant_time_eq(l.0, r.0)),
             OpComp::EQ => Ok(l == r),
             _ => Err(InterpErr::BadTypeOperation),
         },
-        (PactType::List(_), _) => match comparator.op {
+        (PactType::Bool(l), PactType::Bool(r)) => match comparator.op {
+            OpComp::EQ => Ok(l == r),
             _ => Err(InterpErr::BadTypeOperation),
         },
-        (l, PactType::List(r)) => match comparator.op {
-            OpComp::IN => Ok(r.contains(l)),
-            _ => Err(InterpErr::BadTypeOperation),
+        (l, r) if matches!(comparator.op, OpComp::SUBSET | OpComp::INTERSECTS) => {
+            match (as_list(l), as_list(r)) {
+                (Some((l, _)), Some((r, sorted))) => {
+                    let mut found = l
+                        .iter()
+                        .map(|element| matches!(search(r, sorted, element), ListScan::Matched(_)));
+                    if comparator.op == OpComp::SUBSET {
+                        Ok(found.all(|found| found))
+                    } else {
+                        Ok(found.any(|found| found))
+                    }
+                }
+                _ => Err(InterpErr::BadTypeOperation),
+            }
+        }
+        // A list LHS may only be checked for membership of a list of lists
+        (l, r) => match (as_list(r), comparator.op) {
+            (Some((r, sorted)), OpComp::IN) => {
+                let scan = search(r, sorted, l);
+                list_scan = Some(scan);
+                Ok(matches!(scan, ListScan::Matched(_)))
+            }
+            (Some(_), _) => Err(InterpErr::BadTypeOperation),
+            (None, _) if as_list(l).is_some() => Err(InterpErr::BadTypeOperation),
+            (None, _) => Err(InterpErr::TypeMismatch),
         },
-        _ => Err(InterpErr::TypeMismatch),
     }?;
 
     // Apply inversion if required
-    if comparator.invert {
-        Ok(!value)
-    } else {
-        Ok(value)
+    Ok((comparator.invert.apply(value), list_scan))
+}
+
+/// Return the elements of `value` if it's a list, and whether they are sorted
+fn as_list<'a>(value: &'a PactType<'a>) -> Option<(&'a [PactType<'a>], bool)> {
+    match value {
+        PactType::List(list) => Some((list, false)),
+        PactType::SortedList(list) => Some((list, true)),
+        _ => None,
+    }
+}
+
+/// Search `list` for `needle`
+/// Unsorted lists are scanned up to the first match, sorted lists are binary searched.
+fn search(list: &[PactType], sorted: bool, needle: &PactType) -> ListScan {
+    if !sorted {
+        return match list.iter().position(|element| element == needle) {
+            Some(index) => ListScan::Matched(index),
+            None => ListScan::Missed(list.len()),
+        };
+    }
+    // Elements of another type are never equal, so aren't searched
+    let mut compared = 0;
+    let position = match list.first() {
+        Some(first) if compare_sorted(first, needle).is_some() => list
+            .binary_search_by(|element| {
+                compared += 1;
+                compare_sorted(element, needle).unwrap_or(Ordering::Less)
+            })
+            .ok(),
+        _ => None,
+    };
+    match position {
+        Some(index) => ListScan::Matched(index),
+        None => ListScan::Missed(compared),
+    }
+}
+
+/// Return the length of the longest string-like value in `value`
+fn longest_string(value: &PactType) -> usize {
+    match value {
+        PactType::StringLike(s) => s.0.len(),
+        PactType::List(list) | PactType::SortedList(list) => {
+            list.iter().map(longest_string).max().unwrap_or(0)
+        }
+        _ => 0,
     }
 }
 
@@ -115,10 +941,30 @@ fn eval_conjunction(conjunction: &Conjunction, lhs: bool, rhs: bool) -> Result<b
     };
 
     // Apply inversion if required
-    if conjunction.invert {
-        Ok(!value)
-    } else {
-        Ok(value)
+    Ok(conjunction.invert.apply(value))
+}
+
+/// Return whether `l` and `r` are equal, comparing every byte of equal length slices
+/// The running difference is read back volatile after each byte so the optimizer can't see
+/// which bytes decide the result, and the loop can't exit early. This is best-effort.
+fn constant_time_eq(l: &[u8], r: &[u8]) -> bool {
+    if l.len() != r.len() {
+        return false;
+    }
+    let mut difference = 0u8;
+    for (l, r) in l.iter().zip(r) {
+        difference |= l ^ r;
+        // Safe: `difference` is an initialized and aligned local
+        difference = unsafe { core::ptr::read_volatile(&difference) };
+    }
+    difference == 0
+}
+
+/// Return the result of a conjunction if it is decided by its LHS alone e.g. `false AND ...`
+fn short_circuit(conjunction: &Conjunction, lhs: bool) -> Option<bool> {
+    match (conjunction.op, lhs) {
+        (OpConj::AND, false) | (OpConj::OR, true) => Some(conjunction.invert.apply(lhs)),
+        _ => None,
     }
 }
 
@@ -126,58 +972,210 @@ fn eval_conjunction(conjunction: &Conjunction, lhs: bool, rhs: bool) -> Result<b
 /// It evaluates `OpCode`s maintaining the state of the current contract execution
 /// Uses the rust type system to encode state, see: https://hoverbear.org/2016/10/12/rust-state-machine-pattern/
 /// States provide transformations into other valid states and failure cases.
-#[cfg_attr(feature = "std", derive(Debug))]
 pub struct Interpreter<'a> {
     state: State,
     input_data: &'a [PactType<'a>],
     user_data: &'a [PactType<'a>],
+    /// Resolves `CALL` comparators
+    host: &'a dyn HostContext,
+    /// Bounds on operand sizes
+    limits: Limits,
+    /// The features OpCodes may use
+    features: Capabilities,
+    /// The most recently evaluated comparator, if not yet consumed
+    last_assertion: Option<Assertion<'a>>,
+}
+
+#[cfg(feature = "std")]
+impl<'a> std::fmt::Debug for Interpreter<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Interpreter")
+            .field("state", &self.state)
+            .field("input_data", &self.input_data)
+            .field("user_data", &self.user_data)
+            .field("limits", &self.limits)
+            .field("features", &self.features)
+            .field("last_assertion", &self.last_assertion)
+            .finish()
+    }
+}
+
+/// A single evaluated comparator and its raw result, before any conjunction is applied
+#[cfg_attr(feature = "std", derive(Debug))]
+struct Assertion<'a> {
+    comparator: Comparator,
+    lhs: &'a PactType<'a>,
+    rhs: &'a PactType<'a>,
+    result: bool,
+    list_scan: Option<ListScan>,
 }
 
 impl<'a> Interpreter<'a> {
     /// Return a new interpreter, ready for execution
     pub fn new(input_data: &'a [PactType<'a>], user_data: &'a [PactType<'a>]) -> Self {
+        Self::with_context(input_data, user_data, &())
+    }
+
+    /// Return a new interpreter which resolves `CALL` comparators with `host`
+    pub fn with_context(
+        input_data: &'a [PactType<'a>],
+        user_data: &'a [PactType<'a>],
+        host: &'a dyn HostContext,
+    ) -> Self {
         Interpreter {
             state: State::Initial,
             input_data,
             user_data,
+            host,
+            limits: Limits::default(),
+            features: Capabilities::all(),
+            last_assertion: None,
         }
     }
 
+    /// Fail comparisons whose operands exceed `limits`
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Fail OpCodes which need features outside `features`, rather than every feature of this
+    /// build e.g. `Capabilities::supported_by(version)` to behave like an older interpreter
+    pub fn with_features(mut self, features: Capabilities) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Load the input register at `index`, or the number of input registers for `ARITY_INDEX`
+    fn load_input(&self, index: u8) -> Result<&'a PactType<'a>, InterpErr> {
+        if index == ARITY_INDEX {
+            return Ok(&ARITIES[self.input_data.len().min(MAX_ARITY)]);
+        }
+        self.input_data
+            .get(index as usize)
+            .ok_or(InterpErr::MissingIndex(index))
+    }
+
+    /// Load the data table entry at `index`, failing if it's an unbound placeholder
+    fn load_user(&self, index: u8) -> Result<&'a PactType<'a>, InterpErr> {
+        match self.user_data.get(index as usize) {
+            Some(PactType::Placeholder(..)) => Err(InterpErr::UnboundPlaceholder(index)),
+            Some(value) => Ok(value),
+            None => Err(InterpErr::MissingIndex(index)),
+        }
+    }
+
+    /// Load the (numerator, denominator) ratio at data table `index`, see `OpRatio`
+    fn load_ratio(&self, index: u8) -> Result<(u64, u64), InterpErr> {
+        match as_list(self.load_user(index)?) {
+            Some(([PactType::Numeric(numerator), PactType::Numeric(denominator)], _))
+                if denominator.0 != 0 =>
+            {
+                Ok((numerator.0, denominator.0))
+            }
+            _ => Err(InterpErr::BadTypeOperation),
+        }
+    }
+
+    /// Evaluate a quantified comparator against each input register it ranges over in turn
+    /// Returns the register which decided the result, or the arity register when none were
+    /// compared, along with the result and its list scan.
+    fn eval_quantified(
+        &self,
+        comparator: Comparator,
+        rhs: &'a PactType<'a>,
+        ratio: Option<(u64, u64)>,
+    ) -> Result<(&'a PactType<'a>, bool, Option<ListScan>), InterpErr> {
+        if self.input_data.len() > MAX_QUANTIFIED {
+            return Err(InterpErr::TooManyInputs(self.input_data.len()));
+        }
+        // `ALL` is decided by the first false comparison, `ANY` by the first true one
+        let decisive = comparator.quant == OpQuant::ANY;
+        let mut decided = (&ARITIES[0], !decisive, None);
+        for (index, lhs) in self
+            .input_data
+            .iter()
+            .enumerate()
+            .skip(comparator.indices.lhs as usize)
+        {
+            if comparator.load == OpLoad::INPUT_VS_INPUT && index == comparator.indices.rhs as usize
+            {
+                continue;
+            }
+            let (result, list_scan) =
+                eval_comparator(comparator, lhs, rhs, ratio, self.host, self.limits)?;
+            decided = (lhs, result, list_scan);
+            if result == decisive {
+                break;
+            }
+        }
+        Ok(decided)
+    }
+
     /// Executes a comparator OpCode
     /// This belongs to the interpreter state machine and will update state
     /// based on the outcome
     fn execute_comparator(&mut self, op: OpCode) -> Result<(), InterpErr> {
         match op {
             OpCode::COMP(comparator) => {
+                // Skip loading and comparing operands which can't change the outcome
+                if let State::Conjunctive {
+                    last_assertion,
+                    conjunction,
+                } = &self.state
+                {
+                    if let Some(result) = short_circuit(conjunction, *last_assertion)
+                        .filter(|_| self.limits.short_circuit)
+                    {
+                        self.state = if result {
+                            State::AssertionTrue
+                        } else {
+                            State::AssertionFalse
+                        };
+                        return Ok(());
+                    }
+                }
+
                 // Gather left and right hand side values
-                let lhs = self
-                    .input_data
-                    .get(comparator.indices.lhs as usize)
-                    .ok_or(InterpErr::MissingIndex(comparator.indices.lhs))?;
+                // Quantified comparators load each of their LHS registers in turn
+                let lhs = match comparator.quant {
+                    OpQuant::ONE => Some(self.load_input(comparator.indices.lhs)?),
+                    OpQuant::ALL | OpQuant::ANY => None,
+                };
 
                 let rhs = match comparator.load {
-                    OpLoad::INPUT_VS_USER => self
-                        .user_data
-                        .get(comparator.indices.rhs as usize)
-                        .ok_or(InterpErr::MissingIndex(comparator.indices.rhs)),
-                    OpLoad::INPUT_VS_INPUT => self
-                        .input_data
-                        .get(comparator.indices.rhs as usize)
-                        .ok_or(InterpErr::MissingIndex(comparator.indices.rhs)),
+                    OpLoad::INPUT_VS_USER => self.load_user(comparator.indices.rhs),
+                    OpLoad::INPUT_VS_INPUT => self.load_input(comparator.indices.rhs),
                 }?;
+                let ratio = match comparator.op {
+                    OpComp::RATIO(ratio) => Some(self.load_ratio(ratio.index)?),
+                    _ => None,
+                };
 
-                let mut result = eval_comparator(comparator, &lhs, rhs)?;
-
-                // Evaluate the conjunction if necessary
-                match &self.state {
-                    State::Conjunctive {
-                        last_assertion,
-                        conjunction,
-                    } => {
-                        result = eval_conjunction(conjunction, *last_assertion, result)?;
+                let (lhs, mut result, list_scan) = match lhs {
+                    Some(lhs) => {
+                        let (result, list_scan) =
+                            eval_comparator(comparator, lhs, rhs, ratio, self.host, self.limits)?;
+                        (lhs, result, list_scan)
                     }
-                    _ => {}
+                    None => self.eval_quantified(comparator, rhs, ratio)?,
                 };
+                self.last_assertion = Some(Assertion {
+                    comparator,
+                    lhs,
+                    rhs,
+                    result,
+                    list_scan,
+                });
+
+                // Evaluate the conjunction if necessary
+                if let State::Conjunctive {
+                    last_assertion,
+                    conjunction,
+                } = &self.state
+                {
+                    result = eval_conjunction(conjunction, *last_assertion, result)?;
+                }
 
                 // The assertions and operations upto this point have all been collapsed into
                 // a single boolean.
@@ -192,8 +1190,20 @@ impl<'a> Interpreter<'a> {
         }
     }
 
+    /// Return the interpreter state after the last executed OpCode
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
     /// Interpreter state machine
+    /// Executes one parsed OpCode, see `Executor` to execute byte code one OpCode at a time.
     pub fn interpret(&mut self, op: OpCode) -> Result<(), InterpErr> {
+        if !matches!(self.state, State::Failed) {
+            let missing = op.capability().difference(self.features);
+            if !missing.is_empty() {
+                return Err(InterpErr::UnsupportedFeature(missing));
+            }
+        }
         match &self.state {
             // First op code must be a comparator
             State::Initial => self.execute_comparator(op),
@@ -202,7 +1212,7 @@ impl<'a> Interpreter<'a> {
                 OpCode::CONJ(conjunction) => {
                     self.state = State::Conjunctive {
                         last_assertion: true,
-                        conjunction: conjunction,
+                        conjunction,
                     };
                     Ok(())
                 }
@@ -219,7 +1229,7 @@ impl<'a> Interpreter<'a> {
                     OpCode::CONJ(conjunction) => {
                         self.state = State::Conjunctive {
                             last_assertion: false,
-                            conjunction: conjunction,
+                            conjunction,
                         };
                         Ok(())
                     }
@@ -232,9 +1242,7 @@ impl<'a> Interpreter<'a> {
                 // A Conjunction must be followed by a comparator
                 match op {
                     OpCode::COMP(_) => self.execute_comparator(op),
-                    OpCode::CONJ(_) => {
-                        return Err(InterpErr::UnexpectedOpCode(op.into()));
-                    }
+                    OpCode::CONJ(_) => Err(InterpErr::UnexpectedOpCode(op.into())),
                 }
             }
             State::Failed => Err(InterpErr::Refused),
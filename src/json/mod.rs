@@ -0,0 +1,591 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! JSON converter for pact input registers
+//! Reads a JSON array e.g. `[5, "hello friend", ["alice", "bob"], true]` into an owned input table
+//!
+//! Also reads and writes verdict fixtures shared with other pact implementations, see `Fixture`,
+//! and ASTs built by external frontends, see `parser::ast::from_json`
+//!
+use crate::interpreter::{self, InterpErr};
+use crate::types::{BinaryFormatErr, Contract, Numeric, PactType, StringLike};
+
+use pest::Parser;
+
+pub(crate) mod nodes;
+pub mod vectors;
+
+#[derive(Parser)]
+#[grammar = "json/grammar.pest"]
+struct JsonParser;
+
+/// A JSON conversion error
+#[derive(Debug, PartialEq)]
+pub enum JsonErr {
+    /// The source is not valid JSON
+    Syntax(String),
+    /// The top level JSON value must be an array of registers
+    ExpectedArray,
+    /// A number does not fit into a 64-bit numeric
+    NumericOverflow(String),
+    /// The JSON value has no `PactType` equivalent
+    UnsupportedValue(&'static str),
+    /// A verdict fixture is malformed
+    InvalidFixture(&'static str),
+    /// A JSON AST is malformed
+    InvalidAst(&'static str),
+}
+
+impl std::fmt::Display for JsonErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            JsonErr::Syntax(reason) => write!(f, "invalid JSON: {}", reason),
+            JsonErr::ExpectedArray => write!(f, "expected a JSON array of registers"),
+            JsonErr::NumericOverflow(number) => {
+                write!(f, "{} does not fit into a 64-bit numeric", number)
+            }
+            JsonErr::UnsupportedValue(reason) => write!(f, "unsupported JSON value: {}", reason),
+            JsonErr::InvalidFixture(reason) => write!(f, "invalid fixture: {}", reason),
+            JsonErr::InvalidAst(reason) => write!(f, "invalid JSON AST: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for JsonErr {}
+
+/// An owned input register value
+/// `PactType`s borrow their data, this type owns it so it can outlive the JSON source
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    StringLike(Vec<u8>),
+    Numeric(u64),
+    List(Vec<Value>),
+    Bool(bool),
+}
+
+impl Value {
+    /// Borrow this value as a `PactType`
+    pub fn as_pact(&self) -> PactType<'_> {
+        match self {
+            Value::StringLike(s) => PactType::StringLike(StringLike(s)),
+            Value::Numeric(n) => PactType::Numeric(Numeric(*n)),
+            Value::List(l) => PactType::List(l.iter().map(Value::as_pact).collect()),
+            Value::Bool(b) => PactType::Bool(*b),
+        }
+    }
+    /// Render this value as JSON
+    /// Fails if a string-like value is not valid UTF-8
+    pub fn to_json(&self) -> Result<String, JsonErr> {
+        match self {
+            Value::StringLike(s) => {
+                let s = core::str::from_utf8(s)
+                    .map_err(|_| JsonErr::UnsupportedValue("non UTF-8 string"))?;
+                Ok(escape(s))
+            }
+            Value::Numeric(n) => Ok(n.to_string()),
+            Value::List(l) => list_to_json(l),
+            Value::Bool(b) => Ok(b.to_string()),
+        }
+    }
+}
+
+/// The verdict of interpreting a contract
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+    Upheld,
+    Rejected,
+    /// The contract failed to decode or interpret
+    Error,
+}
+
+impl Verdict {
+    fn as_str(self) -> &'static str {
+        match self {
+            Verdict::Upheld => "upheld",
+            Verdict::Rejected => "rejected",
+            Verdict::Error => "error",
+        }
+    }
+}
+
+/// A cross-implementation test case, a binary contract with inputs and its expected verdict
+/// Fixture files are a JSON array of objects e.g.
+/// `[{"contract": "00400000", "inputs": [5, "alice"], "verdict": "upheld"}]`
+/// where `contract` is the hex binary format. Fixtures may also have a `name`, and an `error`
+/// naming the expected error of an `"error"` verdict. Other members are ignored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fixture {
+    /// A short description of the case e.g. `"eq: numeric mismatch"`
+    pub name: Option<String>,
+    pub contract: Vec<u8>,
+    pub inputs: Vec<Value>,
+    pub verdict: Verdict,
+    /// The expected error's name, an `InterpErr` or `BinaryFormatErr` variant e.g.
+    /// `"MissingIndex"`. Implementations which don't distinguish errors may ignore it.
+    pub error: Option<String>,
+}
+
+impl Fixture {
+    /// Return a fixture of `contract` and `inputs` with the verdict and error of this crate
+    pub fn evaluate(contract: Vec<u8>, inputs: Vec<Value>) -> Self {
+        let (verdict, error) = evaluate(&contract, &inputs);
+        Fixture {
+            name: None,
+            contract,
+            inputs,
+            verdict,
+            error: error.map(String::from),
+        }
+    }
+    /// Render this fixture as a JSON object
+    pub fn to_json(&self) -> Result<String, JsonErr> {
+        let contract: String = self.contract.iter().map(|b| format!("{:02x}", b)).collect();
+        let name = match &self.name {
+            Some(name) => format!("\"name\": {}, ", escape(name)),
+            None => String::new(),
+        };
+        let error = match &self.error {
+            Some(error) => format!(", \"error\": {}", escape(error)),
+            None => String::new(),
+        };
+        Ok(format!(
+            "{{{}\"contract\": \"{}\", \"inputs\": {}, \"verdict\": \"{}\"{}}}",
+            name,
+            contract,
+            list_to_json(&self.inputs)?,
+            self.verdict.as_str(),
+            error
+        ))
+    }
+}
+
+/// Decode and interpret the binary `contract` with `inputs`
+/// Returns the verdict, and the error's name for an `Error` verdict, see `Fixture::error`.
+pub fn evaluate(contract: &[u8], inputs: &[Value]) -> (Verdict, Option<&'static str>) {
+    let contract = match Contract::decode(contract) {
+        Ok(contract) => contract,
+        Err(err) => return (Verdict::Error, Some(format_err_name(&err))),
+    };
+    let input_table: Vec<PactType> = inputs.iter().map(Value::as_pact).collect();
+    match interpreter::interpret(
+        &input_table,
+        contract.data_table().as_ref(),
+        contract.bytecode(),
+    ) {
+        Ok(true) => (Verdict::Upheld, None),
+        Ok(false) => (Verdict::Rejected, None),
+        Err(err) => (Verdict::Error, Some(interp_err_name(&err))),
+    }
+}
+
+/// The name of a binary format error in fixtures
+fn format_err_name(err: &BinaryFormatErr) -> &'static str {
+    match err {
+        BinaryFormatErr::UnsupportedVersion => "UnsupportedVersion",
+        BinaryFormatErr::MalformedDataTable(_) => "MalformedDataTable",
+        BinaryFormatErr::MalformedMetadata(_) => "MalformedMetadata",
+        BinaryFormatErr::TooShort => "TooShort",
+        BinaryFormatErr::InvalidText(_) => "InvalidText",
+        BinaryFormatErr::MalformedBytecode(_) => "MalformedBytecode",
+    }
+}
+
+/// The name of an interpreter error in fixtures
+fn interp_err_name(err: &InterpErr) -> &'static str {
+    match err {
+        InterpErr::TypeMismatch => "TypeMismatch",
+        InterpErr::BadTypeOperation => "BadTypeOperation",
+        InterpErr::UnexpectedEOI(_) => "UnexpectedEOI",
+        InterpErr::UnexpectedOpCode(_) => "UnexpectedOpCode",
+        InterpErr::UnsupportedOpCode(_) => "UnsupportedOpCode",
+        InterpErr::InvalidOpCode(_) => "InvalidOpCode",
+        InterpErr::InvalidOperands(..) => "InvalidOperands",
+        InterpErr::MissingIndex(_) => "MissingIndex",
+        InterpErr::UnknownHostFn(_) => "UnknownHostFn",
+        InterpErr::UnnamedInputs => "UnnamedInputs",
+        InterpErr::MissingInput(_) => "MissingInput",
+        InterpErr::UnexpectedInput(_) => "UnexpectedInput",
+        InterpErr::StringTooLong(_) => "StringTooLong",
+        InterpErr::LimitExceeded(_) => "LimitExceeded",
+        InterpErr::Refused => "Refused",
+        InterpErr::UnknownClause => "UnknownClause",
+        InterpErr::TooManyInputs(_) => "TooManyInputs",
+        InterpErr::UnboundPlaceholder(_) => "UnboundPlaceholder",
+        InterpErr::UnsupportedFeature(_) => "UnsupportedFeature",
+    }
+}
+
+/// Parse a JSON array from `source` into a list of input register values
+pub fn parse_inputs(source: &str) -> Result<Vec<Value>, JsonErr> {
+    parse_array(source)?.into_inner().map(build_value).collect()
+}
+
+/// Parse a JSON array of verdict fixtures from `source`
+pub fn parse_fixtures(source: &str) -> Result<Vec<Fixture>, JsonErr> {
+    parse_array(source)?
+        .into_inner()
+        .map(build_fixture)
+        .collect()
+}
+
+/// Render `fixtures` as a JSON array with one fixture per line, the layout of fixture files
+pub fn fixtures_to_json(fixtures: &[Fixture]) -> Result<String, JsonErr> {
+    let lines = fixtures
+        .iter()
+        .map(|fixture| Ok(format!("  {}", fixture.to_json()?)))
+        .collect::<Result<Vec<_>, JsonErr>>()?;
+    Ok(format!("[\n{}\n]\n", lines.join(",\n")))
+}
+
+/// Parse `source` which must hold a top level JSON array
+fn parse_array(source: &str) -> Result<pest::iterators::Pair<'_, Rule>, JsonErr> {
+    let mut pairs =
+        JsonParser::parse(Rule::json, source).map_err(|err| JsonErr::Syntax(err.to_string()))?;
+    let root = pairs.next().ok_or(JsonErr::ExpectedArray)?;
+    if root.as_rule() != Rule::array {
+        return Err(JsonErr::ExpectedArray);
+    }
+    Ok(root)
+}
+
+/// Build a `Fixture` from a pest object pair
+fn build_fixture(pair: pest::iterators::Pair<Rule>) -> Result<Fixture, JsonErr> {
+    if pair.as_rule() != Rule::object {
+        return Err(JsonErr::InvalidFixture("expected an object"));
+    }
+    let (mut name, mut contract, mut inputs, mut verdict, mut error) =
+        (None, None, None, None, None);
+    for member in pair.into_inner() {
+        let mut member = member.into_inner();
+        // The grammar guarantees a member is a string key and a value
+        let (key, value) = match (member.next(), member.next()) {
+            (Some(key), Some(value)) => (build_string(key)?, value),
+            _ => unreachable!("grammar only produces key value members"),
+        };
+        match key.as_str() {
+            "name" => name = Some(build_string(value)?),
+            "error" => error = Some(build_string(value)?),
+            "contract" => contract = Some(decode_hex(&build_string(value)?)?),
+            "inputs" => match build_value(value)? {
+                Value::List(l) => inputs = Some(l),
+                _ => return Err(JsonErr::InvalidFixture("`inputs` must be an array")),
+            },
+            "verdict" => {
+                verdict = Some(match build_string(value)?.as_str() {
+                    "upheld" => Verdict::Upheld,
+                    "rejected" => Verdict::Rejected,
+                    "error" => Verdict::Error,
+                    _ => return Err(JsonErr::InvalidFixture("unknown `verdict`")),
+                })
+            }
+            _ => {}
+        }
+    }
+    Ok(Fixture {
+        name,
+        contract: contract.ok_or(JsonErr::InvalidFixture("missing `contract`"))?,
+        inputs: inputs.ok_or(JsonErr::InvalidFixture("missing `inputs`"))?,
+        verdict: verdict.ok_or(JsonErr::InvalidFixture("missing `verdict`"))?,
+        error,
+    })
+}
+
+/// Build a `String` from a pest string pair
+fn build_string(pair: pest::iterators::Pair<Rule>) -> Result<String, JsonErr> {
+    if pair.as_rule() != Rule::string {
+        return Err(JsonErr::InvalidFixture("expected a string"));
+    }
+    Ok(unescape(
+        pair.into_inner().next().map_or("", |p| p.as_str()),
+    ))
+}
+
+/// Decode a hex string e.g. `"00ff"`
+fn decode_hex(s: &str) -> Result<Vec<u8>, JsonErr> {
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            core::str::from_utf8(pair)
+                .ok()
+                .filter(|_| pair.len() == 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or(JsonErr::InvalidFixture("`contract` is not valid hex"))
+        })
+        .collect()
+}
+
+/// Build a `Value` from a pest input pair
+fn build_value(pair: pest::iterators::Pair<Rule>) -> Result<Value, JsonErr> {
+    match pair.as_rule() {
+        Rule::string => {
+            let inner = pair.into_inner().next().map_or("", |p| p.as_str());
+            Ok(Value::StringLike(unescape(inner).into_bytes()))
+        }
+        Rule::number => {
+            let n = pair.as_str();
+            if n.starts_with('-') {
+                return Err(JsonErr::UnsupportedValue("negative number"));
+            }
+            if n.contains(&['.', 'e', 'E'][..]) {
+                return Err(JsonErr::UnsupportedValue("fractional number"));
+            }
+            n.parse()
+                .map(Value::Numeric)
+                .map_err(|_| JsonErr::NumericOverflow(n.into()))
+        }
+        Rule::array => Ok(Value::List(
+            pair.into_inner()
+                .map(build_value)
+                .collect::<Result<_, _>>()?,
+        )),
+        Rule::object => Err(JsonErr::UnsupportedValue("object")),
+        Rule::boolean => Ok(Value::Bool(pair.as_str() == "true")),
+        Rule::null => Err(JsonErr::UnsupportedValue("null")),
+        _ => unreachable!("grammar only produces values here"),
+    }
+}
+
+/// Resolve JSON escape sequences in a string body
+/// The grammar has already validated every escape sequence
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('b') => out.push('\u{8}'),
+            Some('f') => out.push('\u{c}'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('u') => {
+                let high = read_hex4(&mut chars);
+                let code = if (0xd800..0xdc00).contains(&high) {
+                    // UTF-16 surrogate pair, the low half must follow as another `\u` escape
+                    let mut lookahead = chars.clone();
+                    if lookahead.next() == Some('\\') && lookahead.next() == Some('u') {
+                        let low = read_hex4(&mut lookahead);
+                        if (0xdc00..0xe000).contains(&low) {
+                            chars = lookahead;
+                            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                        } else {
+                            high
+                        }
+                    } else {
+                        high
+                    }
+                } else {
+                    high
+                };
+                out.push(core::char::from_u32(code).unwrap_or('\u{fffd}'));
+            }
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// Render `values` as a JSON array
+fn list_to_json(values: &[Value]) -> Result<String, JsonErr> {
+    let elements = values
+        .iter()
+        .map(Value::to_json)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(format!("[{}]", elements.join(", ")))
+}
+
+/// Quote `s` as a JSON string, escaping as required
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Read 4 hex digits from `chars` as a code unit
+fn read_hex4(chars: &mut core::str::Chars) -> u32 {
+    (0..4).fold(0, |acc, _| {
+        acc * 16 + chars.next().and_then(|c| c.to_digit(16)).unwrap_or(0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_inputs() {
+        assert_eq!(
+            parse_inputs(r#"[5, "hello friend", ["alice", 7], []]"#),
+            Ok(vec![
+                Value::Numeric(5),
+                Value::StringLike(b"hello friend".to_vec()),
+                Value::List(vec![
+                    Value::StringLike(b"alice".to_vec()),
+                    Value::Numeric(7)
+                ]),
+                Value::List(vec![]),
+            ])
+        );
+    }
+
+    #[test]
+    fn it_converts_to_pact_types() {
+        let inputs = parse_inputs(r#"[18446744073709551615, "a", [1]]"#).unwrap();
+        let registers: Vec<PactType> = inputs.iter().map(Value::as_pact).collect();
+        assert_eq!(
+            registers,
+            vec![
+                PactType::Numeric(Numeric(u64::MAX)),
+                PactType::StringLike(StringLike(b"a")),
+                PactType::List(vec![PactType::Numeric(Numeric(1))]),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_booleans() {
+        let inputs = parse_inputs("[true, [false]]").unwrap();
+        assert_eq!(
+            inputs,
+            vec![Value::Bool(true), Value::List(vec![Value::Bool(false)])]
+        );
+        assert_eq!(inputs[0].as_pact(), PactType::Bool(true));
+        assert_eq!(inputs[1].to_json(), Ok("[false]".into()));
+    }
+
+    #[test]
+    fn it_unescapes_strings() {
+        assert_eq!(
+            parse_inputs(r#"["a\"b\\c\n", "\u00e9\ud83d\ude00"]"#),
+            Ok(vec![
+                Value::StringLike(b"a\"b\\c\n".to_vec()),
+                Value::StringLike("é😀".as_bytes().to_vec()),
+            ])
+        );
+    }
+
+    #[test]
+    fn it_fails_with_unsupported_values() {
+        assert_eq!(parse_inputs("{}"), Err(JsonErr::ExpectedArray));
+        assert_eq!(parse_inputs("5"), Err(JsonErr::ExpectedArray));
+        assert_eq!(
+            parse_inputs("[-1]"),
+            Err(JsonErr::UnsupportedValue("negative number"))
+        );
+        assert_eq!(
+            parse_inputs("[1.5]"),
+            Err(JsonErr::UnsupportedValue("fractional number"))
+        );
+        assert_eq!(
+            parse_inputs("[null]"),
+            Err(JsonErr::UnsupportedValue("null"))
+        );
+        assert_eq!(
+            parse_inputs("[18446744073709551616]"),
+            Err(JsonErr::NumericOverflow("18446744073709551616".into()))
+        );
+        assert!(parse_inputs("[1,").is_err());
+    }
+
+    #[test]
+    fn it_reads_and_writes_fixtures() {
+        let fixture = Fixture {
+            name: None,
+            contract: vec![0x00, 0x40, 0xff],
+            inputs: vec![
+                Value::Numeric(5),
+                Value::StringLike(b"a\"b\x01".to_vec()),
+                Value::List(vec![]),
+            ],
+            verdict: Verdict::Rejected,
+            error: None,
+        };
+        let json = fixture.to_json().unwrap();
+        assert_eq!(
+            json,
+            r#"{"contract": "0040ff", "inputs": [5, "a\"b\u0001", []], "verdict": "rejected"}"#
+        );
+        assert_eq!(parse_fixtures(&format!("[{}]", json)), Ok(vec![fixture]));
+        let fixture = Fixture {
+            name: Some("missing user data".into()),
+            ..Fixture::evaluate(vec![0x00, 0x00, 0x00, 0x00], vec![Value::Numeric(5)])
+        };
+        assert_eq!(fixture.error.as_deref(), Some("MissingIndex"));
+        let json = fixture.to_json().unwrap();
+        assert_eq!(
+            json,
+            r#"{"name": "missing user data", "contract": "00000000", "inputs": [5], "verdict": "error", "error": "MissingIndex"}"#
+        );
+        assert_eq!(parse_fixtures(&format!("[{}]", json)), Ok(vec![fixture]));
+        // Unknown members are ignored
+        assert_eq!(
+            parse_fixtures(r#"[{"js": 1, "verdict": "upheld", "inputs": [], "contract": "00"}]"#),
+            Ok(vec![Fixture {
+                name: None,
+                contract: vec![0x00],
+                inputs: vec![],
+                verdict: Verdict::Upheld,
+                error: None,
+            }])
+        );
+    }
+
+    #[test]
+    fn it_fails_with_invalid_fixtures() {
+        let invalid = |json| match parse_fixtures(json) {
+            Err(JsonErr::InvalidFixture(message)) => message,
+            result => panic!("unexpected: {:?}", result),
+        };
+        assert_eq!(invalid("[1]"), "expected an object");
+        assert_eq!(
+            invalid(r#"[{"contract": "0", "inputs": [], "verdict": "upheld"}]"#),
+            "`contract` is not valid hex"
+        );
+        assert_eq!(
+            invalid(r#"[{"contract": "00", "inputs": 1, "verdict": "upheld"}]"#),
+            "`inputs` must be an array"
+        );
+        assert_eq!(
+            invalid(r#"[{"contract": "00", "inputs": [], "verdict": "maybe"}]"#),
+            "unknown `verdict`"
+        );
+        assert_eq!(
+            invalid(r#"[{"contract": "00", "inputs": []}]"#),
+            "missing `verdict`"
+        );
+        assert_eq!(
+            Value::StringLike(vec![0xff]).to_json(),
+            Err(JsonErr::UnsupportedValue("non UTF-8 string"))
+        );
+    }
+}
@@ -0,0 +1,550 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! JSON converter for pact ASTs
+//! External frontends e.g. a visual rule builder hand the compiler a JSON AST rather than source.
+//!
+//! Nodes use serde's default externally tagged representation, so frontends may derive it:
+//! - unit variants are strings e.g. `"MustBe"`
+//! - other variants are an object with one member named by the variant, holding the variant's
+//!   field, or an array of its fields e.g. `{"Identifier": ["$a", {"line": 2, "column": 1}]}`
+//! - structs are objects with a member per field, and `None` is `null`
+//!
+use super::{escape, unescape, JsonErr, JsonParser, Rule};
+use crate::parser::ast::{
+    Assertion, Comparator, Conjunctive, Imperative, InputType, Location, Node, Quantifier, Subject,
+    Value,
+};
+use crate::types::MAX_DECIMALS;
+
+use pest::Parser;
+
+/// Render AST `nodes` as a JSON array
+pub fn to_json(nodes: &[Node]) -> String {
+    array(nodes.iter().map(node_to_json))
+}
+
+/// Parse AST nodes from a JSON array in `source`
+/// The nodes are not checked to form a valid contract, the compiler does that.
+pub fn from_json(source: &str) -> Result<Vec<Node>, JsonErr> {
+    let mut pairs =
+        JsonParser::parse(Rule::json, source).map_err(|err| JsonErr::Syntax(err.to_string()))?;
+    let root = pairs.next().ok_or(JsonErr::ExpectedArray)?;
+    if root.as_rule() != Rule::array {
+        return Err(JsonErr::ExpectedArray);
+    }
+    match build_json(root) {
+        Json::Array(nodes) => nodes.iter().map(node_from_json).collect(),
+        _ => Err(JsonErr::ExpectedArray),
+    }
+}
+
+/// A parsed JSON value
+enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Return the variant name and content of an externally tagged enum value
+    /// Unit variants have `Json::Null` content.
+    fn variant(&self) -> Result<(&str, &Json), JsonErr> {
+        match self {
+            Json::String(name) => Ok((name, &Json::Null)),
+            Json::Object(members) if members.len() == 1 => Ok((&members[0].0, &members[0].1)),
+            _ => Err(JsonErr::InvalidAst("expected a variant")),
+        }
+    }
+    /// Return the elements of an array of `length` elements
+    fn fields(&self, length: usize) -> Result<&[Json], JsonErr> {
+        match self {
+            Json::Array(elements) if elements.len() == length => Ok(elements),
+            _ => Err(JsonErr::InvalidAst("expected an array of variant fields")),
+        }
+    }
+    /// Return the value of the object member named `key`
+    fn member(&self, key: &str) -> Result<&Json, JsonErr> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value)
+                .ok_or(JsonErr::InvalidAst("missing object member")),
+            _ => Err(JsonErr::InvalidAst("expected an object")),
+        }
+    }
+    fn string(&self) -> Result<String, JsonErr> {
+        match self {
+            Json::String(s) => Ok(s.clone()),
+            _ => Err(JsonErr::InvalidAst("expected a string")),
+        }
+    }
+    fn number(&self) -> Result<u64, JsonErr> {
+        match self {
+            Json::Number(n) => n
+                .parse()
+                .map_err(|_| JsonErr::InvalidAst("expected an unsigned 64-bit integer")),
+            _ => Err(JsonErr::InvalidAst("expected a number")),
+        }
+    }
+}
+
+/// Build a `Json` value from a pest input pair
+fn build_json(pair: pest::iterators::Pair<Rule>) -> Json {
+    match pair.as_rule() {
+        Rule::string => Json::String(unescape(
+            pair.into_inner().next().map_or("", |p| p.as_str()),
+        )),
+        Rule::number => Json::Number(pair.as_str().into()),
+        Rule::array => Json::Array(pair.into_inner().map(build_json).collect()),
+        Rule::object => Json::Object(
+            pair.into_inner()
+                .map(|member| {
+                    let mut member = member.into_inner();
+                    // The grammar guarantees a member is a string key and a value
+                    match (member.next(), member.next()) {
+                        (Some(key), Some(value)) => (
+                            unescape(key.into_inner().next().map_or("", |p| p.as_str())),
+                            build_json(value),
+                        ),
+                        _ => unreachable!("grammar only produces key value members"),
+                    }
+                })
+                .collect(),
+        ),
+        Rule::boolean => Json::Bool(pair.as_str() == "true"),
+        Rule::null => Json::Null,
+        _ => unreachable!("grammar only produces values here"),
+    }
+}
+
+/// Render `elements` as a JSON array
+fn array<I: IntoIterator<Item = String>>(elements: I) -> String {
+    format!("[{}]", elements.into_iter().collect::<Vec<_>>().join(", "))
+}
+
+/// Render an externally tagged enum variant with `content`
+fn variant(name: &str, content: String) -> String {
+    format!("{{{}: {}}}", escape(name), content)
+}
+
+fn node_to_json(node: &Node) -> String {
+    match node {
+        Node::InputDeclaration(parameters) => variant(
+            "InputDeclaration",
+            array(parameters.iter().map(|(identifier, input_type)| {
+                array(vec![escape(identifier), input_type_to_json(*input_type)])
+            })),
+        ),
+        Node::Definition(identifier, value, location) => variant(
+            "Definition",
+            array(vec![
+                escape(identifier),
+                value_to_json(value),
+                location_to_json(location),
+            ]),
+        ),
+        Node::Alias(identifier, aliased, location) => variant(
+            "Alias",
+            array(vec![
+                escape(identifier),
+                escape(aliased),
+                location_to_json(location),
+            ]),
+        ),
+        Node::Placeholder(identifier, input_type, location) => variant(
+            "Placeholder",
+            array(vec![
+                escape(identifier),
+                input_type_to_json(*input_type),
+                location_to_json(location),
+            ]),
+        ),
+        Node::Include(name, location) => variant(
+            "Include",
+            array(vec![escape(name), location_to_json(location)]),
+        ),
+        Node::Meta(key, value, location) => variant(
+            "Meta",
+            array(vec![escape(key), escape(value), location_to_json(location)]),
+        ),
+        Node::Clause(assertion) => variant("Clause", assertion_to_json(assertion)),
+        Node::NamedClause(name, assertion) => variant(
+            "NamedClause",
+            array(vec![escape(name), assertion_to_json(assertion)]),
+        ),
+    }
+}
+
+fn node_from_json(json: &Json) -> Result<Node, JsonErr> {
+    match json.variant()? {
+        ("InputDeclaration", Json::Array(parameters)) => parameters
+            .iter()
+            .map(|parameter| {
+                let fields = parameter.fields(2)?;
+                Ok((fields[0].string()?, input_type_from_json(&fields[1])?))
+            })
+            .collect::<Result<_, _>>()
+            .map(Node::InputDeclaration),
+        ("Definition", fields) => {
+            let fields = fields.fields(3)?;
+            Ok(Node::Definition(
+                fields[0].string()?,
+                value_from_json(&fields[1])?,
+                location_from_json(&fields[2])?,
+            ))
+        }
+        ("Alias", fields) => {
+            let fields = fields.fields(3)?;
+            Ok(Node::Alias(
+                fields[0].string()?,
+                fields[1].string()?,
+                location_from_json(&fields[2])?,
+            ))
+        }
+        ("Placeholder", fields) => {
+            let fields = fields.fields(3)?;
+            Ok(Node::Placeholder(
+                fields[0].string()?,
+                input_type_from_json(&fields[1])?,
+                location_from_json(&fields[2])?,
+            ))
+        }
+        ("Include", fields) => {
+            let fields = fields.fields(2)?;
+            Ok(Node::Include(
+                fields[0].string()?,
+                location_from_json(&fields[1])?,
+            ))
+        }
+        ("Meta", fields) => {
+            let fields = fields.fields(3)?;
+            Ok(Node::Meta(
+                fields[0].string()?,
+                fields[1].string()?,
+                location_from_json(&fields[2])?,
+            ))
+        }
+        ("Clause", assertion) => Ok(Node::Clause(assertion_from_json(assertion)?)),
+        ("NamedClause", fields) => {
+            let fields = fields.fields(2)?;
+            Ok(Node::NamedClause(
+                fields[0].string()?,
+                assertion_from_json(&fields[1])?,
+            ))
+        }
+        _ => Err(JsonErr::InvalidAst("unknown node")),
+    }
+}
+
+fn assertion_to_json(assertion: &Assertion) -> String {
+    let conjoined = match &assertion.conjoined_assertion {
+        Some((conjunctive, conjoined)) => array(vec![
+            escape(conjunctive_name(conjunctive)),
+            assertion_to_json(conjoined),
+        ]),
+        None => "null".into(),
+    };
+    format!(
+        "{{\"lhs_subject\": {}, \"imperative\": {}, \"comparator\": {}, \"rhs_subject\": {}, \"conjoined_assertion\": {}, \"location\": {}}}",
+        subject_to_json(&assertion.lhs_subject),
+        escape(imperative_name(&assertion.imperative)),
+        escape(comparator_name(&assertion.comparator)),
+        subject_to_json(&assertion.rhs_subject),
+        conjoined,
+        location_to_json(&assertion.location)
+    )
+}
+
+fn assertion_from_json(json: &Json) -> Result<Assertion, JsonErr> {
+    let conjoined_assertion = match json.member("conjoined_assertion")? {
+        Json::Null => None,
+        conjoined => {
+            let fields = conjoined.fields(2)?;
+            let conjunctive = match fields[0].variant()? {
+                ("And", Json::Null) => Conjunctive::And,
+                ("Or", Json::Null) => Conjunctive::Or,
+                _ => return Err(JsonErr::InvalidAst("unknown conjunctive")),
+            };
+            Some((conjunctive, Box::new(assertion_from_json(&fields[1])?)))
+        }
+    };
+    Ok(Assertion {
+        lhs_subject: subject_from_json(json.member("lhs_subject")?)?,
+        imperative: match json.member("imperative")?.variant()? {
+            ("MustBe", Json::Null) => Imperative::MustBe,
+            ("MustNotBe", Json::Null) => Imperative::MustNotBe,
+            _ => return Err(JsonErr::InvalidAst("unknown imperative")),
+        },
+        comparator: comparator_from_json(json.member("comparator")?)?,
+        rhs_subject: subject_from_json(json.member("rhs_subject")?)?,
+        conjoined_assertion,
+        location: location_from_json(json.member("location")?)?,
+    })
+}
+
+fn subject_to_json(subject: &Subject) -> String {
+    match subject {
+        Subject::Value(value, location) => variant(
+            "Value",
+            array(vec![value_to_json(value), location_to_json(location)]),
+        ),
+        Subject::Identifier(identifier, location) => variant(
+            "Identifier",
+            array(vec![escape(identifier), location_to_json(location)]),
+        ),
+        Subject::Arity(location) => variant("Arity", location_to_json(location)),
+        Subject::Quantified(quantifier, location) => {
+            let quantifier = match quantifier {
+                Quantifier::Every => "Every",
+                Quantifier::Any => "Any",
+            };
+            variant(
+                "Quantified",
+                array(vec![escape(quantifier), location_to_json(location)]),
+            )
+        }
+        Subject::Percentage(percentage, subject, location) => variant(
+            "Percentage",
+            array(vec![
+                value_to_json(percentage),
+                subject_to_json(subject),
+                location_to_json(location),
+            ]),
+        ),
+    }
+}
+
+fn subject_from_json(json: &Json) -> Result<Subject, JsonErr> {
+    match json.variant()? {
+        ("Value", fields) => {
+            let fields = fields.fields(2)?;
+            Ok(Subject::Value(
+                value_from_json(&fields[0])?,
+                location_from_json(&fields[1])?,
+            ))
+        }
+        ("Identifier", fields) => {
+            let fields = fields.fields(2)?;
+            Ok(Subject::Identifier(
+                fields[0].string()?,
+                location_from_json(&fields[1])?,
+            ))
+        }
+        ("Arity", location) => Ok(Subject::Arity(location_from_json(location)?)),
+        ("Quantified", fields) => {
+            let fields = fields.fields(2)?;
+            let quantifier = match fields[0].variant()? {
+                ("Every", Json::Null) => Quantifier::Every,
+                ("Any", Json::Null) => Quantifier::Any,
+                _ => return Err(JsonErr::InvalidAst("unknown quantifier")),
+            };
+            Ok(Subject::Quantified(
+                quantifier,
+                location_from_json(&fields[1])?,
+            ))
+        }
+        ("Percentage", fields) => {
+            let fields = fields.fields(3)?;
+            Ok(Subject::Percentage(
+                value_from_json(&fields[0])?,
+                Box::new(subject_from_json(&fields[1])?),
+                location_from_json(&fields[2])?,
+            ))
+        }
+        _ => Err(JsonErr::InvalidAst("unknown subject")),
+    }
+}
+
+fn value_to_json(value: &Value) -> String {
+    match value {
+        Value::StringLike(s) => variant("StringLike", escape(s)),
+        Value::Numeric(n) => variant("Numeric", n.to_string()),
+        Value::List(l) => variant("List", array(l.iter().map(value_to_json))),
+        Value::Bool(b) => variant("Bool", b.to_string()),
+        Value::FixedPoint(value, decimals) => variant(
+            "FixedPoint",
+            array(vec![value.to_string(), decimals.to_string()]),
+        ),
+    }
+}
+
+fn value_from_json(json: &Json) -> Result<Value, JsonErr> {
+    match json.variant()? {
+        ("StringLike", s) => s.string().map(Value::StringLike),
+        ("Numeric", n) => n.number().map(Value::Numeric),
+        ("List", Json::Array(l)) => l
+            .iter()
+            .map(value_from_json)
+            .collect::<Result<_, _>>()
+            .map(Value::List),
+        ("Bool", Json::Bool(b)) => Ok(Value::Bool(*b)),
+        ("FixedPoint", fields) => {
+            let fields = fields.fields(2)?;
+            let value = match &fields[0] {
+                Json::Number(n) => n
+                    .parse()
+                    .map_err(|_| JsonErr::InvalidAst("expected an unsigned 128-bit integer"))?,
+                _ => return Err(JsonErr::InvalidAst("expected a number")),
+            };
+            Ok(Value::FixedPoint(value, decimals_from_json(&fields[1])?))
+        }
+        _ => Err(JsonErr::InvalidAst("unknown value")),
+    }
+}
+
+fn decimals_from_json(json: &Json) -> Result<u8, JsonErr> {
+    match json.number()? {
+        decimals if decimals <= u64::from(MAX_DECIMALS) => Ok(decimals as u8),
+        _ => Err(JsonErr::InvalidAst("too many decimals")),
+    }
+}
+
+fn location_to_json(location: &Location) -> String {
+    format!(
+        "{{\"line\": {}, \"column\": {}}}",
+        location.line, location.column
+    )
+}
+
+fn location_from_json(json: &Json) -> Result<Location, JsonErr> {
+    Ok(Location {
+        line: json.member("line")?.number()? as usize,
+        column: json.member("column")?.number()? as usize,
+    })
+}
+
+fn input_type_to_json(input_type: InputType) -> String {
+    let name = match input_type {
+        InputType::Any => "Any",
+        InputType::Numeric => "Numeric",
+        InputType::StringLike => "StringLike",
+        InputType::List => "List",
+        InputType::Bool => "Bool",
+        InputType::FixedPoint(decimals) => return variant("FixedPoint", decimals.to_string()),
+    };
+    escape(name)
+}
+
+fn input_type_from_json(json: &Json) -> Result<InputType, JsonErr> {
+    match json.variant()? {
+        ("Any", Json::Null) => Ok(InputType::Any),
+        ("Numeric", Json::Null) => Ok(InputType::Numeric),
+        ("StringLike", Json::Null) => Ok(InputType::StringLike),
+        ("List", Json::Null) => Ok(InputType::List),
+        ("Bool", Json::Null) => Ok(InputType::Bool),
+        ("FixedPoint", decimals) => decimals_from_json(decimals).map(InputType::FixedPoint),
+        _ => Err(JsonErr::InvalidAst("unknown input type")),
+    }
+}
+
+fn imperative_name(imperative: &Imperative) -> &'static str {
+    match imperative {
+        Imperative::MustBe => "MustBe",
+        Imperative::MustNotBe => "MustNotBe",
+    }
+}
+
+fn conjunctive_name(conjunctive: &Conjunctive) -> &'static str {
+    match conjunctive {
+        Conjunctive::And => "And",
+        Conjunctive::Or => "Or",
+    }
+}
+
+fn comparator_name(comparator: &Comparator) -> &'static str {
+    match comparator {
+        Comparator::Equal => "Equal",
+        Comparator::GreaterThan => "GreaterThan",
+        Comparator::GreaterThanOrEqual => "GreaterThanOrEqual",
+        Comparator::LessThan => "LessThan",
+        Comparator::LessThanOrEqual => "LessThanOrEqual",
+        Comparator::OneOf => "OneOf",
+        Comparator::SubsetOf => "SubsetOf",
+        Comparator::IntersectingWith => "IntersectingWith",
+    }
+}
+
+fn comparator_from_json(json: &Json) -> Result<Comparator, JsonErr> {
+    match json.variant()? {
+        ("Equal", Json::Null) => Ok(Comparator::Equal),
+        ("GreaterThan", Json::Null) => Ok(Comparator::GreaterThan),
+        ("GreaterThanOrEqual", Json::Null) => Ok(Comparator::GreaterThanOrEqual),
+        ("LessThan", Json::Null) => Ok(Comparator::LessThan),
+        ("LessThanOrEqual", Json::Null) => Ok(Comparator::LessThanOrEqual),
+        ("OneOf", Json::Null) => Ok(Comparator::OneOf),
+        ("SubsetOf", Json::Null) => Ok(Comparator::SubsetOf),
+        ("IntersectingWith", Json::Null) => Ok(Comparator::IntersectingWith),
+        _ => Err(JsonErr::InvalidAst("unknown comparator")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn it_renders_externally_tagged_nodes() {
+        let ast = parser::parse("given parameters $a: numeric\n$a must be less than 5").unwrap();
+        assert_eq!(
+            to_json(&ast),
+            concat!(
+                r#"[{"InputDeclaration": [["$a", "Numeric"]]}, "#,
+                r#"{"Clause": {"lhs_subject": {"Identifier": ["$a", {"line": 2, "column": 1}]}, "#,
+                r#""imperative": "MustBe", "comparator": "LessThan", "#,
+                r#""rhs_subject": {"Value": [{"Numeric": 5}, {"line": 2, "column": 22}]}, "#,
+                r#""conjoined_assertion": null, "location": {"line": 2, "column": 1}}}]"#
+            )
+        );
+    }
+
+    #[test]
+    fn it_round_trips_fixed_points() {
+        let ast =
+            parser::parse("given parameters $a: decimal(2)\n$a must be less than 1.50").unwrap();
+        let json = to_json(&ast);
+        assert!(json.contains(r#"["$a", {"FixedPoint": 2}]"#));
+        assert!(json.contains(r#"{"FixedPoint": [150, 2]}"#));
+        assert_eq!(to_json(&from_json(&json).unwrap()), json);
+    }
+
+    #[test]
+    fn it_fails_with_invalid_nodes() {
+        assert_eq!(
+            from_json(r#"{"Clause": null}"#).unwrap_err(),
+            JsonErr::ExpectedArray
+        );
+        assert_eq!(
+            from_json(r#"[{"Statement": []}]"#).unwrap_err(),
+            JsonErr::InvalidAst("unknown node")
+        );
+        assert_eq!(
+            from_json(r#"[{"Definition": ["$a", {"Numeric": -1}, {"line": 1, "column": 1}]}]"#)
+                .unwrap_err(),
+            JsonErr::InvalidAst("expected an unsigned 64-bit integer")
+        );
+        assert_eq!(
+            from_json(r#"[{"Definition": ["$a", {"Bool": true}, {"line": 1}]}]"#).unwrap_err(),
+            JsonErr::InvalidAst("missing object member")
+        );
+    }
+}
@@ -0,0 +1,735 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Interpreter conformance vectors for other pact implementations e.g. the JS SDK
+//! Every comparator, conjunction, load, and quantifier is covered along with each interpreter
+//! and binary format error path. Verdicts and errors are this crate's, so implementations which
+//! replay the vectors behave identically. `pactc vectors` writes them to `tests/vectors/`.
+//!
+use super::{Fixture, Value};
+use crate::interpreter::{Comparator, Conjunction, OpComp, OpConj, OpQuant};
+use crate::testing::*;
+use crate::types::{Contract, DataTable, InputType, Numeric, PactType, StringLike};
+use bit_reverse::ParallelReverse;
+
+/// A numeric data table value
+fn num(n: u64) -> PactType<'static> {
+    PactType::Numeric(Numeric(n))
+}
+
+/// A string-like data table value
+fn text(s: &'static str) -> PactType<'static> {
+    PactType::StringLike(StringLike(s.as_bytes()))
+}
+
+/// A (numerator, denominator) ratio data table value, see `OpComp::RATIO`
+fn ratio(numerator: u64, denominator: u64) -> PactType<'static> {
+    PactType::List(vec![num(numerator), num(denominator)])
+}
+
+/// A numeric input value
+fn n(n: u64) -> Value {
+    Value::Numeric(n)
+}
+
+/// A string-like input value
+fn s(s: &str) -> Value {
+    Value::StringLike(s.as_bytes().to_vec())
+}
+
+/// A named vector of the contract `table` and `bytecode` interpreted with `inputs`
+fn vector(name: &str, table: Vec<PactType>, bytecode: Vec<u8>, inputs: Vec<Value>) -> Fixture {
+    let mut contract = Vec::new();
+    Contract::from_parts_unchecked(DataTable::new(table), bytecode)
+        .encode(&mut contract)
+        .expect("vector contracts are encodable");
+    raw(name, contract, inputs)
+}
+
+/// A named vector of the binary `contract` interpreted with `inputs`
+fn raw(name: &str, contract: Vec<u8>, inputs: Vec<Value>) -> Fixture {
+    Fixture {
+        name: Some(name.into()),
+        ..Fixture::evaluate(contract, inputs)
+    }
+}
+
+/// Return the conformance vectors, in a stable order
+pub fn conformance() -> Vec<Fixture> {
+    let mut vectors = Vec::new();
+    vectors.extend(comparators());
+    vectors.extend(conjunctions());
+    vectors.extend(loads());
+    vectors.extend(errors());
+    vectors.extend(formats());
+    vectors
+}
+
+/// Each comparator and its inversion over each operand type
+fn comparators() -> Vec<Fixture> {
+    let lt = || Comparator::new(OpComp::GTE).invert();
+    let list = || PactType::List(vec![num(1), num(3), num(5)]);
+    let strings = || PactType::List(vec![text("alice"), text("bob")]);
+    let sorted = || PactType::sorted_list(vec![num(5), num(1), num(3)]).unwrap();
+    vec![
+        vector(
+            "eq: numeric",
+            vec![num(5)],
+            comp_eq(input(0), user(0)),
+            vec![n(5)],
+        ),
+        vector(
+            "eq: numeric mismatch",
+            vec![num(5)],
+            comp_eq(input(0), user(0)),
+            vec![n(6)],
+        ),
+        vector(
+            "eq: string",
+            vec![text("alice")],
+            comp_eq(input(0), user(0)),
+            vec![s("alice")],
+        ),
+        vector(
+            "eq: string mismatch",
+            vec![text("alice")],
+            comp_eq(input(0), user(0)),
+            vec![s("alicf")],
+        ),
+        vector(
+            "eq: empty string",
+            vec![text("")],
+            comp_eq(input(0), user(0)),
+            vec![s("")],
+        ),
+        vector(
+            "eq: bool",
+            vec![PactType::Bool(true)],
+            comp_eq(input(0), user(0)),
+            vec![Value::Bool(true)],
+        ),
+        vector(
+            "eq: bool mismatch",
+            vec![PactType::Bool(true)],
+            comp_eq(input(0), user(0)),
+            vec![Value::Bool(false)],
+        ),
+        vector(
+            "eq: list",
+            vec![list()],
+            comp_eq(input(0), user(0)),
+            vec![Value::List(vec![n(1), n(3), n(5)])],
+        ),
+        vector(
+            "eq: max numeric",
+            vec![num(u64::MAX)],
+            comp_eq(input(0), user(0)),
+            vec![n(u64::MAX)],
+        ),
+        vector(
+            "not eq: numeric",
+            vec![num(5)],
+            comp_neq(input(0), user(0)),
+            vec![n(5)],
+        ),
+        vector(
+            "not eq: numeric mismatch",
+            vec![num(5)],
+            comp_neq(input(0), user(0)),
+            vec![n(6)],
+        ),
+        vector(
+            "gt: greater",
+            vec![num(5)],
+            comp_gt(input(0), user(0)),
+            vec![n(6)],
+        ),
+        vector(
+            "gt: equal",
+            vec![num(5)],
+            comp_gt(input(0), user(0)),
+            vec![n(5)],
+        ),
+        vector(
+            "gt: string",
+            vec![text("b")],
+            comp_gt(input(0), user(0)),
+            vec![s("c")],
+        ),
+        vector(
+            "gte: equal",
+            vec![num(5)],
+            comp_gte(input(0), user(0)),
+            vec![n(5)],
+        ),
+        vector(
+            "gte: less",
+            vec![num(5)],
+            comp_gte(input(0), user(0)),
+            vec![n(4)],
+        ),
+        vector(
+            "not gte: less",
+            vec![num(5)],
+            comp_lt(input(0), user(0)),
+            vec![n(4)],
+        ),
+        vector(
+            "not gte: equal",
+            vec![num(5)],
+            comp_lt(input(0), user(0)),
+            vec![n(5)],
+        ),
+        vector(
+            "not gt: equal",
+            vec![num(5)],
+            comp_lte(input(0), user(0)),
+            vec![n(5)],
+        ),
+        vector(
+            "not gt: greater",
+            vec![num(5)],
+            comp_lte(input(0), user(0)),
+            vec![n(6)],
+        ),
+        vector(
+            "in: numeric member",
+            vec![list()],
+            comp_in(input(0), user(0)),
+            vec![n(3)],
+        ),
+        vector(
+            "in: numeric non-member",
+            vec![list()],
+            comp_in(input(0), user(0)),
+            vec![n(4)],
+        ),
+        vector(
+            "in: string member",
+            vec![strings()],
+            comp_in(input(0), user(0)),
+            vec![s("bob")],
+        ),
+        vector(
+            "in: empty list",
+            vec![PactType::List(vec![])],
+            comp_in(input(0), user(0)),
+            vec![n(1)],
+        ),
+        vector(
+            "in: sorted list member",
+            vec![sorted()],
+            comp_in(input(0), user(0)),
+            vec![n(5)],
+        ),
+        vector(
+            "in: sorted list non-member",
+            vec![sorted()],
+            comp_in(input(0), user(0)),
+            vec![n(2)],
+        ),
+        vector(
+            "in: string in string",
+            vec![text("hello world")],
+            comp_in(input(0), user(0)),
+            vec![s("lo w")],
+        ),
+        vector(
+            "not in: numeric member",
+            vec![list()],
+            comp_nin(input(0), user(0)),
+            vec![n(3)],
+        ),
+        vector(
+            "not in: numeric non-member",
+            vec![list()],
+            comp_nin(input(0), user(0)),
+            vec![n(4)],
+        ),
+        vector(
+            "subset: subset",
+            vec![list()],
+            comp_subset(input(0), user(0)),
+            vec![Value::List(vec![n(5), n(1)])],
+        ),
+        vector(
+            "subset: not a subset",
+            vec![list()],
+            comp_subset(input(0), user(0)),
+            vec![Value::List(vec![n(5), n(2)])],
+        ),
+        vector(
+            "subset: empty",
+            vec![list()],
+            comp_subset(input(0), user(0)),
+            vec![Value::List(vec![])],
+        ),
+        vector(
+            "not subset: subset",
+            vec![list()],
+            comp(Comparator::new(OpComp::SUBSET).invert(), input(0), user(0)),
+            vec![Value::List(vec![n(1)])],
+        ),
+        vector(
+            "intersects: shared",
+            vec![list()],
+            comp_intersects(input(0), user(0)),
+            vec![Value::List(vec![n(2), n(3)])],
+        ),
+        vector(
+            "intersects: disjoint",
+            vec![list()],
+            comp_intersects(input(0), user(0)),
+            vec![Value::List(vec![n(2), n(4)])],
+        ),
+        vector(
+            "not intersects: disjoint",
+            vec![list()],
+            comp(
+                Comparator::new(OpComp::INTERSECTS).invert(),
+                input(0),
+                user(0),
+            ),
+            vec![Value::List(vec![n(2)])],
+        ),
+        vector(
+            "ratio: less than 1%",
+            vec![ratio(1, 100)],
+            comp_ratio(lt(), input(0), input(1), user(0)),
+            vec![n(9), n(1_000)],
+        ),
+        vector(
+            "ratio: equal to 1%",
+            vec![ratio(1, 100)],
+            comp_ratio(lt(), input(0), input(1), user(0)),
+            vec![n(10), n(1_000)],
+        ),
+        vector(
+            "ratio: gt data table value",
+            vec![num(1_000), ratio(1, 200)],
+            comp_ratio(Comparator::new(OpComp::GT), input(0), user(0), user(1)),
+            vec![n(6)],
+        ),
+        vector(
+            "ratio: max numerics",
+            vec![ratio(u64::MAX, u64::MAX - 1)],
+            comp_ratio(Comparator::new(OpComp::EQ), input(0), input(1), user(0)),
+            vec![n(u64::MAX), n(u64::MAX - 1)],
+        ),
+    ]
+}
+
+/// Each conjunction and its inversion, and implicitly conjoined clauses
+fn conjunctions() -> Vec<Fixture> {
+    let table = || vec![num(5)];
+    // `input[0] == 5 <conjunction> input[1] == 5`
+    let conjoined = |name: &str, conjunction: Conjunction, lhs: u64, rhs: u64| {
+        vector(
+            name,
+            table(),
+            bytecode(&[
+                comp_eq(input(0), user(0)),
+                conj(conjunction),
+                comp_eq(input(1), user(0)),
+            ]),
+            vec![n(lhs), n(rhs)],
+        )
+    };
+    let mut vectors = Vec::new();
+    for (op, label) in [
+        (OpConj::AND, "and"),
+        (OpConj::OR, "or"),
+        (OpConj::XOR, "xor"),
+    ]
+    .iter()
+    {
+        for invert in [false, true].iter() {
+            let conjunction = if *invert {
+                Conjunction::new(*op).invert()
+            } else {
+                Conjunction::new(*op)
+            };
+            let prefix = if *invert { "not " } else { "" };
+            for (lhs, rhs) in [(5, 5), (5, 0), (0, 5), (0, 0)].iter() {
+                vectors.push(conjoined(
+                    &format!("{}{}: {} {}", prefix, label, *lhs == 5, *rhs == 5),
+                    conjunction,
+                    *lhs,
+                    *rhs,
+                ));
+            }
+        }
+    }
+    vectors.push(vector(
+        "clauses: both hold",
+        table(),
+        bytecode(&[comp_eq(input(0), user(0)), comp_eq(input(1), user(0))]),
+        vec![n(5), n(5)],
+    ));
+    vectors.push(vector(
+        "clauses: second fails",
+        table(),
+        bytecode(&[comp_eq(input(0), user(0)), comp_eq(input(1), user(0))]),
+        vec![n(5), n(0)],
+    ));
+    vectors.push(vector(
+        "clauses: chained conjunctions",
+        table(),
+        bytecode(&[
+            comp_eq(input(0), user(0)),
+            conj_or(),
+            comp_eq(input(1), user(0)),
+            conj_and(),
+            comp_eq(input(2), user(0)),
+        ]),
+        vec![n(0), n(5), n(5)],
+    ));
+    vectors
+}
+
+/// Input vs. input loads, the arity register, and quantifiers
+fn loads() -> Vec<Fixture> {
+    let all = |op: OpComp| Comparator::new(op).quantify(OpQuant::ALL);
+    let any = |op: OpComp| Comparator::new(op).quantify(OpQuant::ANY);
+    vec![
+        vector(
+            "input vs input: eq",
+            vec![],
+            comp_eq(input(0), input(1)),
+            vec![n(5), n(5)],
+        ),
+        vector(
+            "input vs input: gt",
+            vec![],
+            comp_gt(input(0), input(1)),
+            vec![n(5), n(6)],
+        ),
+        vector(
+            "input vs input: in",
+            vec![],
+            comp_in(input(0), input(1)),
+            vec![n(5), Value::List(vec![n(5)])],
+        ),
+        vector(
+            "input vs input: list in list of lists",
+            vec![],
+            comp_in(input(0), input(1)),
+            vec![
+                Value::List(vec![n(5)]),
+                Value::List(vec![Value::List(vec![n(5)])]),
+            ],
+        ),
+        vector(
+            "arity: equal",
+            vec![num(2)],
+            comp_eq(input(ARITY), user(0)),
+            vec![n(0), n(0)],
+        ),
+        vector(
+            "arity: no inputs",
+            vec![num(0)],
+            comp_eq(input(ARITY), user(0)),
+            vec![],
+        ),
+        vector(
+            "arity: input vs arity",
+            vec![],
+            comp_gt(input(0), input(ARITY)),
+            vec![n(5), n(0)],
+        ),
+        vector(
+            "all: hold",
+            vec![num(10)],
+            comp(all(OpComp::GT).invert(), input(0), user(0)),
+            vec![n(1), n(10), n(3)],
+        ),
+        vector(
+            "all: one fails",
+            vec![num(10)],
+            comp(all(OpComp::GT).invert(), input(0), user(0)),
+            vec![n(1), n(11), n(3)],
+        ),
+        vector(
+            "all: from index",
+            vec![num(10)],
+            comp(all(OpComp::GT).invert(), input(1), user(0)),
+            vec![n(11), n(10), n(3)],
+        ),
+        vector(
+            "all: no inputs",
+            vec![num(10)],
+            comp(all(OpComp::EQ), input(0), user(0)),
+            vec![],
+        ),
+        vector(
+            "any: one holds",
+            vec![num(10)],
+            comp(any(OpComp::EQ), input(0), user(0)),
+            vec![n(1), n(10)],
+        ),
+        vector(
+            "any: none hold",
+            vec![num(10)],
+            comp(any(OpComp::EQ), input(0), user(0)),
+            vec![n(1), n(2)],
+        ),
+        vector(
+            "any: no inputs",
+            vec![num(10)],
+            comp(any(OpComp::EQ), input(0), user(0)),
+            vec![],
+        ),
+        vector(
+            "any: skips the compared input",
+            vec![],
+            comp(any(OpComp::EQ), input(0), input(1)),
+            vec![n(1), n(2), n(2)],
+        ),
+    ]
+}
+
+/// The arity register index
+const ARITY: u8 = crate::interpreter::ARITY_INDEX;
+
+/// Interpreter error paths
+fn errors() -> Vec<Fixture> {
+    let too_many: Vec<Value> = (0..16).map(n).collect();
+    vec![
+        vector(
+            "error: type mismatch",
+            vec![num(5)],
+            comp_eq(input(0), user(0)),
+            vec![s("5")],
+        ),
+        vector(
+            "error: bool ordering",
+            vec![PactType::Bool(true)],
+            comp_gt(input(0), user(0)),
+            vec![Value::Bool(true)],
+        ),
+        vector(
+            "error: list ordering",
+            vec![PactType::List(vec![num(1)])],
+            comp_gt(input(0), user(0)),
+            vec![Value::List(vec![n(1)])],
+        ),
+        vector(
+            "error: in a numeric",
+            vec![num(5)],
+            comp_in(input(0), user(0)),
+            vec![n(5)],
+        ),
+        vector(
+            "error: subset of a numeric",
+            vec![num(5)],
+            comp_subset(input(0), user(0)),
+            vec![n(5)],
+        ),
+        vector(
+            "error: missing user data",
+            vec![],
+            comp_eq(input(0), user(0)),
+            vec![n(5)],
+        ),
+        vector(
+            "error: missing input",
+            vec![num(5)],
+            comp_eq(input(1), user(0)),
+            vec![n(5)],
+        ),
+        vector(
+            "error: unknown host predicate",
+            vec![num(7)],
+            comp_call(input(0), user(0)),
+            vec![n(5)],
+        ),
+        vector(
+            "error: unbound placeholder",
+            vec![PactType::Placeholder("limit", InputType::Numeric)],
+            comp_eq(input(0), user(0)),
+            vec![n(5)],
+        ),
+        vector(
+            "error: quantified over too many inputs",
+            vec![num(100)],
+            comp(
+                Comparator::new(OpComp::GT).quantify(OpQuant::ALL),
+                input(0),
+                user(0),
+            ),
+            too_many,
+        ),
+        vector("error: empty bytecode", vec![], vec![], vec![]),
+        vector(
+            "error: trailing conjunction",
+            vec![num(5)],
+            bytecode(&[comp_eq(input(0), user(0)), conj_and()]),
+            vec![n(5)],
+        ),
+        vector(
+            "error: missing comparator indices",
+            vec![num(5)],
+            vec![0x00],
+            vec![n(5)],
+        ),
+        vector(
+            "error: leading conjunction",
+            vec![num(5)],
+            bytecode(&[conj_and(), comp_eq(input(0), user(0))]),
+            vec![n(5)],
+        ),
+        vector(
+            "error: consecutive conjunctions",
+            vec![num(5)],
+            bytecode(&[
+                comp_eq(input(0), user(0)),
+                conj_and(),
+                conj_or(),
+                comp_eq(input(0), user(0)),
+            ]),
+            vec![n(5)],
+        ),
+        vector(
+            "error: invalid comparator",
+            vec![num(5)],
+            vec![0xc0, 0x00],
+            vec![n(5)],
+        ),
+        vector(
+            "error: missing ratio",
+            vec![ratio(1, 100)],
+            vec![0x0f, 0x01],
+            vec![n(5), n(5)],
+        ),
+        vector(
+            "error: invalid ratio order",
+            vec![ratio(1, 100)],
+            vec![0x0f, 0x01, 0x30],
+            vec![n(5), n(5)],
+        ),
+        vector(
+            "error: zero ratio denominator",
+            vec![ratio(1, 0)],
+            comp_ratio(Comparator::new(OpComp::EQ), input(0), input(1), user(0)),
+            vec![n(5), n(5)],
+        ),
+        vector(
+            "error: ratio of strings",
+            vec![ratio(1, 100)],
+            comp_ratio(Comparator::new(OpComp::EQ), input(0), input(1), user(0)),
+            vec![s("a"), s("b")],
+        ),
+        vector(
+            "error: in the arity register",
+            vec![],
+            comp_in(input(0), input(ARITY)),
+            vec![n(5)],
+        ),
+        vector(
+            "error: in itself",
+            vec![],
+            comp_in(input(0), input(0)),
+            vec![Value::List(vec![n(5)])],
+        ),
+        vector(
+            "error: invalid conjunction",
+            vec![num(5)],
+            bytecode(&[
+                comp_eq(input(0), user(0)),
+                vec![0x2f],
+                comp_eq(input(0), user(0)),
+            ]),
+            vec![n(5)],
+        ),
+    ]
+}
+
+/// Binary format error paths
+fn formats() -> Vec<Fixture> {
+    let v = |version: u8| version.swap_bits();
+    vec![
+        raw("format: empty", vec![], vec![]),
+        raw(
+            "format: unsupported version",
+            vec![v(7), 0, 0x00, 0x00],
+            vec![n(5)],
+        ),
+        raw("format: missing data table", vec![v(0)], vec![n(5)]),
+        raw(
+            "format: data table value too long",
+            vec![v(0), v(1), 0, v(4), 1],
+            vec![n(5)],
+        ),
+        raw(
+            "format: unknown value type",
+            vec![v(0), v(1), 0xff, 0],
+            vec![n(5)],
+        ),
+        raw(
+            "format: truncated input names",
+            vec![v(1), v(2), v(1), b'a'],
+            vec![n(5)],
+        ),
+        raw(
+            "format: unknown parameters layout",
+            vec![v(3), v(9), 0],
+            vec![n(5)],
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::Verdict;
+
+    #[test]
+    fn it_covers_every_verdict_and_error() {
+        let vectors = conformance();
+        for verdict in [Verdict::Upheld, Verdict::Rejected, Verdict::Error].iter() {
+            assert!(vectors.iter().any(|v| v.verdict == *verdict));
+        }
+        for error in [
+            "TypeMismatch",
+            "BadTypeOperation",
+            "UnexpectedEOI",
+            "UnexpectedOpCode",
+            "InvalidOpCode",
+            "InvalidOperands",
+            "MissingIndex",
+            "UnknownHostFn",
+            "TooManyInputs",
+            "UnboundPlaceholder",
+            "UnsupportedVersion",
+            "MalformedDataTable",
+            "MalformedMetadata",
+            "TooShort",
+        ]
+        .iter()
+        {
+            assert!(
+                vectors.iter().any(|v| v.error.as_deref() == Some(*error)),
+                "no vector for {}",
+                error
+            );
+        }
+        let mut names: Vec<_> = vectors.iter().map(|v| v.name.clone()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), vectors.len(), "vector names are distinct");
+    }
+}
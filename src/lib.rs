@@ -29,10 +29,44 @@ extern crate pest;
 #[macro_use]
 extern crate pest_derive;
 
+#[macro_use]
+mod trace;
+
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "cache")]
+pub mod cache;
 #[cfg(feature = "compiler")]
 pub mod compiler;
 #[cfg(feature = "compiler")]
+pub mod decompiler;
+#[cfg(feature = "doughnut")]
+pub mod doughnut;
+#[cfg(feature = "std")]
+pub mod errors;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "compiler")]
+pub mod json;
+#[cfg(feature = "ast")]
 pub mod parser;
 
 pub mod interpreter;
+#[cfg(feature = "substrate")]
+pub mod substrate;
+#[cfg(feature = "testgen")]
+pub mod testgen;
+pub mod testing;
 pub mod types;
+pub mod verifier;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// Paths used by `#[derive(ToPactInputs)]`, which work without `std`
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __derive {
+    pub use alloc::vec;
+    pub use alloc::vec::Vec;
+    pub use core::result::Result::{self, Ok};
+}
@@ -18,48 +18,75 @@
 //! The pact AST
 //! It represents a contract composed of definitions and clauses
 //!
+pub use crate::types::InputType;
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+#[cfg(feature = "compiler")]
+pub use crate::json::nodes::{from_json, to_json};
 
 /// AST node types
-#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub enum Node {
-    /// The declaration of input variable names for the contract
-    InputDeclaration(Vec<Identifier>),
+    /// The declaration of input variable names and types for the contract
+    /// Parameters without a declared type are `InputType::Any`
+    InputDeclaration(Vec<(Identifier, InputType)>),
 
     /// A statement mapping an identifier to a value
-    /// (identifier, value) .e.g ("account", "Qm53w689adflkhnknkjhkj")
-    Definition(Identifier, Value),
+    /// (identifier, value, location) .e.g ("account", "Qm53w689adflkhnknkjhkj", 2:1)
+    Definition(Identifier, Value, Location),
+
+    /// A statement mapping an identifier to the value of an earlier definition
+    /// (identifier, aliased identifier, location) .e.g ("$double_limit", "$limit", 3:1)
+    Alias(Identifier, Identifier, Location),
+
+    /// A statement declaring a placeholder, bound to a value of the type after compilation
+    /// (identifier, type, location) .e.g ("$limit", InputType::Numeric, 2:1)
+    Placeholder(Identifier, InputType, Location),
+
+    /// A statement naming a library of definitions to include in place
+    /// (library name, location) .e.g ("common_lists", 2:1)
+    Include(String, Location),
+
+    /// A statement attaching human-readable metadata to the contract
+    /// (key, value, location) .e.g ("title", "Spending limit", 1:1)
+    Meta(String, String, Location),
 
     /// A high-level construct formed by one or more conjoined assertions
     Clause(Assertion),
+
+    /// A clause with a name, so hosts can evaluate it alone
+    /// (name, assertion) .e.g ("transfer_limit", $amount must be less than 100)
+    NamedClause(Identifier, Assertion),
 }
 
 /// A primitive construct which describes a single invariant
 /// (identifier, imperative, comparator, subject)
-#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct Assertion {
     pub lhs_subject: Subject,
     pub imperative: Imperative,
     pub comparator: Comparator,
     pub rhs_subject: Subject,
     pub conjoined_assertion: Option<(Conjunctive, Box<Self>)>,
+    pub location: Location,
 }
 
 /// `MustBe` implies `Comparator == true` while `MustNotBe` implies `Comparator == false`
-#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub enum Imperative {
     MustBe,
     MustNotBe,
 }
 
 /// Represents a logical join of two clauses
-#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub enum Conjunctive {
     Or,
     And,
 }
 
 /// A logical operation to assert
-#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub enum Comparator {
     Equal,
     GreaterThan,
@@ -67,22 +94,63 @@ pub enum Comparator {
     LessThan,
     LessThanOrEqual,
     OneOf,
+    SubsetOf,
+    IntersectingWith,
 }
 
 /// A subject of a comparator (LHS / RHS).
-/// It may be a literal value or an identifier
-#[derive(Debug)]
+/// It may be a literal value, an identifier, the number of input parameters supplied, each
+/// input parameter in turn, or a percentage of another subject
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone)]
 pub enum Subject {
-    Value(Value),
-    Identifier(Identifier),
+    Value(Value, Location),
+    Identifier(Identifier, Location),
+    Arity(Location),
+    Quantified(Quantifier, Location),
+    /// (percentage, subject, location) .e.g `1.5 percent of $amount` is (1.5, $amount, 3:26)
+    Percentage(Value, Box<Subject>, Location),
+}
+
+/// Which of the input parameters must satisfy an assertion
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone)]
+pub enum Quantifier {
+    Every,
+    Any,
+}
+
+impl Subject {
+    /// Return the source location of this subject
+    pub fn location(&self) -> Location {
+        match self {
+            Subject::Value(_, location)
+            | Subject::Identifier(_, location)
+            | Subject::Arity(location)
+            | Subject::Quantified(_, location)
+            | Subject::Percentage(_, _, location) => *location,
+        }
+    }
 }
 
 /// A literal value, used in place for a comparator or on the RHS of a definition
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone)]
 pub enum Value {
     StringLike(String),
     Numeric(u64),
     List(Vec<Value>),
+    Bool(bool),
+    /// (value, decimals) .e.g `1.50` is (150, 2)
+    FixedPoint(u128, u8),
 }
 
 pub type Identifier = String;
+
+/// A position in the pact source, 1-indexed
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
@@ -0,0 +1,381 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! The pest parser for pact source
+//!
+
+// pest errors are large by design and are returned as-is from every parse step
+#![allow(clippy::result_large_err)]
+
+use super::ast;
+use crate::types::MAX_DECIMALS;
+use pest::error::{Error, ErrorVariant};
+use pest::Parser;
+
+#[derive(Parser)]
+#[grammar = "parser/grammar.pest"]
+pub struct PactParser;
+
+/// Attempt to parse the given `source` string as pact code.
+/// Returns an AST on success, otherwise the relevant error
+/// This is `parse_to_ast`, kept under its original name for the compiler and existing callers.
+pub fn parse(source: &str) -> Result<Vec<ast::Node>, Error<Rule>> {
+    parse_to_ast(source)
+}
+
+/// Parse the given `source` string as pact code into AST nodes, without compiling it
+/// Tools which lint, format or generate code from pact source start from here.
+pub fn parse_to_ast(source: &str) -> Result<Vec<ast::Node>, Error<Rule>> {
+    trace_span!("parse", bytes = source.len());
+    let mut ast: Vec<ast::Node> = Default::default();
+    let pairs = PactParser::parse(Rule::contract, source)?;
+    for pair in pairs {
+        trace_event!(
+            rule = ?pair.as_rule(),
+            line = pair.as_span().start_pos().line_col().0,
+            "parsed statement"
+        );
+        match pair.as_rule() {
+            Rule::input_declaration => {
+                let node = pair.into_inner();
+                ast.push(ast::Node::InputDeclaration(
+                    node.map(build_parameter).collect::<Result<_, _>>()?,
+                ))
+            }
+            Rule::assertion
+            | Rule::definition
+            | Rule::named_clause
+            | Rule::include
+            | Rule::meta => {
+                let node = build_ast_from_statement(pair)?;
+                ast.push(node);
+            }
+            Rule::EOI => {}
+            _ => {
+                panic!("unreachable: '{}'", pair.as_str());
+            }
+        }
+    }
+
+    Ok(ast)
+}
+
+/// Parse the given `source` string as a library of pact definitions, for a `compiler::Resolver`
+/// A library is a contract without input parameters or clauses, only `define` statements.
+pub fn parse_library(source: &str) -> Result<Vec<ast::Node>, Error<Rule>> {
+    trace_span!("parse_library", bytes = source.len());
+    PactParser::parse(Rule::library, source)?
+        .filter(|pair| pair.as_rule() != Rule::EOI)
+        .map(build_ast_from_statement)
+        .collect()
+}
+
+/// Build an input parameter's identifier and type from a `parameter` pair
+fn build_parameter(
+    pair: pest::iterators::Pair<Rule>,
+) -> Result<(ast::Identifier, ast::InputType), Error<Rule>> {
+    let mut inner = pair.into_inner();
+    let identifier = inner.next().unwrap().as_str().into();
+    Ok((identifier, build_input_type(inner.next())?))
+}
+
+/// Build a declared type from an optional `input_type` pest input pair
+/// Returns an error pointing at a `decimal` type with more than `MAX_DECIMALS` decimals
+fn build_input_type(
+    pair: Option<pest::iterators::Pair<Rule>>,
+) -> Result<ast::InputType, Error<Rule>> {
+    let pair = match pair {
+        Some(pair) => pair,
+        None => return Ok(ast::InputType::Any),
+    };
+    Ok(match pair.as_rule() {
+        Rule::numeric_type => ast::InputType::Numeric,
+        Rule::string_type => ast::InputType::StringLike,
+        Rule::bool_type => ast::InputType::Bool,
+        Rule::list_type => ast::InputType::List,
+        Rule::decimal_type => {
+            let places = pair.into_inner().next().unwrap();
+            match places.as_str().parse() {
+                Ok(decimals) if decimals <= MAX_DECIMALS => ast::InputType::FixedPoint(decimals),
+                _ => return Err(too_many_decimals(places.as_span())),
+            }
+        }
+        _ => ast::InputType::Any,
+    })
+}
+
+fn build_ast_from_statement(pair: pest::iterators::Pair<Rule>) -> Result<ast::Node, Error<Rule>> {
+    match pair.as_rule() {
+        Rule::assertion => Ok(ast::Node::Clause(build_assertion(pair, None)?)),
+        Rule::named_clause => {
+            let mut clause = pair.into_inner();
+            let name = clause.next().unwrap().as_str().into();
+            let assertion = build_assertion(clause.next().unwrap(), None)?;
+
+            Ok(ast::Node::NamedClause(name, assertion))
+        }
+        Rule::include => {
+            let location = build_location(&pair);
+            let name = build_string(pair.into_inner().next().unwrap())?;
+
+            Ok(ast::Node::Include(name, location))
+        }
+        Rule::meta => {
+            let location = build_location(&pair);
+            let mut meta = pair.into_inner();
+            let key = meta.next().unwrap().as_str().into();
+            let value = build_string(meta.next().unwrap())?;
+
+            Ok(ast::Node::Meta(key, value, location))
+        }
+        Rule::definition => {
+            let location = build_location(&pair);
+            let mut definition = pair.into_inner();
+            let identifier = definition.next().unwrap().as_str().into();
+            let value = definition.next().unwrap();
+            match value.as_rule() {
+                Rule::identifier => {
+                    return Ok(ast::Node::Alias(
+                        identifier,
+                        value.as_str().into(),
+                        location,
+                    ))
+                }
+                Rule::placeholder => {
+                    let input_type = build_input_type(value.into_inner().next())?;
+                    return Ok(ast::Node::Placeholder(identifier, input_type, location));
+                }
+                _ => {}
+            }
+
+            Ok(ast::Node::Definition(
+                identifier,
+                build_value(value)?,
+                location,
+            ))
+        }
+        _ => {
+            panic!(
+                "Invalid syntax. Expected assertion, named clause, include, meta, or definition"
+            );
+        }
+    }
+}
+
+// Build an `Assertion` node from a pest input pair
+// A `chained_assertion` pair has no subject of its own so takes a copy of `chained_lhs`
+fn build_assertion(
+    pair: pest::iterators::Pair<Rule>,
+    chained_lhs: Option<&ast::Subject>,
+) -> Result<ast::Assertion, Error<Rule>> {
+    let location = build_location(&pair);
+    let is_chained = pair.as_rule() == Rule::chained_assertion;
+    let mut assertion_pair = pair.into_inner();
+
+    let lhs = match chained_lhs {
+        Some(lhs) if is_chained => lhs.clone(),
+        _ => build_subject(assertion_pair.next().unwrap())?,
+    };
+
+    let imperative = match assertion_pair.next().unwrap().as_rule() {
+        Rule::must_be => ast::Imperative::MustBe,
+        Rule::must_not_be => ast::Imperative::MustNotBe,
+        _ => panic!("unreachable"),
+    };
+
+    let comparator = match assertion_pair.next().unwrap().as_rule() {
+        Rule::eq => ast::Comparator::Equal,
+        Rule::gt => ast::Comparator::GreaterThan,
+        Rule::gte => ast::Comparator::GreaterThanOrEqual,
+        Rule::lt => ast::Comparator::LessThan,
+        Rule::lte => ast::Comparator::LessThanOrEqual,
+        Rule::one_of => ast::Comparator::OneOf,
+        Rule::subset_of => ast::Comparator::SubsetOf,
+        Rule::intersecting_with => ast::Comparator::IntersectingWith,
+        _ => panic!("unreachable"),
+    };
+
+    let rhs = build_subject(assertion_pair.next().unwrap())?;
+
+    let conjoined_assertion = if let Some(c) = assertion_pair.next() {
+        let conjunctive = match c.as_rule() {
+            Rule::or => ast::Conjunctive::Or,
+            Rule::and => ast::Conjunctive::And,
+            _ => panic!("unreachable"),
+        };
+        let conjoined = build_assertion(assertion_pair.next().unwrap(), Some(&lhs))?;
+        Some((conjunctive, Box::from(conjoined)))
+    } else {
+        None
+    };
+
+    Ok(ast::Assertion {
+        lhs_subject: lhs,
+        imperative,
+        comparator,
+        rhs_subject: rhs,
+        conjoined_assertion,
+        location,
+    })
+}
+
+/// Build a `Subject` node from a pest input pair
+fn build_subject(pair: pest::iterators::Pair<Rule>) -> Result<ast::Subject, Error<Rule>> {
+    let location = build_location(&pair);
+    match pair.as_rule() {
+        Rule::identifier => Ok(ast::Subject::Identifier(pair.as_str().into(), location)),
+        Rule::value => Ok(ast::Subject::Value(build_value(pair)?, location)),
+        Rule::arity => Ok(ast::Subject::Arity(location)),
+        Rule::every_parameter => Ok(ast::Subject::Quantified(ast::Quantifier::Every, location)),
+        Rule::any_parameter => Ok(ast::Subject::Quantified(ast::Quantifier::Any, location)),
+        Rule::percentage => {
+            let mut inner = pair.into_inner();
+            let percentage = inner.next().unwrap();
+            let percentage = match percentage.as_rule() {
+                Rule::fixed_point => build_fixed_point(percentage)?,
+                _ => ast::Value::Numeric(build_integer(percentage)?),
+            };
+            let subject = build_subject(inner.next().unwrap())?;
+            Ok(ast::Subject::Percentage(
+                percentage,
+                Box::new(subject),
+                location,
+            ))
+        }
+        _ => panic!("unreachable"),
+    }
+}
+
+/// Return the source location where a pest input pair starts
+fn build_location(pair: &pest::iterators::Pair<Rule>) -> ast::Location {
+    let (line, column) = pair.as_span().start_pos().line_col();
+    ast::Location { line, column }
+}
+
+/// Build a `value` node from a pest input pair
+fn build_value(pair: pest::iterators::Pair<Rule>) -> Result<ast::Value, Error<Rule>> {
+    let value = pair.into_inner().next().unwrap();
+    match value.as_rule() {
+        Rule::string => build_string(value).map(ast::Value::StringLike),
+        Rule::integer => build_integer(value).map(ast::Value::Numeric),
+        Rule::fixed_point => build_fixed_point(value),
+        Rule::boolean => Ok(ast::Value::Bool(value.as_str() == "true")),
+        Rule::strings => Ok(ast::Value::List(
+            value
+                .into_inner()
+                .map(|s| build_string(s).map(ast::Value::StringLike))
+                .collect::<Result<_, _>>()?,
+        )),
+        Rule::integers => Ok(ast::Value::List(
+            value
+                .into_inner()
+                .map(|n| build_integer(n).map(ast::Value::Numeric))
+                .collect::<Result<_, _>>()?,
+        )),
+        _ => panic!("unreachable"),
+    }
+}
+
+/// Build a u64 from an `integer` pest input pair, in decimal or `0x` prefixed hex
+/// Digit separators are dropped. Returns an error pointing at the literal if it does not fit
+// `str::strip_prefix` is not available on our minimum supported Rust
+#[allow(clippy::manual_strip)]
+fn build_integer(pair: pest::iterators::Pair<Rule>) -> Result<u64, Error<Rule>> {
+    let digits = pair.as_str().replace('_', "");
+    let parsed = if digits.starts_with("0x") {
+        u64::from_str_radix(&digits[2..], 16)
+    } else {
+        digits.parse()
+    };
+    parsed.map_err(|_| {
+        Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: "integer literal does not fit in 64 bits".into(),
+            },
+            pair.as_span(),
+        )
+    })
+}
+
+/// Build a `FixedPoint` value from a `fixed_point` pest input pair, with a decimal per digit after
+/// the point. Returns an error pointing at the literal if it does not fit
+fn build_fixed_point(pair: pest::iterators::Pair<Rule>) -> Result<ast::Value, Error<Rule>> {
+    let digits = pair.as_str().replace('_', "");
+    let (whole, fraction) = digits.split_at(digits.find('.').unwrap());
+    let fraction = &fraction[1..];
+    if fraction.len() > usize::from(MAX_DECIMALS) {
+        return Err(too_many_decimals(pair.as_span()));
+    }
+    let value = format!("{}{}", whole, fraction).parse().map_err(|_| {
+        Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: "fixed point literal does not fit in 128 bits".into(),
+            },
+            pair.as_span(),
+        )
+    })?;
+    Ok(ast::Value::FixedPoint(value, fraction.len() as u8))
+}
+
+/// Return an error pointing at `span`, a fixed point with more than `MAX_DECIMALS` decimals
+fn too_many_decimals(span: pest::Span) -> Error<Rule> {
+    Error::new_from_span(
+        ErrorVariant::CustomError {
+            message: format!("fixed points have at most {} decimals", MAX_DECIMALS),
+        },
+        span,
+    )
+}
+
+/// Build the unescaped contents of a `string` pest input pair
+/// Returns an error pointing at the literal if a `\u{...}` escape is not a unicode scalar value
+fn build_string(pair: pest::iterators::Pair<Rule>) -> Result<String, Error<Rule>> {
+    let span = pair.as_span();
+    let characters = pair.into_inner().next().unwrap().as_str();
+    let mut unescaped = String::with_capacity(characters.len());
+    let mut chars = characters.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        // The grammar only admits valid escapes
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some('u') => {
+                let hex: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                let scalar = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(core::char::from_u32)
+                    .ok_or_else(|| {
+                        Error::new_from_span(
+                            ErrorVariant::CustomError {
+                                message: format!("`\\u{{{}}}` is not a unicode scalar value", hex),
+                            },
+                            span.clone(),
+                        )
+                    })?;
+                unescaped.push(scalar);
+            }
+            Some(c) => unescaped.push(c),
+            None => unreachable!(),
+        }
+    }
+    Ok(unescaped)
+}
@@ -0,0 +1,120 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Gating Substrate extrinsics with pact contracts
+//! A runtime implements `PactFilter` to find the contract gating a call, and calls
+//! `PactFilter::filter` from its `SignedExtension::validate` (or `TransactionExtension::validate`)
+//! with the call's arguments converted by `ToPactInputs`.
+//! Substrate isn't a dependency, so failures map to `InvalidTransaction::Custom` codes e.g.
+//! ```ignore
+//! impl SignedExtension for CheckPact<Runtime> {
+//!     // ...
+//!     fn validate(&self, _: &AccountId, call: &Call, _: &DispatchInfo, _: usize)
+//!         -> TransactionValidity
+//!     {
+//!         StoredContracts
+//!             .filter(call)
+//!             .map_err(|err| InvalidTransaction::Custom(err.code()))?;
+//!         Ok(ValidTransaction::default())
+//!     }
+//! }
+//! ```
+//!
+use crate::types::{traits::ToPactInputs, Capabilities};
+use crate::verifier::{Decision, PactErr, Verifier};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A call was refused by the contract gating it
+#[cfg_attr(feature = "std", derive(Debug, PartialEq))]
+pub enum FilterErr {
+    /// The contract rejected the call arguments, see `Decision::Rejected`
+    Rejected(Decision),
+    /// The call arguments can't be converted into `PactType`s
+    InvalidArguments,
+    /// The contract can't be verified
+    Verify(PactErr),
+}
+
+impl FilterErr {
+    /// Return an `InvalidTransaction::Custom` code for the error
+    /// Rejections are `REJECTED`, invalid arguments `INVALID_ARGUMENTS`, and verification
+    /// errors `UNVERIFIABLE`.
+    pub fn code(&self) -> u8 {
+        match self {
+            FilterErr::Rejected(_) => REJECTED,
+            FilterErr::InvalidArguments => INVALID_ARGUMENTS,
+            FilterErr::Verify(_) => UNVERIFIABLE,
+        }
+    }
+}
+
+/// The `InvalidTransaction::Custom` code of `FilterErr::Rejected`
+pub const REJECTED: u8 = 0xa0;
+/// The `InvalidTransaction::Custom` code of `FilterErr::InvalidArguments`
+pub const INVALID_ARGUMENTS: u8 = 0xa1;
+/// The `InvalidTransaction::Custom` code of `FilterErr::Verify`
+pub const UNVERIFIABLE: u8 = 0xa2;
+
+impl fmt::Display for FilterErr {
+    /// Wrapped errors are not repeated, see `source`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterErr::Rejected(_) => write!(f, "call was rejected by its contract"),
+            FilterErr::InvalidArguments => {
+                write!(f, "call arguments can't be converted to pact types")
+            }
+            FilterErr::Verify(_) => write!(f, "call contract can't be verified"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FilterErr {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FilterErr::Verify(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the pact contracts gating a runtime's calls
+pub trait PactFilter<Call: ToPactInputs> {
+    /// Return the encoded contract gating `call` e.g. from storage, or `None` to allow it
+    fn contract(&self, call: &Call) -> Option<Vec<u8>>;
+    /// Return the verifier for contracts
+    /// By default `CALL` comparators are refused as runtimes have no host functions to call.
+    fn verifier(&self) -> Verifier {
+        Verifier::new().with_capabilities(Capabilities::all().difference(Capabilities::COMP_CALL))
+    }
+    /// Check `call` against the contract gating it, if any
+    fn filter(&self, call: &Call) -> Result<(), FilterErr> {
+        let blob = match self.contract(call) {
+            Some(blob) => blob,
+            None => return Ok(()),
+        };
+        let inputs = call
+            .to_pact_inputs()
+            .map_err(|_| FilterErr::InvalidArguments)?;
+        match self.verifier().verify(&blob, &inputs) {
+            Ok(Decision::Upheld) => Ok(()),
+            Ok(rejected) => Err(FilterErr::Rejected(rejected)),
+            Err(err) => Err(FilterErr::Verify(err)),
+        }
+    }
+}
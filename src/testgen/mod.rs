@@ -0,0 +1,305 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Random input generation for fuzzing hosts
+//! Each input register's type is inferred from the comparators which load it, and values are
+//! drawn near the constants it is compared against so both outcomes of an assertion are exercised.
+//!
+use crate::interpreter::{OpCode, OpComp, OpLoad, OpQuant, ARITY_INDEX};
+use crate::types::{Contract, InputType, Numeric, PactType, StringLike};
+use alloc::{vec, vec::Vec};
+use rand::Rng;
+
+/// Random string-like inputs are slices of this buffer
+const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// The inferred type of an input register
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Numeric,
+    StringLike,
+    Bool,
+    /// Fixed points with the given # of decimals
+    FixedPoint(u8),
+}
+
+/// What is known about an input register
+struct Hint<'a> {
+    kind: Option<Kind>,
+    /// The register is a list of `kind` elements
+    list: bool,
+    numerics: Vec<u64>,
+    strings: Vec<&'a [u8]>,
+    fixed_points: Vec<u128>,
+}
+
+impl<'a> Hint<'a> {
+    /// Record a data table `value` the register is compared against
+    fn observe(&mut self, value: &PactType<'a>) {
+        match value {
+            PactType::Numeric(n) => {
+                self.kind = Some(Kind::Numeric);
+                self.numerics.push(n.0);
+            }
+            PactType::StringLike(s) => {
+                self.kind = Some(Kind::StringLike);
+                self.strings.push(s.0);
+            }
+            PactType::Bool(_) => self.kind = Some(Kind::Bool),
+            PactType::FixedPoint { value, decimals } => {
+                self.kind = Some(Kind::FixedPoint(*decimals));
+                self.fixed_points.push(*value);
+            }
+            // The register is checked for membership so it has the element type
+            PactType::List(list) | PactType::SortedList(list) => {
+                list.iter().for_each(|element| self.observe(element))
+            }
+            // An unbound placeholder has its declared type
+            PactType::Placeholder(_, input_type) => match input_type {
+                InputType::Numeric => self.kind = Some(Kind::Numeric),
+                InputType::StringLike => self.kind = Some(Kind::StringLike),
+                InputType::Bool => self.kind = Some(Kind::Bool),
+                InputType::FixedPoint(decimals) => self.kind = Some(Kind::FixedPoint(*decimals)),
+                InputType::List | InputType::Any => {}
+            },
+        }
+    }
+}
+
+/// Generate a random input register set for `contract`
+/// Bytecode after the first malformed OpCode is ignored.
+/// Every declared input parameter is generated, even if unused, so arity checks can hold.
+pub fn inputs_for<'a, R: Rng + ?Sized>(contract: &Contract<'a>, rng: &mut R) -> Vec<PactType<'a>> {
+    let table = contract.data_table().as_ref();
+    let declared = contract.input_names().map_or(0, |names| names.len());
+    let mut hints: Vec<Hint<'a>> = Vec::new();
+    // Input registers which are compared against each other share a type
+    let mut links: Vec<(usize, usize)> = Vec::new();
+
+    let mut scanner = contract.bytecode().iter();
+    while let Ok(Some(op)) = OpCode::parse(&mut scanner) {
+        if let OpCode::COMP(comparator) = op {
+            let lhs = comparator.indices.lhs as usize;
+            let rhs = comparator.indices.rhs as usize;
+            let quantified = comparator.quant != OpQuant::ONE;
+            // The arity register is loaded by the interpreter, not generated
+            let mut registers = declared;
+            if comparator.indices.lhs != ARITY_INDEX && !quantified {
+                registers = registers.max(lhs + 1);
+            }
+            if comparator.load == OpLoad::INPUT_VS_INPUT && comparator.indices.rhs != ARITY_INDEX {
+                registers = registers.max(rhs + 1);
+            }
+            while hints.len() < registers {
+                hints.push(Hint {
+                    kind: None,
+                    list: false,
+                    numerics: Vec::new(),
+                    strings: Vec::new(),
+                    fixed_points: Vec::new(),
+                });
+            }
+            if comparator.loads_arity() {
+                continue;
+            }
+            // Quantified comparators compare every input register from their LHS onward
+            let compared: Vec<usize> = if quantified {
+                (lhs..hints.len())
+                    .filter(|index| comparator.load != OpLoad::INPUT_VS_INPUT || *index != rhs)
+                    .collect()
+            } else {
+                vec![lhs]
+            };
+            for lhs in compared {
+                // Ratio comparators scale numerics
+                if let OpComp::RATIO(_) = comparator.op {
+                    hints[lhs].kind = Some(Kind::Numeric);
+                }
+                // Set comparators load lists on both sides
+                if matches!(comparator.op, OpComp::SUBSET | OpComp::INTERSECTS) {
+                    hints[lhs].list = true;
+                    if comparator.load == OpLoad::INPUT_VS_INPUT {
+                        hints[rhs].list = true;
+                    }
+                }
+                match comparator.load {
+                    // The RHS of a `CALL` is a predicate number, not a value to compare with
+                    OpLoad::INPUT_VS_USER if comparator.op == OpComp::CALL => {}
+                    OpLoad::INPUT_VS_USER => {
+                        if let Some(value) = table.get(rhs) {
+                            hints[lhs].observe(value);
+                        }
+                    }
+                    OpLoad::INPUT_VS_INPUT => links.push((lhs, rhs)),
+                }
+            }
+        }
+    }
+
+    // Propagate known types across linked registers until settled
+    for _ in 0..hints.len() {
+        for (lhs, rhs) in &links {
+            match (hints[*lhs].kind, hints[*rhs].kind) {
+                (Some(kind), None) => hints[*rhs].kind = Some(kind),
+                (None, Some(kind)) => hints[*lhs].kind = Some(kind),
+                _ => {}
+            }
+            let list = hints[*lhs].list || hints[*rhs].list;
+            hints[*lhs].list = list;
+            hints[*rhs].list = list;
+        }
+    }
+
+    hints
+        .iter()
+        .map(|hint| {
+            let kind = hint.kind.unwrap_or_else(|| {
+                if rng.gen() {
+                    Kind::Numeric
+                } else {
+                    Kind::StringLike
+                }
+            });
+            if hint.list {
+                let length = rng.gen_range(0, 4);
+                PactType::List((0..length).map(|_| random_value(kind, hint, rng)).collect())
+            } else {
+                random_value(kind, hint, rng)
+            }
+        })
+        .collect()
+}
+
+/// Return a random value of `kind`, near the constants in `hint`
+fn random_value<'a, R: Rng + ?Sized>(kind: Kind, hint: &Hint<'a>, rng: &mut R) -> PactType<'a> {
+    match kind {
+        Kind::Numeric => PactType::Numeric(Numeric(random_numeric(&hint.numerics, rng))),
+        Kind::StringLike => PactType::StringLike(StringLike(random_string(&hint.strings, rng))),
+        Kind::Bool => PactType::Bool(rng.gen()),
+        Kind::FixedPoint(decimals) => PactType::FixedPoint {
+            value: random_fixed_point(&hint.fixed_points, rng),
+            decimals,
+        },
+    }
+}
+
+/// Return a value near one of `constants` or an arbitrary value
+fn random_numeric<R: Rng + ?Sized>(constants: &[u64], rng: &mut R) -> u64 {
+    if constants.is_empty() || rng.gen_bool(0.25) {
+        return rng.gen();
+    }
+    let constant = constants[rng.gen_range(0, constants.len())];
+    match rng.gen_range(0, 3) {
+        0 => constant.saturating_sub(1),
+        1 => constant,
+        _ => constant.saturating_add(1),
+    }
+}
+
+/// Return a fixed point value near one of `constants` or an arbitrary value
+fn random_fixed_point<R: Rng + ?Sized>(constants: &[u128], rng: &mut R) -> u128 {
+    if constants.is_empty() || rng.gen_bool(0.25) {
+        return rng.gen();
+    }
+    let constant = constants[rng.gen_range(0, constants.len())];
+    match rng.gen_range(0, 3) {
+        0 => constant.saturating_sub(1),
+        1 => constant,
+        _ => constant.saturating_add(1),
+    }
+}
+
+/// Return one of `constants` or an arbitrary alphanumeric string
+fn random_string<'a, R: Rng + ?Sized>(constants: &[&'a [u8]], rng: &mut R) -> &'a [u8] {
+    if constants.is_empty() || rng.gen_bool(0.5) {
+        let start = rng.gen_range(0, ALPHABET.len());
+        let end = rng.gen_range(start, ALPHABET.len()) + 1;
+        return &ALPHABET[start..end];
+    }
+    constants[rng.gen_range(0, constants.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compiler, interpreter, parser};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn it_generates_plausible_inputs() {
+        let ast = parser::parse(
+            "given parameters $amount, $payee, $limit
+             define $payees as [\"alice\", \"bob\"]
+             $amount must be less than 100 and $payee must be one of $payees
+             $limit must be greater than or equal to $amount",
+        )
+        .unwrap();
+        let contract = compiler::compile(&ast).unwrap();
+        let mut rng = StdRng::seed_from_u64(42);
+
+        let (mut accepted, mut rejected) = (0, 0);
+        for _ in 0..200 {
+            let inputs = inputs_for(&contract, &mut rng);
+            assert_eq!(inputs.len(), 3);
+            assert!(matches!(inputs[0], PactType::Numeric(_)));
+            assert!(matches!(inputs[1], PactType::StringLike(_)));
+            // Inferred from the comparison with `$amount`
+            assert!(matches!(inputs[2], PactType::Numeric(_)));
+
+            let result = interpreter::interpret(
+                &inputs,
+                contract.data_table().as_ref(),
+                contract.bytecode(),
+            );
+            match result {
+                Ok(true) => accepted += 1,
+                Ok(false) => rejected += 1,
+                Err(err) => panic!("inputs: {:?} errored: {:?}", inputs, err),
+            }
+        }
+        assert!(accepted > 0 && rejected > 0);
+    }
+
+    #[test]
+    fn it_generates_lists_for_set_comparators() {
+        let ast = parser::parse(
+            "given parameters $requested, $granted
+             $requested must be a subset of [1, 2, 3]
+             $granted must be a subset of [2, 3, 4] and $requested must be intersecting with $granted",
+        )
+        .unwrap();
+        let contract = compiler::compile(&ast).unwrap();
+        let mut rng = StdRng::seed_from_u64(42);
+
+        let (mut accepted, mut rejected) = (0, 0);
+        for _ in 0..200 {
+            let inputs = inputs_for(&contract, &mut rng);
+            assert!(matches!(inputs[0], PactType::List(_)));
+            assert!(matches!(inputs[1], PactType::List(_)));
+            match interpreter::interpret(
+                &inputs,
+                contract.data_table().as_ref(),
+                contract.bytecode(),
+            ) {
+                Ok(true) => accepted += 1,
+                Ok(false) => rejected += 1,
+                Err(err) => panic!("inputs: {:?} errored: {:?}", inputs, err),
+            }
+        }
+        assert!(accepted > 0 && rejected > 0);
+    }
+}
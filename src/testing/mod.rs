@@ -0,0 +1,210 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Helpers for assembling pact bytecode in tests, for this crate and downstream crates
+//! Comparators load their LHS from an input register and their RHS from an input register or
+//! the data table e.g.
+//! ```
+//! use pact::testing::*;
+//! // INPUT(1) == USER(0) AND INPUT(0) < INPUT(1)
+//! let code = bytecode(&[
+//!     comp_eq(input(1), user(0)),
+//!     conj_and(),
+//!     comp_lt(input(0), input(1)),
+//! ]);
+//! assert_eq!(code, vec![0x00, 0x10, 0x20, 0x1a, 0x01]);
+//! ```
+//!
+use crate::interpreter::{Comparator, Conjunction, OpCode, OpComp, OpConj, OpLoad, MAX_INDEX};
+use alloc::vec::Vec;
+
+/// An input register
+#[derive(Clone, Copy)]
+pub struct Input(pub u8);
+
+/// A data table entry
+#[derive(Clone, Copy)]
+pub struct User(pub u8);
+
+/// The input register at `index`
+pub fn input(index: u8) -> Input {
+    Input(index)
+}
+
+/// The data table entry at `index`
+pub fn user(index: u8) -> User {
+    User(index)
+}
+
+/// A comparator RHS, either an `Input` or a `User` entry
+pub trait Operand {
+    /// Return the load to use and the RHS index
+    fn load(self) -> (OpLoad, u8);
+}
+
+impl Operand for Input {
+    fn load(self) -> (OpLoad, u8) {
+        (OpLoad::INPUT_VS_INPUT, self.0)
+    }
+}
+
+impl Operand for User {
+    fn load(self) -> (OpLoad, u8) {
+        (OpLoad::INPUT_VS_USER, self.0)
+    }
+}
+
+/// Concatenate OpCodes into bytecode
+pub fn bytecode(ops: &[Vec<u8>]) -> Vec<u8> {
+    ops.concat()
+}
+
+/// Assemble `comparator` comparing `lhs` to `rhs`, with its OpCode's load and indices set
+/// Panics if an index can't be encoded.
+pub fn comp(comparator: Comparator, lhs: Input, rhs: impl Operand) -> Vec<u8> {
+    let (load, rhs) = rhs.load();
+    let comparator = comparator
+        .load(load)
+        .try_indices(lhs.0, rhs)
+        .unwrap_or_else(|_| panic!("comparator indices must be less than {}", MAX_INDEX + 1));
+    let mut buf = Vec::with_capacity(OpCode::COMP(comparator).encoded_len());
+    OpCode::COMP(comparator)
+        .compile(&mut buf)
+        .unwrap_or_else(|_| unreachable!("indices are checked"));
+    buf
+}
+
+/// `lhs == rhs`
+pub fn comp_eq(lhs: Input, rhs: impl Operand) -> Vec<u8> {
+    comp(Comparator::new(OpComp::EQ), lhs, rhs)
+}
+
+/// `lhs != rhs`
+pub fn comp_neq(lhs: Input, rhs: impl Operand) -> Vec<u8> {
+    comp(Comparator::new(OpComp::EQ).invert(), lhs, rhs)
+}
+
+/// `lhs > rhs`
+pub fn comp_gt(lhs: Input, rhs: impl Operand) -> Vec<u8> {
+    comp(Comparator::new(OpComp::GT), lhs, rhs)
+}
+
+/// `lhs >= rhs`
+pub fn comp_gte(lhs: Input, rhs: impl Operand) -> Vec<u8> {
+    comp(Comparator::new(OpComp::GTE), lhs, rhs)
+}
+
+/// `lhs < rhs`, encoded as `NOT GTE`
+pub fn comp_lt(lhs: Input, rhs: impl Operand) -> Vec<u8> {
+    comp(Comparator::new(OpComp::GTE).invert(), lhs, rhs)
+}
+
+/// `lhs <= rhs`, encoded as `NOT GT`
+pub fn comp_lte(lhs: Input, rhs: impl Operand) -> Vec<u8> {
+    comp(Comparator::new(OpComp::GT).invert(), lhs, rhs)
+}
+
+/// `lhs` is one of the list `rhs`
+pub fn comp_in(lhs: Input, rhs: impl Operand) -> Vec<u8> {
+    comp(Comparator::new(OpComp::IN), lhs, rhs)
+}
+
+/// `lhs` is not one of the list `rhs`
+pub fn comp_nin(lhs: Input, rhs: impl Operand) -> Vec<u8> {
+    comp(Comparator::new(OpComp::IN).invert(), lhs, rhs)
+}
+
+/// Every element of the list `lhs` is one of the list `rhs`
+pub fn comp_subset(lhs: Input, rhs: impl Operand) -> Vec<u8> {
+    comp(Comparator::new(OpComp::SUBSET), lhs, rhs)
+}
+
+/// The lists `lhs` and `rhs` share an element
+pub fn comp_intersects(lhs: Input, rhs: impl Operand) -> Vec<u8> {
+    comp(Comparator::new(OpComp::INTERSECTS), lhs, rhs)
+}
+
+/// The host predicate numbered by `rhs` holds for `lhs`
+pub fn comp_call(lhs: Input, rhs: User) -> Vec<u8> {
+    comp(Comparator::new(OpComp::CALL), lhs, rhs)
+}
+
+/// `lhs` compared by `comparator` with `rhs` scaled by the ratio at data table entry `ratio`
+/// e.g. `comp_ratio(Comparator::new(OpComp::GTE).invert(), lhs, rhs, ratio)` for
+/// `lhs < rhs * ratio`, see `OpComp::RATIO`. Only `EQ`, `GT` and `GTE` comparators are scaled.
+/// Panics if an index can't be encoded.
+pub fn comp_ratio(comparator: Comparator, lhs: Input, rhs: impl Operand, ratio: User) -> Vec<u8> {
+    if ratio.0 > MAX_INDEX {
+        panic!("comparator indices must be less than {}", MAX_INDEX + 1);
+    }
+    comp(comparator.scaled(ratio.0), lhs, rhs)
+}
+
+/// Assemble `conjunction`
+pub fn conj(conjunction: Conjunction) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(1);
+    OpCode::CONJ(conjunction)
+        .compile(&mut buf)
+        .unwrap_or_else(|_| unreachable!("conjunctions have no indices"));
+    buf
+}
+
+/// `AND` conjunction
+pub fn conj_and() -> Vec<u8> {
+    conj(Conjunction::new(OpConj::AND))
+}
+
+/// `OR` conjunction
+pub fn conj_or() -> Vec<u8> {
+    conj(Conjunction::new(OpConj::OR))
+}
+
+/// `XOR` conjunction
+pub fn conj_xor() -> Vec<u8> {
+    conj(Conjunction::new(OpConj::XOR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_assembles_comparators() {
+        assert_eq!(comp_eq(input(1), user(0)), vec![0x00, 0x10]);
+        assert_eq!(comp_neq(input(0), user(2)), vec![0x10, 0x02]);
+        assert_eq!(comp_gt(input(3), user(1)), vec![0x01, 0x31]);
+        assert_eq!(comp_lt(input(2), user(3)), vec![0x12, 0x23]);
+        assert_eq!(comp_lte(input(0), input(15)), vec![0x19, 0x0f]);
+        assert_eq!(comp_nin(input(1), user(1)), vec![0x13, 0x11]);
+        assert_eq!(comp_call(input(0), user(0)), vec![0x04, 0x00]);
+    }
+
+    #[test]
+    fn it_assembles_conjunctions() {
+        assert_eq!(
+            bytecode(&[conj_and(), conj_or(), conj_xor()]),
+            vec![0x20, 0x21, 0x22]
+        );
+        assert_eq!(conj(Conjunction::new(OpConj::AND).invert()), vec![0x30]);
+    }
+
+    #[test]
+    #[should_panic(expected = "comparator indices must be less than 16")]
+    fn it_rejects_wide_indices() {
+        comp_eq(input(16), user(0));
+    }
+}
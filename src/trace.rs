@@ -0,0 +1,41 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Structured tracing of the parser and compiler passes, and interpreted clauses
+//! With the `trace` feature these macros emit debug level `tracing` spans and events, otherwise
+//! they expand to nothing so call sites need no `cfg` attributes.
+//!
+
+/// Emit a debug level `tracing` event
+macro_rules! trace_event {
+    ($($field:tt)*) => {
+        #[cfg(feature = "trace")]
+        tracing::debug!($($field)*);
+    };
+}
+
+/// Enter a debug level `tracing` span until the end of the enclosing block
+// Only the parser and compiler enter spans for a block
+#[cfg_attr(not(feature = "compiler"), allow(unused_macros))]
+macro_rules! trace_span {
+    ($($field:tt)*) => {
+        #[cfg(feature = "trace")]
+        let span = tracing::debug_span!($($field)*);
+        #[cfg(feature = "trace")]
+        let _entered = span.enter();
+    };
+}
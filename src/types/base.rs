@@ -17,135 +17,788 @@
 //!
 //! Types in the pact interpreter aka "PactType"s
 //!
+use crate::types::{InputType, MAX_ENTRIES};
 use alloc::vec::Vec;
 use bit_reverse::ParallelReverse;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+
+/// The maximum depth of nested `List`s accepted by `PactType::decode`
+/// A top-level list has depth 1
+pub const MAX_LIST_DEPTH: usize = 8;
+
+/// The most elements in one list accepted by `PactType::decode`
+/// As many as the smallest elements which fit in a list with 2 byte lengths.
+pub const MAX_LIST_ELEMENTS: usize = 0xffff / 3;
+
+/// The decoding error for lists nested deeper than `DecodeLimits::max_depth`
+pub const LIST_TOO_DEEP: &str = "list nesting too deep";
+
+/// The decoding error for lists with more elements than `DecodeLimits::max_elements`
+pub const LIST_TOO_LONG: &str = "list has too many elements";
+
+/// Limits on the lists accepted by `PactType::decode_with_limits`
+/// Hosts decoding untrusted values may tighten them to bound decoding time and memory.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct DecodeLimits {
+    /// The deepest nesting of lists, a top-level list has depth 1
+    pub max_depth: usize,
+    /// The most elements in any one list
+    pub max_elements: usize,
+}
+
+impl Default for DecodeLimits {
+    /// `MAX_LIST_DEPTH` and `MAX_LIST_ELEMENTS`, which any encodable value is within
+    fn default() -> Self {
+        DecodeLimits {
+            max_depth: MAX_LIST_DEPTH,
+            max_elements: MAX_LIST_ELEMENTS,
+        }
+    }
+}
+
+/// The most decimals of a fixed point literal or `InputType::FixedPoint`
+/// `u128` holds every 38 digit value, so any such fraction can be represented.
+pub const MAX_DECIMALS: u8 = 38;
+
+/// The largest length or count which fits in its single encoded byte
+pub(crate) const MAX_ENCODED_LENGTH: usize = 255;
 
 /// A string-like type
 #[cfg_attr(feature = "std", derive(Debug))]
-#[derive(PartialEq, PartialOrd, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct StringLike<'a>(pub &'a [u8]);
 
 /// A numeric type
 #[cfg_attr(feature = "std", derive(Debug))]
-#[derive(PartialEq, PartialOrd, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct Numeric(pub u64);
 
 /// Over-arching pact type system
+/// Values are floating point free, so equality is total and `PactType`s can key sets and maps.
+/// Equal values hash equally, a `List` and `SortedList` of the same elements are not equal.
 #[cfg_attr(feature = "std", derive(Debug))]
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub enum PactType<'a> {
     StringLike(StringLike<'a>),
     Numeric(Numeric),
     List(Vec<PactType<'a>>),
+    Bool(bool),
+    /// Numerics or string-likes in strictly ascending order, see `PactType::sorted_list`
+    /// `IN` comparisons binary search it rather than scanning every element.
+    SortedList(Vec<PactType<'a>>),
+    /// A named data table slot of a declared type, bound to a value by `Contract::bind`
+    /// Loading an unbound placeholder is an interpreter error.
+    Placeholder(&'a str, InputType),
+    /// The decimal `value / 10^decimals` e.g. `1.50` is `{ value: 150, decimals: 2 }`
+    /// Comparators normalise scales so `1.5` equals `1.50`, and `Numeric`s compare as fixed points
+    /// with no decimals. `PartialEq` and list membership compare values exactly, as encoded.
+    FixedPoint {
+        value: u128,
+        decimals: u8,
+    },
+}
+
+/// A `PactType`, `DataTable`, or `Contract` encoding error
+/// Lengths and counts are encoded in one or two bytes so larger values can't be represented.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum EncodeErr {
+    /// A string-like value is longer than its length prefix allows
+    StringTooLong,
+    /// A list's encoded elements are longer than its length prefix allows
+    ListTooLarge,
+    /// Lists are nested deeper than `MAX_LIST_DEPTH`
+    ListTooDeep,
+    /// The data table has more than `MAX_ENTRIES` entries
+    DataTableTooLong,
+    /// The contract has more than 255 input parameters
+    TooManyInputs,
+    /// An input parameter or clause name is longer than 255 bytes
+    NameTooLong,
+    /// The contract has more than 255 clause names, or a clause index above 65535
+    TooManyClauses,
+    /// The contract has more than 255 metadata fields, or a field is longer than 255 bytes
+    MetadataTooLong,
+    /// The output buffer is shorter than the `required` encoded length
+    BufferTooShort { required: usize },
+    /// A `SortedList` is not strictly ascending, or has elements other than numerics or
+    /// string-likes of one type
+    UnsortedList,
+}
+
+impl fmt::Display for EncodeErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeErr::StringTooLong => write!(f, "string is too long to encode"),
+            EncodeErr::ListTooLarge => write!(f, "list is too large to encode"),
+            EncodeErr::ListTooDeep => {
+                write!(f, "lists are nested more than {} deep", MAX_LIST_DEPTH)
+            }
+            EncodeErr::DataTableTooLong => {
+                write!(f, "data table has more than {} entries", MAX_ENTRIES)
+            }
+            EncodeErr::TooManyInputs => write!(f, "contract has more than 255 input parameters"),
+            EncodeErr::NameTooLong => {
+                write!(f, "input parameter or clause name is longer than 255 bytes")
+            }
+            EncodeErr::TooManyClauses => write!(f, "contract has too many clauses to name"),
+            EncodeErr::MetadataTooLong => write!(
+                f,
+                "metadata has more than 255 fields, or a field longer than 255 bytes"
+            ),
+            EncodeErr::BufferTooShort { required } => {
+                write!(f, "buffer is too short, {} bytes are required", required)
+            }
+            EncodeErr::UnsortedList => write!(
+                f,
+                "sorted list is not strictly ascending numerics or string-likes"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeErr {}
+
+/// A `PactType` is not of the type it's being converted to
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct ExtractErr {
+    /// The type conversion requires
+    pub expected: InputType,
+}
+
+impl fmt::Display for ExtractErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value is not of type {}", self.expected)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ExtractErr {}
+
+impl<'a> TryFrom<PactType<'a>> for u64 {
+    type Error = ExtractErr;
+    fn try_from(value: PactType<'a>) -> Result<Self, ExtractErr> {
+        value.as_numeric().ok_or(ExtractErr {
+            expected: InputType::Numeric,
+        })
+    }
+}
+
+impl<'a> TryFrom<PactType<'a>> for &'a [u8] {
+    type Error = ExtractErr;
+    fn try_from(value: PactType<'a>) -> Result<Self, ExtractErr> {
+        value.as_str_bytes().ok_or(ExtractErr {
+            expected: InputType::StringLike,
+        })
+    }
+}
+
+/// `SortedList`s convert to their elements, in order
+impl<'a> TryFrom<PactType<'a>> for Vec<PactType<'a>> {
+    type Error = ExtractErr;
+    fn try_from(value: PactType<'a>) -> Result<Self, ExtractErr> {
+        match value {
+            PactType::List(values) | PactType::SortedList(values) => Ok(values),
+            _ => Err(ExtractErr {
+                expected: InputType::List,
+            }),
+        }
+    }
+}
+
+impl<'a> TryFrom<PactType<'a>> for bool {
+    type Error = ExtractErr;
+    fn try_from(value: PactType<'a>) -> Result<Self, ExtractErr> {
+        value.as_bool().ok_or(ExtractErr {
+            expected: InputType::Bool,
+        })
+    }
+}
+
+/// A destination for encoded bytes
+pub(crate) trait Sink {
+    /// Write `byte`
+    fn put(&mut self, byte: u8);
+    /// Write `bytes`
+    fn put_slice(&mut self, bytes: &[u8]);
+}
+
+impl Sink for Vec<u8> {
+    fn put(&mut self, byte: u8) {
+        self.push(byte);
+    }
+    fn put_slice(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+/// Counts encoded bytes without writing them
+#[derive(Default)]
+pub(crate) struct ByteCounter(pub usize);
+
+impl Sink for ByteCounter {
+    fn put(&mut self, _byte: u8) {
+        self.0 += 1;
+    }
+    fn put_slice(&mut self, bytes: &[u8]) {
+        self.0 += bytes.len();
+    }
+}
+
+/// Writes encoded bytes into a slice, which must be long enough to hold them
+pub(crate) struct SliceSink<'b> {
+    buf: &'b mut [u8],
+    written: usize,
+}
+
+impl<'b> Sink for SliceSink<'b> {
+    fn put(&mut self, byte: u8) {
+        self.buf[self.written] = byte;
+        self.written += 1;
+    }
+    fn put_slice(&mut self, bytes: &[u8]) {
+        self.buf[self.written..self.written + bytes.len()].copy_from_slice(bytes);
+        self.written += bytes.len();
+    }
+}
+
+/// Write an encoding of `length` bytes into the start of `buf` with `write`
+/// Returns `length`, `buf` is unchanged if it is too short.
+pub(crate) fn encode_into(
+    buf: &mut [u8],
+    length: usize,
+    write: impl FnOnce(&mut dyn Sink) -> Result<(), EncodeErr>,
+) -> Result<usize, EncodeErr> {
+    if length > buf.len() {
+        return Err(EncodeErr::BufferTooShort { required: length });
+    }
+    write(&mut SliceSink {
+        buf: &mut buf[..length],
+        written: 0,
+    })?;
+    Ok(length)
+}
+
+/// The width of encoded `PactType` length prefixes
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum LengthWidth {
+    /// 1 byte lengths, used by the v0, v1, and v2 contract formats
+    U8,
+    /// 2 byte LE lengths, used by the v3 contract format
+    U16,
+}
+
+impl LengthWidth {
+    /// The largest length which fits in the prefix
+    pub fn max_length(self) -> usize {
+        match self {
+            LengthWidth::U8 => MAX_ENCODED_LENGTH,
+            LengthWidth::U16 => 0xffff,
+        }
+    }
+    /// The # of bytes in the prefix
+    fn bytes(self) -> usize {
+        match self {
+            LengthWidth::U8 => 1,
+            LengthWidth::U16 => 2,
+        }
+    }
+    /// Write `length` into `sink`, it must be at most `max_length`
+    fn push(self, length: usize, sink: &mut dyn Sink) {
+        match self {
+            LengthWidth::U8 => sink.put((length as u8).swap_bits()),
+            LengthWidth::U16 => {
+                for b in (length as u16).to_le_bytes().iter() {
+                    sink.put(b.swap_bits())
+                }
+            }
+        }
+    }
+    /// Read a length from the start of `buf`
+    fn read(self, buf: &[u8]) -> Option<usize> {
+        match self {
+            LengthWidth::U8 => buf.first().map(|b| b.swap_bits() as usize),
+            LengthWidth::U16 => match buf {
+                [lo, hi, ..] => Some(u16::from_le_bytes([lo.swap_bits(), hi.swap_bits()]) as usize),
+                _ => None,
+            },
+        }
+    }
 }
 
 impl<'a> PactType<'a> {
-    /// Encode the PactType into `buf`c
-    pub fn encode(&self, buf: &mut Vec<u8>) {
+    /// Sort and deduplicate `values` into a `SortedList`
+    /// Returns `None` unless `values` are all numerics or all string-likes.
+    pub fn sorted_list(mut values: Vec<PactType<'a>>) -> Option<Self> {
+        let homogeneous = values
+            .windows(2)
+            .all(|pair| compare_sorted(&pair[0], &pair[1]).is_some());
+        let scalar = matches!(
+            values.first(),
+            Some(PactType::Numeric(_)) | Some(PactType::StringLike(_)) | None
+        );
+        if !homogeneous || !scalar {
+            return None;
+        }
+        values.sort_by(|a, b| compare_sorted(a, b).unwrap_or(Ordering::Equal));
+        values.dedup();
+        Some(PactType::SortedList(values))
+    }
+    /// Return the value of a `Numeric`
+    pub fn as_numeric(&self) -> Option<u64> {
+        match self {
+            PactType::Numeric(Numeric(value)) => Some(*value),
+            _ => None,
+        }
+    }
+    /// Return the value and decimals of a `FixedPoint`, or a `Numeric` with no decimals
+    pub fn as_fixed_point(&self) -> Option<(u128, u8)> {
+        match self {
+            PactType::Numeric(Numeric(value)) => Some((u128::from(*value), 0)),
+            PactType::FixedPoint { value, decimals } => Some((*value, *decimals)),
+            _ => None,
+        }
+    }
+    /// Return the bytes of a `StringLike`
+    pub fn as_str_bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            PactType::StringLike(StringLike(bytes)) => Some(bytes),
+            _ => None,
+        }
+    }
+    /// Return the elements of a `List` or `SortedList`
+    pub fn as_list(&self) -> Option<&[PactType<'a>]> {
+        match self {
+            PactType::List(values) | PactType::SortedList(values) => Some(values),
+            _ => None,
+        }
+    }
+    /// Return the value of a `Bool`
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            PactType::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+    /// Encode the PactType into `buf` with 1 byte lengths
+    /// `buf` is unchanged on failure.
+    pub fn encode(&self, buf: &mut Vec<u8>) -> Result<(), EncodeErr> {
+        self.encode_as(buf, LengthWidth::U8)
+    }
+    /// Encode the PactType into `buf` with `width` lengths
+    /// `buf` is unchanged on failure.
+    pub fn encode_as(&self, buf: &mut Vec<u8>, width: LengthWidth) -> Result<(), EncodeErr> {
+        buf.reserve(self.encoded_len_as(width)?);
+        self.write(buf, width)
+    }
+    /// Encode the PactType with 1 byte lengths into the start of `buf`
+    /// Returns the # of bytes written, `buf` is unchanged on failure.
+    pub fn encode_to(&self, buf: &mut [u8]) -> Result<usize, EncodeErr> {
+        encode_into(buf, self.encoded_len()?, |sink| {
+            self.write(sink, LengthWidth::U8)
+        })
+    }
+    /// Return the # of bytes `encode` would write
+    pub fn encoded_len(&self) -> Result<usize, EncodeErr> {
+        self.encoded_len_as(LengthWidth::U8)
+    }
+    /// Return the # of bytes `encode_as` would write with `width` lengths
+    /// Fails as `encode_as` would, without encoding.
+    pub fn encoded_len_as(&self, width: LengthWidth) -> Result<usize, EncodeErr> {
+        self.len_nested(width, 0)
+    }
+    /// Return the encoded length of the PactType inside `depth` enclosing lists
+    fn len_nested(&self, width: LengthWidth, depth: usize) -> Result<usize, EncodeErr> {
+        let length = match self {
+            PactType::StringLike(s) => {
+                if s.0.len() > width.max_length() {
+                    return Err(EncodeErr::StringTooLong);
+                }
+                s.0.len()
+            }
+            PactType::Numeric(_) => 8,
+            PactType::List(l) => elements_len(l, width, depth)?,
+            PactType::Bool(_) => 1,
+            PactType::SortedList(l) => {
+                if !is_sorted(l) {
+                    return Err(EncodeErr::UnsortedList);
+                }
+                elements_len(l, width, depth)?
+            }
+            PactType::Placeholder(name, _) => {
+                // 1 byte type code + name
+                if 1 + name.len() > width.max_length() {
+                    return Err(EncodeErr::NameTooLong);
+                }
+                1 + name.len()
+            }
+            // 16 byte value + 1 byte decimals
+            PactType::FixedPoint { .. } => 17,
+        };
+        // 1 byte type ID + length bytes
+        Ok(1 + width.bytes() + length)
+    }
+    /// Write the PactType encoding into `sink` with `width` lengths
+    pub(crate) fn write(&self, sink: &mut dyn Sink, width: LengthWidth) -> Result<(), EncodeErr> {
+        self.write_nested(sink, width, 0)
+    }
+    /// Write the PactType encoding into `sink` inside `depth` enclosing lists
+    fn write_nested(
+        &self,
+        sink: &mut dyn Sink,
+        width: LengthWidth,
+        depth: usize,
+    ) -> Result<(), EncodeErr> {
         match self {
             PactType::StringLike(s) => {
-                buf.push(0);
-                buf.push((s.0.len() as u8).swap_bits());
-                buf.extend(s.0.iter());
+                if s.0.len() > width.max_length() {
+                    return Err(EncodeErr::StringTooLong);
+                }
+                sink.put(0);
+                width.push(s.0.len(), sink);
+                sink.put_slice(s.0);
             }
             PactType::Numeric(n) => {
-                buf.push(1.swap_bits());
+                sink.put(1.swap_bits());
                 // only supporting 64-bit numeric here.
-                buf.push(8.swap_bits());
+                width.push(8, sink);
                 for b in n.0.to_le_bytes().iter() {
-                    buf.push(b.swap_bits())
+                    sink.put(b.swap_bits())
                 }
             }
             PactType::List(l) => {
-                let mut buf_elements: Vec<u8> = Vec::<u8>::default();
-                for element in l {
-                    match element {
-                        PactType::StringLike(_) => element.encode(&mut buf_elements),
-                        PactType::Numeric(_) => element.encode(&mut buf_elements),
-                        _ => {} // element not supported
-                    }
+                let length = elements_len(l, width, depth)?;
+                sink.put(2.swap_bits());
+                width.push(length, sink);
+                write_elements(l, sink, width, depth)?;
+            }
+            PactType::Bool(b) => {
+                sink.put(3.swap_bits());
+                width.push(1, sink);
+                sink.put((*b as u8).swap_bits());
+            }
+            PactType::SortedList(l) => {
+                if !is_sorted(l) {
+                    return Err(EncodeErr::UnsortedList);
                 }
-
-                buf.push(2.swap_bits());
-                buf.push((buf_elements.len() as u8).swap_bits());
-                buf.append(&mut buf_elements);
-
-                //panic!("todo");
+                let length = elements_len(l, width, depth)?;
+                sink.put(4.swap_bits());
+                width.push(length, sink);
+                write_elements(l, sink, width, depth)?;
+            }
+            PactType::Placeholder(name, input_type) => {
+                if 1 + name.len() > width.max_length() {
+                    return Err(EncodeErr::NameTooLong);
+                }
+                sink.put(5.swap_bits());
+                width.push(1 + name.len(), sink);
+                sink.put(input_type.code().swap_bits());
+                sink.put_slice(name.as_bytes());
+            }
+            PactType::FixedPoint { value, decimals } => {
+                sink.put(6.swap_bits());
+                width.push(17, sink);
+                for b in value.to_le_bytes().iter() {
+                    sink.put(b.swap_bits())
+                }
+                sink.put(decimals.swap_bits());
             }
         };
+        Ok(())
     }
-    /// Decode a pact type from the given buffer
+    /// Decode a pact type with 1 byte lengths from the given buffer
     /// Returns (decoded type, bytes read) or error on failure
+    /// Lists may be nested up to `MAX_LIST_DEPTH` deep
     pub fn decode(buf: &'a [u8]) -> Result<(Self, usize), &'static str> {
+        Self::decode_as(buf, LengthWidth::U8)
+    }
+    /// Decode a pact type with `width` lengths from the given buffer
+    /// Returns (decoded type, bytes read) or error on failure
+    pub fn decode_as(buf: &'a [u8], width: LengthWidth) -> Result<(Self, usize), &'static str> {
+        Self::decode_with_limits(buf, width, DecodeLimits::default())
+    }
+    /// Decode a pact type with `width` lengths from the given buffer, within `limits`
+    /// Returns (decoded type, bytes read) or error on failure, `LIST_TOO_DEEP` or `LIST_TOO_LONG`
+    /// if a list exceeds the limits
+    pub fn decode_with_limits(
+        buf: &'a [u8],
+        width: LengthWidth,
+        limits: DecodeLimits,
+    ) -> Result<(Self, usize), &'static str> {
+        Self::decode_nested(buf, width, &limits, 0)
+    }
+    /// Decode a pact type from the given buffer inside `depth` enclosing lists
+    fn decode_nested(
+        buf: &'a [u8],
+        width: LengthWidth,
+        limits: &DecodeLimits,
+        depth: usize,
+    ) -> Result<(Self, usize), &'static str> {
         // Check type header bytes
-        match buf.len() {
-            0 => return Err("missing type ID byte"),
-            1 => return Err("missing type length byte"),
-            _ => (),
-        };
-
-        // 1 byte type ID + 1 byte length gives 2 offset
-        let mut read_offset = 2_usize;
+        if buf.is_empty() {
+            return Err("missing type ID byte");
+        }
 
-        // Read length byte
-        let data_length = buf[1].swap_bits() as usize;
+        // Read length bytes
+        let data_length = width.read(&buf[1..]).ok_or("missing type length byte")?;
+        // 1 byte type ID + length bytes
+        let mut read_offset = 1 + width.bytes();
         if data_length > buf[read_offset..].len() {
             return Err("type length > buffer length");
         }
+        let data = &buf[read_offset..read_offset + data_length];
 
         // Read type ID byte
         match buf[0].swap_bits() {
             0 => {
-                let read_length = read_offset + data_length;
-                let s = PactType::StringLike(StringLike(&buf[read_offset..read_length]));
-                Ok((s, read_length))
+                let s = PactType::StringLike(StringLike(data));
+                Ok((s, read_offset + data_length))
             }
             1 => {
-                let data_length = buf[1].swap_bits() as usize;
                 if data_length != 8 {
                     return Err("implementation only supports 64-bit numerics");
                 }
 
                 let n = PactType::Numeric(Numeric(u64::from_le_bytes([
-                    buf[2].swap_bits(),
-                    buf[3].swap_bits(),
-                    buf[4].swap_bits(),
-                    buf[5].swap_bits(),
-                    buf[6].swap_bits(),
-                    buf[7].swap_bits(),
-                    buf[8].swap_bits(),
-                    buf[9].swap_bits(),
+                    data[0].swap_bits(),
+                    data[1].swap_bits(),
+                    data[2].swap_bits(),
+                    data[3].swap_bits(),
+                    data[4].swap_bits(),
+                    data[5].swap_bits(),
+                    data[6].swap_bits(),
+                    data[7].swap_bits(),
                 ])));
-                Ok((n, 10usize))
+                Ok((n, read_offset + data_length))
             }
             2 => {
-                let mut values: Vec<PactType> = Vec::<PactType>::default();
-                let mut remaining_length = data_length;
-
-                while remaining_length > 0 {
-                    let (new_value, offset) = Self::decode(&buf[read_offset..])?;
-                    read_offset = read_offset + offset;
-                    remaining_length = remaining_length
-                        .checked_sub(offset)
-                        .ok_or("list length overflow")?;
-                    values.push(new_value);
-                }
+                let values = Self::decode_elements(
+                    buf,
+                    width,
+                    limits,
+                    depth,
+                    &mut read_offset,
+                    data_length,
+                )?;
                 Ok((PactType::List(values), read_offset))
             }
+            3 => {
+                if data_length != 1 {
+                    return Err("bool length must be 1");
+                }
+                match data[0].swap_bits() {
+                    0 => Ok((PactType::Bool(false), read_offset + 1)),
+                    1 => Ok((PactType::Bool(true), read_offset + 1)),
+                    _ => Err("bool must be 0 or 1"),
+                }
+            }
+            4 => {
+                let values = Self::decode_elements(
+                    buf,
+                    width,
+                    limits,
+                    depth,
+                    &mut read_offset,
+                    data_length,
+                )?;
+                if !is_sorted(&values) {
+                    return Err("sorted list is not sorted");
+                }
+                Ok((PactType::SortedList(values), read_offset))
+            }
+            5 => {
+                let (code, name) = data.split_first().ok_or("missing placeholder type byte")?;
+                let input_type =
+                    InputType::from_code(code.swap_bits()).ok_or("unknown placeholder type")?;
+                let name = core::str::from_utf8(name)
+                    .map_err(|_| "placeholder name is not valid UTF-8")?;
+                Ok((
+                    PactType::Placeholder(name, input_type),
+                    read_offset + data_length,
+                ))
+            }
+            6 => {
+                if data_length != 17 {
+                    return Err("fixed point length must be 17");
+                }
+                let mut value = [0u8; 16];
+                for (byte, encoded) in value.iter_mut().zip(data) {
+                    *byte = encoded.swap_bits();
+                }
+                let fixed_point = PactType::FixedPoint {
+                    value: u128::from_le_bytes(value),
+                    decimals: data[16].swap_bits(),
+                };
+                Ok((fixed_point, read_offset + data_length))
+            }
             _ => Err("unsupported type ID"),
         }
     }
+    /// Decode `data_length` bytes of list elements from `buf` at `read_offset`, advancing it
+    fn decode_elements(
+        buf: &'a [u8],
+        width: LengthWidth,
+        limits: &DecodeLimits,
+        depth: usize,
+        read_offset: &mut usize,
+        data_length: usize,
+    ) -> Result<Vec<Self>, &'static str> {
+        if depth >= limits.max_depth {
+            return Err(LIST_TOO_DEEP);
+        }
+        let mut values: Vec<PactType> = Vec::<PactType>::default();
+        let mut remaining_length = data_length;
+
+        while remaining_length > 0 {
+            if values.len() >= limits.max_elements {
+                return Err(LIST_TOO_LONG);
+            }
+            let element = buf.get(*read_offset..).ok_or("missing type ID byte")?;
+            let (new_value, offset) = Self::decode_nested(element, width, limits, depth + 1)?;
+            *read_offset += offset;
+            remaining_length = remaining_length
+                .checked_sub(offset)
+                .ok_or("list length overflow")?;
+            values.push(new_value);
+        }
+        Ok(values)
+    }
+}
+
+/// Return the encoded length of the elements of a list inside `depth` enclosing lists
+fn elements_len(list: &[PactType], width: LengthWidth, depth: usize) -> Result<usize, EncodeErr> {
+    if depth >= MAX_LIST_DEPTH {
+        return Err(EncodeErr::ListTooDeep);
+    }
+    let mut length = 0;
+    for element in list {
+        length += element.len_nested(width, depth + 1)?;
+    }
+    if length > width.max_length() {
+        return Err(EncodeErr::ListTooLarge);
+    }
+    Ok(length)
+}
+
+/// Write the elements of a list inside `depth` enclosing lists into `sink`
+fn write_elements(
+    list: &[PactType],
+    sink: &mut dyn Sink,
+    width: LengthWidth,
+    depth: usize,
+) -> Result<(), EncodeErr> {
+    if depth >= MAX_LIST_DEPTH {
+        return Err(EncodeErr::ListTooDeep);
+    }
+    for element in list {
+        element.write_nested(sink, width, depth + 1)?;
+    }
+    Ok(())
+}
+
+/// Order two fixed point `(value, decimals)` pairs by the decimals they represent
+pub(crate) fn compare_fixed_point(l: (u128, u8), r: (u128, u8)) -> Ordering {
+    if l.1 > r.1 {
+        return compare_fixed_point(r, l).reverse();
+    }
+    // Scale `l` up to `r`'s decimals, a scaled value which overflows is larger than any `u128`
+    match rescale(l.0, l.1, r.1) {
+        Some(scaled) => scaled.cmp(&r.0),
+        None => Ordering::Greater,
+    }
+}
+
+/// Return `value` with `from` decimals rescaled to `to` decimals, if it's exactly representable
+pub(crate) fn rescale(value: u128, from: u8, to: u8) -> Option<u128> {
+    if value == 0 {
+        return Some(0);
+    }
+    if to >= from {
+        10u128
+            .checked_pow(u32::from(to - from))
+            .and_then(|scale| value.checked_mul(scale))
+    } else {
+        let scale = 10u128.checked_pow(u32::from(from - to))?;
+        match (value / scale, value % scale) {
+            (scaled, 0) => Some(scaled),
+            _ => None,
+        }
+    }
+}
+
+/// Order two `SortedList` elements, or `None` unless both are numerics or both string-likes
+pub(crate) fn compare_sorted(a: &PactType, b: &PactType) -> Option<Ordering> {
+    match (a, b) {
+        (PactType::Numeric(a), PactType::Numeric(b)) => a.partial_cmp(b),
+        (PactType::StringLike(a), PactType::StringLike(b)) => a.partial_cmp(b),
+        _ => None,
+    }
+}
+
+/// Whether `list` is strictly ascending numerics or string-likes of one type
+fn is_sorted(list: &[PactType]) -> bool {
+    let scalar = matches!(
+        list.first(),
+        Some(PactType::Numeric(_)) | Some(PactType::StringLike(_)) | None
+    );
+    scalar
+        && list
+            .windows(2)
+            .all(|pair| compare_sorted(&pair[0], &pair[1]) == Some(Ordering::Less))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn it_extracts_values() {
+        let list = vec![PactType::Numeric(Numeric(1)), PactType::Bool(true)];
+        assert_eq!(PactType::Numeric(Numeric(7)).as_numeric(), Some(7));
+        assert_eq!(PactType::Bool(true).as_numeric(), None);
+        assert_eq!(
+            PactType::StringLike(StringLike(b"hi")).as_str_bytes(),
+            Some(&b"hi"[..])
+        );
+        assert_eq!(PactType::List(list.clone()).as_list(), Some(&list[..]));
+        assert_eq!(
+            PactType::SortedList(list.clone()).as_list(),
+            Some(&list[..])
+        );
+        assert_eq!(PactType::Bool(false).as_bool(), Some(false));
+
+        assert_eq!(u64::try_from(PactType::Numeric(Numeric(7))), Ok(7));
+        assert_eq!(
+            <&[u8]>::try_from(PactType::StringLike(StringLike(b"hi"))),
+            Ok(&b"hi"[..])
+        );
+        assert_eq!(Vec::try_from(PactType::List(list.clone())), Ok(list));
+        assert_eq!(bool::try_from(PactType::Bool(true)), Ok(true));
+        assert_eq!(
+            u64::try_from(PactType::StringLike(StringLike(b"7"))),
+            Err(ExtractErr {
+                expected: InputType::Numeric
+            })
+        );
+        assert_eq!(
+            Vec::<PactType>::try_from(PactType::Placeholder("$a", InputType::List)),
+            Err(ExtractErr {
+                expected: InputType::List
+            })
+        );
+    }
+
     #[test]
     fn it_encodes_string_like() {
         let s = PactType::StringLike(StringLike(b"hello world"));
         let buf: &mut Vec<u8> = &mut Vec::new();
-        s.encode(buf);
+        s.encode(buf).unwrap();
         assert_eq!(buf[0], 0);
         assert_eq!(buf[1].swap_bits(), 11);
         assert_eq!(&buf[2..], "hello world".as_bytes());
@@ -155,13 +808,33 @@ mod tests {
     fn it_encodes_numeric() {
         let n = PactType::Numeric(Numeric(123));
         let buf: &mut Vec<u8> = &mut Vec::new();
-        n.encode(buf);
+        n.encode(buf).unwrap();
 
         let mut expected: Vec<u8> = vec![1, 8, 123, 0, 0, 0, 0, 0, 0, 0];
         expected = expected.into_iter().map(|b| b.swap_bits()).collect(); // convert to LE bit orders
         assert_eq!(buf, &expected);
     }
 
+    #[test]
+    fn it_encodes_placeholder() {
+        let p = PactType::Placeholder("limit", InputType::Numeric);
+        let buf: &mut Vec<u8> = &mut Vec::new();
+        p.encode(buf).unwrap();
+        assert_eq!(buf[0].swap_bits(), 5);
+        assert_eq!(buf[1].swap_bits(), 6);
+        assert_eq!(buf[2].swap_bits(), InputType::Numeric.code());
+        assert_eq!(&buf[3..], b"limit");
+        assert_eq!(PactType::decode(buf), Ok((p, 8)));
+
+        buf[2] = 9.swap_bits();
+        assert_eq!(PactType::decode(buf), Err("unknown placeholder type"));
+        let empty = [5.swap_bits(), 0];
+        assert_eq!(
+            PactType::decode(&empty),
+            Err("missing placeholder type byte")
+        );
+    }
+
     #[test]
     fn it_encodes_string_list() {
         let l = PactType::List(vec![
@@ -170,7 +843,7 @@ mod tests {
             PactType::StringLike(StringLike(b"to love")),
         ]);
         let buf: &mut Vec<u8> = &mut Vec::new();
-        l.encode(buf);
+        l.encode(buf).unwrap();
 
         assert_eq!(buf[0].swap_bits(), 2);
         assert_eq!(buf[1].swap_bits(), 30);
@@ -192,7 +865,7 @@ mod tests {
             PactType::Numeric(Numeric(0xfedcba9876543210)),
         ]);
         let buf: &mut Vec<u8> = &mut Vec::new();
-        l.encode(buf);
+        l.encode(buf).unwrap();
 
         let list_header: Vec<u8> = vec![2, 20];
         let item_0: Vec<u8> = vec![1, 8, 0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0x23, 0x01];
@@ -227,6 +900,72 @@ mod tests {
         assert_eq!(10usize, bytes_read,);
     }
 
+    #[test]
+    fn it_encodes_and_decodes_bool() {
+        for b in &[false, true] {
+            let buf: &mut Vec<u8> = &mut Vec::new();
+            PactType::Bool(*b).encode(buf).unwrap();
+            let expected: Vec<u8> = vec![3, 1, *b as u8];
+            let expected: Vec<u8> = expected.into_iter().map(|b| b.swap_bits()).collect();
+            assert_eq!(buf, &expected);
+            assert_eq!(PactType::decode(buf), Ok((PactType::Bool(*b), 3)));
+        }
+
+        let bad_value: Vec<u8> = vec![3, 1, 2].into_iter().map(|b| b.swap_bits()).collect();
+        assert_eq!(PactType::decode(&bad_value), Err("bool must be 0 or 1"));
+        let bad_length: Vec<u8> = vec![3, 2, 1, 0]
+            .into_iter()
+            .map(|b| b.swap_bits())
+            .collect();
+        assert_eq!(PactType::decode(&bad_length), Err("bool length must be 1"));
+    }
+
+    #[test]
+    fn it_encodes_and_decodes_fixed_point() {
+        let fixed_point = PactType::FixedPoint {
+            value: 0x0102,
+            decimals: 2,
+        };
+        let buf: &mut Vec<u8> = &mut Vec::new();
+        fixed_point.encode(buf).unwrap();
+        let mut expected: Vec<u8> = vec![6, 17, 0x02, 0x01];
+        expected.extend_from_slice(&[0; 14]);
+        expected.push(2);
+        let expected: Vec<u8> = expected.into_iter().map(|b| b.swap_bits()).collect();
+        assert_eq!(buf, &expected);
+        assert_eq!(fixed_point.encoded_len(), Ok(19));
+        assert_eq!(PactType::decode(buf), Ok((fixed_point, 19)));
+
+        let bad_length: Vec<u8> = vec![6, 1, 0].into_iter().map(|b| b.swap_bits()).collect();
+        assert_eq!(
+            PactType::decode(&bad_length),
+            Err("fixed point length must be 17")
+        );
+    }
+
+    #[test]
+    fn it_compares_fixed_points_at_a_common_scale() {
+        assert_eq!(compare_fixed_point((15, 1), (150, 2)), Ordering::Equal);
+        assert_eq!(compare_fixed_point((151, 2), (15, 1)), Ordering::Greater);
+        assert_eq!(compare_fixed_point((1, 0), (999, 3)), Ordering::Greater);
+        assert_eq!(compare_fixed_point((0, 0), (0, 255)), Ordering::Equal);
+        // Scaling `u128::MAX` up overflows, so it's larger than any value with fewer decimals
+        assert_eq!(
+            compare_fixed_point((u128::MAX, 0), (u128::MAX, 1)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_fixed_point((1, 200), (u128::MAX, 0)),
+            Ordering::Less
+        );
+
+        assert_eq!(rescale(150, 2, 1), Some(15));
+        assert_eq!(rescale(151, 2, 1), None);
+        assert_eq!(rescale(15, 1, 3), Some(1500));
+        assert_eq!(rescale(u128::MAX, 0, 1), None);
+        assert_eq!(PactType::Numeric(Numeric(7)).as_fixed_point(), Some((7, 0)));
+    }
+
     #[test]
     fn it_decodes_string_lists() {
         let list_header: Vec<u8> = vec![2, 35].into_iter().map(|b| b.swap_bits()).collect();
@@ -320,6 +1059,290 @@ mod tests {
         assert_eq!(PactType::decode(&buf), Err("list length overflow"));
     }
 
+    #[test]
+    fn it_encodes_and_decodes_nested_lists() {
+        let l = PactType::List(vec![
+            PactType::List(vec![PactType::Numeric(Numeric(1)), PactType::Bool(true)]),
+            PactType::StringLike(StringLike(b"rick")),
+            PactType::List(vec![]),
+        ]);
+        let buf: &mut Vec<u8> = &mut Vec::new();
+        l.encode(buf).unwrap();
+
+        let expected: Vec<u8> = [
+            vec![2, 23],
+            vec![2, 13],
+            vec![1, 8, 1, 0, 0, 0, 0, 0, 0, 0],
+            vec![3, 1, 1],
+            vec![0, 4],
+        ]
+        .concat()
+        .into_iter()
+        .map(|b| b.swap_bits())
+        .chain(b"rick".iter().cloned())
+        .chain(vec![2.swap_bits(), 0])
+        .collect();
+        assert_eq!(buf, &expected);
+        assert_eq!(PactType::decode(buf), Ok((l, 25)));
+    }
+
+    #[test]
+    fn it_limits_list_nesting_depth() {
+        let mut nested = PactType::List(vec![]);
+        for _ in 1..MAX_LIST_DEPTH {
+            nested = PactType::List(vec![nested]);
+        }
+        let mut encoded_nested: Vec<u8> = Vec::new();
+        nested.encode(&mut encoded_nested).unwrap();
+        assert_eq!(
+            PactType::decode(&encoded_nested),
+            Ok((nested.clone(), encoded_nested.len()))
+        );
+
+        let too_deep = PactType::List(vec![nested]);
+        let buf: &mut Vec<u8> = &mut Vec::new();
+        assert_eq!(too_deep.encode(buf), Err(EncodeErr::ListTooDeep));
+        assert!(buf.is_empty());
+
+        // A decoder rejects lists nested too deeply by other encoders
+        let mut buf: Vec<u8> = vec![2, 2].into_iter().map(|b| b.swap_bits()).collect();
+        buf.extend(&encoded_nested);
+        buf[1] = ((buf.len() - 2) as u8).swap_bits();
+        assert_eq!(PactType::decode(&buf), Err("list nesting too deep"));
+    }
+
+    #[test]
+    fn it_keys_sets_and_orders_values() {
+        use std::collections::HashSet;
+        let values = vec![
+            PactType::Numeric(Numeric(1)),
+            PactType::Numeric(Numeric(1)),
+            PactType::List(vec![PactType::Numeric(Numeric(1))]),
+            PactType::SortedList(vec![PactType::Numeric(Numeric(1))]),
+            PactType::StringLike(StringLike(b"a")),
+        ];
+        let set: HashSet<PactType> = values.into_iter().collect();
+        assert_eq!(set.len(), 4);
+        assert!(set.contains(&PactType::Numeric(Numeric(1))));
+
+        let mut numerics = vec![Numeric(3), Numeric(1), Numeric(2)];
+        numerics.sort();
+        assert_eq!(numerics, vec![Numeric(1), Numeric(2), Numeric(3)]);
+        assert_eq!(
+            StringLike(b"ab").cmp(&StringLike(b"b")),
+            core::cmp::Ordering::Less
+        );
+    }
+
+    #[test]
+    fn it_decodes_lists_within_limits() {
+        let nested = PactType::List(vec![PactType::List(vec![
+            PactType::Numeric(Numeric(1)),
+            PactType::Numeric(Numeric(2)),
+        ])]);
+        let mut encoded = Vec::new();
+        nested.encode(&mut encoded).unwrap();
+        let limits = DecodeLimits {
+            max_depth: 2,
+            max_elements: 2,
+        };
+        assert_eq!(
+            PactType::decode_with_limits(&encoded, LengthWidth::U8, limits),
+            Ok((nested.clone(), encoded.len()))
+        );
+        assert_eq!(
+            PactType::decode_with_limits(
+                &encoded,
+                LengthWidth::U8,
+                DecodeLimits {
+                    max_depth: 1,
+                    ..limits
+                }
+            ),
+            Err(LIST_TOO_DEEP)
+        );
+        assert_eq!(
+            PactType::decode_with_limits(
+                &encoded,
+                LengthWidth::U8,
+                DecodeLimits {
+                    max_elements: 1,
+                    ..limits
+                }
+            ),
+            Err(LIST_TOO_LONG)
+        );
+        // A list of the smallest elements with 2 byte lengths is within the default limits
+        let list = PactType::List(vec![
+            PactType::StringLike(StringLike(b""));
+            MAX_LIST_ELEMENTS
+        ]);
+        let mut encoded = Vec::new();
+        list.encode_as(&mut encoded, LengthWidth::U16).unwrap();
+        assert_eq!(
+            PactType::decode_as(&encoded, LengthWidth::U16),
+            Ok((list, encoded.len()))
+        );
+    }
+
+    #[test]
+    fn it_fails_to_encode_oversize_values() {
+        let long = [b'x'; 256];
+        let buf: &mut Vec<u8> = &mut Vec::new();
+        assert_eq!(
+            PactType::StringLike(StringLike(&long)).encode(buf),
+            Err(EncodeErr::StringTooLong)
+        );
+        assert_eq!(
+            PactType::List(vec![PactType::StringLike(StringLike(&long))]).encode(buf),
+            Err(EncodeErr::StringTooLong)
+        );
+        assert_eq!(
+            PactType::List(vec![PactType::Numeric(Numeric(1)); 26]).encode(buf),
+            Err(EncodeErr::ListTooLarge)
+        );
+        assert!(buf.is_empty());
+
+        PactType::List(vec![PactType::Numeric(Numeric(1)); 25])
+            .encode(buf)
+            .unwrap();
+        assert_eq!(buf.len(), 2 + 250);
+    }
+
+    #[test]
+    fn it_encodes_and_decodes_u16_lengths() {
+        let long = [b'x'; 300];
+        let l = PactType::List(vec![
+            PactType::StringLike(StringLike(&long)),
+            PactType::Numeric(Numeric(1)),
+            PactType::Bool(false),
+        ]);
+        assert_eq!(l.encode(&mut Vec::new()), Err(EncodeErr::StringTooLong));
+
+        let buf: &mut Vec<u8> = &mut Vec::new();
+        l.encode_as(buf, LengthWidth::U16).unwrap();
+        let header: Vec<u8> = vec![2, 62, 1, 0, 44, 1]
+            .into_iter()
+            .map(|b| b.swap_bits())
+            .collect();
+        assert_eq!(&buf[..6], &header[..]);
+        assert_eq!(&buf[6..306], &long[..]);
+        assert_eq!(buf.len(), 3 + 318);
+        assert_eq!(PactType::decode_as(buf, LengthWidth::U16), Ok((l, 321)));
+
+        let too_long = [b'x'; 0x1_0000];
+        assert_eq!(
+            PactType::StringLike(StringLike(&too_long)).encode_as(buf, LengthWidth::U16),
+            Err(EncodeErr::StringTooLong)
+        );
+        assert_eq!(
+            PactType::decode_as(&[0, 1], LengthWidth::U16),
+            Err("missing type length byte")
+        );
+    }
+
+    #[test]
+    fn it_sorts_lists() {
+        let n = |n| PactType::Numeric(Numeric(n));
+        assert_eq!(
+            PactType::sorted_list(vec![n(3), n(1), n(3), n(2)]),
+            Some(PactType::SortedList(vec![n(1), n(2), n(3)]))
+        );
+        assert_eq!(
+            PactType::sorted_list(vec![
+                PactType::StringLike(StringLike(b"bob")),
+                PactType::StringLike(StringLike(b"alice")),
+            ]),
+            Some(PactType::SortedList(vec![
+                PactType::StringLike(StringLike(b"alice")),
+                PactType::StringLike(StringLike(b"bob")),
+            ]))
+        );
+        assert_eq!(
+            PactType::sorted_list(vec![]),
+            Some(PactType::SortedList(vec![]))
+        );
+        assert_eq!(
+            PactType::sorted_list(vec![n(1), PactType::StringLike(StringLike(b"1"))]),
+            None
+        );
+        assert_eq!(PactType::sorted_list(vec![PactType::Bool(true)]), None);
+        assert_eq!(PactType::sorted_list(vec![PactType::List(vec![])]), None);
+    }
+
+    #[test]
+    fn it_encodes_and_decodes_sorted_lists() {
+        let l = PactType::SortedList(vec![
+            PactType::StringLike(StringLike(b"a")),
+            PactType::StringLike(StringLike(b"b")),
+        ]);
+        let buf: &mut Vec<u8> = &mut Vec::new();
+        l.encode(buf).unwrap();
+        let expected: Vec<u8> = vec![4, 6, 0, 1, b'a'.swap_bits(), 0, 1, b'b'.swap_bits()]
+            .into_iter()
+            .map(|b| b.swap_bits())
+            .collect();
+        assert_eq!(buf, &expected);
+        assert_eq!(PactType::decode(buf), Ok((l, 8)));
+
+        // Descending, duplicated, and mixed elements are rejected
+        for unsorted in &[
+            vec![PactType::Numeric(Numeric(2)), PactType::Numeric(Numeric(1))],
+            vec![PactType::Numeric(Numeric(1)), PactType::Numeric(Numeric(1))],
+            vec![
+                PactType::Numeric(Numeric(1)),
+                PactType::StringLike(StringLike(b"a")),
+            ],
+            vec![PactType::Bool(false)],
+        ] {
+            assert_eq!(
+                PactType::SortedList(unsorted.clone()).encode(&mut Vec::new()),
+                Err(EncodeErr::UnsortedList)
+            );
+            let buf: &mut Vec<u8> = &mut Vec::new();
+            PactType::List(unsorted.clone()).encode(buf).unwrap();
+            buf[0] = 4.swap_bits();
+            assert_eq!(PactType::decode(buf), Err("sorted list is not sorted"));
+        }
+    }
+
+    #[test]
+    fn it_measures_encoded_lengths() {
+        let nested = PactType::List(vec![
+            PactType::Bool(true),
+            PactType::List(vec![PactType::Numeric(Numeric(1))]),
+            PactType::StringLike(StringLike(b"hello")),
+        ]);
+        for width in [LengthWidth::U8, LengthWidth::U16].iter() {
+            let mut buf = Vec::new();
+            nested.encode_as(&mut buf, *width).unwrap();
+            assert_eq!(nested.encoded_len_as(*width), Ok(buf.len()));
+        }
+        let too_long = PactType::StringLike(StringLike(&[0_u8; 256]));
+        assert_eq!(too_long.encoded_len(), Err(EncodeErr::StringTooLong));
+    }
+
+    #[test]
+    fn it_encodes_into_slices() {
+        let value = PactType::List(vec![
+            PactType::Numeric(Numeric(7)),
+            PactType::StringLike(StringLike(b"hi")),
+        ]);
+        let mut expected = Vec::new();
+        value.encode(&mut expected).unwrap();
+        let mut buf = [0_u8; 32];
+        assert_eq!(value.encode_to(&mut buf), Ok(expected.len()));
+        assert_eq!(&buf[..expected.len()], &expected[..]);
+        let mut short = [0_u8; 4];
+        assert_eq!(
+            value.encode_to(&mut short),
+            Err(EncodeErr::BufferTooShort {
+                required: expected.len()
+            })
+        );
+        assert_eq!(short, [0_u8; 4]);
+    }
+
     #[test]
     fn it_fails_with_missing_type_id() {
         assert_eq!(PactType::decode(&[]), Err("missing type ID byte"));
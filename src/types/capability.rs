@@ -0,0 +1,248 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Capability sets describing the OpCode families and encoding features a contract uses
+//! Hosts pass an allowlist to `Contract::validate` to roll out new OpCodes gradually.
+//! Each capability has a minimum crate version so runtimes embedding an older interpreter can
+//! be told which upgrade a contract needs.
+//!
+use core::fmt;
+use core::ops::BitOr;
+
+/// A pact crate version
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl Version {
+    /// The version of this build
+    pub const CURRENT: Self = Version::new(0, 2, 0);
+
+    /// Create a version from its parts
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// A set of OpCode families
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// No capabilities
+    pub const NONE: Self = Capabilities(0);
+    /// `EQ` comparator
+    pub const COMP_EQ: Self = Capabilities(1 << 0);
+    /// `GT` comparator
+    pub const COMP_GT: Self = Capabilities(1 << 1);
+    /// `GTE` comparator
+    pub const COMP_GTE: Self = Capabilities(1 << 2);
+    /// `IN` comparator
+    pub const COMP_IN: Self = Capabilities(1 << 3);
+    /// Comparing an input against another input
+    pub const LOAD_INPUT_VS_INPUT: Self = Capabilities(1 << 4);
+    /// `AND` conjunction
+    pub const CONJ_AND: Self = Capabilities(1 << 5);
+    /// `OR` conjunction
+    pub const CONJ_OR: Self = Capabilities(1 << 6);
+    /// `XOR` conjunction
+    pub const CONJ_XOR: Self = Capabilities(1 << 7);
+    /// `CALL` comparator, only hosts which register predicates should allow it
+    pub const COMP_CALL: Self = Capabilities(1 << 8);
+    /// Input parameter names, the v1 binary format
+    pub const INPUT_NAMES: Self = Capabilities(1 << 9);
+    /// Input parameter types, the v2 binary format
+    pub const INPUT_SCHEMA: Self = Capabilities(1 << 10);
+    /// `Bool` values
+    pub const TYPE_BOOL: Self = Capabilities(1 << 11);
+    /// `List`s nested in `List`s
+    pub const NESTED_LIST: Self = Capabilities(1 << 12);
+    /// Data table values with 2 byte lengths, the v3 binary format
+    pub const WIDE_LENGTHS: Self = Capabilities(1 << 13);
+    /// `SortedList` values, searched by `IN` comparators
+    pub const SORTED_LIST: Self = Capabilities(1 << 14);
+    /// `SUBSET` comparator
+    pub const COMP_SUBSET: Self = Capabilities(1 << 15);
+    /// `INTERSECTS` comparator
+    pub const COMP_INTERSECTS: Self = Capabilities(1 << 16);
+    /// Clause names, the v4 binary format
+    pub const CLAUSE_NAMES: Self = Capabilities(1 << 17);
+    /// Loading the number of input registers from the arity register
+    pub const LOAD_ARITY: Self = Capabilities(1 << 18);
+    /// `ALL` and `ANY` quantified comparators over the input registers
+    pub const QUANTIFIERS: Self = Capabilities(1 << 19);
+    /// `Placeholder` data table values, see `Contract::bind`
+    pub const PLACEHOLDERS: Self = Capabilities(1 << 20);
+    /// The v5 metadata section
+    pub const METADATA: Self = Capabilities(1 << 21);
+    /// The v6 declared features section, see `Contract::with_declared_features`
+    pub const DECLARED_FEATURES: Self = Capabilities(1 << 22);
+    /// `FixedPoint` values and input types
+    pub const TYPE_FIXED_POINT: Self = Capabilities(1 << 23);
+    /// `RATIO` comparator
+    pub const COMP_RATIO: Self = Capabilities(1 << 24);
+
+    /// The capabilities supported by the v0 interpreter
+    pub const V0: Self = Capabilities(0b1111_1111);
+
+    /// Every capability known to this build
+    pub fn all() -> Self {
+        SUPPORT_MATRIX
+            .iter()
+            .fold(Self::NONE, |all, (caps, _)| all | *caps)
+    }
+
+    /// Return the capabilities supported by an interpreter of crate `version`
+    pub fn supported_by(version: Version) -> Self {
+        SUPPORT_MATRIX
+            .iter()
+            .filter(|(_, since)| *since <= version)
+            .fold(Self::NONE, |supported, (caps, _)| supported | *caps)
+    }
+
+    /// Return the minimum crate version whose interpreter supports every capability in `self`
+    /// Capabilities unknown to this build are ignored.
+    pub fn min_version(self) -> Version {
+        SUPPORT_MATRIX
+            .iter()
+            .filter(|(caps, _)| self.0 & caps.0 != 0)
+            .map(|(_, since)| *since)
+            .max()
+            .unwrap_or_else(|| Version::new(0, 1, 0))
+    }
+
+    /// Create a set from a raw bitset
+    /// Bits unknown to this build are kept, so capabilities of later versions can be reported.
+    pub const fn from_bits(bits: u32) -> Self {
+        Capabilities(bits)
+    }
+
+    /// Return the raw bitset
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Whether every capability in `other` is also in `self`
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Return the capabilities in `self` which are missing from `allowed`
+    pub fn difference(self, allowed: Self) -> Self {
+        Capabilities(self.0 & !allowed.0)
+    }
+
+    /// Whether the set is empty
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+/// The crate version which introduced each capability
+const SUPPORT_MATRIX: &[(Capabilities, Version)] = &[
+    (Capabilities::V0, Version::new(0, 1, 0)),
+    (Capabilities::COMP_CALL, Version::new(0, 2, 0)),
+    (Capabilities::INPUT_NAMES, Version::new(0, 2, 0)),
+    (Capabilities::INPUT_SCHEMA, Version::new(0, 2, 0)),
+    (Capabilities::TYPE_BOOL, Version::new(0, 2, 0)),
+    (Capabilities::NESTED_LIST, Version::new(0, 2, 0)),
+    (Capabilities::WIDE_LENGTHS, Version::new(0, 2, 0)),
+    (Capabilities::SORTED_LIST, Version::new(0, 2, 0)),
+    (Capabilities::COMP_SUBSET, Version::new(0, 2, 0)),
+    (Capabilities::COMP_INTERSECTS, Version::new(0, 2, 0)),
+    (Capabilities::CLAUSE_NAMES, Version::new(0, 2, 0)),
+    (Capabilities::LOAD_ARITY, Version::new(0, 2, 0)),
+    (Capabilities::QUANTIFIERS, Version::new(0, 2, 0)),
+    (Capabilities::PLACEHOLDERS, Version::new(0, 2, 0)),
+    (Capabilities::METADATA, Version::new(0, 2, 0)),
+    (Capabilities::DECLARED_FEATURES, Version::new(0, 2, 0)),
+    (Capabilities::TYPE_FIXED_POINT, Version::new(0, 2, 0)),
+    (Capabilities::COMP_RATIO, Version::new(0, 2, 0)),
+];
+
+impl BitOr for Capabilities {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Capabilities(self.0 | rhs.0)
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_combines_capabilities() {
+        let caps = Capabilities::COMP_EQ | Capabilities::CONJ_AND;
+        assert!(caps.contains(Capabilities::COMP_EQ));
+        assert!(!caps.contains(Capabilities::COMP_IN));
+        assert!(Capabilities::all().contains(caps));
+        assert_eq!(
+            (caps | Capabilities::COMP_IN).difference(caps),
+            Capabilities::COMP_IN
+        );
+        assert!(caps.difference(Capabilities::all()).is_empty());
+        let unknown = Capabilities::from_bits(1 << 31);
+        assert_eq!((caps | unknown).difference(Capabilities::all()), unknown);
+    }
+
+    #[test]
+    fn it_maps_capabilities_to_versions() {
+        let v0_1 = Version::new(0, 1, 0);
+        assert_eq!(Capabilities::supported_by(v0_1), Capabilities::V0);
+        assert_eq!(
+            Capabilities::supported_by(Version::CURRENT),
+            Capabilities::all()
+        );
+        assert_eq!(
+            Capabilities::supported_by(Version::new(0, 0, 9)),
+            Capabilities::NONE
+        );
+
+        assert_eq!(Capabilities::NONE.min_version(), v0_1);
+        assert_eq!(
+            (Capabilities::COMP_EQ | Capabilities::CONJ_OR).min_version(),
+            v0_1
+        );
+        assert_eq!(
+            (Capabilities::COMP_EQ | Capabilities::TYPE_BOOL).min_version(),
+            Version::new(0, 2, 0)
+        );
+        assert_eq!(Capabilities::all().min_version(), Version::CURRENT);
+        assert!(Version::new(0, 10, 0) > Version::new(0, 9, 1));
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Hex and base64 text encodings for contract blobs
+//! Errors are reasons for `BinaryFormatErr::InvalidText`
+//!
+use alloc::{string::String, vec::Vec};
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+const BASE64_DIGITS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode `bytes` as lowercase hex, without a prefix
+pub fn encode_hex(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        text.push(HEX_DIGITS[usize::from(byte >> 4)] as char);
+        text.push(HEX_DIGITS[usize::from(byte & 0xf)] as char);
+    }
+    text
+}
+
+/// Decode hex of either case, with an optional `0x` prefix, appending the bytes to `buf`
+/// `buf` is unchanged on failure.
+// `str::strip_prefix` is not available on our minimum supported Rust
+#[allow(clippy::manual_strip)]
+pub fn decode_hex(text: &str, buf: &mut Vec<u8>) -> Result<(), &'static str> {
+    let digits = if text.starts_with("0x") {
+        &text[2..]
+    } else {
+        text
+    };
+    let digits = digits.as_bytes();
+    let pairs = digits.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err("hex has an odd number of digits");
+    }
+    let start = buf.len();
+    buf.reserve(digits.len() / 2);
+    for pair in pairs {
+        match (hex_value(pair[0]), hex_value(pair[1])) {
+            (Some(high), Some(low)) => buf.push(high << 4 | low),
+            _ => {
+                buf.truncate(start);
+                return Err("hex has a non hex digit");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Encode `bytes` as padded base64 with the standard alphabet
+pub fn encode_base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len() / 3 * 4 + 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| {
+            group | u32::from(*byte) << (16 - 8 * i)
+        });
+        // n bytes fill n + 1 digits, the rest is padding
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(BASE64_DIGITS[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+/// Decode base64 with the standard alphabet, padded or not, appending the bytes to `buf`
+/// `buf` is unchanged on failure.
+pub fn decode_base64(text: &str, buf: &mut Vec<u8>) -> Result<(), &'static str> {
+    let digits = text.trim_end_matches('=').as_bytes();
+    let padding = text.len() - digits.len();
+    // Padding completes the last group of 4 digits
+    let partial = digits.len() % 4;
+    if partial == 1 || padding > 2 || (padding > 0 && partial + padding != 4) {
+        return Err("base64 has the wrong length");
+    }
+    let start = buf.len();
+    buf.reserve(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        let mut group = 0u32;
+        for (i, digit) in chunk.iter().enumerate() {
+            match base64_value(*digit) {
+                Some(value) => group |= u32::from(value) << (18 - 6 * i),
+                None => {
+                    buf.truncate(start);
+                    return Err("base64 has a non base64 digit");
+                }
+            }
+        }
+        // n digits hold n - 1 whole bytes
+        for i in 0..chunk.len() - 1 {
+            buf.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(())
+}
+
+/// Return the value of a hex digit
+fn hex_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Return the value of a base64 digit
+fn base64_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'A'..=b'Z' => Some(digit - b'A'),
+        b'a'..=b'z' => Some(digit - b'a' + 26),
+        b'0'..=b'9' => Some(digit - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(text: &str) -> Result<Vec<u8>, &'static str> {
+        let mut buf = Vec::new();
+        decode_hex(text, &mut buf).map(|_| buf)
+    }
+
+    fn base64(text: &str) -> Result<Vec<u8>, &'static str> {
+        let mut buf = Vec::new();
+        decode_base64(text, &mut buf).map(|_| buf)
+    }
+
+    #[test]
+    fn it_roundtrips_hex() {
+        assert_eq!(encode_hex(&[]), "");
+        assert_eq!(encode_hex(&[0x00, 0x9f, 0xff]), "009fff");
+        assert_eq!(hex("009fff"), Ok(vec![0x00, 0x9f, 0xff]));
+        assert_eq!(hex("0x009FfF"), Ok(vec![0x00, 0x9f, 0xff]));
+        assert_eq!(hex("0x"), Ok(vec![]));
+        assert_eq!(hex("abc"), Err("hex has an odd number of digits"));
+        assert_eq!(hex("0g"), Err("hex has a non hex digit"));
+        assert_eq!(hex("é"), Err("hex has a non hex digit"));
+    }
+
+    #[test]
+    fn it_roundtrips_base64() {
+        // RFC 4648 test vectors
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (bytes, text) in vectors.iter() {
+            assert_eq!(encode_base64(bytes.as_bytes()), *text);
+            assert_eq!(base64(text), Ok(bytes.as_bytes().to_vec()));
+            assert_eq!(
+                base64(text.trim_end_matches('=')),
+                Ok(bytes.as_bytes().to_vec())
+            );
+        }
+        assert_eq!(base64("/+8="), Ok(vec![0xff, 0xef]));
+        assert_eq!(base64("Zm9vY"), Err("base64 has the wrong length"));
+        assert_eq!(base64("Zg="), Err("base64 has the wrong length"));
+        assert_eq!(base64("Zg==="), Err("base64 has the wrong length"));
+        assert_eq!(base64("Zm-v"), Err("base64 has a non base64 digit"));
+        let mut buf = vec![1];
+        assert!(decode_base64("Zm9v Zm9v", &mut buf).is_err());
+        assert_eq!(buf, vec![1]);
+    }
+}
@@ -17,9 +17,21 @@
 //!
 //! Contract struct
 //!
-use crate::types::DataTable;
-use alloc::vec::Vec;
+use crate::interpreter::{
+    Conjunction, InterpErr, OpCode, OpCodeErr, OpCodes, OpComp, OpConj, OpInvert, OpLoad, OpQuant,
+    Visitor,
+};
+use crate::types::{
+    base::{encode_into, ByteCounter, Sink, MAX_ENCODED_LENGTH},
+    schema, Capabilities, DataTable, EncodeErr, InputType, LengthWidth, Metadata, PactType,
+    SchemaErr, Version,
+};
+#[cfg(feature = "codecs")]
+use alloc::string::String;
+use alloc::{vec, vec::Vec};
 use bit_reverse::ParallelReverse;
+use core::convert::Infallible;
+use core::fmt;
 
 #[cfg_attr(feature = "std", derive(Debug, PartialEq))]
 /// A binary format error
@@ -28,46 +40,1351 @@ pub enum BinaryFormatErr {
     UnsupportedVersion,
     /// DataTable is invalid
     MalformedDataTable(&'static str),
+    /// The v1, v2, v4, or v5 metadata section is invalid
+    MalformedMetadata(&'static str),
     // The buffer is to short to be valid
     TooShort,
+    /// The hex or base64 text isn't valid, see `Contract::from_hex` and `Contract::from_base64`
+    InvalidText(&'static str),
+    /// The bytecode is invalid at the `ValidationLevel` given to `Contract::decode_validated`
+    MalformedBytecode(InterpErr),
 }
 
+impl fmt::Display for BinaryFormatErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryFormatErr::UnsupportedVersion => write!(f, "unsupported binary format version"),
+            BinaryFormatErr::MalformedDataTable(reason) => {
+                write!(f, "malformed data table: {}", reason)
+            }
+            BinaryFormatErr::MalformedMetadata(reason) => {
+                write!(f, "malformed metadata: {}", reason)
+            }
+            BinaryFormatErr::TooShort => write!(f, "contract is too short"),
+            BinaryFormatErr::InvalidText(reason) => write!(f, "invalid contract text: {}", reason),
+            BinaryFormatErr::MalformedBytecode(_) => write!(f, "malformed bytecode"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BinaryFormatErr {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BinaryFormatErr::MalformedBytecode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// How thoroughly `Contract::decode_validated` checks bytecode
+/// Neither level checks capabilities or self comparisons, see `Contract::validate`.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum ValidationLevel {
+    /// Every OpCode parses, and every conjunction is between comparators
+    Structure,
+    /// `Structure`, and every data table index a comparator loads exists
+    Indices,
+}
+
+/// An error found by `Contract::walk_bytecode`
+enum WalkErr<E> {
+    /// The bytecode is structurally invalid
+    Malformed(InterpErr),
+    /// The OpCode at `offset` loads a data table `index` which does not exist
+    MissingIndex { offset: usize, index: u8 },
+    /// The visitor failed
+    Visit(E),
+}
+
+/// A contract validation error
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(PartialEq)]
+pub enum ValidationErr {
+    /// The bytecode is structurally invalid
+    Malformed(InterpErr),
+    /// The OpCode at `offset` requires capabilities which are not allowed
+    Disallowed {
+        offset: usize,
+        required: Capabilities,
+    },
+    /// The OpCode at `offset` references a data table `index` which does not exist
+    MissingIndex { offset: usize, index: u8 },
+    /// The contract's encoding or data table requires capabilities which are not allowed
+    Unsupported { required: Capabilities },
+    /// A data table value can't be encoded
+    Unencodable(EncodeErr),
+    /// The comparator at `offset` compares input register `index` with itself
+    /// Intended self comparisons must bypass validation with `Contract::from_parts_unchecked`.
+    SelfComparison { offset: usize, index: u8 },
+    /// A clause name refers to clause `index` which does not exist
+    MissingClause { index: usize },
+    /// An OpCode can't be assembled into bytecode
+    Unassemblable(OpCodeErr),
+}
+
+impl fmt::Display for ValidationErr {
+    /// Wrapped errors are not repeated, see `source`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationErr::Malformed(_) => write!(f, "bytecode is malformed"),
+            ValidationErr::Disallowed { offset, required } => write!(
+                f,
+                "OpCode at offset {} requires capabilities which are not allowed (since {})",
+                offset,
+                required.min_version()
+            ),
+            ValidationErr::MissingIndex { offset, index } => write!(
+                f,
+                "OpCode at offset {} loads missing data table index {}",
+                offset, index
+            ),
+            ValidationErr::Unsupported { required } => write!(
+                f,
+                "contract encoding requires capabilities which are not allowed (since {})",
+                required.min_version()
+            ),
+            ValidationErr::Unencodable(_) => write!(f, "data table can't be encoded"),
+            ValidationErr::SelfComparison { offset, index } => write!(
+                f,
+                "comparator at offset {} compares input {} with itself",
+                offset, index
+            ),
+            ValidationErr::MissingClause { index } => {
+                write!(f, "clause name refers to missing clause {}", index)
+            }
+            ValidationErr::Unassemblable(_) => write!(f, "OpCode can't be assembled"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationErr {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ValidationErr::Malformed(err) => Some(err),
+            ValidationErr::Unencodable(err) => Some(err),
+            ValidationErr::Unassemblable(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl ValidationErr {
+    /// Return the minimum crate version whose interpreter supports the disallowed capabilities
+    /// Runtimes embedding an older interpreter need at least this version to accept the contract.
+    pub fn min_version(&self) -> Option<Version> {
+        match self {
+            ValidationErr::Disallowed { required, .. }
+            | ValidationErr::Unsupported { required } => Some(required.min_version()),
+            _ => None,
+        }
+    }
+}
+
+/// The maximum number of data table entries an OpCode can index
+const MAX_DATA_TABLE_ENTRIES: usize = 16;
+
+/// A contract merge error
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(PartialEq)]
+pub enum MergeErr {
+    /// One of the contracts is invalid
+    Invalid(ValidationErr),
+    /// The combined data table has more entries than an OpCode can index
+    DataTableFull,
+    /// The conjunction of these contracts can't be expressed in bytecode, see `Contract::merge`
+    Unrepresentable,
+}
+
+impl fmt::Display for MergeErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeErr::Invalid(_) => write!(f, "a merged contract is invalid"),
+            MergeErr::DataTableFull => write!(
+                f,
+                "merged data table has more than {} entries",
+                MAX_DATA_TABLE_ENTRIES
+            ),
+            MergeErr::Unrepresentable => {
+                write!(f, "the merged contracts can't be expressed in bytecode")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MergeErr {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MergeErr::Invalid(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// A data table patch error, see `Contract::replace_data`
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(PartialEq)]
+pub enum PatchErr {
+    /// The data table has no value at this index
+    MissingIndex(usize),
+    /// The replacement value is not of the same type as the value at this index
+    TypeMismatch(usize),
+}
+
+impl fmt::Display for PatchErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchErr::MissingIndex(index) => {
+                write!(f, "data table has no value at index {}", index)
+            }
+            PatchErr::TypeMismatch(index) => write!(
+                f,
+                "replacement is not of the same type as the value at index {}",
+                index
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PatchErr {}
+
+/// A placeholder binding error, see `Contract::bind`
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(PartialEq)]
+pub enum BindErr<'b> {
+    /// The contract has no placeholder with this name
+    UnknownPlaceholder(&'b str),
+    /// The value bound to this placeholder is not of its declared type
+    Mismatch { name: &'b str, expected: InputType },
+}
+
+impl<'b> fmt::Display for BindErr<'b> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindErr::UnknownPlaceholder(name) => {
+                write!(f, "contract has no placeholder `${}`", name)
+            }
+            BindErr::Mismatch { name, expected } => {
+                write!(f, "placeholder `${}` must be bound to {}", name, expected)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'b> std::error::Error for BindErr<'b> {}
+
 /// A pact contract
 /// It has byte code and an accompanying data section
+/// Create one with `Contract::new`, which checks the bytecode against the data table, or with
+/// `Contract::from_parts_unchecked` for trusted input.
 #[cfg_attr(feature = "std", derive(Debug, PartialEq))]
 pub struct Contract<'a> {
-    pub data_table: DataTable<'a>,
-    pub bytecode: Vec<u8>,
+    data_table: DataTable<'a>,
+    bytecode: Vec<u8>,
+    /// Declared input parameter names in positional order, without the `$` sigil
+    input_names: Option<Vec<&'a str>>,
+    /// Declared input parameter types in positional order, only present with `input_names`
+    input_types: Option<Vec<InputType>>,
+    /// The source contracts of this contract's OpCodes, ordered by offset. Not encoded
+    origins: Option<Vec<Origin<'a>>>,
+    /// Clause indices and their names, ordered by index
+    clause_names: Option<Vec<(usize, &'a str)>>,
+    /// Human-readable metadata e.g. a title, ignored by the interpreter
+    metadata: Option<Metadata<'a>>,
+    /// Features declared in the encoding, which may include features unknown to this build
+    declared_features: Option<Capabilities>,
+}
+
+/// The source contract of a run of OpCodes in a composed contract
+/// OpCodes from `opcode_offset` up to the next `Origin` came from `source`, or an unlabelled
+/// contract if it is `None`.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Origin<'a> {
+    /// Byte offset of the first OpCode from `source`
+    pub opcode_offset: usize,
+    /// The source contract label, see `Contract::with_origin`
+    pub source: Option<&'a str>,
+}
+
+/// Size counts of a contract for fee estimation before execution, see `Contract::stats`
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct ContractStats {
+    /// Number of comparator OpCodes
+    pub comparators: usize,
+    /// Number of conjunction OpCodes
+    pub conjunctions: usize,
+    /// Number of data table entries
+    pub data_table_entries: usize,
+    /// Number of list elements in the data table, including elements of nested lists
+    pub list_elements: usize,
+    /// Length of the binary encoded contract in bytes
+    pub encoded_size: usize,
 }
 
 impl<'a> Contract<'a> {
-    /// Encode the contract as v0 binary format into `buf`
-    pub fn encode(&self, buf: &mut Vec<u8>) {
-        buf.push(0); // binary format version: `0`
-        self.data_table.encode(buf);
-        buf.extend(self.bytecode.clone());
+    /// Create a contract from its parts, validating `bytecode` against `data_table`
+    pub fn new(data_table: DataTable<'a>, bytecode: Vec<u8>) -> Result<Self, ValidationErr> {
+        let contract = Self::from_parts_unchecked(data_table, bytecode);
+        contract.validate(Capabilities::all())?;
+        Ok(contract)
+    }
+    /// Create a contract from its parts without validation
+    /// Only for trusted input e.g. compiler output. An inconsistent contract fails when it is
+    /// interpreted instead.
+    pub fn from_parts_unchecked(data_table: DataTable<'a>, bytecode: Vec<u8>) -> Self {
+        Contract {
+            data_table,
+            bytecode,
+            input_names: None,
+            input_types: None,
+            origins: None,
+            clause_names: None,
+            metadata: None,
+            declared_features: None,
+        }
+    }
+    /// Attach the declared input parameter `names`, in positional order
+    /// Contracts with input names are encoded in the v1 binary format.
+    /// Any input schema is removed.
+    pub fn with_input_names(mut self, names: Vec<&'a str>) -> Self {
+        self.input_names = Some(names);
+        self.input_types = None;
+        self
+    }
+    /// Attach an input schema of declared parameter names and types, in positional order
+    /// Contracts with an input schema are encoded in the v2 binary format.
+    pub fn with_input_schema(mut self, parameters: Vec<(&'a str, InputType)>) -> Self {
+        let (names, types) = parameters.into_iter().unzip();
+        self.input_names = Some(names);
+        self.input_types = Some(types);
+        self
+    }
+    /// Label every OpCode as originating from the `source` contract e.g. a document name
+    /// Labels are kept by `merge` so each clause of a composed contract can be attributed to its
+    /// origin in traces. They are in-memory metadata and are not encoded.
+    pub fn with_origin(mut self, source: &'a str) -> Self {
+        self.origins = Some(vec![Origin {
+            opcode_offset: 0,
+            source: Some(source),
+        }]);
+        self
+    }
+    /// Attach clause `names` as `(clause index, name)` pairs, ordered by index
+    /// Clauses are numbered from 0 in bytecode order and need not all be named.
+    /// Contracts with clause names are encoded in the v4 binary format.
+    pub fn with_clause_names(mut self, names: Vec<(usize, &'a str)>) -> Self {
+        self.clause_names = Some(names);
+        self
+    }
+    /// Return the clause names as `(clause index, name)` pairs, if known
+    pub fn clause_names(&self) -> Option<&[(usize, &'a str)]> {
+        self.clause_names.as_deref()
+    }
+    /// Attach human-readable `metadata` e.g. a title and author
+    /// Contracts with metadata are encoded in the v5 binary format.
+    pub fn with_metadata(mut self, metadata: Metadata<'a>) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+    /// Return the contract's metadata, if any
+    pub fn metadata(&self) -> Option<&Metadata<'a>> {
+        self.metadata.as_ref()
+    }
+    /// Declare the features the contract requires in its encoding, see `required_features`
+    /// Interpreters of later crate versions refuse the contract before executing it if they don't
+    /// support every declared feature, rather than failing part way through on an unknown OpCode.
+    /// Contracts with declared features are encoded in the v6 binary format.
+    pub fn with_declared_features(mut self) -> Self {
+        self.declared_features = Some(self.capabilities() | Capabilities::DECLARED_FEATURES);
+        self
+    }
+    /// Return the features an interpreter needs to decode and execute this contract
+    /// These are its `capabilities`, and any features declared in its encoding, which may be
+    /// unknown to this build if the contract was encoded by a later crate version.
+    pub fn required_features(&self) -> Capabilities {
+        self.capabilities() | self.declared_features.unwrap_or_default()
+    }
+    /// Return each clause's name, if it has one, and bytecode in bytecode order
+    /// Each clause's bytecode can be interpreted on its own against this contract's data table.
+    /// A malformed OpCode and the bytes after it are part of the last clause.
+    pub fn clauses(&self) -> Vec<(Option<&'a str>, &[u8])> {
+        let starts = self.clause_offsets();
+        let names = self.clause_names.as_deref().unwrap_or(&[]);
+        starts
+            .iter()
+            .enumerate()
+            .map(|(index, start)| {
+                let end = starts
+                    .get(index + 1)
+                    .copied()
+                    .unwrap_or(self.bytecode.len());
+                let name = names
+                    .iter()
+                    .find(|(clause, _)| *clause == index)
+                    .map(|(_, name)| *name);
+                (name, &self.bytecode[*start..end])
+            })
+            .collect()
+    }
+    /// Return the bytecode of the clause named `name`, if there is one
+    pub fn clause(&self, name: &str) -> Option<&[u8]> {
+        self.clauses()
+            .into_iter()
+            .find(|(clause, _)| *clause == Some(name))
+            .map(|(_, bytecode)| bytecode)
+    }
+    /// Reorder the clauses so the clause at index `order[i]` is the `i`th, keeping their names
+    /// Origins are dropped as their offsets no longer match. `order` must be a permutation of
+    /// the clause indices.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn reorder_clauses(mut self, order: &[usize]) -> Self {
+        let clauses: Vec<Vec<u8>> = self
+            .clauses()
+            .into_iter()
+            .map(|(_, bytecode)| bytecode.to_vec())
+            .collect();
+        self.bytecode = order.iter().flat_map(|i| clauses[*i].clone()).collect();
+        if let Some(names) = &mut self.clause_names {
+            for (index, _) in names.iter_mut() {
+                *index = order.iter().position(|i| i == index).unwrap_or(*index);
+            }
+            names.sort_by_key(|(index, _)| *index);
+        }
+        self.origins = None;
+        self
+    }
+    /// Replace each clause's bytecode with `f` of it and the data table, keeping their names
+    /// Origins are dropped as their offsets may no longer match. `f` must return one clause.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn map_clauses(mut self, f: impl Fn(&[u8], &[PactType<'a>]) -> Vec<u8>) -> Self {
+        self.bytecode = self
+            .clauses()
+            .into_iter()
+            .flat_map(|(_, bytecode)| f(bytecode, self.data_table.as_ref()))
+            .collect();
+        self.origins = None;
+        self
+    }
+    /// Return the byte offset of each clause's first OpCode
+    fn clause_offsets(&self) -> Vec<usize> {
+        let mut offsets = Vec::new();
+        if !self.bytecode.is_empty() {
+            offsets.push(0);
+        }
+        // The first clause starts at 0, later clauses at a comparator which isn't conjoined
+        let mut conjoined = true;
+        for (offset, op) in self.ops().flatten() {
+            if let OpCode::COMP(_) = op {
+                if !conjoined {
+                    offsets.push(offset);
+                }
+            }
+            conjoined = matches!(op, OpCode::CONJ(_));
+        }
+        offsets
+    }
+    /// Return the labelled source contracts of this contract's OpCodes, if known
+    pub fn origins(&self) -> Option<&[Origin<'a>]> {
+        self.origins.as_deref()
+    }
+    /// Return the source contract label of the OpCode at `opcode_offset`, if known
+    /// e.g. with the offset of `Outcome::Rejected` or an `AssertionEvent`.
+    pub fn origin(&self, opcode_offset: usize) -> Option<&'a str> {
+        self.origins
+            .as_ref()?
+            .iter()
+            .take_while(|origin| origin.opcode_offset <= opcode_offset)
+            .last()?
+            .source
+    }
+    /// Return the contract's data table
+    pub fn data_table(&self) -> &DataTable<'a> {
+        &self.data_table
+    }
+    /// Return the contract's bytecode
+    pub fn bytecode(&self) -> &[u8] {
+        &self.bytecode
+    }
+    /// Return the declared input parameter names in positional order, if known
+    pub fn input_names(&self) -> Option<&[&'a str]> {
+        self.input_names.as_deref()
+    }
+    /// Return the declared input parameter types in positional order, if known
+    pub fn input_types(&self) -> Option<&[InputType]> {
+        self.input_types.as_deref()
+    }
+    /// Check `inputs` against the contract's input schema, if it has one
+    /// Hosts should call this before interpreting so mismatched input data is rejected with a
+    /// clear error, rather than `InterpErr::TypeMismatch` from whichever comparator loads it.
+    pub fn check_inputs(&self, inputs: &[PactType]) -> Result<(), SchemaErr> {
+        match &self.input_types {
+            Some(types) => schema::check(types, inputs),
+            None => Ok(()),
+        }
+    }
+    /// Iterate this contract's OpCodes with their byte offsets
+    pub fn ops(&self) -> OpCodes<'_> {
+        OpCodes::new(&self.bytecode)
+    }
+    /// Call `visitor` with each of this contract's OpCodes, stopping at the first malformed OpCode
+    pub fn visit(&self, visitor: &mut dyn Visitor) -> Result<(), InterpErr> {
+        self.ops().visit(visitor)
+    }
+    /// Count this contract's OpCodes, data table entries, list elements and encoded size
+    /// Runtimes apply a weight formula to these to charge fees without executing the contract.
+    pub fn stats(&self) -> Result<ContractStats, ValidationErr> {
+        let mut stats = ContractStats::default();
+        for op in self.ops() {
+            match op.map_err(ValidationErr::Malformed)? {
+                (_, OpCode::COMP(_)) => stats.comparators += 1,
+                (_, OpCode::CONJ(_)) => stats.conjunctions += 1,
+            }
+        }
+        let values = self.data_table.as_ref();
+        stats.data_table_entries = values.len();
+        stats.list_elements = values.iter().map(list_elements).sum();
+        stats.encoded_size = self.encoded_len().map_err(ValidationErr::Unencodable)?;
+        Ok(stats)
+    }
+    /// Return the capabilities an interpreter needs to decode and execute this contract
+    /// OpCodes after the first malformed OpCode are ignored.
+    pub fn capabilities(&self) -> Capabilities {
+        // `ops` ends after the first malformed OpCode
+        self.ops()
+            .flatten()
+            .fold(self.encoding_capabilities(), |capabilities, (_, op)| {
+                capabilities | op.capability()
+            })
+    }
+    /// Return the capabilities required by the binary format and data table
+    fn encoding_capabilities(&self) -> Capabilities {
+        let mut capabilities = match (&self.input_names, &self.input_types) {
+            (None, _) => Capabilities::NONE,
+            (Some(_), None) => Capabilities::INPUT_NAMES,
+            (Some(_), Some(types)) => {
+                let bool_input = types.contains(&InputType::Bool);
+                let fixed_point_input = types.iter().any(|t| matches!(t, InputType::FixedPoint(_)));
+                Capabilities::INPUT_NAMES
+                    | Capabilities::INPUT_SCHEMA
+                    | if bool_input {
+                        Capabilities::TYPE_BOOL
+                    } else {
+                        Capabilities::NONE
+                    }
+                    | if fixed_point_input {
+                        Capabilities::TYPE_FIXED_POINT
+                    } else {
+                        Capabilities::NONE
+                    }
+            }
+        };
+        for value in self.data_table.as_ref() {
+            capabilities = capabilities | value_capabilities(value, false);
+        }
+        if self.data_table.length_width() == Ok(LengthWidth::U16) {
+            capabilities = capabilities | Capabilities::WIDE_LENGTHS;
+        }
+        if self.clause_names.is_some() {
+            capabilities = capabilities | Capabilities::CLAUSE_NAMES;
+        }
+        if self.metadata.is_some() {
+            capabilities = capabilities | Capabilities::METADATA;
+        }
+        if self.declared_features.is_some() {
+            capabilities = capabilities | Capabilities::DECLARED_FEATURES;
+        }
+        capabilities
     }
-    /// Decode a pact contract from v0 binary format
+    /// Return the names and declared types of the contract's unbound placeholders, in data
+    /// table order
+    pub fn placeholders(&self) -> Vec<(&'a str, InputType)> {
+        self.data_table
+            .as_ref()
+            .iter()
+            .filter_map(|value| match value {
+                PactType::Placeholder(name, input_type) => Some((*name, *input_type)),
+                _ => None,
+            })
+            .collect()
+    }
+    /// Bind placeholders to values by name e.g. `("limit", PactType::Numeric(Numeric(100)))`
+    /// Names are as declared by `define`, without the `$` sigil. Placeholders left unbound fail
+    /// with `InterpErr::UnboundPlaceholder` when a comparator loads them.
+    /// The bytecode is unchanged, so templates can be filled in without recompiling.
+    pub fn bind<'b>(mut self, bindings: &[(&'b str, PactType<'a>)]) -> Result<Self, BindErr<'b>> {
+        let mut values = self.data_table.as_ref().to_vec();
+        for (name, value) in bindings {
+            let slot = values
+                .iter_mut()
+                .find(|slot| matches!(slot, PactType::Placeholder(n, _) if n == name))
+                .ok_or(BindErr::UnknownPlaceholder(name))?;
+            if let PactType::Placeholder(_, expected) = slot {
+                if !expected.matches(value) {
+                    return Err(BindErr::Mismatch {
+                        name,
+                        expected: *expected,
+                    });
+                }
+            }
+            *slot = value.clone();
+        }
+        self.data_table = DataTable::new(values);
+        Ok(self)
+    }
+    /// Replace the data table value at `index` with `value`, returning the previous value
+    /// The replacement must have the type of the value it replaces, so the bytecode's comparisons
+    /// stay well typed. Lists and sorted lists are interchangeable, and a placeholder may be
+    /// replaced by a value of its declared type.
+    pub fn replace_data(
+        &mut self,
+        index: usize,
+        value: PactType<'a>,
+    ) -> Result<PactType<'a>, PatchErr> {
+        let current = self
+            .data_table
+            .get(index)
+            .ok_or(PatchErr::MissingIndex(index))?;
+        let same_type = match (current, &value) {
+            (PactType::Placeholder(_, expected), value) => expected.matches(value),
+            (PactType::List(_), PactType::List(_))
+            | (PactType::List(_), PactType::SortedList(_))
+            | (PactType::SortedList(_), PactType::List(_))
+            | (PactType::SortedList(_), PactType::SortedList(_)) => true,
+            (current, value) => core::mem::discriminant(current) == core::mem::discriminant(value),
+        };
+        if !same_type {
+            return Err(PatchErr::TypeMismatch(index));
+        }
+        self.data_table.set(index, value)
+    }
+    /// Split the contract into its data table and bytecode
+    pub fn into_parts(self) -> (DataTable<'a>, Vec<u8>) {
+        (self.data_table, self.bytecode)
+    }
+    /// Encode the contract into `buf`
+    /// The v0 binary format is used unless the contract has input names, which require v1,
+    /// or an input schema, which requires v2.
+    /// Data table values too long for 1 byte lengths require v3, which wraps the v0-v2 layouts.
+    /// Clause names require v4, which prefixes the v0-v3 formats.
+    /// Metadata requires v5, which prefixes the v0-v4 formats.
+    /// Declared features require v6, which prefixes the v0-v5 formats.
+    /// `buf` is unchanged on failure.
+    pub fn encode(&self, buf: &mut Vec<u8>) -> Result<(), EncodeErr> {
+        buf.reserve(self.encoded_len()?);
+        self.write(buf)
+    }
+    /// Encode the contract into the start of `buf`, see `encode`
+    /// Returns the # of bytes written, `buf` is unchanged on failure.
+    pub fn encode_to(&self, buf: &mut [u8]) -> Result<usize, EncodeErr> {
+        encode_into(buf, self.encoded_len()?, |sink| self.write(sink))
+    }
+    /// Encode the contract as lowercase hex, see `encode`
+    #[cfg(feature = "codecs")]
+    pub fn to_hex(&self) -> Result<String, EncodeErr> {
+        let mut buf = Vec::new();
+        self.encode(&mut buf)?;
+        Ok(super::codec::encode_hex(&buf))
+    }
+    /// Encode the contract as padded base64 with the standard alphabet, see `encode`
+    #[cfg(feature = "codecs")]
+    pub fn to_base64(&self) -> Result<String, EncodeErr> {
+        let mut buf = Vec::new();
+        self.encode(&mut buf)?;
+        Ok(super::codec::encode_base64(&buf))
+    }
+    /// Return the # of bytes `encode` would write
+    pub fn encoded_len(&self) -> Result<usize, EncodeErr> {
+        let mut counter = ByteCounter::default();
+        self.write(&mut counter)?;
+        Ok(counter.0)
+    }
+    /// Write the contract encoding into `sink`
+    fn write(&self, sink: &mut dyn Sink) -> Result<(), EncodeErr> {
+        let width = self.data_table.length_width()?;
+        if self.declared_features.is_some() {
+            sink.put(6.swap_bits());
+            for b in self.required_features().bits().to_le_bytes().iter() {
+                sink.put(b.swap_bits());
+            }
+        }
+        if let Some(metadata) = &self.metadata {
+            sink.put(5.swap_bits());
+            metadata.write(sink)?;
+        }
+        if let Some(names) = &self.clause_names {
+            if names.len() > MAX_ENCODED_LENGTH {
+                return Err(EncodeErr::TooManyClauses);
+            }
+            sink.put(4.swap_bits());
+            sink.put((names.len() as u8).swap_bits());
+            for (clause, name) in names {
+                if *clause > 0xffff {
+                    return Err(EncodeErr::TooManyClauses);
+                }
+                if name.len() > MAX_ENCODED_LENGTH {
+                    return Err(EncodeErr::NameTooLong);
+                }
+                for b in (*clause as u16).to_le_bytes().iter() {
+                    sink.put(b.swap_bits());
+                }
+                sink.put((name.len() as u8).swap_bits());
+                sink.put_slice(name.as_bytes());
+            }
+        }
+        // The v0-v2 version, or the v3 parameters layout
+        let layout: u8 = match (&self.input_names, &self.input_types) {
+            (None, _) => 0,
+            (Some(_), None) => 1,
+            (Some(_), Some(_)) => 2,
+        };
+        if width == LengthWidth::U16 {
+            sink.put(3.swap_bits());
+        }
+        sink.put(layout.swap_bits());
+        if let Some(names) = &self.input_names {
+            if names.len() > MAX_ENCODED_LENGTH {
+                return Err(EncodeErr::TooManyInputs);
+            }
+            sink.put((names.len() as u8).swap_bits());
+            for (index, name) in names.iter().enumerate() {
+                if name.len() > MAX_ENCODED_LENGTH {
+                    return Err(EncodeErr::NameTooLong);
+                }
+                sink.put((name.len() as u8).swap_bits());
+                sink.put_slice(name.as_bytes());
+                if let Some(types) = &self.input_types {
+                    sink.put(types[index].code().swap_bits());
+                }
+            }
+        }
+        self.data_table.write(sink, width)?;
+        sink.put_slice(&self.bytecode);
+        Ok(())
+    }
+    /// Validate the contract bytecode without executing it
+    /// Checks the OpCode structure, data table indices, and that every OpCode and declared feature
+    /// is in `allowed`.
+    /// Comparators with a fixed outcome from comparing an input with itself are rejected.
+    /// Hosts and wallets use this to refuse contracts using capabilities they don't support.
+    pub fn validate(&self, allowed: Capabilities) -> Result<(), ValidationErr> {
+        let missing = (self.encoding_capabilities() | self.declared_features.unwrap_or_default())
+            .difference(allowed);
+        if !missing.is_empty() {
+            return Err(ValidationErr::Unsupported { required: missing });
+        }
+        self.walk_bytecode(true, |offset, op| {
+            let missing = op.capability().difference(allowed);
+            if !missing.is_empty() {
+                return Err(ValidationErr::Disallowed {
+                    offset,
+                    required: missing,
+                });
+            }
+            match op {
+                OpCode::COMP(comparator) if comparator.is_self_comparison() => {
+                    Err(ValidationErr::SelfComparison {
+                        offset,
+                        index: comparator.indices.lhs,
+                    })
+                }
+                _ => Ok(()),
+            }
+        })
+        .map_err(|err| match err {
+            WalkErr::Malformed(err) => ValidationErr::Malformed(err),
+            WalkErr::MissingIndex { offset, index } => {
+                ValidationErr::MissingIndex { offset, index }
+            }
+            WalkErr::Visit(err) => err,
+        })?;
+        if let Some(names) = &self.clause_names {
+            let clauses = self.clause_offsets().len();
+            if let Some((index, _)) = names.iter().find(|(index, _)| *index >= clauses) {
+                return Err(ValidationErr::MissingClause { index: *index });
+            }
+        }
+        Ok(())
+    }
+    /// Return the canonical form of this contract
+    /// Different compilations of the same logic share a canonical form, so its encoding can be
+    /// hashed to deduplicate stored contracts.
+    /// - Input names and types, clause names, metadata, declared features, and origins are dropped
+    ///   as they don't affect evaluation
+    /// - The data table holds only referenced values, deduplicated and sorted by their encoding
+    /// - Unquantified input vs. input comparators are flipped to prefer non-inverted OpCodes
+    ///   e.g. `input[0] NOT GTE input[1]` becomes `input[1] GT input[0]`
+    /// - Input vs. input `EQ` comparators have the lower index on the LHS
+    pub fn canonicalize(&self) -> Result<Contract<'a>, ValidationErr> {
+        self.validate(Capabilities::all())?;
+
+        // Collect referenced values, deduplicated and sorted by encoding
+        let mut table: Vec<(Vec<u8>, &PactType<'a>)> = Vec::new();
+        let mut scanner = self.bytecode.iter();
+        // The bytecode has been validated so it parses
+        while let Ok(Some(op)) = OpCode::parse(&mut scanner) {
+            if let OpCode::COMP(comparator) = op {
+                let rhs = Some(comparator.indices.rhs)
+                    .filter(|_| comparator.load == OpLoad::INPUT_VS_USER);
+                let ratio = match comparator.op {
+                    OpComp::RATIO(ratio) => Some(ratio.index),
+                    _ => None,
+                };
+                for index in rhs.into_iter().chain(ratio) {
+                    let value = &self.data_table.as_ref()[index as usize];
+                    let mut encoded = Vec::new();
+                    value
+                        .encode(&mut encoded)
+                        .map_err(ValidationErr::Unencodable)?;
+                    if let Err(index) = table.binary_search_by(|(e, _)| e.cmp(&encoded)) {
+                        table.insert(index, (encoded, value));
+                    }
+                }
+            }
+        }
+
+        let mut bytecode: Vec<u8> = Vec::with_capacity(self.bytecode.len());
+        let mut scanner = self.bytecode.iter();
+        while let Ok(Some(op)) = OpCode::parse(&mut scanner) {
+            match op {
+                OpCode::COMP(mut comparator) => {
+                    let canonical_index = |index: u8| {
+                        let value = &self.data_table.as_ref()[index as usize];
+                        table.iter().position(|(_, v)| *v == value).unwrap_or(0) as u8
+                    };
+                    if let OpComp::RATIO(ratio) = &mut comparator.op {
+                        ratio.index = canonical_index(ratio.index);
+                    }
+                    match comparator.load {
+                        OpLoad::INPUT_VS_USER => {
+                            comparator.indices.rhs = canonical_index(comparator.indices.rhs);
+                        }
+                        // Quantified comparators range over their LHS so can't be flipped
+                        OpLoad::INPUT_VS_INPUT if comparator.quant != OpQuant::ONE => {}
+                        OpLoad::INPUT_VS_INPUT => {
+                            let flip = match comparator.op {
+                                OpComp::EQ | OpComp::INTERSECTS => {
+                                    comparator.indices.lhs > comparator.indices.rhs
+                                }
+                                OpComp::GT | OpComp::GTE => comparator.invert == OpInvert::NOT,
+                                // `IN` and `SUBSET` are not symmetric so they can't be flipped,
+                                // `RATIO` only scales its RHS, and `CALL` never loads two inputs
+                                OpComp::IN | OpComp::SUBSET | OpComp::RATIO(_) | OpComp::CALL => {
+                                    false
+                                }
+                            };
+                            if flip {
+                                comparator = comparator.flip_indices();
+                            }
+                        }
+                    }
+                    OpCode::COMP(comparator)
+                        .compile(&mut bytecode)
+                        .map_err(ValidationErr::Unassemblable)?;
+                }
+                OpCode::CONJ(_) => {
+                    op.compile(&mut bytecode)
+                        .map_err(ValidationErr::Unassemblable)?;
+                }
+            }
+        }
+
+        Ok(Contract {
+            data_table: DataTable::new(table.into_iter().map(|(_, v)| v.clone()).collect()),
+            bytecode,
+            input_names: None,
+            input_types: None,
+            origins: None,
+            clause_names: None,
+            metadata: None,
+            declared_features: None,
+        })
+    }
+    /// Whether this contract is semantically identical to `other`
+    /// Contracts are compared in canonical form, see `canonicalize`.
+    /// Returns `false` if either contract is invalid.
+    pub fn equivalent(&self, other: &Contract) -> bool {
+        match (self.canonicalize(), other.canonicalize()) {
+            (Ok(lhs), Ok(rhs)) => {
+                lhs.data_table.as_ref() == rhs.data_table.as_ref() && lhs.bytecode == rhs.bytecode
+            }
+            _ => false,
+        }
+    }
+    /// Return the BLAKE2b-256 hash of this contract's canonical v0 (or v3 for long values) encoding
+    /// Equivalent contracts share a hash, see `canonicalize`.
+    /// The hash is stable across crate versions so it may be stored and verified later.
+    #[cfg(feature = "hashing")]
+    pub fn hash(&self) -> Result<[u8; 32], ValidationErr> {
+        let mut buf = Vec::new();
+        self.canonicalize()?
+            .encode(&mut buf)
+            .map_err(ValidationErr::Unencodable)?;
+        Ok(super::blake2b::blake2b_256(&buf))
+    }
+    /// Combine this contract with `other` so the result holds iff `self <conjunction> other` holds
+    /// Both contracts are evaluated against the same input registers.
+    /// `other`'s data table is appended to this contract's and its indices rebased.
+    /// Input names are kept if one contract's names are a prefix of the other's, and likewise for
+    /// input types if both contracts have them.
+    ///
+    /// Clauses are implicitly `AND`ed so any contracts can be merged with `AND`.
+    /// Conjunctions are evaluated left to right without precedence, so merging with `OR` or `XOR`
+    /// requires `self` to be a single clause and `other` a single clause joined only by the same
+    /// conjunction.
+    /// Clause names are kept when merging with `AND`, as each contract's clauses are kept. Merging
+    /// with `OR` or `XOR` joins two clauses into one so their names are dropped.
+    /// This contract's metadata is kept, or `other`'s if this contract has none.
+    pub fn merge(self, other: Contract<'a>, conjunction: OpConj) -> Result<Contract<'a>, MergeErr> {
+        self.validate(Capabilities::all())
+            .map_err(MergeErr::Invalid)?;
+        other
+            .validate(Capabilities::all())
+            .map_err(MergeErr::Invalid)?;
+
+        let offset = self.data_table.as_ref().len();
+        if offset + other.data_table.as_ref().len() > MAX_DATA_TABLE_ENTRIES {
+            return Err(MergeErr::DataTableFull);
+        }
+
+        let conjunction = Conjunction::new(conjunction);
+        // `other`'s clauses follow this contract's
+        let rebase = self.clause_offsets().len();
+        let origins = match (self.origins, other.origins) {
+            (None, None) => None,
+            (lhs, rhs) => {
+                let unlabelled = || {
+                    vec![Origin {
+                        opcode_offset: 0,
+                        source: None,
+                    }]
+                };
+                // `other`'s OpCodes follow this contract's and any joining conjunction
+                let start = self.bytecode.len() + if conjunction.op == OpConj::AND { 0 } else { 1 };
+                let mut origins = lhs.unwrap_or_else(unlabelled);
+                origins.extend(
+                    rhs.unwrap_or_else(unlabelled)
+                        .into_iter()
+                        .map(|origin| Origin {
+                            opcode_offset: origin.opcode_offset + start,
+                            source: origin.source,
+                        }),
+                );
+                Some(origins)
+            }
+        };
+        let clause_names = match (self.clause_names, other.clause_names) {
+            (None, None) => None,
+            _ if conjunction.op != OpConj::AND => None,
+            (lhs, rhs) => {
+                let mut names = lhs.unwrap_or_default();
+                names.extend(
+                    rhs.unwrap_or_default()
+                        .into_iter()
+                        .map(|(index, name)| (index + rebase, name)),
+                );
+                Some(names)
+            }
+        };
+        let mut bytecode = self.bytecode;
+        if conjunction.op != OpConj::AND {
+            if !is_single_clause(&bytecode, None)
+                || !is_single_clause(&other.bytecode, Some(conjunction))
+            {
+                return Err(MergeErr::Unrepresentable);
+            }
+            OpCode::CONJ(conjunction)
+                .compile(&mut bytecode)
+                .map_err(|_| MergeErr::Unrepresentable)?;
+        }
+
+        let mut scanner = other.bytecode.iter();
+        // The bytecode has been validated so it parses
+        while let Ok(Some(mut op)) = OpCode::parse(&mut scanner) {
+            if let OpCode::COMP(comparator) = &mut op {
+                if comparator.load == OpLoad::INPUT_VS_USER {
+                    comparator.indices.rhs += offset as u8;
+                }
+                if let OpComp::RATIO(ratio) = &mut comparator.op {
+                    ratio.index += offset as u8;
+                }
+            }
+            // Rebased indices are within the data table, which has been checked
+            op.compile(&mut bytecode)
+                .map_err(|_| MergeErr::DataTableFull)?;
+        }
+
+        let mut data_table = self.data_table;
+        for value in other.data_table.as_ref() {
+            data_table.push(value.clone());
+        }
+
+        let input_names = merge_prefixed(self.input_names, other.input_names);
+        let input_types = match (self.input_types, other.input_types) {
+            (Some(lhs), Some(rhs)) if input_names.is_some() => merge_prefixed(Some(lhs), Some(rhs)),
+            _ => None,
+        };
+
+        Ok(Contract {
+            data_table,
+            bytecode,
+            input_names,
+            input_types,
+            origins,
+            clause_names,
+            metadata: self.metadata.or(other.metadata),
+            declared_features: match (self.declared_features, other.declared_features) {
+                (None, None) => None,
+                (lhs, rhs) => Some(lhs.unwrap_or_default() | rhs.unwrap_or_default()),
+            },
+        })
+    }
+    /// Decode a pact contract from v0, v1, v2, v3, v4, v5, or v6 binary format
+    /// Features declared by a v6 contract are kept even if they are unknown to this build, so
+    /// interpreters can refuse it, see `required_features`.
     pub fn decode(buf: &'a [u8]) -> Result<Self, BinaryFormatErr> {
+        match buf.first().map(|version| version.swap_bits()) {
+            Some(6) => {
+                let features = match buf.get(1..5) {
+                    Some([a, b, c, d]) => u32::from_le_bytes([
+                        a.swap_bits(),
+                        b.swap_bits(),
+                        c.swap_bits(),
+                        d.swap_bits(),
+                    ]),
+                    _ => return Err(BinaryFormatErr::TooShort),
+                };
+                let mut contract = Self::decode_described(&buf[5..])?;
+                contract.declared_features = Some(Capabilities::from_bits(features));
+                Ok(contract)
+            }
+            _ => Self::decode_described(buf),
+        }
+    }
+    /// Decode a pact contract as `decode`, then check its bytecode at `level`
+    /// Malformed bytecode is otherwise only found by `validate` or when it's interpreted.
+    pub fn decode_validated(
+        buf: &'a [u8],
+        level: ValidationLevel,
+    ) -> Result<Self, BinaryFormatErr> {
+        let contract = Self::decode(buf)?;
+        contract
+            .check_bytecode(level)
+            .map_err(BinaryFormatErr::MalformedBytecode)?;
+        Ok(contract)
+    }
+    /// Check the bytecode at `level`, failing with the first error an interpreter would raise
+    fn check_bytecode(&self, level: ValidationLevel) -> Result<(), InterpErr> {
+        self.walk_bytecode(level == ValidationLevel::Indices, |_, _| {
+            Ok::<(), Infallible>(())
+        })
+        .map_err(|err| match err {
+            WalkErr::Malformed(err) => err,
+            WalkErr::MissingIndex { index, .. } => InterpErr::MissingIndex(index),
+            WalkErr::Visit(never) => match never {},
+        })
+    }
+    /// Walk the bytecode, calling `visit` with each OpCode and its offset before checking it
+    /// Every OpCode must parse and every conjunction must be between comparators. If
+    /// `check_indices`, every data table index a comparator loads must exist.
+    fn walk_bytecode<E>(
+        &self,
+        check_indices: bool,
+        mut visit: impl FnMut(usize, OpCode) -> Result<(), E>,
+    ) -> Result<(), WalkErr<E>> {
+        let entries = self.data_table.as_ref().len();
+        let mut expect_comparator = true;
+        for op in OpCodes::new(&self.bytecode) {
+            let (offset, op) = op.map_err(WalkErr::Malformed)?;
+            visit(offset, op).map_err(WalkErr::Visit)?;
+            match op {
+                OpCode::COMP(comparator) => {
+                    let ratio = match comparator.op {
+                        OpComp::RATIO(ratio) => Some(ratio.index),
+                        _ => None,
+                    };
+                    let rhs = Some(comparator.indices.rhs)
+                        .filter(|_| comparator.load == OpLoad::INPUT_VS_USER);
+                    for index in rhs.iter().chain(ratio.iter()) {
+                        if check_indices && *index as usize >= entries {
+                            return Err(WalkErr::MissingIndex {
+                                offset,
+                                index: *index,
+                            });
+                        }
+                    }
+                    expect_comparator = false;
+                }
+                OpCode::CONJ(_) => {
+                    if expect_comparator {
+                        return Err(WalkErr::Malformed(InterpErr::UnexpectedOpCode(op.into())));
+                    }
+                    expect_comparator = true;
+                }
+            }
+        }
+        if expect_comparator {
+            return Err(WalkErr::Malformed(InterpErr::UnexpectedEOI(
+                "incomplete operation",
+            )));
+        }
+        Ok(())
+    }
+    /// Decode a pact contract from hex of either case, with an optional `0x` prefix, see `decode`
+    /// The contract borrows its strings from the binary format, which is written into `buf`.
+    #[cfg(feature = "codecs")]
+    pub fn from_hex(text: &str, buf: &'a mut Vec<u8>) -> Result<Self, BinaryFormatErr> {
+        buf.clear();
+        super::codec::decode_hex(text, buf).map_err(BinaryFormatErr::InvalidText)?;
+        Self::decode(buf)
+    }
+    /// Decode a pact contract from base64 with the standard alphabet, padded or not, see `decode`
+    /// The contract borrows its strings from the binary format, which is written into `buf`.
+    #[cfg(feature = "codecs")]
+    pub fn from_base64(text: &str, buf: &'a mut Vec<u8>) -> Result<Self, BinaryFormatErr> {
+        buf.clear();
+        super::codec::decode_base64(text, buf).map_err(BinaryFormatErr::InvalidText)?;
+        Self::decode(buf)
+    }
+    /// Decode a pact contract from v0, v1, v2, v3, v4, or v5 binary format
+    fn decode_described(buf: &'a [u8]) -> Result<Self, BinaryFormatErr> {
+        match buf.first().map(|version| version.swap_bits()) {
+            Some(5) => {
+                let (metadata, read) = Metadata::decode(&buf[1..])?;
+                Ok(Self::decode_named(&buf[1 + read..])?.with_metadata(metadata))
+            }
+            _ => Self::decode_named(buf),
+        }
+    }
+    /// Decode a pact contract from v0, v1, v2, v3, or v4 binary format
+    fn decode_named(buf: &'a [u8]) -> Result<Self, BinaryFormatErr> {
+        match buf.first().map(|version| version.swap_bits()) {
+            Some(4) => {
+                let (names, read) = decode_clause_names(&buf[1..])?;
+                Ok(Self::decode_unnamed(&buf[1 + read..])?.with_clause_names(names))
+            }
+            _ => Self::decode_unnamed(buf),
+        }
+    }
+    /// Decode a pact contract from v0, v1, v2, or v3 binary format
+    fn decode_unnamed(buf: &'a [u8]) -> Result<Self, BinaryFormatErr> {
         if buf.len() < 2 {
             return Err(BinaryFormatErr::TooShort);
         }
-        if buf[0].swap_bits() != 0 {
-            return Err(BinaryFormatErr::UnsupportedVersion);
+        // v3 is followed by the v0-v2 layout with 2 byte data table value lengths
+        let (width, layout, offset) = match buf[0].swap_bits() {
+            3 => match buf[1].swap_bits() {
+                layout @ 0..=2 => (LengthWidth::U16, layout, 2),
+                _ => {
+                    return Err(BinaryFormatErr::MalformedMetadata(
+                        "unknown parameters layout",
+                    ))
+                }
+            },
+            version @ 0..=2 => (LengthWidth::U8, version, 1),
+            _ => return Err(BinaryFormatErr::UnsupportedVersion),
+        };
+        if offset >= buf.len() {
+            return Err(BinaryFormatErr::TooShort);
+        }
+        let (input_names, input_types, offset) = match layout {
+            0 => (None, None, offset),
+            _ => {
+                let (names, types, read) = decode_parameters(&buf[offset..], layout == 2)?;
+                (Some(names), types, offset + read)
+            }
+        };
+        if offset >= buf.len() {
+            return Err(BinaryFormatErr::TooShort);
         }
-        let (data_table, offset) =
-            DataTable::decode(&buf[1..]).map_err(|err| BinaryFormatErr::MalformedDataTable(err))?;
-        let bytecode = buf[1usize + offset..].to_vec();
+        let (data_table, read) = DataTable::decode_as(&buf[offset..], width)
+            .map_err(BinaryFormatErr::MalformedDataTable)?;
+        let bytecode = buf[offset + read..].to_vec();
         Ok(Self {
             data_table,
             bytecode,
+            input_names,
+            input_types,
+            origins: None,
+            clause_names: None,
+            metadata: None,
+            declared_features: None,
         })
     }
 }
 
+/// Count the elements of `value` if it is a list, including elements of nested lists
+fn list_elements(value: &PactType) -> usize {
+    match value {
+        PactType::List(list) | PactType::SortedList(list) => {
+            list.len() + list.iter().map(list_elements).sum::<usize>()
+        }
+        _ => 0,
+    }
+}
+
+/// Return the capabilities required to decode `value`, which is `nested` in a list
+fn value_capabilities(value: &PactType, nested: bool) -> Capabilities {
+    match value {
+        PactType::Bool(_) => Capabilities::TYPE_BOOL,
+        PactType::List(list) => list.iter().fold(
+            if nested {
+                Capabilities::NESTED_LIST
+            } else {
+                Capabilities::NONE
+            },
+            |capabilities, element| capabilities | value_capabilities(element, true),
+        ),
+        PactType::SortedList(_) if nested => Capabilities::SORTED_LIST | Capabilities::NESTED_LIST,
+        PactType::SortedList(_) => Capabilities::SORTED_LIST,
+        PactType::Placeholder(_, InputType::FixedPoint(_)) => {
+            Capabilities::PLACEHOLDERS | Capabilities::TYPE_FIXED_POINT
+        }
+        PactType::Placeholder(..) => Capabilities::PLACEHOLDERS,
+        PactType::FixedPoint { .. } => Capabilities::TYPE_FIXED_POINT,
+        _ => Capabilities::NONE,
+    }
+}
+
+/// The decoded input parameter names, types (if `typed`), and # of bytes read
+type Parameters<'a> = (Vec<&'a str>, Option<Vec<InputType>>, usize);
+
+/// Decode the v1 (or v2 if `typed`) metadata section of input parameters from `buf`
+fn decode_parameters(buf: &[u8], typed: bool) -> Result<Parameters<'_>, BinaryFormatErr> {
+    let count = buf.first().ok_or(BinaryFormatErr::TooShort)?.swap_bits();
+    let mut names = Vec::with_capacity(count as usize);
+    let mut types = Vec::with_capacity(if typed { count as usize } else { 0 });
+    let mut offset = 1;
+    for _ in 0..count {
+        let len = buf
+            .get(offset)
+            .ok_or(BinaryFormatErr::MalformedMetadata(
+                "missing name length byte",
+            ))?
+            .swap_bits() as usize;
+        let name =
+            buf.get(offset + 1..offset + 1 + len)
+                .ok_or(BinaryFormatErr::MalformedMetadata(
+                    "name length > buffer length",
+                ))?;
+        let name = core::str::from_utf8(name)
+            .map_err(|_| BinaryFormatErr::MalformedMetadata("name is not valid UTF-8"))?;
+        names.push(name);
+        offset += 1 + len;
+        if typed {
+            let code = buf
+                .get(offset)
+                .ok_or(BinaryFormatErr::MalformedMetadata(
+                    "missing input type byte",
+                ))?
+                .swap_bits();
+            types.push(
+                InputType::from_code(code)
+                    .ok_or(BinaryFormatErr::MalformedMetadata("unknown input type"))?,
+            );
+            offset += 1;
+        }
+    }
+    Ok((names, if typed { Some(types) } else { None }, offset))
+}
+
+/// The decoded clause indices and names, and # of bytes read
+type ClauseNames<'a> = (Vec<(usize, &'a str)>, usize);
+
+/// Decode the v4 metadata section of clause names from `buf`
+fn decode_clause_names(buf: &[u8]) -> Result<ClauseNames<'_>, BinaryFormatErr> {
+    let count = buf.first().ok_or(BinaryFormatErr::TooShort)?.swap_bits();
+    let mut names: Vec<(usize, &str)> = Vec::with_capacity(count as usize);
+    let mut offset = 1;
+    for _ in 0..count {
+        let index = match buf.get(offset..offset + 2) {
+            Some([lo, hi]) => u16::from_le_bytes([lo.swap_bits(), hi.swap_bits()]) as usize,
+            _ => {
+                return Err(BinaryFormatErr::MalformedMetadata(
+                    "missing clause index bytes",
+                ))
+            }
+        };
+        if matches!(names.last(), Some((last, _)) if *last >= index) {
+            return Err(BinaryFormatErr::MalformedMetadata(
+                "clause indices are not ascending",
+            ));
+        }
+        let len = buf
+            .get(offset + 2)
+            .ok_or(BinaryFormatErr::MalformedMetadata(
+                "missing name length byte",
+            ))?
+            .swap_bits() as usize;
+        let name =
+            buf.get(offset + 3..offset + 3 + len)
+                .ok_or(BinaryFormatErr::MalformedMetadata(
+                    "name length > buffer length",
+                ))?;
+        let name = core::str::from_utf8(name)
+            .map_err(|_| BinaryFormatErr::MalformedMetadata("name is not valid UTF-8"))?;
+        if names.iter().any(|(_, n)| *n == name) {
+            return Err(BinaryFormatErr::MalformedMetadata("duplicate clause name"));
+        }
+        names.push((index, name));
+        offset += 3 + len;
+    }
+    Ok((names, offset))
+}
+
+/// Merge positional metadata, keeping the longer of `lhs` and `rhs` if the shorter is its prefix
+fn merge_prefixed<T: PartialEq>(lhs: Option<Vec<T>>, rhs: Option<Vec<T>>) -> Option<Vec<T>> {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => {
+            let (shorter, longer) = if lhs.len() <= rhs.len() {
+                (lhs, rhs)
+            } else {
+                (rhs, lhs)
+            };
+            if longer.starts_with(&shorter) {
+                Some(longer)
+            } else {
+                None
+            }
+        }
+        (values, None) | (None, values) => values,
+    }
+}
+
+/// Whether `bytecode` is a single clause, i.e. every comparator after the first is conjoined
+/// If `only` is given every conjunction must equal it
+fn is_single_clause(bytecode: &[u8], only: Option<Conjunction>) -> bool {
+    let mut scanner = bytecode.iter();
+    let mut conjoined = true;
+    while let Ok(Some(op)) = OpCode::parse(&mut scanner) {
+        match op {
+            OpCode::COMP(_) => {
+                if !conjoined {
+                    return false;
+                }
+                conjoined = false;
+            }
+            OpCode::CONJ(conjunction) => {
+                if only.is_some() && only != Some(conjunction) {
+                    return false;
+                }
+                conjoined = true;
+            }
+        }
+    }
+    true
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::types::{Numeric, PactType, StringLike};
 
     #[test]
     fn contract_binary_format_unsupported_version() {
@@ -81,4 +1398,732 @@ mod test {
     fn contract_binary_format_too_short() {
         assert_eq!(Contract::decode(&[0]), Err(BinaryFormatErr::TooShort));
     }
+
+    #[test]
+    fn contract_binary_format_malformed_metadata() {
+        let v1 = 1u8.swap_bits();
+        assert_eq!(
+            Contract::decode(&[v1, 1u8.swap_bits()]),
+            Err(BinaryFormatErr::MalformedMetadata(
+                "missing name length byte"
+            ))
+        );
+        assert_eq!(
+            Contract::decode(&[v1, 1u8.swap_bits(), 3u8.swap_bits(), b'a']),
+            Err(BinaryFormatErr::MalformedMetadata(
+                "name length > buffer length"
+            ))
+        );
+        assert_eq!(
+            Contract::decode(&[v1, 1u8.swap_bits(), 1u8.swap_bits(), 0xff, 0]),
+            Err(BinaryFormatErr::MalformedMetadata(
+                "name is not valid UTF-8"
+            ))
+        );
+        // Names without a data table
+        assert_eq!(
+            Contract::decode(&[v1, 1u8.swap_bits(), 1u8.swap_bits(), b'a']),
+            Err(BinaryFormatErr::TooShort)
+        );
+    }
+
+    #[test]
+    fn contract_binary_format_malformed_schema() {
+        let v2 = 2u8.swap_bits();
+        let one = 1u8.swap_bits();
+        assert_eq!(
+            Contract::decode(&[v2, one, one, b'a']),
+            Err(BinaryFormatErr::MalformedMetadata(
+                "missing input type byte"
+            ))
+        );
+        assert_eq!(
+            Contract::decode(&[v2, one, one, b'a', 5u8.swap_bits(), 0]),
+            Err(BinaryFormatErr::MalformedMetadata("unknown input type"))
+        );
+    }
+
+    #[test]
+    fn contract_binary_format_v2_round_trip() {
+        let typed = contract(vec![0x00, 0x00]).with_input_schema(vec![
+            ("amount", InputType::Numeric),
+            ("memo", InputType::Any),
+        ]);
+        let mut encoded = Vec::<u8>::default();
+        typed.encode(&mut encoded).unwrap();
+        assert_eq!(encoded[0], 2u8.swap_bits());
+        assert_eq!(Contract::decode(&encoded), Ok(typed));
+    }
+
+    #[test]
+    fn contract_binary_format_v1_round_trip() {
+        let named = contract(vec![0x00, 0x00]).with_input_names(vec!["amount"]);
+        let mut encoded = Vec::<u8>::default();
+        named.encode(&mut encoded).unwrap();
+        assert_eq!(encoded[0], 1u8.swap_bits());
+        assert_eq!(Contract::decode(&encoded), Ok(named));
+
+        // Unnamed contracts stay in the v0 format
+        let mut encoded = Vec::<u8>::default();
+        contract(vec![0x00, 0x00]).encode(&mut encoded).unwrap();
+        assert_eq!(encoded[0], 0);
+    }
+
+    #[test]
+    fn contract_binary_format_v3_wide_lengths() {
+        let long = "x".repeat(300);
+        let table = || DataTable::new(vec![PactType::StringLike(StringLike(long.as_bytes()))]);
+        let plain = Contract::from_parts_unchecked(table(), vec![0x00, 0x00]);
+        let typed = Contract::from_parts_unchecked(table(), vec![0x00, 0x00])
+            .with_input_schema(vec![("memo", InputType::StringLike)]);
+        for contract in &[plain, typed] {
+            let mut encoded = Vec::<u8>::default();
+            contract.encode(&mut encoded).unwrap();
+            assert_eq!(encoded[0], 3u8.swap_bits());
+            assert_eq!(&Contract::decode(&encoded).unwrap(), contract);
+        }
+
+        assert_eq!(
+            Contract::decode(&[3u8.swap_bits(), 3u8.swap_bits(), 0]),
+            Err(BinaryFormatErr::MalformedMetadata(
+                "unknown parameters layout"
+            ))
+        );
+        assert_eq!(
+            Contract::decode(&[3u8.swap_bits(), 0]),
+            Err(BinaryFormatErr::TooShort)
+        );
+        assert_eq!(
+            Contract::from_parts_unchecked(table(), vec![0x00, 0x00]).capabilities(),
+            Capabilities::COMP_EQ | Capabilities::WIDE_LENGTHS
+        );
+    }
+
+    #[test]
+    fn contract_binary_format_v4_clause_names() {
+        let long = "x".repeat(300);
+        let wide = Contract::from_parts_unchecked(
+            DataTable::new(vec![PactType::StringLike(StringLike(long.as_bytes()))]),
+            vec![0x00, 0x00],
+        )
+        .with_clause_names(vec![(0, "limit")]);
+        let named = contract(vec![0x00, 0x00, 0x01, 0x00])
+            .with_input_names(vec!["amount"])
+            .with_clause_names(vec![(1, "limit")]);
+        for contract in &[named, wide] {
+            let mut encoded = Vec::<u8>::default();
+            contract.encode(&mut encoded).unwrap();
+            assert_eq!(encoded[0], 4u8.swap_bits());
+            assert_eq!(&Contract::decode(&encoded).unwrap(), contract);
+            assert!(contract.capabilities().contains(Capabilities::CLAUSE_NAMES));
+        }
+
+        let v4 = 4u8.swap_bits();
+        let one = 1u8.swap_bits();
+        assert_eq!(Contract::decode(&[v4]), Err(BinaryFormatErr::TooShort));
+        assert_eq!(
+            Contract::decode(&[v4, one, 0]),
+            Err(BinaryFormatErr::MalformedMetadata(
+                "missing clause index bytes"
+            ))
+        );
+        assert_eq!(
+            Contract::decode(&[
+                v4,
+                2u8.swap_bits(),
+                one,
+                0,
+                one,
+                b'a',
+                0,
+                0,
+                one,
+                b'b',
+                0,
+                0
+            ]),
+            Err(BinaryFormatErr::MalformedMetadata(
+                "clause indices are not ascending"
+            ))
+        );
+        assert_eq!(
+            Contract::decode(&[
+                v4,
+                2u8.swap_bits(),
+                0,
+                0,
+                one,
+                b'a',
+                one,
+                0,
+                one,
+                b'a',
+                0,
+                0
+            ]),
+            Err(BinaryFormatErr::MalformedMetadata("duplicate clause name"))
+        );
+        // Clause names are not nested
+        assert_eq!(
+            Contract::decode(&[v4, 0, v4, 0, 0, 0]),
+            Err(BinaryFormatErr::UnsupportedVersion)
+        );
+    }
+
+    #[test]
+    fn contract_binary_format_v5_metadata() {
+        let metadata = Metadata {
+            title: Some("Spending limit"),
+            entries: vec![("team", "payments")],
+            ..Default::default()
+        };
+        let plain = contract(vec![0x00, 0x00]).with_metadata(metadata.clone());
+        let named = contract(vec![0x00, 0x00])
+            .with_clause_names(vec![(0, "limit")])
+            .with_metadata(metadata);
+        for contract in &[plain, named] {
+            let mut encoded = Vec::<u8>::default();
+            contract.encode(&mut encoded).unwrap();
+            assert_eq!(encoded[0], 5u8.swap_bits());
+            assert_eq!(&Contract::decode(&encoded).unwrap(), contract);
+            assert!(contract.capabilities().contains(Capabilities::METADATA));
+            // Metadata doesn't affect evaluation
+            assert_eq!(contract.canonicalize().unwrap().metadata(), None);
+        }
+
+        let v5 = 5u8.swap_bits();
+        assert_eq!(Contract::decode(&[v5]), Err(BinaryFormatErr::TooShort));
+        // Metadata is not nested
+        assert_eq!(
+            Contract::decode(&[v5, 0, v5, 0, 0, 0]),
+            Err(BinaryFormatErr::UnsupportedVersion)
+        );
+    }
+
+    #[test]
+    fn contract_binary_format_v6_declared_features() {
+        let declared = contract(vec![0x00, 0x00])
+            .with_clause_names(vec![(0, "limit")])
+            .with_declared_features();
+        let mut encoded = Vec::<u8>::default();
+        declared.encode(&mut encoded).unwrap();
+        assert_eq!(encoded[0], 6u8.swap_bits());
+        assert_eq!(encoded[5], 4u8.swap_bits());
+        assert_eq!(Contract::decode(&encoded).unwrap(), declared);
+        assert_eq!(
+            declared.required_features(),
+            Capabilities::COMP_EQ | Capabilities::CLAUSE_NAMES | Capabilities::DECLARED_FEATURES
+        );
+        assert!(declared.canonicalize().unwrap().declared_features.is_none());
+
+        // Unknown declared features fail validation
+        let unknown = Capabilities::from_bits(1 << 31);
+        let required = declared.required_features();
+        let mut future = declared;
+        future.declared_features = Some(required | unknown);
+        assert_eq!(
+            future.validate(Capabilities::all()),
+            Err(ValidationErr::Unsupported { required: unknown })
+        );
+
+        let v6 = 6u8.swap_bits();
+        assert_eq!(
+            Contract::decode(&[v6, 0, 0, 0]),
+            Err(BinaryFormatErr::TooShort)
+        );
+        assert_eq!(
+            Contract::decode(&[v6, 0, 0, 0, 0, v6, 0, 0, 0, 0, 0, 0]),
+            Err(BinaryFormatErr::UnsupportedVersion)
+        );
+    }
+
+    #[test]
+    fn it_fails_to_encode_oversize_metadata() {
+        let mut encoded = Vec::<u8>::default();
+        let long = "x".repeat(256);
+        assert_eq!(
+            contract(vec![0x00, 0x00])
+                .with_input_names(vec![&long])
+                .encode(&mut encoded),
+            Err(EncodeErr::NameTooLong)
+        );
+        assert_eq!(
+            contract(vec![0x00, 0x00])
+                .with_input_names(vec!["a"; 256])
+                .encode(&mut encoded),
+            Err(EncodeErr::TooManyInputs)
+        );
+        assert!(encoded.is_empty());
+    }
+
+    fn contract(bytecode: Vec<u8>) -> Contract<'static> {
+        Contract {
+            data_table: DataTable::new(vec![PactType::Numeric(Numeric(1))]),
+            bytecode,
+            input_names: None,
+            input_types: None,
+            origins: None,
+            clause_names: None,
+            metadata: None,
+            declared_features: None,
+        }
+    }
+
+    #[test]
+    fn it_validates_new_contracts() {
+        let table = || DataTable::new(vec![PactType::Numeric(Numeric(1))]);
+        // INPUT(0) == USER(1)
+        assert_eq!(
+            Contract::new(table(), vec![0x00, 0x01]),
+            Err(ValidationErr::MissingIndex {
+                offset: 0,
+                index: 1
+            })
+        );
+        let contract = Contract::new(table(), vec![0x00, 0x00]).unwrap();
+        assert_eq!(contract.data_table().as_ref(), table().as_ref());
+        assert_eq!(contract.bytecode(), &[0x00, 0x00]);
+
+        // Trusted paths skip validation
+        let contract = Contract::from_parts_unchecked(table(), vec![0x00, 0x01]);
+        assert_eq!(contract.into_parts().1, vec![0x00, 0x01]);
+    }
+
+    #[test]
+    fn it_canonicalizes() {
+        // INPUT(0) == USER(2) AND INPUT(0) NOT GTE INPUT(1),
+        // INPUT(1) == USER(3) OR INPUT(1) == INPUT(0)
+        let c = Contract {
+            data_table: DataTable::new(vec![
+                PactType::Numeric(Numeric(1)),
+                PactType::Numeric(Numeric(3)),
+                PactType::Numeric(Numeric(2)),
+                PactType::Numeric(Numeric(3)),
+            ]),
+            bytecode: vec![0x00, 0x02, 0x20, 0x1a, 0x01, 0x00, 0x13, 0x21, 0x08, 0x10],
+            input_names: None,
+            input_types: None,
+            origins: None,
+            clause_names: None,
+            metadata: None,
+            declared_features: None,
+        };
+        // INPUT(0) == USER(0) AND INPUT(1) GT INPUT(0),
+        // INPUT(1) == USER(1) OR INPUT(0) == INPUT(1)
+        let canonical = Contract {
+            data_table: DataTable::new(vec![
+                PactType::Numeric(Numeric(2)),
+                PactType::Numeric(Numeric(3)),
+            ]),
+            bytecode: vec![0x00, 0x00, 0x20, 0x09, 0x10, 0x00, 0x11, 0x21, 0x08, 0x01],
+            input_names: None,
+            input_types: None,
+            origins: None,
+            clause_names: None,
+            metadata: None,
+            declared_features: None,
+        };
+        assert_eq!(c.canonicalize(), Ok(canonical));
+
+        // Canonicalization is idempotent
+        let canonical = c.canonicalize().unwrap();
+        assert_eq!(canonical.canonicalize().unwrap(), canonical);
+
+        assert!(c.equivalent(&canonical));
+        assert!(!c.equivalent(&contract(vec![0x00, 0x00])));
+        assert!(!contract(vec![0x20]).equivalent(&contract(vec![0x20])));
+    }
+
+    #[test]
+    fn it_decodes_validated_bytecode() {
+        let decode = |bytecode: Vec<u8>, level| {
+            let mut encoded = Vec::new();
+            contract(bytecode.clone()).encode(&mut encoded).unwrap();
+            Contract::decode_validated(&encoded, level).map(|c| c.bytecode == bytecode)
+        };
+        // INPUT(0) == USER(0) OR INPUT(0) == INPUT(1)
+        let valid = vec![0x00, 0x00, 0x21, 0x08, 0x01];
+        assert_eq!(decode(valid.clone(), ValidationLevel::Structure), Ok(true));
+        assert_eq!(decode(valid, ValidationLevel::Indices), Ok(true));
+        // INPUT(0) == USER(1)
+        assert_eq!(
+            decode(vec![0x00, 0x01], ValidationLevel::Structure),
+            Ok(true)
+        );
+        assert_eq!(
+            decode(vec![0x00, 0x01], ValidationLevel::Indices),
+            Err(BinaryFormatErr::MalformedBytecode(InterpErr::MissingIndex(
+                1
+            )))
+        );
+        // INPUT(0) == INPUT(1) * USER(1)
+        assert_eq!(
+            decode(vec![0x0f, 0x01, 0x01], ValidationLevel::Indices),
+            Err(BinaryFormatErr::MalformedBytecode(InterpErr::MissingIndex(
+                1
+            )))
+        );
+        for level in [ValidationLevel::Structure, ValidationLevel::Indices].iter() {
+            assert_eq!(
+                decode(vec![], *level),
+                Err(BinaryFormatErr::MalformedBytecode(
+                    InterpErr::UnexpectedEOI("incomplete operation")
+                ))
+            );
+            // Consecutive clauses
+            assert_eq!(decode(vec![0x00, 0x00, 0x00, 0x00], *level), Ok(true));
+            assert_eq!(
+                decode(vec![0x00, 0x00, 0x20], *level),
+                Err(BinaryFormatErr::MalformedBytecode(
+                    InterpErr::UnexpectedEOI("incomplete operation")
+                ))
+            );
+            assert_eq!(
+                decode(vec![0x20, 0x00, 0x00], *level),
+                Err(BinaryFormatErr::MalformedBytecode(
+                    InterpErr::UnexpectedOpCode(0x20)
+                ))
+            );
+            assert_eq!(
+                decode(vec![0xc0, 0x00], *level),
+                Err(BinaryFormatErr::MalformedBytecode(
+                    InterpErr::InvalidOpCode(0xc0)
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn it_validates_allowed_capabilities() {
+        // INPUT(0) == USER(0) OR INPUT(0) == INPUT(1)
+        let c = contract(vec![0x00, 0x00, 0x21, 0x08, 0x01]);
+        assert_eq!(c.validate(Capabilities::all()), Ok(()));
+        assert_eq!(
+            c.validate(Capabilities::COMP_EQ | Capabilities::CONJ_OR),
+            Err(ValidationErr::Disallowed {
+                offset: 3,
+                required: Capabilities::LOAD_INPUT_VS_INPUT,
+            })
+        );
+        assert_eq!(
+            c.validate(Capabilities::COMP_EQ | Capabilities::LOAD_INPUT_VS_INPUT),
+            Err(ValidationErr::Disallowed {
+                offset: 2,
+                required: Capabilities::CONJ_OR,
+            })
+        );
+    }
+
+    #[test]
+    fn it_reports_the_version_required_by_a_contract() {
+        let v0_1 = Capabilities::supported_by(Version::new(0, 1, 0));
+        let c = contract(vec![0x00, 0x00]);
+        assert_eq!(c.capabilities(), Capabilities::COMP_EQ);
+        assert_eq!(c.validate(v0_1), Ok(()));
+
+        let named = contract(vec![0x00, 0x00]).with_input_schema(vec![("a", InputType::Bool)]);
+        assert_eq!(
+            named.capabilities(),
+            Capabilities::COMP_EQ
+                | Capabilities::INPUT_NAMES
+                | Capabilities::INPUT_SCHEMA
+                | Capabilities::TYPE_BOOL
+        );
+        let err = named.validate(v0_1).unwrap_err();
+        assert_eq!(
+            err,
+            ValidationErr::Unsupported {
+                required: Capabilities::INPUT_NAMES
+                    | Capabilities::INPUT_SCHEMA
+                    | Capabilities::TYPE_BOOL
+            }
+        );
+        assert_eq!(err.min_version(), Some(Version::new(0, 2, 0)));
+
+        let nested = Contract::from_parts_unchecked(
+            DataTable::new(vec![PactType::List(vec![PactType::List(vec![])])]),
+            vec![0x03, 0x00],
+        );
+        assert_eq!(
+            nested.validate(v0_1),
+            Err(ValidationErr::Unsupported {
+                required: Capabilities::NESTED_LIST
+            })
+        );
+
+        // INPUT(0) CALL USER(0)
+        let call = contract(vec![0x04, 0x00]);
+        assert_eq!(
+            call.validate(v0_1).unwrap_err().min_version(),
+            Some(Version::new(0, 2, 0))
+        );
+        assert_eq!(
+            ValidationErr::MissingIndex {
+                offset: 0,
+                index: 1
+            }
+            .min_version(),
+            None
+        );
+    }
+
+    #[test]
+    fn it_fails_validation_with_self_comparisons() {
+        let all = Capabilities::all();
+        // INPUT(0) == USER(0) AND INPUT(1) == INPUT(1)
+        assert_eq!(
+            contract(vec![0x00, 0x00, 0x20, 0x08, 0x11]).validate(all),
+            Err(ValidationErr::SelfComparison {
+                offset: 3,
+                index: 1
+            })
+        );
+        // INPUT(2) NOT GT INPUT(2)
+        assert_eq!(
+            contract(vec![0x19, 0x22]).validate(all),
+            Err(ValidationErr::SelfComparison {
+                offset: 0,
+                index: 2
+            })
+        );
+        // INPUT(0) == INPUT(1) is allowed, INPUT(0) IN INPUT(0) is malformed
+        assert_eq!(contract(vec![0x08, 0x01]).validate(all), Ok(()));
+        assert_eq!(
+            contract(vec![0x0b, 0x00]).validate(all),
+            Err(ValidationErr::Malformed(InterpErr::InvalidOperands(
+                0x0b, 0x00
+            )))
+        );
+    }
+
+    #[test]
+    fn it_fails_validation_with_malformed_bytecode() {
+        let all = Capabilities::all();
+        assert_eq!(
+            contract(vec![]).validate(all),
+            Err(ValidationErr::Malformed(InterpErr::UnexpectedEOI(
+                "incomplete operation"
+            )))
+        );
+        assert_eq!(
+            contract(vec![0x20]).validate(all),
+            Err(ValidationErr::Malformed(InterpErr::UnexpectedOpCode(0x20)))
+        );
+        assert_eq!(
+            contract(vec![0x00, 0x00, 0x20]).validate(all),
+            Err(ValidationErr::Malformed(InterpErr::UnexpectedEOI(
+                "incomplete operation"
+            )))
+        );
+        assert_eq!(
+            contract(vec![0xc0, 0x00]).validate(all),
+            Err(ValidationErr::Malformed(InterpErr::InvalidOpCode(0xc0)))
+        );
+        assert_eq!(
+            contract(vec![0x00, 0x00, 0x00, 0x01]).validate(all),
+            Err(ValidationErr::MissingIndex {
+                offset: 2,
+                index: 1
+            })
+        );
+    }
+
+    #[test]
+    fn it_counts_contract_stats() {
+        // INPUT(0) == USER(0) AND INPUT(0) IN USER(1)
+        let contract = Contract::from_parts_unchecked(
+            DataTable::new(vec![
+                PactType::Numeric(Numeric(1)),
+                PactType::List(vec![
+                    PactType::Numeric(Numeric(1)),
+                    PactType::List(vec![
+                        PactType::Numeric(Numeric(2)),
+                        PactType::Numeric(Numeric(3)),
+                    ]),
+                ]),
+            ]),
+            vec![0x00, 0x00, 0x20, 0x03, 0x01],
+        );
+        let mut encoded = Vec::new();
+        contract.encode(&mut encoded).unwrap();
+        assert_eq!(
+            contract.stats(),
+            Ok(ContractStats {
+                comparators: 2,
+                conjunctions: 1,
+                data_table_entries: 2,
+                list_elements: 4,
+                encoded_size: encoded.len(),
+            })
+        );
+        assert_eq!(
+            Contract::from_parts_unchecked(DataTable::new(vec![]), vec![0x00]).stats(),
+            Err(ValidationErr::Malformed(InterpErr::UnexpectedEOI(
+                "expected index"
+            )))
+        );
+    }
+
+    #[test]
+    fn it_attributes_merged_opcodes_to_their_origin() {
+        // INPUT(0) == USER(0) AND INPUT(0) == USER(0)
+        let kyc = contract(vec![0x00, 0x00, 0x20, 0x00, 0x00]).with_origin("kyc.pact");
+        let limits = contract(vec![0x01, 0x00]).with_origin("limits.pact");
+        let merged = kyc.merge(limits, OpConj::AND).unwrap();
+        assert_eq!(merged.origin(0), Some("kyc.pact"));
+        assert_eq!(merged.origin(3), Some("kyc.pact"));
+        assert_eq!(merged.origin(5), Some("limits.pact"));
+
+        // Unlabelled contracts have no origin, and `OR` conjunctions belong to the LHS
+        let merged = contract(vec![0x00, 0x00])
+            .merge(
+                contract(vec![0x01, 0x00]).with_origin("limits.pact"),
+                OpConj::OR,
+            )
+            .unwrap();
+        assert_eq!(merged.bytecode(), &[0x00, 0x00, 0x21, 0x01, 0x01]);
+        assert_eq!(merged.origin(0), None);
+        assert_eq!(merged.origin(2), None);
+        assert_eq!(merged.origin(3), Some("limits.pact"));
+        assert_eq!(
+            merged.origins(),
+            Some(
+                &[
+                    Origin {
+                        opcode_offset: 0,
+                        source: None
+                    },
+                    Origin {
+                        opcode_offset: 3,
+                        source: Some("limits.pact")
+                    }
+                ][..]
+            )
+        );
+
+        let unlabelled = contract(vec![0x00, 0x00])
+            .merge(contract(vec![0x01, 0x00]), OpConj::AND)
+            .unwrap();
+        assert_eq!(unlabelled.origins(), None);
+        assert_eq!(merged.canonicalize().unwrap().origins(), None);
+    }
+
+    #[test]
+    fn it_splits_named_clauses() {
+        // INPUT(0) == USER(0) AND INPUT(0) GT USER(0), INPUT(0) GT USER(0)
+        let limits = contract(vec![0x00, 0x00, 0x20, 0x01, 0x00, 0x01, 0x00])
+            .with_clause_names(vec![(1, "limit")]);
+        assert_eq!(
+            limits.clauses(),
+            vec![
+                (None, &[0x00, 0x00, 0x20, 0x01, 0x00][..]),
+                (Some("limit"), &[0x01, 0x00][..])
+            ]
+        );
+        assert_eq!(limits.clause("limit"), Some(&[0x01, 0x00][..]));
+        assert_eq!(limits.clause("kyc"), None);
+        assert_eq!(contract(Vec::new()).clauses(), vec![]);
+
+        assert_eq!(
+            contract(vec![0x00, 0x00])
+                .with_clause_names(vec![(1, "limit")])
+                .validate(Capabilities::all()),
+            Err(ValidationErr::MissingClause { index: 1 })
+        );
+        assert_eq!(
+            limits.validate(Capabilities::all().difference(Capabilities::CLAUSE_NAMES)),
+            Err(ValidationErr::Unsupported {
+                required: Capabilities::CLAUSE_NAMES
+            })
+        );
+
+        // `AND` keeps every clause so names are rebased, `OR` joins clauses so names are dropped
+        let kyc = || contract(vec![0x00, 0x00]).with_clause_names(vec![(0, "kyc")]);
+        let merged = kyc().merge(limits, OpConj::AND).unwrap();
+        assert_eq!(merged.clause_names(), Some(&[(0, "kyc"), (2, "limit")][..]));
+        // `limit`'s data table index is rebased too
+        assert_eq!(merged.clause("limit"), Some(&[0x01, 0x01][..]));
+        assert_eq!(merged.canonicalize().unwrap().clause_names(), None);
+        let merged = kyc().merge(contract(vec![0x01, 0x00]), OpConj::OR).unwrap();
+        assert_eq!(merged.clause_names(), None);
+    }
+
+    #[test]
+    fn it_merges_input_schemas() {
+        let typed = |params| contract(vec![0x00, 0x00]).with_input_schema(params);
+        let merged = typed(vec![("a", InputType::Numeric)])
+            .merge(
+                typed(vec![("a", InputType::Numeric), ("b", InputType::List)]),
+                OpConj::AND,
+            )
+            .unwrap();
+        assert_eq!(merged.input_names(), Some(&["a", "b"][..]));
+        assert_eq!(
+            merged.input_types(),
+            Some(&[InputType::Numeric, InputType::List][..])
+        );
+
+        // Conflicting types are dropped while the names are kept
+        let merged = typed(vec![("a", InputType::Numeric)])
+            .merge(typed(vec![("a", InputType::StringLike)]), OpConj::AND)
+            .unwrap();
+        assert_eq!(merged.input_names(), Some(&["a"][..]));
+        assert_eq!(merged.input_types(), None);
+
+        let merged = typed(vec![("a", InputType::Numeric)])
+            .merge(
+                contract(vec![0x00, 0x00]).with_input_names(vec!["a"]),
+                OpConj::AND,
+            )
+            .unwrap();
+        assert_eq!(merged.input_types(), None);
+    }
+
+    #[test]
+    fn it_fails_to_merge() {
+        let full = || Contract {
+            data_table: DataTable::new(vec![PactType::Numeric(Numeric(1)); 10]),
+            bytecode: vec![0x00, 0x00],
+            input_names: None,
+            input_types: None,
+            origins: None,
+            clause_names: None,
+            metadata: None,
+            declared_features: None,
+        };
+        assert_eq!(
+            full().merge(full(), OpConj::AND),
+            Err(MergeErr::DataTableFull)
+        );
+        assert_eq!(
+            contract(vec![0x00]).merge(contract(vec![0x00, 0x00]), OpConj::AND),
+            Err(MergeErr::Invalid(ValidationErr::Malformed(
+                InterpErr::UnexpectedEOI("expected index")
+            )))
+        );
+        // INPUT(0) == USER(0) OR INPUT(0) == USER(0) can't be `XOR`ed as one clause
+        assert_eq!(
+            contract(vec![0x00, 0x00])
+                .merge(contract(vec![0x00, 0x00, 0x21, 0x00, 0x00]), OpConj::XOR),
+            Err(MergeErr::Unrepresentable)
+        );
+        // INPUT(0) == USER(0) XOR INPUT(0) == USER(1) XOR INPUT(0) == USER(1)
+        assert_eq!(
+            contract(vec![0x00, 0x00])
+                .merge(contract(vec![0x00, 0x00, 0x22, 0x00, 0x00]), OpConj::XOR),
+            Ok(Contract {
+                data_table: DataTable::new(vec![
+                    PactType::Numeric(Numeric(1)),
+                    PactType::Numeric(Numeric(1))
+                ]),
+                bytecode: vec![0x00, 0x00, 0x22, 0x00, 0x01, 0x22, 0x00, 0x01],
+                input_names: None,
+                input_types: None,
+                origins: None,
+                clause_names: None,
+                metadata: None,
+                declared_features: None,
+            })
+        );
+    }
 }
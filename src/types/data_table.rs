@@ -14,10 +14,17 @@
 //   <https://centrality.ai/licenses/gplv3.txt>
 //   <https://centrality.ai/licenses/lgplv3.txt>
 
-use crate::types::PactType;
+use crate::types::opcode::MAX_INDEX;
+use crate::types::{
+    base::{encode_into, Sink},
+    DecodeLimits, EncodeErr, LengthWidth, PactType, PatchErr,
+};
 use alloc::vec::Vec;
 use bit_reverse::ParallelReverse;
 
+/// The most entries in a data table, as many as comparator indices can address
+pub const MAX_ENTRIES: usize = MAX_INDEX as usize + 1;
+
 /// A pact contract's static data table
 #[cfg_attr(feature = "std", derive(PartialEq, Debug))]
 pub struct DataTable<'a>(Vec<PactType<'a>>);
@@ -25,27 +32,146 @@ pub struct DataTable<'a>(Vec<PactType<'a>>);
 impl<'a> DataTable<'a> {
     /// Create a new `DataTable` with `values`
     pub fn new(values: Vec<PactType<'a>>) -> Self {
-        Self { 0: values }
+        Self(values)
     }
     /// Push a PactType value into the table
     pub fn push(&mut self, val: PactType<'a>) {
         self.0.push(val);
     }
-    /// Encode the data table
-    pub fn encode(&self, buf: &mut Vec<u8>) {
-        buf.push((self.0.len() as u8).swap_bits());
+    /// Return the # of values in the table
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    /// Whether the table has no values
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Return the value at `index`, if there is one
+    pub fn get(&self, index: usize) -> Option<&PactType<'a>> {
+        self.0.get(index)
+    }
+    /// Return the value at `index` if it is a numeric
+    pub fn get_numeric(&self, index: usize) -> Option<u64> {
+        match self.0.get(index) {
+            Some(PactType::Numeric(n)) => Some(n.0),
+            _ => None,
+        }
+    }
+    /// Return the value at `index` if it is a string-like
+    pub fn get_string(&self, index: usize) -> Option<&'a [u8]> {
+        match self.0.get(index) {
+            Some(PactType::StringLike(s)) => Some(s.0),
+            _ => None,
+        }
+    }
+    /// Return the value at `index` if it is a bool
+    pub fn get_bool(&self, index: usize) -> Option<bool> {
+        match self.0.get(index) {
+            Some(PactType::Bool(b)) => Some(*b),
+            _ => None,
+        }
+    }
+    /// Return the elements of the value at `index` if it is a list or sorted list
+    pub fn get_list(&self, index: usize) -> Option<&[PactType<'a>]> {
+        match self.0.get(index) {
+            Some(PactType::List(l)) | Some(PactType::SortedList(l)) => Some(l),
+            _ => None,
+        }
+    }
+    /// Replace the value at `index` with `value`, returning the previous value
+    /// The table is unchanged if `index` is out of range. Values of any type are accepted, see
+    /// `Contract::replace_data` to keep the contract's comparisons meaningful.
+    pub fn set(&mut self, index: usize, value: PactType<'a>) -> Result<PactType<'a>, PatchErr> {
+        let slot = self.0.get_mut(index).ok_or(PatchErr::MissingIndex(index))?;
+        Ok(core::mem::replace(slot, value))
+    }
+    /// Encode the data table with 1 byte value lengths
+    /// `buf` is unchanged on failure.
+    pub fn encode(&self, buf: &mut Vec<u8>) -> Result<(), EncodeErr> {
+        self.encode_as(buf, LengthWidth::U8)
+    }
+    /// Encode the data table with `width` value lengths
+    /// `buf` is unchanged on failure.
+    pub fn encode_as(&self, buf: &mut Vec<u8>, width: LengthWidth) -> Result<(), EncodeErr> {
+        buf.reserve(self.encoded_len_as(width)?);
+        self.write(buf, width)
+    }
+    /// Encode the data table with 1 byte value lengths into the start of `buf`
+    /// Returns the # of bytes written, `buf` is unchanged on failure.
+    pub fn encode_to(&self, buf: &mut [u8]) -> Result<usize, EncodeErr> {
+        encode_into(buf, self.encoded_len()?, |sink| {
+            self.write(sink, LengthWidth::U8)
+        })
+    }
+    /// Return the # of bytes `encode` would write
+    pub fn encoded_len(&self) -> Result<usize, EncodeErr> {
+        self.encoded_len_as(LengthWidth::U8)
+    }
+    /// Return the # of bytes `encode_as` would write with `width` value lengths
+    pub fn encoded_len_as(&self, width: LengthWidth) -> Result<usize, EncodeErr> {
+        if self.0.len() > MAX_ENTRIES {
+            return Err(EncodeErr::DataTableTooLong);
+        }
+        let mut length = 1;
         for t in self.0.iter() {
-            t.encode(buf);
+            length += t.encoded_len_as(width)?;
         }
+        Ok(length)
     }
-    /// Decode a DataTable from `buf`.
+    /// Write the data table encoding into `sink` with `width` value lengths
+    pub(crate) fn write(&self, sink: &mut dyn Sink, width: LengthWidth) -> Result<(), EncodeErr> {
+        if self.0.len() > MAX_ENTRIES {
+            return Err(EncodeErr::DataTableTooLong);
+        }
+        sink.put((self.0.len() as u8).swap_bits());
+        for t in self.0.iter() {
+            t.write(sink, width)?;
+        }
+        Ok(())
+    }
+    /// Return the narrowest length width which can encode every value
+    pub fn length_width(&self) -> Result<LengthWidth, EncodeErr> {
+        for t in self.0.iter() {
+            match t.encoded_len() {
+                Ok(_) => {}
+                Err(EncodeErr::StringTooLong) | Err(EncodeErr::ListTooLarge) => {
+                    return Ok(LengthWidth::U16)
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(LengthWidth::U8)
+    }
+    /// Decode a DataTable with 1 byte value lengths from `buf`.
     /// Return the DataTable and # of bytes read or error on failure.
     pub fn decode(buf: &'a [u8]) -> Result<(Self, usize), &'static str> {
+        Self::decode_as(buf, LengthWidth::U8)
+    }
+    /// Decode a DataTable with `width` value lengths from `buf`.
+    /// Return the DataTable and # of bytes read or error on failure.
+    pub fn decode_as(buf: &'a [u8], width: LengthWidth) -> Result<(Self, usize), &'static str> {
+        Self::decode_with_limits(buf, width, DecodeLimits::default())
+    }
+    /// Decode a DataTable with `width` value lengths from `buf`, with lists within `limits`.
+    /// Return the DataTable and # of bytes read or error on failure e.g. more than `MAX_ENTRIES`
+    /// entries.
+    pub fn decode_with_limits(
+        buf: &'a [u8],
+        width: LengthWidth,
+        limits: DecodeLimits,
+    ) -> Result<(Self, usize), &'static str> {
         let mut table = DataTable(Default::default());
         let mut offset: usize = 1;
-        let l = buf[0].swap_bits();
+        let l = buf
+            .first()
+            .ok_or("missing data table length byte")?
+            .swap_bits();
+        if l as usize > MAX_ENTRIES {
+            return Err("data table has more than MAX_ENTRIES entries");
+        }
         for _ in 0..l {
-            let (pact_type, read) = PactType::decode(&buf[offset..])?;
+            let entry = buf.get(offset..).ok_or("missing type ID byte")?;
+            let (pact_type, read) = PactType::decode_with_limits(entry, width, limits)?;
             table.push(pact_type);
             offset += read;
         }
@@ -72,7 +198,7 @@ mod tests {
             PactType::StringLike(StringLike("testing".as_bytes())),
         ]);
         let mut encoded: Vec<u8> = Vec::new();
-        table.encode(&mut encoded);
+        table.encode(&mut encoded).unwrap();
 
         // DataTable should simply encode to a concatenated list of it's encoded PactTypes
         let mut expected: Vec<u8> = vec![
@@ -90,6 +216,74 @@ mod tests {
         assert_eq!(encoded, expected,);
     }
 
+    #[test]
+    fn it_fails_to_encode_oversize_tables() {
+        let mut encoded: Vec<u8> = Vec::new();
+        let table = DataTable::new(vec![PactType::Numeric(Numeric(1)); MAX_ENTRIES + 1]);
+        assert_eq!(table.encode(&mut encoded), Err(EncodeErr::DataTableTooLong));
+
+        let long = [b'x'; 256];
+        let table = DataTable::new(vec![
+            PactType::Numeric(Numeric(1)),
+            PactType::StringLike(StringLike(&long)),
+        ]);
+        assert_eq!(table.encode(&mut encoded), Err(EncodeErr::StringTooLong));
+        assert!(encoded.is_empty());
+    }
+
+    #[test]
+    fn it_fails_to_decode_malformed_tables() {
+        assert_eq!(
+            DataTable::decode(&[]),
+            Err("missing data table length byte")
+        );
+        // 2 entries, only 1 of which is present
+        let truncated: Vec<u8> = [2, 3, 1, 1].iter().map(|b: &u8| b.swap_bits()).collect();
+        assert_eq!(DataTable::decode(&truncated), Err("missing type ID byte"));
+        let mut encoded: Vec<u8> = Vec::new();
+        DataTable::new(vec![PactType::Bool(true); MAX_ENTRIES])
+            .encode(&mut encoded)
+            .unwrap();
+        assert!(DataTable::decode(&encoded).is_ok());
+        encoded[0] = ((MAX_ENTRIES + 1) as u8).swap_bits();
+        assert_eq!(
+            DataTable::decode(&encoded),
+            Err("data table has more than MAX_ENTRIES entries")
+        );
+    }
+
+    #[test]
+    fn it_gets_and_sets_values() {
+        let mut table = DataTable::new(vec![
+            PactType::Numeric(Numeric(1)),
+            PactType::StringLike(StringLike(b"alice")),
+            PactType::Bool(true),
+            PactType::List(vec![PactType::Numeric(Numeric(2))]),
+        ]);
+        assert_eq!(table.len(), 4);
+        assert!(!table.is_empty());
+        assert_eq!(table.get_numeric(0), Some(1));
+        assert_eq!(table.get_string(1), Some(&b"alice"[..]));
+        assert_eq!(table.get_bool(2), Some(true));
+        assert_eq!(
+            table.get_list(3),
+            Some(&[PactType::Numeric(Numeric(2))][..])
+        );
+        assert_eq!(table.get_numeric(1), None);
+        assert_eq!(table.get(4), None);
+
+        assert_eq!(
+            table.set(0, PactType::Numeric(Numeric(5))),
+            Ok(PactType::Numeric(Numeric(1)))
+        );
+        assert_eq!(table.get_numeric(0), Some(5));
+        assert_eq!(
+            table.set(4, PactType::Numeric(Numeric(5))),
+            Err(PatchErr::MissingIndex(4))
+        );
+        assert_eq!(table.len(), 4);
+    }
+
     #[test]
     fn it_decodes() {
         let mut buf: Vec<u8> = vec![
@@ -0,0 +1,344 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Human-readable contract metadata e.g. a title and author
+//! Metadata is encoded in the v5 binary format and ignored by the interpreter
+//!
+use crate::types::{
+    base::{Sink, MAX_ENCODED_LENGTH},
+    BinaryFormatErr, EncodeErr,
+};
+use alloc::{vec, vec::Vec};
+use bit_reverse::ParallelReverse;
+
+/// Field tags of the v5 metadata section
+const TAG_TITLE: u8 = 0;
+const TAG_AUTHOR: u8 = 1;
+const TAG_VERSION: u8 = 2;
+const TAG_DESCRIPTION: u8 = 3;
+const TAG_ENTRY: u8 = 4;
+
+/// Human-readable contract metadata, see `Contract::with_metadata`
+/// Values are at most 255 bytes long.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Default, PartialEq)]
+pub struct Metadata<'a> {
+    /// A short title e.g. "Spending limit"
+    pub title: Option<&'a str>,
+    /// The contract author
+    pub author: Option<&'a str>,
+    /// The version of the contract's specification e.g. "1.2.0"
+    pub version: Option<&'a str>,
+    /// A longer description of the contract
+    pub description: Option<&'a str>,
+    /// Free-form `(key, value)` pairs with distinct keys, in insertion order
+    /// The key and value of an entry are at most 254 bytes long together.
+    pub entries: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Metadata<'a> {
+    /// Whether no fields or entries are set
+    pub fn is_empty(&self) -> bool {
+        self.fields().iter().all(|(_, value)| value.is_none()) && self.entries.is_empty()
+    }
+    /// Return the value of the field or free-form entry named `key`
+    /// The fields are named `title`, `author`, `version`, and `description`.
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        match self.field(key) {
+            Some(field) => *field,
+            None => self
+                .entries
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, value)| *value),
+        }
+    }
+    /// Iterate the set fields by name, then the free-form entries, as `(key, value)` pairs
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &'a str)> + '_ {
+        let fields = vec![
+            ("title", self.title),
+            ("author", self.author),
+            ("version", self.version),
+            ("description", self.description),
+        ];
+        fields
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key, value)))
+            .chain(self.entries.iter().copied())
+    }
+    /// Set the field or free-form entry named `key` to `value`, see `get`
+    /// Returns `false` and leaves the metadata unchanged if it is already set.
+    pub fn insert(&mut self, key: &'a str, value: &'a str) -> bool {
+        if self.get(key).is_some() {
+            return false;
+        }
+        match self.field_mut(key) {
+            Some(field) => *field = Some(value),
+            None => self.entries.push((key, value)),
+        }
+        true
+    }
+    /// The tags and values of the fields, in tag order
+    fn fields(&self) -> [(u8, Option<&'a str>); 4] {
+        [
+            (TAG_TITLE, self.title),
+            (TAG_AUTHOR, self.author),
+            (TAG_VERSION, self.version),
+            (TAG_DESCRIPTION, self.description),
+        ]
+    }
+    fn field(&self, key: &str) -> Option<&Option<&'a str>> {
+        match key {
+            "title" => Some(&self.title),
+            "author" => Some(&self.author),
+            "version" => Some(&self.version),
+            "description" => Some(&self.description),
+            _ => None,
+        }
+    }
+    fn field_mut(&mut self, key: &str) -> Option<&mut Option<&'a str>> {
+        match key {
+            "title" => Some(&mut self.title),
+            "author" => Some(&mut self.author),
+            "version" => Some(&mut self.version),
+            "description" => Some(&mut self.description),
+            _ => None,
+        }
+    }
+    /// Write the v5 metadata section into `sink`
+    pub(crate) fn write(&self, sink: &mut dyn Sink) -> Result<(), EncodeErr> {
+        let fields: Vec<(u8, &str)> = self
+            .fields()
+            .iter()
+            .filter_map(|(tag, value)| value.map(|value| (*tag, value)))
+            .collect();
+        if fields.len() + self.entries.len() > MAX_ENCODED_LENGTH {
+            return Err(EncodeErr::MetadataTooLong);
+        }
+        sink.put(((fields.len() + self.entries.len()) as u8).swap_bits());
+        for (tag, value) in fields {
+            if value.len() > MAX_ENCODED_LENGTH {
+                return Err(EncodeErr::MetadataTooLong);
+            }
+            sink.put(tag.swap_bits());
+            sink.put((value.len() as u8).swap_bits());
+            sink.put_slice(value.as_bytes());
+        }
+        for (key, value) in &self.entries {
+            let len = 1 + key.len() + value.len();
+            if len > MAX_ENCODED_LENGTH {
+                return Err(EncodeErr::MetadataTooLong);
+            }
+            sink.put(TAG_ENTRY.swap_bits());
+            sink.put((len as u8).swap_bits());
+            sink.put((key.len() as u8).swap_bits());
+            sink.put_slice(key.as_bytes());
+            sink.put_slice(value.as_bytes());
+        }
+        Ok(())
+    }
+    /// Decode the v5 metadata section from `buf`, returning it and the # of bytes read
+    /// Fields with unknown tags are skipped so later formats may add them.
+    pub(crate) fn decode(buf: &'a [u8]) -> Result<(Self, usize), BinaryFormatErr> {
+        let count = buf.first().ok_or(BinaryFormatErr::TooShort)?.swap_bits();
+        let mut metadata = Metadata::default();
+        let mut offset = 1;
+        for _ in 0..count {
+            let (tag, len) = match buf.get(offset..offset + 2) {
+                Some([tag, len]) => (tag.swap_bits(), len.swap_bits() as usize),
+                _ => {
+                    return Err(BinaryFormatErr::MalformedMetadata(
+                        "missing metadata tag or length byte",
+                    ))
+                }
+            };
+            let value =
+                buf.get(offset + 2..offset + 2 + len)
+                    .ok_or(BinaryFormatErr::MalformedMetadata(
+                        "metadata length > buffer length",
+                    ))?;
+            offset += 2 + len;
+            let (key, value) = match tag {
+                TAG_TITLE => ("title", value),
+                TAG_AUTHOR => ("author", value),
+                TAG_VERSION => ("version", value),
+                TAG_DESCRIPTION => ("description", value),
+                TAG_ENTRY => {
+                    let key_len = match value.first() {
+                        Some(key_len) => key_len.swap_bits() as usize,
+                        None => {
+                            return Err(BinaryFormatErr::MalformedMetadata(
+                                "missing metadata key length byte",
+                            ))
+                        }
+                    };
+                    let key =
+                        value
+                            .get(1..1 + key_len)
+                            .ok_or(BinaryFormatErr::MalformedMetadata(
+                                "metadata key length > entry length",
+                            ))?;
+                    (utf8(key)?, &value[1 + key_len..])
+                }
+                _ => continue,
+            };
+            if !metadata.insert(key, utf8(value)?) {
+                return Err(BinaryFormatErr::MalformedMetadata(
+                    "duplicate metadata field",
+                ));
+            }
+        }
+        Ok((metadata, offset))
+    }
+}
+
+/// Decode a metadata key or value as UTF-8
+fn utf8(buf: &[u8]) -> Result<&str, BinaryFormatErr> {
+    core::str::from_utf8(buf)
+        .map_err(|_| BinaryFormatErr::MalformedMetadata("metadata is not valid UTF-8"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_gets_and_inserts_fields() {
+        let mut metadata = Metadata::default();
+        assert!(metadata.is_empty());
+        assert!(metadata.insert("title", "Spending limit"));
+        assert!(metadata.insert("team", "payments"));
+        assert!(!metadata.insert("title", "Limit"));
+        assert!(!metadata.insert("team", "treasury"));
+        assert_eq!(metadata.title, Some("Spending limit"));
+        assert_eq!(metadata.get("team"), Some("payments"));
+        assert_eq!(metadata.entries, vec![("team", "payments")]);
+        assert_eq!(metadata.get("author"), None);
+        assert!(!metadata.is_empty());
+        assert_eq!(
+            metadata.iter().collect::<Vec<_>>(),
+            vec![("title", "Spending limit"), ("team", "payments")]
+        );
+    }
+
+    #[test]
+    fn it_encodes_and_decodes() {
+        let metadata = Metadata {
+            title: Some("limit"),
+            version: Some("1.0"),
+            entries: vec![("team", "ops")],
+            ..Default::default()
+        };
+        let mut encoded = Vec::new();
+        metadata.write(&mut encoded).unwrap();
+        assert_eq!(
+            encoded,
+            [
+                &[3.swap_bits(), TAG_TITLE.swap_bits(), 5.swap_bits()][..],
+                b"limit",
+                &[TAG_VERSION.swap_bits(), 3.swap_bits()],
+                b"1.0",
+                &[TAG_ENTRY.swap_bits(), 8.swap_bits(), 4.swap_bits()],
+                b"teamops",
+            ]
+            .concat()
+        );
+        assert_eq!(Metadata::decode(&encoded), Ok((metadata, encoded.len())));
+    }
+
+    #[test]
+    fn it_skips_unknown_tags() {
+        let encoded = [
+            &[2.swap_bits(), 9.swap_bits(), 1.swap_bits(), 0][..],
+            &[TAG_AUTHOR.swap_bits(), 3.swap_bits()],
+            b"bob",
+        ]
+        .concat();
+        let (metadata, read) = Metadata::decode(&encoded).unwrap();
+        assert_eq!(metadata.author, Some("bob"));
+        assert_eq!(metadata.entries, vec![]);
+        assert_eq!(read, encoded.len());
+    }
+
+    #[test]
+    fn it_fails_to_decode_malformed_metadata() {
+        assert_eq!(Metadata::decode(&[]), Err(BinaryFormatErr::TooShort));
+        assert_eq!(
+            Metadata::decode(&[1.swap_bits(), TAG_TITLE.swap_bits()]),
+            Err(BinaryFormatErr::MalformedMetadata(
+                "missing metadata tag or length byte"
+            ))
+        );
+        assert_eq!(
+            Metadata::decode(&[1.swap_bits(), TAG_TITLE.swap_bits(), 2.swap_bits(), 0]),
+            Err(BinaryFormatErr::MalformedMetadata(
+                "metadata length > buffer length"
+            ))
+        );
+        assert_eq!(
+            Metadata::decode(&[
+                1.swap_bits(),
+                TAG_ENTRY.swap_bits(),
+                1.swap_bits(),
+                2.swap_bits()
+            ]),
+            Err(BinaryFormatErr::MalformedMetadata(
+                "metadata key length > entry length"
+            ))
+        );
+        assert_eq!(
+            Metadata::decode(&[1.swap_bits(), TAG_TITLE.swap_bits(), 1.swap_bits(), 0xff]),
+            Err(BinaryFormatErr::MalformedMetadata(
+                "metadata is not valid UTF-8"
+            ))
+        );
+        assert_eq!(
+            Metadata::decode(&[
+                2.swap_bits(),
+                TAG_TITLE.swap_bits(),
+                0,
+                TAG_TITLE.swap_bits(),
+                0
+            ]),
+            Err(BinaryFormatErr::MalformedMetadata(
+                "duplicate metadata field"
+            ))
+        );
+    }
+
+    #[test]
+    fn it_fails_to_encode_oversize_metadata() {
+        let long = "x".repeat(256);
+        let mut encoded = Vec::new();
+        let metadata = Metadata {
+            title: Some(&long),
+            ..Default::default()
+        };
+        assert_eq!(
+            metadata.write(&mut encoded),
+            Err(EncodeErr::MetadataTooLong)
+        );
+        let metadata = Metadata {
+            entries: vec![("key", &long[..252])],
+            ..Default::default()
+        };
+        assert_eq!(
+            metadata.write(&mut encoded),
+            Err(EncodeErr::MetadataTooLong)
+        );
+    }
+}
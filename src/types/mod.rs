@@ -18,15 +18,37 @@
 //! Type definitions for the Pact interpreter and compiler
 //!
 mod base;
+#[cfg(feature = "hashing")]
+pub(crate) mod blake2b;
+mod capability;
+#[cfg(feature = "codecs")]
+mod codec;
 mod contract;
 mod data_table;
+mod metadata;
 pub mod opcode;
+mod schema;
 mod type_cast;
 
 // Create nice top level exports
-pub use base::{Numeric, PactType, StringLike};
-pub use contract::{BinaryFormatErr, Contract};
-pub use data_table::DataTable;
+#[cfg(feature = "compiler")]
+pub(crate) use base::rescale;
+pub(crate) use base::{compare_fixed_point, compare_sorted};
+pub use base::{
+    DecodeLimits, EncodeErr, ExtractErr, LengthWidth, Numeric, PactType, StringLike, LIST_TOO_DEEP,
+    LIST_TOO_LONG, MAX_DECIMALS, MAX_LIST_DEPTH, MAX_LIST_ELEMENTS,
+};
+pub use capability::{Capabilities, Version};
+pub use contract::{
+    BinaryFormatErr, BindErr, Contract, ContractStats, MergeErr, Origin, PatchErr, ValidationErr,
+    ValidationLevel,
+};
+pub use data_table::{DataTable, MAX_ENTRIES};
+pub use metadata::Metadata;
+pub use schema::{InputType, SchemaErr};
+pub use type_cast::{Address, Hash256};
 pub mod traits {
-    pub use super::type_cast::IntoPact;
+    pub use super::type_cast::{IntoPact, IntoPactTable, ToPactInputs};
+    #[cfg(feature = "derive")]
+    pub use pact_derive::ToPactInputs;
 }
@@ -18,10 +18,11 @@
 //! Pact OpCodes
 //!
 use crate::interpreter::InterpErr;
+use crate::types::Capabilities;
 use alloc::vec::Vec;
-
-#[cfg(feature = "compiler")]
-pub use compiler::*;
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::Not;
 
 // OpCode masks
 const OP_TYPE_MASK: u8 = 0b0010_0000;
@@ -29,6 +30,9 @@ const OP_INVERT_MASK: u8 = 0b0001_0000;
 const OP_LOAD_MASK: u8 = 0b0000_1000;
 const OP_CONJ_MASK: u8 = 0b0000_1111;
 const OP_COMP_MASK: u8 = 0b0000_0111;
+const OP_QUANT_MASK: u8 = 0b1100_0000;
+
+const OP_QUANT_SHIFT: usize = 6;
 
 const INDEX_LHS_MASK: u8 = 0b1111_0000;
 const INDEX_RHS_MASK: u8 = 0b0000_1111;
@@ -36,9 +40,48 @@ const INDEX_RHS_MASK: u8 = 0b0000_1111;
 const INDEX_LHS_SHIFT: usize = 4;
 const INDEX_RHS_SHIFT: usize = 0;
 
+// Ratio comparator masks, for the byte after the indices
+const RATIO_ORDER_MASK: u8 = 0b1111_0000;
+const RATIO_INDEX_MASK: u8 = 0b0000_1111;
+
+const RATIO_ORDER_SHIFT: usize = 4;
+
+/// The input register index reserved for the number of input registers, the arity register
+/// Contracts declare at most 15 input parameters so it never names a declared parameter.
+pub const ARITY_INDEX: u8 = 15;
+
+/// The largest register index an OpCode can encode, indices are packed into 4 bits
+pub const MAX_INDEX: u8 = 15;
+
+/// An error assembling an OpCode into bytecode
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum OpCodeErr {
+    /// A comparator index is greater than `MAX_INDEX`
+    IndexOutOfRange(u8),
+    /// The output buffer is shorter than the `required` encoded length
+    BufferTooShort { required: usize },
+}
+
+impl fmt::Display for OpCodeErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpCodeErr::IndexOutOfRange(index) => {
+                write!(f, "index {} is out of range (max {})", index, MAX_INDEX)
+            }
+            OpCodeErr::BufferTooShort { required } => {
+                write!(f, "buffer is too short, {} bytes are required", required)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OpCodeErr {}
+
 /// Indicates whether the source of a load is an `Input`
 /// or stored on the compiled `DataTable`
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LoadSource {
     Input,
     DataTable,
@@ -52,7 +95,7 @@ pub struct SubjectSource {
 
 /// Data structure which breaks down the anatomy of an OpCode
 #[cfg_attr(feature = "std", derive(Debug))]
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum OpCode {
     COMP(Comparator),
     CONJ(Conjunction),
@@ -60,25 +103,26 @@ pub enum OpCode {
 
 /// Comparator OpCode Structure
 #[cfg_attr(feature = "std", derive(Debug))]
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Comparator {
     pub load: OpLoad,
     pub op: OpComp,
     pub indices: OpIndices,
-    pub invert: bool,
+    pub invert: OpInvert,
+    pub quant: OpQuant,
 }
 
 /// Conjunction OpCode Structure
 #[cfg_attr(feature = "std", derive(Debug))]
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Conjunction {
     pub op: OpConj,
-    pub invert: bool,
+    pub invert: OpInvert,
 }
 
 /// Comparator OpCode Structure
 #[cfg_attr(feature = "std", derive(Debug))]
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct OpIndices {
     pub lhs: u8,
     pub rhs: u8,
@@ -88,27 +132,128 @@ pub struct OpIndices {
 /// is comparing input to datatable or input to input
 #[allow(non_camel_case_types)]
 #[cfg_attr(feature = "std", derive(Debug))]
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum OpLoad {
     INPUT_VS_USER,
     INPUT_VS_INPUT,
 }
 
+/// Whether an OpCode's result is negated
+#[allow(non_camel_case_types)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpInvert {
+    NORMAL,
+    NOT,
+}
+
+impl OpInvert {
+    /// Apply the inversion to `value`
+    pub fn apply(self, value: bool) -> bool {
+        match self {
+            OpInvert::NORMAL => value,
+            OpInvert::NOT => !value,
+        }
+    }
+}
+
+impl Not for OpInvert {
+    type Output = Self;
+    fn not(self) -> Self {
+        match self {
+            OpInvert::NORMAL => OpInvert::NOT,
+            OpInvert::NOT => OpInvert::NORMAL,
+        }
+    }
+}
+
+impl From<bool> for OpInvert {
+    fn from(invert: bool) -> Self {
+        if invert {
+            OpInvert::NOT
+        } else {
+            OpInvert::NORMAL
+        }
+    }
+}
+
+impl From<OpInvert> for bool {
+    fn from(invert: OpInvert) -> bool {
+        invert == OpInvert::NOT
+    }
+}
+
 /// Enum of avaliable comparator OpCode operations
 #[allow(non_camel_case_types)]
 #[cfg_attr(feature = "std", derive(Debug))]
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum OpComp {
     EQ,
     GT,
     GTE,
     IN,
+    /// Calls a host predicate, see `interpreter::HostContext`
+    CALL,
+    /// Every element of the LHS list is an element of the RHS list
+    SUBSET,
+    /// The LHS and RHS lists share an element
+    INTERSECTS,
+    /// Orders the LHS register with the RHS register scaled by a ratio from the data table
+    /// e.g. `input[0] < 1% of input[1]`, see `OpRatio`
+    RATIO(OpRatio),
+}
+
+/// The order and data table ratio of a `RATIO` comparator
+/// The ratio is a list of a numerator and a non-zero denominator e.g. `[1, 100]` for 1%.
+/// Numeric operands are ordered exactly by comparing `lhs * denominator` with
+/// `rhs * numerator`.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OpRatio {
+    pub order: OpOrder,
+    pub index: u8,
+}
+
+/// How a `RATIO` comparator orders its LHS with its scaled RHS
+#[allow(non_camel_case_types)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpOrder {
+    EQ,
+    GT,
+    GTE,
+}
+
+impl OpOrder {
+    /// Whether `ordering` of the LHS with the scaled RHS satisfies this order
+    pub fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            OpOrder::EQ => ordering == Ordering::Equal,
+            OpOrder::GT => ordering == Ordering::Greater,
+            OpOrder::GTE => ordering != Ordering::Less,
+        }
+    }
+}
+
+/// Whether a comparator compares one input register, or each of them in turn
+/// Quantified comparators compare every input register from the LHS index onward, skipping the
+/// RHS register when it is an input.
+#[allow(non_camel_case_types)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpQuant {
+    /// Compare the LHS input register only
+    ONE,
+    /// Every compared input register must satisfy the comparison, vacuously true without inputs
+    ALL,
+    /// At least one compared input register must satisfy the comparison
+    ANY,
 }
 
 /// Enum of avaliable conjunction OpCode operations
 #[allow(non_camel_case_types)]
 #[cfg_attr(feature = "std", derive(Debug))]
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum OpConj {
     AND,
     OR,
@@ -116,27 +261,105 @@ pub enum OpConj {
 }
 
 impl OpCode {
-    // Compiles the OpCode object into one or more bytes
-    pub fn compile(self, stream: &mut Vec<u8>) {
-        stream.push(self.into());
+    // Compiles the OpCode object into one or more bytes, returning the # of bytes written
+    // Fails without writing to `stream` if a comparator index can't be encoded
+    pub fn compile(self, stream: &mut Vec<u8>) -> Result<usize, OpCodeErr> {
+        let mut buf = [0_u8; 3];
+        let length = self.compile_to(&mut buf)?;
+        stream.extend_from_slice(&buf[..length]);
+        Ok(length)
+    }
+
+    // Compiles the OpCode object into the start of `buf`, returning the # of bytes written
+    // Fails without writing to `buf` if it is too short or a comparator index can't be encoded
+    pub fn compile_to(self, buf: &mut [u8]) -> Result<usize, OpCodeErr> {
+        if let OpCode::COMP(comparator) = self {
+            comparator.indices.check()?;
+            if let OpComp::RATIO(ratio) = comparator.op {
+                ratio.check()?;
+            }
+        }
+        let length = self.encoded_len();
+        if buf.len() < length {
+            return Err(OpCodeErr::BufferTooShort { required: length });
+        }
+        buf[0] = self.into();
+        if let OpCode::COMP(comparator) = self {
+            buf[1] = comparator.indices.into();
+            if let OpComp::RATIO(ratio) = comparator.op {
+                buf[2] = ratio.into();
+            }
+        }
+        Ok(length)
+    }
+
+    /// Return the # of bytes this OpCode compiles to
+    pub fn encoded_len(self) -> usize {
         match self {
-            OpCode::COMP(comparator) => stream.push(comparator.indices.into()),
-            _ => {}
-        };
+            // The OpCode, its indices, and a ratio's order and index
+            OpCode::COMP(Comparator {
+                op: OpComp::RATIO(_),
+                ..
+            }) => 3,
+            OpCode::COMP(_) => 2,
+            OpCode::CONJ(_) => 1,
+        }
+    }
+
+    /// Return a short description of this OpCode for tooling and inline help
+    pub fn describe(self) -> &'static str {
+        match self {
+            OpCode::COMP(comparator) => comparator.op.describe(),
+            OpCode::CONJ(conjunction) => conjunction.op.describe(),
+        }
+    }
+
+    /// Return the capabilities required to execute this OpCode
+    pub fn capability(self) -> Capabilities {
+        match self {
+            OpCode::COMP(comparator) => {
+                let op = match comparator.op {
+                    OpComp::EQ => Capabilities::COMP_EQ,
+                    OpComp::GT => Capabilities::COMP_GT,
+                    OpComp::GTE => Capabilities::COMP_GTE,
+                    OpComp::IN => Capabilities::COMP_IN,
+                    OpComp::CALL => Capabilities::COMP_CALL,
+                    OpComp::SUBSET => Capabilities::COMP_SUBSET,
+                    OpComp::INTERSECTS => Capabilities::COMP_INTERSECTS,
+                    OpComp::RATIO(_) => Capabilities::COMP_RATIO,
+                };
+                let load = match comparator.load {
+                    OpLoad::INPUT_VS_USER => op,
+                    OpLoad::INPUT_VS_INPUT => op | Capabilities::LOAD_INPUT_VS_INPUT,
+                };
+                let arity = if comparator.loads_arity() {
+                    load | Capabilities::LOAD_ARITY
+                } else {
+                    load
+                };
+                match comparator.quant {
+                    OpQuant::ONE => arity,
+                    OpQuant::ALL | OpQuant::ANY => arity | Capabilities::QUANTIFIERS,
+                }
+            }
+            OpCode::CONJ(conjunction) => match conjunction.op {
+                OpConj::AND => Capabilities::CONJ_AND,
+                OpConj::OR => Capabilities::CONJ_OR,
+                OpConj::XOR => Capabilities::CONJ_XOR,
+            },
+        }
     }
 
     /// Return the next OpCode by parsing an input byte stream
     pub fn parse(stream: &mut dyn Iterator<Item = &u8>) -> Result<Option<Self>, InterpErr> {
-        let op_index = stream.next();
-        if op_index.is_none() {
+        let index = match stream.next() {
+            Some(index) => index,
             // This is a valid EOI
-            return Ok(None);
-        }
-
-        let index = op_index.unwrap();
+            None => return Ok(None),
+        };
 
         // Check if the invert Bit is Set
-        let invert = (index & OP_INVERT_MASK) == OP_INVERT_MASK;
+        let invert = OpInvert::from((index & OP_INVERT_MASK) == OP_INVERT_MASK);
 
         // Check the Type of OpCode (0 ? comparator : conjunction)
         match index & OP_TYPE_MASK {
@@ -148,11 +371,29 @@ impl OpCode {
                     _ => OpLoad::INPUT_VS_INPUT,
                 };
                 // Determine comparator operation
-                let op = match index & OP_COMP_MASK {
+                // A ratio's order and index follow the indices
+                let mut op = match index & OP_COMP_MASK {
                     0 => OpComp::EQ,
                     1 => OpComp::GT,
                     2 => OpComp::GTE,
                     3 => OpComp::IN,
+                    4 => OpComp::CALL,
+                    5 => OpComp::SUBSET,
+                    6 => OpComp::INTERSECTS,
+                    _ => OpComp::RATIO(OpRatio {
+                        order: OpOrder::EQ,
+                        index: 0,
+                    }),
+                };
+                // Host predicates are selected by the data table, never by untrusted input
+                if op == OpComp::CALL && load == OpLoad::INPUT_VS_INPUT {
+                    return Err(InterpErr::InvalidOpCode(*index));
+                }
+                // Determine the quantifier
+                let quant = match (index & OP_QUANT_MASK) >> OP_QUANT_SHIFT {
+                    0 => OpQuant::ONE,
+                    1 => OpQuant::ALL,
+                    2 => OpQuant::ANY,
                     _ => return Err(InterpErr::InvalidOpCode(*index)),
                 };
                 // Load indices from the stream
@@ -161,17 +402,34 @@ impl OpCode {
                 } else {
                     Err(InterpErr::UnexpectedEOI("expected index"))
                 }?;
+                if let OpComp::RATIO(ratio) = &mut op {
+                    let byte = stream
+                        .next()
+                        .ok_or(InterpErr::UnexpectedEOI("expected ratio"))?;
+                    ratio.order = match (byte & RATIO_ORDER_MASK) >> RATIO_ORDER_SHIFT {
+                        0 => OpOrder::EQ,
+                        1 => OpOrder::GT,
+                        2 => OpOrder::GTE,
+                        _ => return Err(InterpErr::InvalidOpCode(*index)),
+                    };
+                    ratio.index = byte & RATIO_INDEX_MASK;
+                }
 
                 // form and return the comparator OpCode
-                Ok(Some(OpCode::COMP(Comparator {
-                    load: load,
-                    op: op,
+                let comparator = Comparator {
+                    load,
+                    op,
                     indices: OpIndices {
                         lhs: (indices & INDEX_LHS_MASK) >> INDEX_LHS_SHIFT,
                         rhs: (indices & INDEX_RHS_MASK) >> INDEX_RHS_SHIFT,
                     },
-                    invert: invert,
-                })))
+                    invert,
+                    quant,
+                };
+                if comparator.has_invalid_operands() {
+                    return Err(InterpErr::InvalidOperands(*index, indices));
+                }
+                Ok(Some(OpCode::COMP(comparator)))
             }
             _ => {
                 // Conjunction
@@ -182,23 +440,113 @@ impl OpCode {
                     _ => return Err(InterpErr::InvalidOpCode(*index)),
                 };
                 // form and return the conjunction OpCode
-                Ok(Some(OpCode::CONJ(Conjunction {
-                    op: op,
-                    invert: invert,
-                })))
+                Ok(Some(OpCode::CONJ(Conjunction { op, invert })))
+            }
+        }
+    }
+}
+
+/// An iterator over the OpCodes of some byte code, yielding each with its byte offset.
+/// Iteration stops after the first malformed OpCode, which is yielded as an `Err`.
+pub struct OpCodes<'b> {
+    source: &'b [u8],
+    scanner: core::slice::Iter<'b, u8>,
+    failed: bool,
+}
+
+impl<'b> OpCodes<'b> {
+    /// Iterate the OpCodes of `source` byte code
+    pub fn new(source: &'b [u8]) -> Self {
+        OpCodes {
+            source,
+            scanner: source.iter(),
+            failed: false,
+        }
+    }
+    /// Call `visitor` with each OpCode in turn, stopping at the first malformed OpCode
+    pub fn visit(self, visitor: &mut dyn Visitor) -> Result<(), InterpErr> {
+        for op in self {
+            match op? {
+                (offset, OpCode::COMP(comparator)) => visitor.comparator(offset, comparator),
+                (offset, OpCode::CONJ(conjunction)) => visitor.conjunction(offset, conjunction),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'b> Iterator for OpCodes<'b> {
+    type Item = Result<(usize, OpCode), InterpErr>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let offset = self.source.len() - self.scanner.len();
+        match OpCode::parse(&mut self.scanner) {
+            Ok(op) => op.map(|op| Ok((offset, op))),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Callbacks for analysing byte code one OpCode at a time e.g. weight estimation.
+/// Each callback receives the byte offset of the OpCode, and does nothing by default.
+pub trait Visitor {
+    /// Visit a comparator OpCode
+    fn comparator(&mut self, _offset: usize, _comparator: Comparator) {}
+    /// Visit a conjunction OpCode
+    fn conjunction(&mut self, _offset: usize, _conjunction: Conjunction) {}
+}
+
+impl OpComp {
+    /// Return a short description of this comparator operation
+    pub fn describe(self) -> &'static str {
+        match self {
+            OpComp::EQ => "COMP EQ: compares two registers for equality",
+            OpComp::GT => "COMP GT: checks the LHS register is greater than the RHS register",
+            OpComp::GTE => {
+                "COMP GTE: checks the LHS register is greater than or equal to the RHS register"
+            }
+            OpComp::IN => "COMP IN: checks the LHS register is an element of the RHS list",
+            OpComp::CALL => {
+                "COMP CALL: calls the host predicate numbered by the RHS register with the LHS register"
+            }
+            OpComp::SUBSET => {
+                "COMP SUBSET: checks every element of the LHS list is an element of the RHS list"
+            }
+            OpComp::INTERSECTS => {
+                "COMP INTERSECTS: checks the LHS and RHS lists share an element"
+            }
+            OpComp::RATIO(_) => {
+                "COMP RATIO: orders the LHS register with the RHS register scaled by a data table ratio"
             }
         }
     }
 }
 
+impl OpConj {
+    /// Return a short description of this conjunction operation
+    pub fn describe(self) -> &'static str {
+        match self {
+            OpConj::AND => "CONJ AND: both the previous and next assertions must hold",
+            OpConj::OR => "CONJ OR: either the previous or next assertion must hold",
+            OpConj::XOR => "CONJ XOR: exactly one of the previous and next assertions must hold",
+        }
+    }
+}
+
 impl Comparator {
     // Constructor for `Comparator`
     pub fn new(op: OpComp) -> Self {
         Comparator {
             load: OpLoad::INPUT_VS_USER,
-            op: op,
+            op,
             indices: OpIndices { lhs: 0, rhs: 0 },
-            invert: false,
+            invert: OpInvert::NORMAL,
+            quant: OpQuant::ONE,
         }
     }
 
@@ -209,18 +557,74 @@ impl Comparator {
     }
 
     // Update the `indices` field
+    // Indices greater than `MAX_INDEX` fail to compile, see `try_indices`
     pub fn indices(mut self, lhs: u8, rhs: u8) -> Self {
         self.indices.lhs = lhs;
         self.indices.rhs = rhs;
         self
     }
 
+    // Update the `indices` field, failing if either index can't be encoded
+    pub fn try_indices(self, lhs: u8, rhs: u8) -> Result<Self, OpCodeErr> {
+        OpIndices { lhs, rhs }.check()?;
+        Ok(self.indices(lhs, rhs))
+    }
+
+    // Set the `invert` field to `OpInvert::NOT`
+    pub fn invert(self) -> Self {
+        self.with_invert(OpInvert::NOT)
+    }
+
     // Update the `invert` field
-    pub fn invert(mut self) -> Self {
-        self.invert = true;
+    pub fn with_invert(mut self, invert: OpInvert) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    // Update the `quant` field
+    pub fn quantify(mut self, quant: OpQuant) -> Self {
+        self.quant = quant;
         self
     }
 
+    /// Whether this comparator loads the arity register, see `ARITY_INDEX`
+    /// Quantified comparators only range over input parameters so never load it on their LHS.
+    pub fn loads_arity(self) -> bool {
+        (self.indices.lhs == ARITY_INDEX && self.quant == OpQuant::ONE)
+            || (self.load == OpLoad::INPUT_VS_INPUT && self.indices.rhs == ARITY_INDEX)
+    }
+
+    /// Whether this comparator orders or equates an input register with itself
+    /// Its outcome is fixed e.g. `EQ` always holds and `GT` never does, which usually indicates an
+    /// index mix-up.
+    pub fn is_self_comparison(self) -> bool {
+        self.load == OpLoad::INPUT_VS_INPUT
+            && self.quant == OpQuant::ONE
+            && self.indices.lhs == self.indices.rhs
+            && matches!(
+                self.op,
+                OpComp::EQ | OpComp::GT | OpComp::GTE | OpComp::SUBSET
+            )
+    }
+
+    /// Whether this comparator's operands can never be compared, whatever the inputs
+    /// This is a list comparator loading the arity register, which is never a list, as a list
+    /// operand, or an `IN` checking an input register for membership of itself.
+    /// `OpCode::parse` rejects these comparators with `InterpErr::InvalidOperands`.
+    pub fn has_invalid_operands(self) -> bool {
+        let arity_rhs = self.load == OpLoad::INPUT_VS_INPUT && self.indices.rhs == ARITY_INDEX;
+        let arity_lhs = self.quant == OpQuant::ONE && self.indices.lhs == ARITY_INDEX;
+        // A list never contains itself, and anything else isn't a list
+        let self_membership = self.load == OpLoad::INPUT_VS_INPUT
+            && self.quant == OpQuant::ONE
+            && self.indices.lhs == self.indices.rhs;
+        match self.op {
+            OpComp::IN => arity_rhs || self_membership,
+            OpComp::SUBSET | OpComp::INTERSECTS => arity_rhs || arity_lhs,
+            OpComp::EQ | OpComp::GT | OpComp::GTE | OpComp::CALL | OpComp::RATIO(_) => false,
+        }
+    }
+
     // Update the `load` field based on a subject set
     // If lhs = `DataTable` and rhs = `Input`, we need to change sides so that
     // lhs = `Input` and rhs = `DataTable` as per the `OpCode` encoding spec
@@ -244,18 +648,36 @@ impl Comparator {
         }
     }
 
+    // Compare the lhs with the rhs scaled by the ratio at data table `index`
+    // Only `EQ`, `GT` and `GTE` comparators can be scaled, others are unchanged
+    pub fn scaled(mut self, index: u8) -> Self {
+        let order = match self.op {
+            OpComp::EQ => OpOrder::EQ,
+            OpComp::GT => OpOrder::GT,
+            OpComp::GTE => OpOrder::GTE,
+            _ => return self,
+        };
+        self.op = OpComp::RATIO(OpRatio { order, index });
+        self
+    }
+
     // Flips the lhs and rhs indices and applies any necessary changes to the `op` and
     // `invert` parameters to keep the expressions consistent
+    // `RATIO` comparators can't be flipped without inverting their ratio so are unchanged
     pub fn flip_indices(mut self) -> Self {
-        self.indices = OpIndices {
-            lhs: self.indices.rhs,
-            rhs: self.indices.lhs,
-        };
         let (op, invert) = match self.op {
             OpComp::EQ => (self.op, self.invert),
             OpComp::IN => (self.op, self.invert),
+            OpComp::CALL => (self.op, self.invert),
+            OpComp::SUBSET => (self.op, self.invert),
+            OpComp::INTERSECTS => (self.op, self.invert),
             OpComp::GT => (OpComp::GTE, !self.invert),
             OpComp::GTE => (OpComp::GT, !self.invert),
+            OpComp::RATIO(_) => return self,
+        };
+        self.indices = OpIndices {
+            lhs: self.indices.rhs,
+            rhs: self.indices.lhs,
         };
         self.op = op;
         self.invert = invert;
@@ -267,14 +689,19 @@ impl Conjunction {
     // Constructor for `Conjunction`
     pub fn new(op: OpConj) -> Self {
         Conjunction {
-            op: op,
-            invert: false,
+            op,
+            invert: OpInvert::NORMAL,
         }
     }
 
+    // Set the `invert` field to `OpInvert::NOT`
+    pub fn invert(self) -> Self {
+        self.with_invert(OpInvert::NOT)
+    }
+
     // Update the `invert` field
-    pub fn invert(mut self) -> Self {
-        self.invert = true;
+    pub fn with_invert(mut self, invert: OpInvert) -> Self {
+        self.invert = invert;
         self
     }
 }
@@ -307,6 +734,18 @@ mod compiler {
                 ast::Comparator::LessThan => Comparator::new(OpComp::GTE).invert(),
                 ast::Comparator::LessThanOrEqual => Comparator::new(OpComp::GT).invert(),
                 ast::Comparator::OneOf => Comparator::new(OpComp::IN),
+                ast::Comparator::SubsetOf => Comparator::new(OpComp::SUBSET),
+                ast::Comparator::IntersectingWith => Comparator::new(OpComp::INTERSECTS),
+            }
+        }
+    }
+
+    impl From<&ast::Quantifier> for OpQuant {
+        // Creates an `OpQuant` from an `ast::Quantifier` type
+        fn from(quantifier: &ast::Quantifier) -> Self {
+            match quantifier {
+                ast::Quantifier::Every => OpQuant::ALL,
+                ast::Quantifier::Any => OpQuant::ANY,
             }
         }
     }
@@ -322,29 +761,74 @@ mod compiler {
     }
 }
 
-impl Into<u8> for OpLoad {
-    fn into(self) -> u8 {
-        match self {
+impl From<OpLoad> for u8 {
+    fn from(load: OpLoad) -> u8 {
+        match load {
             OpLoad::INPUT_VS_USER => 0,
             OpLoad::INPUT_VS_INPUT => OP_LOAD_MASK,
         }
     }
 }
 
-impl Into<u8> for OpComp {
-    fn into(self) -> u8 {
-        match self {
+impl From<OpComp> for u8 {
+    fn from(comp: OpComp) -> u8 {
+        match comp {
             OpComp::EQ => 0,
             OpComp::GT => 1,
             OpComp::GTE => 2,
             OpComp::IN => 3,
+            OpComp::CALL => 4,
+            OpComp::SUBSET => 5,
+            OpComp::INTERSECTS => 6,
+            OpComp::RATIO(_) => 7,
         }
     }
 }
 
-impl Into<u8> for OpConj {
-    fn into(self) -> u8 {
-        match self {
+impl OpRatio {
+    /// Check the ratio's index fits in its 4 bits
+    fn check(self) -> Result<(), OpCodeErr> {
+        if self.index > MAX_INDEX {
+            return Err(OpCodeErr::IndexOutOfRange(self.index));
+        }
+        Ok(())
+    }
+}
+
+impl From<OpRatio> for u8 {
+    fn from(ratio: OpRatio) -> u8 {
+        let order: u8 = match ratio.order {
+            OpOrder::EQ => 0,
+            OpOrder::GT => 1,
+            OpOrder::GTE => 2,
+        };
+        (order << RATIO_ORDER_SHIFT) & RATIO_ORDER_MASK | ratio.index & RATIO_INDEX_MASK
+    }
+}
+
+impl From<OpOrder> for OpComp {
+    fn from(order: OpOrder) -> OpComp {
+        match order {
+            OpOrder::EQ => OpComp::EQ,
+            OpOrder::GT => OpComp::GT,
+            OpOrder::GTE => OpComp::GTE,
+        }
+    }
+}
+
+impl From<OpQuant> for u8 {
+    fn from(quant: OpQuant) -> u8 {
+        match quant {
+            OpQuant::ONE => 0,
+            OpQuant::ALL => 1 << OP_QUANT_SHIFT,
+            OpQuant::ANY => 2 << OP_QUANT_SHIFT,
+        }
+    }
+}
+
+impl From<OpConj> for u8 {
+    fn from(conj: OpConj) -> u8 {
+        match conj {
             OpConj::AND => 0,
             OpConj::OR => 1,
             OpConj::XOR => 2,
@@ -352,25 +836,45 @@ impl Into<u8> for OpConj {
     }
 }
 
-impl Into<u8> for OpIndices {
-    fn into(self) -> u8 {
-        (self.lhs << INDEX_LHS_SHIFT) & INDEX_LHS_MASK
-            | (self.rhs << INDEX_RHS_SHIFT) & INDEX_RHS_MASK
+impl OpIndices {
+    /// Check both indices fit in their 4 bits
+    fn check(self) -> Result<(), OpCodeErr> {
+        match (self.lhs, self.rhs) {
+            (lhs, _) if lhs > MAX_INDEX => Err(OpCodeErr::IndexOutOfRange(lhs)),
+            (_, rhs) if rhs > MAX_INDEX => Err(OpCodeErr::IndexOutOfRange(rhs)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<OpIndices> for u8 {
+    fn from(indices: OpIndices) -> u8 {
+        (indices.lhs << INDEX_LHS_SHIFT) & INDEX_LHS_MASK
+            | (indices.rhs << INDEX_RHS_SHIFT) & INDEX_RHS_MASK
     }
 }
 
 /// Convert an OpCode into its u8 bytecode
-impl Into<u8> for OpCode {
-    fn into(self) -> u8 {
-        match self {
+impl From<OpCode> for u8 {
+    fn from(op_code: OpCode) -> u8 {
+        match op_code {
             OpCode::COMP(comp) => {
-                let invert_u8: u8 = if comp.invert { OP_INVERT_MASK } else { 0 };
+                let invert_u8: u8 = if comp.invert.into() {
+                    OP_INVERT_MASK
+                } else {
+                    0
+                };
                 let load_u8: u8 = comp.load.into();
                 let comp_u8: u8 = comp.op.into();
-                invert_u8 | load_u8 | comp_u8
+                let quant_u8: u8 = comp.quant.into();
+                quant_u8 | invert_u8 | load_u8 | comp_u8
             }
             OpCode::CONJ(conj) => {
-                let invert_u8: u8 = if conj.invert { OP_INVERT_MASK } else { 0 };
+                let invert_u8: u8 = if conj.invert.into() {
+                    OP_INVERT_MASK
+                } else {
+                    0
+                };
                 let conj_u8: u8 = conj.op.into();
                 OP_TYPE_MASK | invert_u8 | conj_u8
             }
@@ -385,19 +889,33 @@ mod test {
     #[test]
     fn compile_comparators_basic() {
         let mut bytes = Vec::<u8>::default();
-        OpCode::COMP(Comparator::new(OpComp::EQ)).compile(&mut bytes);
-        OpCode::COMP(Comparator::new(OpComp::GT)).compile(&mut bytes);
-        OpCode::COMP(Comparator::new(OpComp::GTE)).compile(&mut bytes);
-        OpCode::COMP(Comparator::new(OpComp::IN)).compile(&mut bytes);
+        OpCode::COMP(Comparator::new(OpComp::EQ))
+            .compile(&mut bytes)
+            .unwrap();
+        OpCode::COMP(Comparator::new(OpComp::GT))
+            .compile(&mut bytes)
+            .unwrap();
+        OpCode::COMP(Comparator::new(OpComp::GTE))
+            .compile(&mut bytes)
+            .unwrap();
+        OpCode::COMP(Comparator::new(OpComp::IN))
+            .compile(&mut bytes)
+            .unwrap();
         assert_eq!(bytes, vec![0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00,]);
     }
 
     #[test]
     fn compile_conjunctions_basic() {
         let mut bytes = Vec::<u8>::default();
-        OpCode::CONJ(Conjunction::new(OpConj::AND)).compile(&mut bytes);
-        OpCode::CONJ(Conjunction::new(OpConj::OR)).compile(&mut bytes);
-        OpCode::CONJ(Conjunction::new(OpConj::XOR)).compile(&mut bytes);
+        OpCode::CONJ(Conjunction::new(OpConj::AND))
+            .compile(&mut bytes)
+            .unwrap();
+        OpCode::CONJ(Conjunction::new(OpConj::OR))
+            .compile(&mut bytes)
+            .unwrap();
+        OpCode::CONJ(Conjunction::new(OpConj::XOR))
+            .compile(&mut bytes)
+            .unwrap();
         assert_eq!(bytes, vec![0x20, 0x21, 0x22,]);
     }
 
@@ -410,17 +928,113 @@ mod test {
                 .invert()
                 .indices(11, 3),
         )
-        .compile(&mut bytes);
+        .compile(&mut bytes)
+        .unwrap();
         assert_eq!(bytes, vec![0x18, 0xb3]);
     }
 
     #[test]
     fn compile_conjunction_advanced() {
         let mut bytes = Vec::<u8>::default();
-        OpCode::CONJ(Conjunction::new(OpConj::OR).invert()).compile(&mut bytes);
+        OpCode::CONJ(Conjunction::new(OpConj::OR).invert())
+            .compile(&mut bytes)
+            .unwrap();
         assert_eq!(bytes, vec![0x31]);
     }
 
+    #[test]
+    fn invert_builders_agree() {
+        assert_eq!(
+            Comparator::new(OpComp::GT).invert(),
+            Comparator::new(OpComp::GT).with_invert(OpInvert::NOT)
+        );
+        assert_eq!(
+            Conjunction::new(OpConj::AND).with_invert(OpInvert::NORMAL),
+            Conjunction::new(OpConj::AND)
+        );
+        assert_eq!(!OpInvert::NOT, OpInvert::NORMAL);
+        assert_eq!(OpInvert::from(true), OpInvert::NOT);
+        assert!(OpInvert::NOT.apply(false));
+        assert!(OpInvert::NORMAL.apply(true));
+    }
+
+    #[test]
+    fn iterate_op_codes() {
+        // EQ, AND, GT (inverted), then an invalid comparator
+        let source = [0x00_u8, 0x01, 0x20, 0x11, 0x23, 0xc0, 0x00];
+        let mut ops = OpCodes::new(&source);
+        assert_eq!(
+            ops.next(),
+            Some(Ok((
+                0,
+                OpCode::COMP(Comparator::new(OpComp::EQ).indices(0, 1))
+            )))
+        );
+        assert_eq!(
+            ops.next(),
+            Some(Ok((2, OpCode::CONJ(Conjunction::new(OpConj::AND)))))
+        );
+        assert_eq!(
+            ops.next(),
+            Some(Ok((
+                3,
+                OpCode::COMP(Comparator::new(OpComp::GT).indices(2, 3).invert())
+            )))
+        );
+        assert_eq!(ops.next(), Some(Err(InterpErr::InvalidOpCode(0xc0))));
+        assert_eq!(ops.next(), None);
+    }
+
+    #[test]
+    fn visit_op_codes() {
+        #[derive(Default)]
+        struct Counter {
+            comparators: Vec<usize>,
+            conjunctions: Vec<usize>,
+        }
+        impl Visitor for Counter {
+            fn comparator(&mut self, offset: usize, _comparator: Comparator) {
+                self.comparators.push(offset);
+            }
+            fn conjunction(&mut self, offset: usize, _conjunction: Conjunction) {
+                self.conjunctions.push(offset);
+            }
+        }
+
+        let mut counter = Counter::default();
+        OpCodes::new(&[0x00, 0x00, 0x21, 0x01, 0x00])
+            .visit(&mut counter)
+            .unwrap();
+        assert_eq!(counter.comparators, vec![0, 3]);
+        assert_eq!(counter.conjunctions, vec![2]);
+
+        assert_eq!(
+            OpCodes::new(&[0x00]).visit(&mut Counter::default()),
+            Err(InterpErr::UnexpectedEOI("expected index"))
+        );
+    }
+
+    #[test]
+    fn describe_op_codes() {
+        assert_eq!(
+            OpCode::COMP(Comparator::new(OpComp::EQ)).describe(),
+            "COMP EQ: compares two registers for equality"
+        );
+        for (op, mnemonic) in &[
+            (
+                OpCode::COMP(Comparator::new(OpComp::GT).invert()),
+                "COMP GT:",
+            ),
+            (OpCode::COMP(Comparator::new(OpComp::GTE)), "COMP GTE:"),
+            (OpCode::COMP(Comparator::new(OpComp::IN)), "COMP IN:"),
+            (OpCode::CONJ(Conjunction::new(OpConj::AND)), "CONJ AND:"),
+            (OpCode::CONJ(Conjunction::new(OpConj::OR)), "CONJ OR:"),
+            (OpCode::CONJ(Conjunction::new(OpConj::XOR)), "CONJ XOR:"),
+        ] {
+            assert!(op.describe().starts_with(mnemonic));
+        }
+    }
+
     #[test]
     fn parse_comparator_basic() {
         let mut stream = [0x00_u8, 0x00_u8].iter();
@@ -462,13 +1076,158 @@ mod test {
 
     #[test]
     fn parse_comparator_invalid() {
-        let mut stream = [0x07_u8, 0x00_u8].iter();
+        // Ratio orders above `GTE` are invalid
+        let mut stream = [0x07_u8, 0x00_u8, 0x30_u8].iter();
         assert_eq!(
             OpCode::parse(&mut stream),
             Err(InterpErr::InvalidOpCode(0x07))
         );
     }
 
+    #[test]
+    fn parse_comparator_ratio() {
+        let mut stream = [0x1f_u8, 0x01_u8, 0x23_u8, 0x07_u8, 0x00_u8].iter();
+        assert_eq!(
+            OpCode::parse(&mut stream).unwrap(),
+            Some(OpCode::COMP(
+                Comparator::new(OpComp::GTE)
+                    .invert()
+                    .load(OpLoad::INPUT_VS_INPUT)
+                    .indices(0, 1)
+                    .scaled(3)
+            ))
+        );
+        assert_eq!(
+            OpCode::parse(&mut stream),
+            Err(InterpErr::UnexpectedEOI("expected ratio"))
+        );
+    }
+
+    #[test]
+    fn compile_comparator_ratio() {
+        let comparator = OpCode::COMP(Comparator::new(OpComp::GT).indices(2, 0).scaled(1));
+        assert_eq!(comparator.encoded_len(), 3);
+        let mut bytes = Vec::<u8>::default();
+        assert_eq!(comparator.compile(&mut bytes), Ok(3));
+        assert_eq!(bytes, vec![0x07, 0x20, 0x11]);
+        assert_eq!(
+            OpCode::COMP(Comparator::new(OpComp::EQ).scaled(16)).compile(&mut bytes),
+            Err(OpCodeErr::IndexOutOfRange(16))
+        );
+        // Only orders are scaled
+        assert_eq!(
+            Comparator::new(OpComp::IN).scaled(1),
+            Comparator::new(OpComp::IN)
+        );
+    }
+
+    #[test]
+    fn parse_comparator_call() {
+        let mut stream = [0x14_u8, 0x21_u8].iter();
+        assert_eq!(
+            OpCode::parse(&mut stream).unwrap(),
+            Some(OpCode::COMP(
+                Comparator::new(OpComp::CALL).invert().indices(2, 1)
+            ))
+        );
+        // The predicate can't be chosen by an input register
+        let mut stream = [0x0c_u8, 0x00_u8].iter();
+        assert_eq!(
+            OpCode::parse(&mut stream),
+            Err(InterpErr::InvalidOpCode(0x0c))
+        );
+    }
+
+    #[test]
+    fn parse_comparator_quantified() {
+        let mut stream = [0x52_u8, 0x00_u8, 0x83_u8, 0x01_u8].iter();
+        assert_eq!(
+            OpCode::parse(&mut stream).unwrap(),
+            Some(OpCode::COMP(
+                Comparator::new(OpComp::GTE).invert().quantify(OpQuant::ALL)
+            ))
+        );
+        assert_eq!(
+            OpCode::parse(&mut stream).unwrap(),
+            Some(OpCode::COMP(
+                Comparator::new(OpComp::IN)
+                    .indices(0, 1)
+                    .quantify(OpQuant::ANY)
+            ))
+        );
+        let mut stream = [0xc0_u8, 0x00_u8].iter();
+        assert_eq!(
+            OpCode::parse(&mut stream),
+            Err(InterpErr::InvalidOpCode(0xc0))
+        );
+    }
+
+    #[test]
+    fn compile_comparator_quantified() {
+        let mut bytes = Vec::<u8>::default();
+        OpCode::COMP(Comparator::new(OpComp::GT).quantify(OpQuant::ALL))
+            .compile(&mut bytes)
+            .unwrap();
+        OpCode::COMP(
+            Comparator::new(OpComp::EQ)
+                .invert()
+                .quantify(OpQuant::ANY)
+                .indices(1, 2),
+        )
+        .compile(&mut bytes)
+        .unwrap();
+        assert_eq!(bytes, vec![0x41, 0x00, 0x90, 0x12]);
+    }
+
+    #[test]
+    fn try_indices_checks_range() {
+        assert_eq!(
+            Comparator::new(OpComp::EQ).try_indices(MAX_INDEX, 3),
+            Ok(Comparator::new(OpComp::EQ).indices(MAX_INDEX, 3))
+        );
+        assert_eq!(
+            Comparator::new(OpComp::EQ).try_indices(16, 0),
+            Err(OpCodeErr::IndexOutOfRange(16))
+        );
+        assert_eq!(
+            Comparator::new(OpComp::EQ).try_indices(0, 255),
+            Err(OpCodeErr::IndexOutOfRange(255))
+        );
+    }
+
+    #[test]
+    fn compile_to_writes_into_buffers() {
+        let mut buf = [0_u8; 3];
+        let comparator = OpCode::COMP(Comparator::new(OpComp::GT).indices(1, 2));
+        let conjunction = OpCode::CONJ(Conjunction::new(OpConj::OR));
+        assert_eq!(comparator.encoded_len(), 2);
+        assert_eq!(conjunction.encoded_len(), 1);
+        assert_eq!(comparator.compile_to(&mut buf), Ok(2));
+        assert_eq!(conjunction.compile_to(&mut buf[2..]), Ok(1));
+        assert_eq!(buf, [0x01, 0x12, 0x21]);
+        assert_eq!(
+            comparator.compile_to(&mut buf[2..]),
+            Err(OpCodeErr::BufferTooShort { required: 2 })
+        );
+        // Nothing is written
+        assert_eq!(buf[2], 0x21);
+        let mut bytes = Vec::<u8>::default();
+        assert_eq!(comparator.compile(&mut bytes), Ok(2));
+        assert_eq!(conjunction.compile(&mut bytes), Ok(1));
+        assert_eq!(bytes, buf);
+    }
+
+    #[test]
+    fn compile_fails_with_unencodable_indices() {
+        let mut bytes = Vec::<u8>::default();
+        assert_eq!(
+            OpCode::COMP(Comparator::new(OpComp::EQ).indices(1, 16)).compile(&mut bytes),
+            Err(OpCodeErr::IndexOutOfRange(16))
+        );
+        // Nothing is written
+        assert!(bytes.is_empty());
+    }
+
     #[test]
     fn parse_comparator_missing_indices() {
         let mut stream = [0x00_u8].iter();
@@ -0,0 +1,196 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Input schemas declaring the type of each input parameter
+//! Hosts check input data against a contract's schema before interpreting it
+//!
+use crate::types::{PactType, MAX_DECIMALS};
+use core::fmt;
+
+/// The declared type of an input parameter
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputType {
+    /// Undeclared, any value is accepted
+    Any,
+    /// A `Numeric` value
+    Numeric,
+    /// A `StringLike` value
+    StringLike,
+    /// A `List` value
+    List,
+    /// A `Bool` value
+    Bool,
+    /// A `FixedPoint` value with the given # of decimals, at most `MAX_DECIMALS`
+    FixedPoint(u8),
+}
+
+impl fmt::Display for InputType {
+    /// Write the type as it is declared in pact source e.g. `numeric`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            InputType::Any => "any",
+            InputType::Numeric => "numeric",
+            InputType::StringLike => "string",
+            InputType::List => "list",
+            InputType::Bool => "bool",
+            InputType::FixedPoint(decimals) => return write!(f, "decimal({})", decimals),
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl InputType {
+    /// Whether `value` is of this type
+    /// Fixed points match only with the declared # of decimals.
+    pub fn matches(self, value: &PactType) -> bool {
+        match (self, value) {
+            (InputType::FixedPoint(expected), PactType::FixedPoint { decimals, .. }) => {
+                expected == *decimals
+            }
+            _ => matches!(
+                (self, value),
+                (InputType::Any, _)
+                    | (InputType::Numeric, PactType::Numeric(_))
+                    | (InputType::StringLike, PactType::StringLike(_))
+                    | (InputType::List, PactType::List(_))
+                    | (InputType::List, PactType::SortedList(_))
+                    | (InputType::Bool, PactType::Bool(_))
+            ),
+        }
+    }
+    /// Return the binary format code of this type
+    /// `StringLike`, `Numeric`, and `List` codes match the `PactType` type index, `FixedPoint`
+    /// codes have the high bit set over their decimals.
+    pub(crate) fn code(self) -> u8 {
+        match self {
+            InputType::StringLike => 0,
+            InputType::Numeric => 1,
+            InputType::List => 2,
+            InputType::Any => 3,
+            InputType::Bool => 4,
+            InputType::FixedPoint(decimals) => 0x80 | decimals,
+        }
+    }
+    /// Return the type for a binary format `code`, if it is known
+    pub(crate) fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(InputType::StringLike),
+            1 => Some(InputType::Numeric),
+            2 => Some(InputType::List),
+            3 => Some(InputType::Any),
+            4 => Some(InputType::Bool),
+            0x80..=0xff if code & 0x7f <= MAX_DECIMALS => Some(InputType::FixedPoint(code & 0x7f)),
+            _ => None,
+        }
+    }
+}
+
+/// Input data does not conform to a contract's input schema
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(PartialEq)]
+pub enum SchemaErr {
+    /// The contract declares `expected` input parameters but `actual` inputs were given
+    InputCount { expected: usize, actual: usize },
+    /// The input at `index` is not of the `expected` type
+    Mismatch { index: usize, expected: InputType },
+}
+
+impl fmt::Display for SchemaErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaErr::InputCount { expected, actual } => {
+                write!(f, "expected {} inputs, found {}", expected, actual)
+            }
+            SchemaErr::Mismatch { index, expected } => {
+                write!(f, "input {} is not of type {}", index, expected)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SchemaErr {}
+
+/// Check `inputs` against the parameter `types`, in positional order
+pub(crate) fn check(types: &[InputType], inputs: &[PactType]) -> Result<(), SchemaErr> {
+    if types.len() != inputs.len() {
+        return Err(SchemaErr::InputCount {
+            expected: types.len(),
+            actual: inputs.len(),
+        });
+    }
+    for (index, (expected, input)) in types.iter().zip(inputs).enumerate() {
+        if !expected.matches(input) {
+            return Err(SchemaErr::Mismatch {
+                index,
+                expected: *expected,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Numeric, StringLike};
+
+    #[test]
+    fn it_checks_inputs() {
+        let types = [InputType::Numeric, InputType::Any, InputType::List];
+        let number = PactType::Numeric(Numeric(1));
+        let string = PactType::StringLike(StringLike(b"alice"));
+        let list = PactType::List(vec![string.clone()]);
+
+        assert_eq!(
+            check(&types, &[number.clone(), string.clone(), list.clone()]),
+            Ok(())
+        );
+        assert_eq!(
+            check(&types, &[number.clone(), number.clone(), number.clone()]),
+            Err(SchemaErr::Mismatch {
+                index: 2,
+                expected: InputType::List
+            })
+        );
+        assert_eq!(
+            check(&types, &[string, list]),
+            Err(SchemaErr::InputCount {
+                expected: 3,
+                actual: 2
+            })
+        );
+    }
+
+    #[test]
+    fn it_round_trips_type_codes() {
+        for t in &[
+            InputType::Any,
+            InputType::Numeric,
+            InputType::StringLike,
+            InputType::List,
+            InputType::Bool,
+            InputType::FixedPoint(0),
+            InputType::FixedPoint(MAX_DECIMALS),
+        ] {
+            assert_eq!(InputType::from_code(t.code()), Some(*t));
+        }
+        assert_eq!(InputType::from_code(5), None);
+        assert_eq!(InputType::from_code(0x80 | (MAX_DECIMALS + 1)), None);
+    }
+}
@@ -18,19 +18,34 @@
 //! Type conversion traits and impls for `PactType`s
 //!
 use crate::types::{Numeric, PactType, StringLike};
+use alloc::{vec, vec::Vec};
 use core::convert::TryInto;
 
 /// A blanket trait for conversion into PactType
 pub trait IntoPact<'a, I> {
+    #[allow(clippy::result_unit_err)]
     fn into_pact(self) -> Result<PactType<'a>, ()>;
 }
 
-/// Impl for all types that implement fallible conversion into u64
+/// Impl for the primitive integers, which fail if they don't fit into a u64
+/// Not a blanket impl over `TryInto<u64>` as that would include `bool`
 // FIXME: impl Into<u128> after this is implemented https://github.com/cennznet/pact/issues/1
-impl<'a, T: TryInto<u64> + Copy> IntoPact<'a, &T> for T {
+macro_rules! impl_into_pact_numeric {
+    ($($t:ty),*) => {$(
+        impl<'a> IntoPact<'a, $t> for $t {
+            fn into_pact(self) -> Result<PactType<'a>, ()> {
+                let result: u64 = self.try_into().map_err(|_| ())?;
+                Ok(PactType::Numeric(Numeric(result)))
+            }
+        }
+    )*};
+}
+impl_into_pact_numeric!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Impl for `bool`
+impl<'a> IntoPact<'a, bool> for bool {
     fn into_pact(self) -> Result<PactType<'a>, ()> {
-        let result: u64 = self.try_into().map_err(|_| ())?;
-        Ok(PactType::Numeric(Numeric(result)))
+        Ok(PactType::Bool(self))
     }
 }
 
@@ -41,6 +56,138 @@ impl<'a, T: AsRef<[u8]> + ?Sized> IntoPact<'a, &T> for &'a T {
     }
 }
 
+/// A 32 byte hash e.g. `H256`
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Hash256<'a>(pub &'a [u8; 32]);
+
+/// An account address, either 20 bytes e.g. `H160` or 32 bytes e.g. `AccountId32`
+/// Only constructable from fixed size arrays so the length is checked at compile time
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Address<'a>(&'a [u8]);
+
+impl<'a> Address<'a> {
+    /// Return the address bytes
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+impl<'a> From<&'a [u8; 20]> for Address<'a> {
+    fn from(bytes: &'a [u8; 20]) -> Self {
+        Address(bytes)
+    }
+}
+
+impl<'a> From<&'a [u8; 32]> for Address<'a> {
+    fn from(bytes: &'a [u8; 32]) -> Self {
+        Address(bytes)
+    }
+}
+
+/// Hashes are encoded as `StringLike` until the type system has a dedicated hash type
+impl<'a> IntoPact<'a, Hash256<'a>> for Hash256<'a> {
+    fn into_pact(self) -> Result<PactType<'a>, ()> {
+        Ok(PactType::StringLike(StringLike(self.0)))
+    }
+}
+
+/// Addresses are encoded as `StringLike` until the type system has a dedicated address type
+impl<'a> IntoPact<'a, Address<'a>> for Address<'a> {
+    fn into_pact(self) -> Result<PactType<'a>, ()> {
+        Ok(PactType::StringLike(StringLike(self.0)))
+    }
+}
+
+/// Conversion of several values into an input table for `interpreter::interpret`
+/// e.g. `(123_u64, "hello").into_pact_table()`
+/// Fails if any value fails to convert, see `IntoPact`.
+pub trait IntoPactTable<'a, I> {
+    #[allow(clippy::result_unit_err)]
+    fn into_pact_table(self) -> Result<Vec<PactType<'a>>, ()>;
+}
+
+/// Impl for tuples of up to 12 values, converted in order
+macro_rules! impl_into_pact_table_tuple {
+    ($(($($t:ident $i:ident),+))*) => {$(
+        impl<'a, $($t: IntoPact<'a, $i>, $i),+> IntoPactTable<'a, ($($i,)+)> for ($($t,)+) {
+            #[allow(non_snake_case)]
+            fn into_pact_table(self) -> Result<Vec<PactType<'a>>, ()> {
+                let ($($t,)+) = self;
+                Ok(vec![$($t.into_pact()?),+])
+            }
+        }
+    )*};
+}
+impl_into_pact_table_tuple! {
+    (A AI)
+    (A AI, B BI)
+    (A AI, B BI, C CI)
+    (A AI, B BI, C CI, D DI)
+    (A AI, B BI, C CI, D DI, E EI)
+    (A AI, B BI, C CI, D DI, E EI, F FI)
+    (A AI, B BI, C CI, D DI, E EI, F FI, G GI)
+    (A AI, B BI, C CI, D DI, E EI, F FI, G GI, H HI)
+    (A AI, B BI, C CI, D DI, E EI, F FI, G GI, H HI, J JI)
+    (A AI, B BI, C CI, D DI, E EI, F FI, G GI, H HI, J JI, K KI)
+    (A AI, B BI, C CI, D DI, E EI, F FI, G GI, H HI, J JI, K KI, L LI)
+    (A AI, B BI, C CI, D DI, E EI, F FI, G GI, H HI, J JI, K KI, L LI, M MI)
+}
+
+/// Impl for arrays of values of one type, up to one per input register
+macro_rules! impl_into_pact_table_array {
+    ($($n:literal: ($($v:ident)+))*) => {$(
+        impl<'a, T: IntoPact<'a, I>, I> IntoPactTable<'a, [I; $n]> for [T; $n] {
+            fn into_pact_table(self) -> Result<Vec<PactType<'a>>, ()> {
+                let [$($v),+] = self;
+                Ok(vec![$($v.into_pact()?),+])
+            }
+        }
+    )*};
+}
+impl_into_pact_table_array! {
+    1: (a)
+    2: (a b)
+    3: (a b c)
+    4: (a b c d)
+    5: (a b c d e)
+    6: (a b c d e f)
+    7: (a b c d e f g)
+    8: (a b c d e f g h)
+    9: (a b c d e f g h i)
+    10: (a b c d e f g h i j)
+    11: (a b c d e f g h i j k)
+    12: (a b c d e f g h i j k l)
+    13: (a b c d e f g h i j k l m)
+    14: (a b c d e f g h i j k l m n)
+    15: (a b c d e f g h i j k l m n o)
+    16: (a b c d e f g h i j k l m n o p)
+}
+
+/// Impl for slices of copyable values of one type e.g. `&[u64]` or `&[&str]`
+impl<'a, T: IntoPact<'a, I> + Copy, I> IntoPactTable<'a, &[I]> for &[T] {
+    fn into_pact_table(self) -> Result<Vec<PactType<'a>>, ()> {
+        self.iter().map(|value| value.into_pact()).collect()
+    }
+}
+
+/// Impl for vectors of values of one type
+impl<'a, T: IntoPact<'a, I>, I> IntoPactTable<'a, Vec<I>> for Vec<T> {
+    fn into_pact_table(self) -> Result<Vec<PactType<'a>>, ()> {
+        self.into_iter().map(IntoPact::into_pact).collect()
+    }
+}
+
+/// Conversion of a value's fields into an input table, in the order of the contract's
+/// `given parameters` e.g. for the arguments of a dispatchable call
+/// Values may borrow from `self` e.g. string-likes from `Vec<u8>` fields.
+/// The `derive` feature's `#[derive(ToPactInputs)]` converts each field with `IntoPact`.
+pub trait ToPactInputs {
+    #[allow(clippy::result_unit_err)]
+    fn to_pact_inputs(&self) -> Result<Vec<PactType<'_>>, ()>;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,6 +206,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn it_converts_bool() {
+        assert_eq!(true.into_pact(), Ok(PactType::Bool(true)));
+        assert_eq!(false.into_pact(), Ok(PactType::Bool(false)));
+    }
+
+    #[test]
+    fn it_converts_signed_numeric() {
+        assert_eq!(5_i32.into_pact(), Ok(PactType::Numeric(Numeric(5))));
+        assert_eq!((-1_i64).into_pact(), Err(()));
+    }
+
     #[test]
     fn it_converts_string_like() {
         assert_eq!(
@@ -158,4 +317,57 @@ mod tests {
             assert_eq!(lhs, rhs);
         }
     }
+
+    #[test]
+    fn it_converts_fixed_size_hashes_and_addresses() {
+        let h256 = [7_u8; 32];
+        let h160 = [9_u8; 20];
+        assert_eq!(
+            Hash256(&h256).into_pact(),
+            Ok(PactType::StringLike(StringLike(&h256))),
+        );
+        assert_eq!(
+            Address::from(&h160).into_pact(),
+            Ok(PactType::StringLike(StringLike(&h160))),
+        );
+        assert_eq!(Address::from(&h256).as_bytes(), &h256[..]);
+    }
+
+    #[test]
+    fn it_converts_tuples_into_tables() {
+        let address = [9_u8; 20];
+        assert_eq!(
+            (123_u64, "hello", true, Address::from(&address)).into_pact_table(),
+            Ok(vec![
+                PactType::Numeric(Numeric(123)),
+                PactType::StringLike(StringLike(b"hello")),
+                PactType::Bool(true),
+                PactType::StringLike(StringLike(&address)),
+            ])
+        );
+        assert_eq!(
+            (1_u8,).into_pact_table(),
+            Ok(vec![PactType::Numeric(Numeric(1))])
+        );
+        assert_eq!((1_u8, -1_i32).into_pact_table(), Err(()));
+    }
+
+    #[test]
+    fn it_converts_arrays_slices_and_vectors_into_tables() {
+        let expected = Ok(vec![
+            PactType::Numeric(Numeric(1)),
+            PactType::Numeric(Numeric(2)),
+        ]);
+        assert_eq!([1_u32, 2].into_pact_table(), expected);
+        assert_eq!((&[1_u32, 2][..]).into_pact_table(), expected);
+        assert_eq!(vec![1_u32, 2].into_pact_table(), expected);
+        assert_eq!(
+            ["a", "b"].into_pact_table(),
+            Ok(vec![
+                PactType::StringLike(StringLike(b"a")),
+                PactType::StringLike(StringLike(b"b")),
+            ])
+        );
+        assert_eq!(vec![1_i8, -2].into_pact_table(), Err(()));
+    }
 }
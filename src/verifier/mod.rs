@@ -0,0 +1,201 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! A single entry point for hosts verifying encoded contracts
+//! `Verifier` decodes, validates, and interprets a contract with settings configured once, so
+//! every call site applies the same limits and policies.
+//!
+use crate::interpreter::{self, Comparator, InterpErr, Limits, OpCode, OpLoad, Outcome};
+use crate::types::{BinaryFormatErr, Capabilities, Contract, PactType, SchemaErr, ValidationErr};
+use core::fmt;
+
+/// A contract verification error
+#[cfg_attr(feature = "std", derive(Debug, PartialEq))]
+pub enum PactErr {
+    /// The encoded contract is longer than the configured maximum
+    TooLarge(usize),
+    /// The encoded contract can't be decoded
+    Decode(BinaryFormatErr),
+    /// The contract is invalid or uses capabilities which are not allowed
+    Invalid(ValidationErr),
+    /// The inputs don't conform to the contract's input schema
+    Schema(SchemaErr),
+    /// The contract failed during interpretation
+    Interp(InterpErr),
+}
+
+impl fmt::Display for PactErr {
+    /// Wrapped errors are not repeated, see `source`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PactErr::TooLarge(length) => write!(
+                f,
+                "encoded contract of {} bytes is longer than the maximum",
+                length
+            ),
+            PactErr::Decode(_) => write!(f, "contract can't be decoded"),
+            PactErr::Invalid(_) => write!(f, "contract is invalid"),
+            PactErr::Schema(_) => write!(f, "inputs don't conform to the contract's input schema"),
+            PactErr::Interp(_) => write!(f, "contract failed during interpretation"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PactErr {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PactErr::TooLarge(_) => None,
+            PactErr::Decode(err) => Some(err),
+            PactErr::Invalid(err) => Some(err),
+            PactErr::Schema(err) => Some(err),
+            PactErr::Interp(err) => Some(err),
+        }
+    }
+}
+
+/// How a comparator loading an input register which wasn't supplied is handled
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum MissingInputPolicy {
+    /// Fail with `InterpErr::MissingIndex`
+    Error,
+    /// Reject the contract at the first comparator loading the missing input
+    Reject,
+}
+
+/// The decision of a verified contract, without borrowing its operands
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Decision {
+    /// The contract invariants were maintained
+    Upheld,
+    /// The contract was rejected by `comparator` at `opcode_offset` in the bytecode
+    Rejected {
+        /// Byte offset of the rejecting comparator OpCode
+        opcode_offset: usize,
+        /// The rejecting comparator
+        comparator: Comparator,
+    },
+}
+
+/// Verifies encoded contracts against inputs with fixed limits and policies
+/// The default allows every capability, any contract length, and unlimited operands. `CALL`
+/// comparators fail with `InterpErr::UnknownHostFn` as there is no host context.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Verifier {
+    allowed: Capabilities,
+    limits: Limits,
+    max_contract_bytes: Option<usize>,
+    missing_input: MissingInputPolicy,
+}
+
+impl Default for Verifier {
+    fn default() -> Self {
+        Verifier {
+            allowed: Capabilities::all(),
+            limits: Limits::default(),
+            max_contract_bytes: None,
+            missing_input: MissingInputPolicy::Error,
+        }
+    }
+}
+
+impl Verifier {
+    /// Return a verifier with the default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Refuse contracts using capabilities outside `allowed`
+    pub fn with_capabilities(mut self, allowed: Capabilities) -> Self {
+        self.allowed = allowed;
+        self
+    }
+    /// Interpret contracts within `limits`
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+    /// Refuse encoded contracts longer than `max` bytes before decoding them
+    pub fn with_max_contract_bytes(mut self, max: usize) -> Self {
+        self.max_contract_bytes = Some(max);
+        self
+    }
+    /// Handle comparators loading missing inputs with `policy`
+    pub fn with_missing_input_policy(mut self, policy: MissingInputPolicy) -> Self {
+        self.missing_input = policy;
+        self
+    }
+    /// Decode the contract `blob`, validate it, check `inputs` against its input schema, and
+    /// interpret it with `inputs`
+    pub fn verify(&self, blob: &[u8], inputs: &[PactType]) -> Result<Decision, PactErr> {
+        if let Some(max) = self.max_contract_bytes {
+            if blob.len() > max {
+                return Err(PactErr::TooLarge(blob.len()));
+            }
+        }
+        let contract = Contract::decode(blob).map_err(PactErr::Decode)?;
+        contract.validate(self.allowed).map_err(PactErr::Invalid)?;
+        contract.check_inputs(inputs).map_err(PactErr::Schema)?;
+        let outcome = interpreter::run(
+            inputs,
+            contract.data_table().as_ref(),
+            contract.bytecode(),
+            &(),
+            self.limits,
+            &mut (),
+        );
+        match outcome {
+            Ok(Outcome::Upheld) => Ok(Decision::Upheld),
+            Ok(Outcome::Rejected {
+                opcode_offset,
+                comparator,
+                ..
+            }) => Ok(Decision::Rejected {
+                opcode_offset,
+                comparator,
+            }),
+            Err(InterpErr::MissingIndex(index))
+                if self.missing_input == MissingInputPolicy::Reject =>
+            {
+                first_load(&contract, index).ok_or(PactErr::Interp(InterpErr::MissingIndex(index)))
+            }
+            Err(err) => Err(PactErr::Interp(err)),
+        }
+    }
+}
+
+/// Return a rejection by the first comparator in `contract` loading input register `index`
+fn first_load(contract: &Contract, index: u8) -> Option<Decision> {
+    contract
+        .ops()
+        .flatten()
+        .find_map(|(opcode_offset, op)| match op {
+            OpCode::COMP(comparator)
+                if comparator.indices.lhs == index
+                    || (comparator.load == OpLoad::INPUT_VS_INPUT
+                        && comparator.indices.rhs == index) =>
+            {
+                Some(Decision::Rejected {
+                    opcode_offset,
+                    comparator,
+                })
+            }
+            _ => None,
+        })
+}
@@ -0,0 +1,333 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//!
+//! Ahead-of-time transpiler from pact bytecode to a WASM module
+//!
+//! The module imports one host function and exports one function:
+//! - `pact.compare(opcode: i32, indices: i32) -> i32`
+//!   evaluates a single comparator OpCode against the host's input and user data tables.
+//!   `indices` holds the indices byte, and a `RATIO` comparator's ratio byte in bits 15..8.
+//!   It returns `1` (true), `0` (false) or a negative value on error. See `host_compare`.
+//!   Rust hosts should wrap their import with `guard_compare` so a faulty callback can't
+//!   unwind into the executor.
+//! - `verdict() -> i32`
+//!   returns `1` when the contract is upheld, `0` when it is not, or the negative error
+//!   code from the first failed `compare` call.
+//!
+//! Conjunction and clause logic is compiled into the module, so a host WASM executor meters
+//! the contract's control flow uniformly while typed comparisons stay with the host.
+//!
+use crate::interpreter::{self, InterpErr, OpCode, OpComp, OpConj, OpInvert};
+use crate::types::PactType;
+use alloc::vec::Vec;
+
+/// Error code returned by `host_compare` when the comparator fails to evaluate
+pub const COMPARE_ERR: i32 = -1;
+/// Error code returned by `guard_compare` when a host callback panics or returns a value
+/// other than `1`, `0` or a negative error code
+pub const CALLBACK_ERR: i32 = -2;
+
+// WASM encoding constants
+const MAGIC: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+const SECTION_TYPE: u8 = 1;
+const SECTION_IMPORT: u8 = 2;
+const SECTION_FUNCTION: u8 = 3;
+const SECTION_EXPORT: u8 = 7;
+const SECTION_CODE: u8 = 10;
+const TYPE_FUNC: u8 = 0x60;
+const TYPE_I32: u8 = 0x7f;
+const BLOCK_VOID: u8 = 0x40;
+const EXTERNAL_FUNC: u8 = 0x00;
+
+// WASM instructions emitted by the transpiler
+const OP_IF: u8 = 0x04;
+const OP_END: u8 = 0x0b;
+const OP_RETURN: u8 = 0x0f;
+const OP_CALL: u8 = 0x10;
+const OP_LOCAL_GET: u8 = 0x20;
+const OP_LOCAL_SET: u8 = 0x21;
+const OP_I32_CONST: u8 = 0x41;
+const OP_I32_EQZ: u8 = 0x45;
+const OP_I32_LT_S: u8 = 0x48;
+const OP_I32_AND: u8 = 0x71;
+const OP_I32_OR: u8 = 0x72;
+const OP_I32_XOR: u8 = 0x73;
+
+// Function and local indices
+const FUNC_COMPARE: u32 = 0;
+const FUNC_VERDICT: u32 = 1;
+const LOCAL_ACC: u32 = 0;
+const LOCAL_TMP: u32 = 1;
+
+/// Transpile pact `bytecode` into a WASM module
+/// The bytecode is validated structurally first, returning the same errors the interpreter would
+pub fn transpile(bytecode: &[u8]) -> Result<Vec<u8>, InterpErr> {
+    let mut body = Vec::<u8>::default();
+    // 1 local declaration: 2 x i32 (acc, tmp)
+    body.extend(&[1, 2, TYPE_I32]);
+
+    let mut scanner = bytecode.iter();
+    let mut expect_comparator = true;
+    let mut conjunction = None;
+    while let Some(op) = OpCode::parse(&mut scanner)? {
+        match op {
+            OpCode::COMP(comparator) => {
+                if !expect_comparator && conjunction.is_none() {
+                    // A comparator without a conjunction starts a new clause
+                    emit_clause_end(&mut body);
+                }
+                let opcode = op.into();
+                let indices = i32::from(u8::from(comparator.indices));
+                let indices = match comparator.op {
+                    OpComp::RATIO(ratio) => indices | i32::from(u8::from(ratio)) << 8,
+                    _ => indices,
+                };
+                match conjunction.take() {
+                    None => emit_compare(&mut body, opcode, indices, LOCAL_ACC),
+                    Some(conj) => {
+                        emit_compare(&mut body, opcode, indices, LOCAL_TMP);
+                        emit_conjunction(&mut body, conj);
+                    }
+                }
+                expect_comparator = false;
+            }
+            OpCode::CONJ(conj) => {
+                if expect_comparator {
+                    return Err(InterpErr::UnexpectedOpCode(op.into()));
+                }
+                conjunction = Some(conj);
+                expect_comparator = true;
+            }
+        }
+    }
+    if expect_comparator {
+        return Err(InterpErr::UnexpectedEOI("incomplete operation"));
+    }
+    emit_clause_end(&mut body);
+    body.extend(&[OP_I32_CONST, 1, OP_END]);
+
+    Ok(build_module(&body))
+}
+
+/// Evaluate a single comparator `opcode` and its `indices` against the given data tables
+/// This is the reference implementation of the `pact.compare` import for Rust hosts
+pub fn host_compare(
+    input_data: &[PactType],
+    user_data: &[PactType],
+    opcode: i32,
+    indices: i32,
+) -> i32 {
+    let bytecode = [opcode as u8, indices as u8, (indices >> 8) as u8];
+    // Only `RATIO` comparators have a third byte
+    let length = match OpCode::parse(&mut bytecode.iter()) {
+        Ok(Some(op)) => op.encoded_len(),
+        _ => 2,
+    };
+    match interpreter::interpret(input_data, user_data, &bytecode[..length]) {
+        Ok(true) => 1,
+        Ok(false) => 0,
+        Err(_) => COMPARE_ERR,
+    }
+}
+
+/// Run a host `compare` callback without letting a panic unwind into the WASM executor
+/// Results outside of `1`, `0` or a negative error code would corrupt the compiled conjunction
+/// logic, so they are reported as `CALLBACK_ERR` along with panics.
+#[cfg(feature = "std")]
+pub fn guard_compare<F: FnOnce() -> i32>(compare: F) -> i32 {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(compare)) {
+        Ok(result) if result <= 1 => result,
+        _ => CALLBACK_ERR,
+    }
+}
+
+/// Emit a `pact.compare` call storing the result in `local`
+/// Returns early from `verdict` if the comparison errored
+fn emit_compare(body: &mut Vec<u8>, opcode: u8, indices: i32, local: u32) {
+    body.push(OP_I32_CONST);
+    write_sleb(body, i32::from(opcode));
+    body.push(OP_I32_CONST);
+    write_sleb(body, indices);
+    body.push(OP_CALL);
+    write_uleb(body, FUNC_COMPARE);
+    body.push(OP_LOCAL_SET);
+    write_uleb(body, local);
+    // if (local < 0) return local
+    body.push(OP_LOCAL_GET);
+    write_uleb(body, local);
+    body.extend(&[
+        OP_I32_CONST,
+        0,
+        OP_I32_LT_S,
+        OP_IF,
+        BLOCK_VOID,
+        OP_LOCAL_GET,
+    ]);
+    write_uleb(body, local);
+    body.extend(&[OP_RETURN, OP_END]);
+}
+
+/// Emit `acc = acc <conj> tmp`
+fn emit_conjunction(body: &mut Vec<u8>, conjunction: interpreter::Conjunction) {
+    body.push(OP_LOCAL_GET);
+    write_uleb(body, LOCAL_ACC);
+    body.push(OP_LOCAL_GET);
+    write_uleb(body, LOCAL_TMP);
+    body.push(match conjunction.op {
+        OpConj::AND => OP_I32_AND,
+        OpConj::OR => OP_I32_OR,
+        OpConj::XOR => OP_I32_XOR,
+    });
+    if conjunction.invert == OpInvert::NOT {
+        body.push(OP_I32_EQZ);
+    }
+    body.push(OP_LOCAL_SET);
+    write_uleb(body, LOCAL_ACC);
+}
+
+/// Emit `if (acc == 0) return 0`, failing fast on a false clause
+fn emit_clause_end(body: &mut Vec<u8>) {
+    body.push(OP_LOCAL_GET);
+    write_uleb(body, LOCAL_ACC);
+    body.extend(&[
+        OP_I32_EQZ,
+        OP_IF,
+        BLOCK_VOID,
+        OP_I32_CONST,
+        0,
+        OP_RETURN,
+        OP_END,
+    ]);
+}
+
+/// Wrap a `verdict` function body into a complete module
+fn build_module(body: &[u8]) -> Vec<u8> {
+    let mut module = MAGIC.to_vec();
+
+    // (func (param i32 i32) (result i32)), (func (result i32))
+    let types = [
+        2, TYPE_FUNC, 2, TYPE_I32, TYPE_I32, 1, TYPE_I32, TYPE_FUNC, 0, 1, TYPE_I32,
+    ];
+    write_section(&mut module, SECTION_TYPE, &types);
+
+    let mut imports = [1].to_vec();
+    write_name(&mut imports, "pact");
+    write_name(&mut imports, "compare");
+    imports.extend(&[EXTERNAL_FUNC, 0]);
+    write_section(&mut module, SECTION_IMPORT, &imports);
+
+    write_section(&mut module, SECTION_FUNCTION, &[1, 1]);
+
+    let mut exports = [1].to_vec();
+    write_name(&mut exports, "verdict");
+    exports.push(EXTERNAL_FUNC);
+    write_uleb(&mut exports, FUNC_VERDICT);
+    write_section(&mut module, SECTION_EXPORT, &exports);
+
+    let mut code = [1].to_vec();
+    write_uleb(&mut code, body.len() as u32);
+    code.extend(body);
+    write_section(&mut module, SECTION_CODE, &code);
+
+    module
+}
+
+fn write_section(module: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    module.push(id);
+    write_uleb(module, contents.len() as u32);
+    module.extend(contents);
+}
+
+fn write_name(buf: &mut Vec<u8>, name: &str) {
+    write_uleb(buf, name.len() as u32);
+    buf.extend(name.as_bytes());
+}
+
+/// Write `value` as unsigned LEB128
+fn write_uleb(buf: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+/// Write `value` as signed LEB128
+fn write_sleb(buf: &mut Vec<u8>, mut value: i32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_encodes_leb128() {
+        let mut buf = Vec::new();
+        write_uleb(&mut buf, 624_485);
+        assert_eq!(buf, vec![0xe5, 0x8e, 0x26]);
+
+        let mut buf = Vec::new();
+        write_sleb(&mut buf, 0x20);
+        write_sleb(&mut buf, 0x40);
+        write_sleb(&mut buf, -1);
+        assert_eq!(buf, vec![0x20, 0xc0, 0x00, 0x7f]);
+    }
+
+    #[test]
+    fn it_rejects_structurally_invalid_bytecode() {
+        assert_eq!(
+            transpile(&[]),
+            Err(InterpErr::UnexpectedEOI("incomplete operation"))
+        );
+        assert_eq!(transpile(&[0x20]), Err(InterpErr::UnexpectedOpCode(0x20)));
+        assert_eq!(
+            transpile(&[0x00, 0x00, 0x20]),
+            Err(InterpErr::UnexpectedEOI("incomplete operation"))
+        );
+        assert_eq!(
+            transpile(&[0xc0, 0x00]),
+            Err(InterpErr::InvalidOpCode(0xc0))
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn it_guards_host_callbacks() {
+        assert_eq!(guard_compare(|| 1), 1);
+        assert_eq!(guard_compare(|| 0), 0);
+        assert_eq!(guard_compare(|| COMPARE_ERR), COMPARE_ERR);
+        assert_eq!(guard_compare(|| 42), CALLBACK_ERR);
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        assert_eq!(guard_compare(|| panic!("host failure")), CALLBACK_ERR);
+        std::panic::set_hook(hook);
+    }
+}
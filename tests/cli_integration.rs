@@ -0,0 +1,206 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! CLI integration tests
+
+#![cfg(test)]
+use pact::json;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn pactc(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pactc"))
+        .args(args)
+        .output()
+        .expect("pactc runs")
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("pactc-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn it_compiles_and_simulates() {
+    let source = temp_path("simulate.pact");
+    let contract = temp_path("simulate.bin");
+    let accepted = temp_path("accepted.json");
+    let rejected = temp_path("rejected.json");
+    fs::write(
+        &source,
+        "given parameters $amount, $payee
+         $amount must be less than 100 and $payee must be equal to \"alice\"",
+    )
+    .unwrap();
+    fs::write(&accepted, r#"[99, "alice"]"#).unwrap();
+    fs::write(&rejected, r#"[100, "alice"]"#).unwrap();
+
+    let output = pactc(&[
+        "compile",
+        source.to_str().unwrap(),
+        "-o",
+        contract.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+
+    let output = pactc(&[
+        "simulate",
+        contract.to_str().unwrap(),
+        "--inputs",
+        accepted.to_str().unwrap(),
+    ]);
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(report.contains("input[1] = \"alice\""));
+    assert!(report.contains("COMP NOT GTE input[0] user[0]"));
+    assert!(report.contains("verdict: ACCEPTED"));
+
+    let output = pactc(&[
+        "simulate",
+        contract.to_str().unwrap(),
+        "--inputs",
+        rejected.to_str().unwrap(),
+    ]);
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(report.contains("verdict: REJECTED"));
+    assert!(report.contains("at 0000  COMP NOT GTE input[0] user[0] (100 vs 100)"));
+
+    for path in &[source, contract, accepted, rejected] {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[test]
+fn it_fails_with_bad_inputs() {
+    let contract = temp_path("bad_inputs.bin");
+    let inputs = temp_path("bad_inputs.json");
+    fs::write(&contract, [0, 0]).unwrap();
+    fs::write(&inputs, "{\"amount\": 5}").unwrap();
+
+    let output = pactc(&[
+        "simulate",
+        contract.to_str().unwrap(),
+        "--inputs",
+        inputs.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("ExpectedArray"));
+
+    let _ = fs::remove_file(contract);
+    let _ = fs::remove_file(inputs);
+}
+
+#[test]
+fn it_exports_fixtures() {
+    let contract = temp_path("fixture.bin");
+    let inputs = temp_path("fixture.json");
+    // INPUT(0) == INPUT(0)
+    fs::write(&contract, [0x00, 0x00, 0x08, 0x00]).unwrap();
+    fs::write(&inputs, r#"["alice"]"#).unwrap();
+
+    let output = pactc(&[
+        "fixture",
+        contract.to_str().unwrap(),
+        "--inputs",
+        inputs.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    let exported = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        exported.trim(),
+        r#"[{"contract": "00000800", "inputs": ["alice"], "verdict": "upheld"}]"#
+    );
+    assert_eq!(
+        json::parse_fixtures(&exported).unwrap()[0].verdict,
+        json::Verdict::Upheld
+    );
+
+    let _ = fs::remove_file(contract);
+    let _ = fs::remove_file(inputs);
+}
+
+#[test]
+fn it_prints_usage() {
+    let output = pactc(&[]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("usage"));
+}
+
+#[test]
+fn it_points_at_compile_errors() {
+    let source = temp_path("undeclared.pact");
+    let contract = temp_path("undeclared.bin");
+    fs::write(&source, "given parameters $a\n$a must be equal to $b").unwrap();
+
+    let output = pactc(&[
+        "compile",
+        source.to_str().unwrap(),
+        "-o",
+        contract.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("error: undeclared variable `$b`"));
+    assert!(stderr.contains("undeclared.pact:2:21"));
+    assert!(stderr.contains("2 | $a must be equal to $b\n  |                     ^^"));
+
+    let _ = fs::remove_file(source);
+}
+
+#[test]
+fn it_runs_a_repl_session() {
+    let mut repl = Command::new(env!("CARGO_BIN_EXE_pactc"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("pactc runs");
+    repl.stdin
+        .take()
+        .unwrap()
+        .write_all(
+            br#"given parameters $amount, $payee
+define $limit as 100
+$amount must be less than $limit
+$payee must be equal to $unknown
+:data
+:bytecode
+:inputs [150, "alice"]
+:run
+:try $payee must be equal to "alice"
+:source
+:quit
+"#,
+        )
+        .unwrap();
+    let output = repl.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("ok, 1 data table entries and 2 bytes of bytecode"));
+    // Statements which don't compile are discarded
+    assert!(stdout.contains("error: undeclared variable `$unknown`"));
+    assert!(stdout.contains("user[0] = 100"));
+    assert!(stdout.contains("0000  COMP NOT GTE input[0] user[0]"));
+    assert!(stdout.contains("REJECTED"));
+    assert!(stdout.contains("holds"));
+    assert!(stdout.contains(
+        "given parameters $amount, $payee\ndefine $limit as 100\n$amount must be less than $limit\npact>"
+    ));
+}
@@ -17,24 +17,26 @@
 //! Codec integration tests
 
 #![cfg(test)]
-use pact::interpreter::{Comparator, OpCode, OpComp, OpIndices, OpLoad};
-use pact::types::{BinaryFormatErr, Contract, DataTable, Numeric, PactType, StringLike};
+use pact::interpreter::{Comparator, OpCode, OpComp, OpIndices, OpInvert, OpLoad, OpQuant};
+use pact::types::{BinaryFormatErr, Contract, DataTable, EncodeErr, Numeric, PactType, StringLike};
+use pact::{compiler, parser};
 
 #[test]
 fn contract_binary_format_codec() {
-    let expected = Contract {
-        data_table: DataTable::new(vec![
+    let expected = Contract::from_parts_unchecked(
+        DataTable::new(vec![
             PactType::Numeric(Numeric(111)),
             PactType::Numeric(Numeric(333)),
             PactType::StringLike(StringLike(b"testing")),
         ]),
-        bytecode: [
+        [
             // EQ LD_INPUT(0) LD_USER(0)
             OpCode::COMP(Comparator {
                 load: OpLoad::INPUT_VS_USER,
                 op: OpComp::EQ,
                 indices: OpIndices { lhs: 1, rhs: 0 },
-                invert: false,
+                invert: OpInvert::NORMAL,
+                quant: OpQuant::ONE,
             })
             .into(),
             0x10,
@@ -43,16 +45,17 @@ fn contract_binary_format_codec() {
                 load: OpLoad::INPUT_VS_USER,
                 op: OpComp::EQ,
                 indices: OpIndices { lhs: 1, rhs: 1 },
-                invert: false,
+                invert: OpInvert::NORMAL,
+                quant: OpQuant::ONE,
             })
             .into(),
             0x11,
         ]
         .to_vec(),
-    };
+    );
 
     let mut buf: Vec<u8> = Vec::new();
-    expected.encode(&mut buf);
+    expected.encode(&mut buf).unwrap();
 
     let result = Contract::decode(&buf).expect("it decodes");
 
@@ -61,23 +64,119 @@ fn contract_binary_format_codec() {
 
 #[test]
 fn contract_binary_format_malformed_data_table() {
-    let mut malformed_short: Vec<u8> = vec![0, 1];
+    let malformed_short: Vec<u8> = vec![0, 0b1000_0000];
     assert_eq!(
-        Contract::decode(&mut malformed_short),
+        Contract::decode(&malformed_short),
         Err(BinaryFormatErr::MalformedDataTable("missing type ID byte"))
     );
 
-    let mut bad_type_id = vec![0, 0b1000_0000, 0b0000_0001, 0b0000_0000];
+    // 17 entries
+    let too_many_entries = vec![0, 0b1000_1000];
     assert_eq!(
-        Contract::decode(&mut bad_type_id),
+        Contract::decode(&too_many_entries),
+        Err(BinaryFormatErr::MalformedDataTable(
+            "data table has more than MAX_ENTRIES entries"
+        ))
+    );
+
+    let bad_type_id = vec![0, 0b1000_0000, 0b0000_0001, 0b0000_0000];
+    assert_eq!(
+        Contract::decode(&bad_type_id),
         Err(BinaryFormatErr::MalformedDataTable("unsupported type ID"))
     );
 
-    let mut numeric_too_small = vec![0, 0b1000_0000, 0b1000_0000, 0b0100_0000, 0, 0];
+    let numeric_too_small = vec![0, 0b1000_0000, 0b1000_0000, 0b0100_0000, 0, 0];
     assert_eq!(
-        Contract::decode(&mut numeric_too_small),
+        Contract::decode(&numeric_too_small),
         Err(BinaryFormatErr::MalformedDataTable(
             "implementation only supports 64-bit numerics"
         ))
     );
 }
+
+#[test]
+fn contract_encodes_into_pre_sized_buffers() {
+    let ast = parser::parse(
+        "given parameters $amount, $payee
+         clause limit: $amount must be less than 100 and $payee must be one of [\"alice\", \"bob\"]",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    let mut expected: Vec<u8> = Vec::new();
+    contract.encode(&mut expected).unwrap();
+    assert_eq!(contract.encoded_len(), Ok(expected.len()));
+    assert_eq!(
+        contract.stats().map(|stats| stats.encoded_size),
+        Ok(expected.len())
+    );
+
+    let mut buf = [0_u8; 128];
+    assert_eq!(contract.encode_to(&mut buf), Ok(expected.len()));
+    assert_eq!(&buf[..expected.len()], &expected[..]);
+    assert_eq!(buf[expected.len()], 0);
+
+    let mut short = [0_u8; 8];
+    assert_eq!(
+        contract.encode_to(&mut short),
+        Err(EncodeErr::BufferTooShort {
+            required: expected.len()
+        })
+    );
+    // `short` is unchanged on failure
+    assert_eq!(short, [0_u8; 8]);
+}
+
+#[test]
+fn data_table_encodes_into_pre_sized_buffers() {
+    let table = DataTable::new(vec![
+        PactType::Numeric(Numeric(111)),
+        PactType::List(vec![PactType::StringLike(StringLike(b"testing"))]),
+    ]);
+    let mut expected: Vec<u8> = Vec::new();
+    table.encode(&mut expected).unwrap();
+    assert_eq!(table.encoded_len(), Ok(expected.len()));
+    let mut buf = vec![0_u8; expected.len()];
+    assert_eq!(table.encode_to(&mut buf), Ok(expected.len()));
+    assert_eq!(buf, expected);
+    assert_eq!(
+        table.encode_to(&mut buf[1..]),
+        Err(EncodeErr::BufferTooShort {
+            required: expected.len()
+        })
+    );
+}
+
+#[test]
+#[cfg(feature = "codecs")]
+fn contract_text_codecs() {
+    let ast = parser::parse("given parameters $a\n$a must be equal to \"hello\"").unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    let mut encoded = Vec::new();
+    contract.encode(&mut encoded).unwrap();
+    let same = |decoded: Contract| {
+        decoded.bytecode() == contract.bytecode() && decoded.data_table() == contract.data_table()
+    };
+
+    let hex = contract.to_hex().unwrap();
+    assert_eq!(hex.len(), encoded.len() * 2);
+    let mut buf = Vec::new();
+    assert!(same(Contract::from_hex(&hex, &mut buf).unwrap()));
+    assert_eq!(buf, encoded);
+    let prefixed = format!("0x{}", hex.to_uppercase());
+    assert!(same(Contract::from_hex(&prefixed, &mut buf).unwrap()));
+
+    let base64 = contract.to_base64().unwrap();
+    assert!(same(Contract::from_base64(&base64, &mut buf).unwrap()));
+    assert_eq!(buf, encoded);
+
+    assert_eq!(
+        Contract::from_hex("0x0", &mut buf),
+        Err(BinaryFormatErr::InvalidText(
+            "hex has an odd number of digits"
+        ))
+    );
+    assert_eq!(
+        Contract::from_base64("AA==", &mut buf),
+        Err(BinaryFormatErr::TooShort)
+    );
+}
@@ -17,10 +17,14 @@
 //! Compiler integration tests
 
 #![cfg(test)]
-use pact::compiler::{self, CompileErr};
-use pact::interpreter;
-use pact::parser;
-use pact::types::{Numeric, PactType, StringLike};
+use pact::compiler::{self, CompileErr, Warning};
+use pact::interpreter::{self, Comparator, InterpErr, OpComp, OpConj, OpQuant, ARITY_INDEX};
+use pact::parser::{self, ast};
+use pact::testing::*;
+use pact::types::{
+    BindErr, Capabilities, Contract, InputType, MergeErr, Numeric, PactType, PatchErr, SchemaErr,
+    StringLike,
+};
 
 #[test]
 fn it_compiles() {
@@ -35,8 +39,8 @@ fn it_compiles() {
     .unwrap();
 
     let contract = compiler::compile(&ast).unwrap();
-    println!("Data Table: {:?}", contract.data_table);
-    println!("Bytecode: {:?}", contract.bytecode);
+    println!("Data Table: {:?}", contract.data_table());
+    println!("Bytecode: {:?}", contract.bytecode());
 
     // The manually crafted input table
     // In normal execution, this contains the transaction arguments
@@ -49,14 +53,45 @@ fn it_compiles() {
 
     let result = interpreter::interpret(
         input_table,
-        &contract.data_table.as_ref(),
-        &contract.bytecode,
+        contract.data_table().as_ref(),
+        contract.bytecode(),
     );
 
     println!("Result: {:?}", result);
     assert!(result.unwrap());
 }
 
+#[test]
+fn it_resolves_definitions_after_literals() {
+    let ast = parser::parse(
+        "
+          given parameters $a
+          $a must be greater than 5
+          define $max as 7
+          $a must be less than or equal to $max
+        ",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.data_table().as_ref(),
+        &[PactType::Numeric(Numeric(5)), PactType::Numeric(Numeric(7))]
+    );
+    assert_eq!(
+        contract.bytecode(),
+        &bytecode(&[comp_gt(input(0), user(0)), comp_lte(input(0), user(1))])[..]
+    );
+    let interpret = |a: u64| {
+        interpreter::interpret(
+            &[PactType::Numeric(Numeric(a))],
+            contract.data_table().as_ref(),
+            contract.bytecode(),
+        )
+    };
+    assert_eq!(interpret(6), Ok(true));
+    assert_eq!(interpret(8), Ok(false));
+}
+
 #[test]
 fn it_fails_with_a_large_datatable_from_definitions() {
     let ast = parser::parse(
@@ -114,6 +149,83 @@ fn it_fails_with_a_large_datatable_from_values() {
     assert_eq!(compiler::compile(&ast), Err(CompileErr::DataTableFull));
 }
 
+#[test]
+fn it_fails_with_a_list_over_the_element_limit() {
+    let elements: Vec<String> = (0..65).map(|_| "\"a\"".to_string()).collect();
+    let source = format!(
+        "given parameters $a\ndefine $names as [{}]\n$a must be one of $names",
+        elements.join(", ")
+    );
+    let ast = parser::parse(&source).unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::ListTooLong(ast::Location {
+            line: 2,
+            column: 1
+        }))
+    );
+
+    // 64 single character strings are within both limits
+    let source = source.replacen("\"a\", ", "", 1);
+    let ast = parser::parse(&source).unwrap();
+    assert!(compiler::compile(&ast).is_ok());
+}
+
+#[test]
+fn it_compiles_long_homogeneous_lists_as_sorted_lists() {
+    let elements: Vec<String> = (0..20).rev().map(|n| n.to_string()).collect();
+    let source = format!(
+        "given parameters $a\ndefine $ids as [{}, 7]\n$a must be one of $ids",
+        elements.join(", ")
+    );
+    let ast = parser::parse(&source).unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.data_table().as_ref(),
+        &[PactType::SortedList(
+            (0..20).map(|n| PactType::Numeric(Numeric(n))).collect()
+        )]
+    );
+    assert!(contract.capabilities().contains(Capabilities::SORTED_LIST));
+
+    // Short lists keep the list encoding
+    let ast = parser::parse("given parameters $a\n$a must be one of [2, 1]").unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.data_table().as_ref(),
+        &[PactType::List(vec![
+            PactType::Numeric(Numeric(2)),
+            PactType::Numeric(Numeric(1))
+        ])]
+    );
+}
+
+#[test]
+fn it_fails_with_a_list_over_the_byte_limit() {
+    // Each string element encodes to 3 + 1300 bytes
+    let element = format!("\"{}\"", "x".repeat(1300));
+    let elements = vec![element.as_str(); 51];
+    let source = format!(
+        "given parameters $a\n$a must be one of [{}]",
+        elements.join(", ")
+    );
+    let ast = parser::parse(&source).unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::ListTooLarge(ast::Location {
+            line: 2,
+            column: 19
+        }))
+    );
+
+    let source = source.replacen(&format!("{}, ", element), "", 1);
+    let ast = parser::parse(&source).unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    let mut encoded = Vec::new();
+    contract.encode(&mut encoded).unwrap();
+    assert_eq!(Contract::decode(&encoded), Ok(contract));
+}
+
 #[test]
 fn it_fails_with_too_many_inputs() {
     let ast = parser::parse(
@@ -124,3 +236,1481 @@ fn it_fails_with_too_many_inputs() {
     ).unwrap();
     assert_eq!(compiler::compile(&ast), Err(CompileErr::TooManyInputs));
 }
+
+#[test]
+fn it_fails_with_a_self_comparison() {
+    let ast = parser::parse(
+        "given parameters $a, $b\n$a must be less than $b and $b must not be greater than $b",
+    )
+    .unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::SelfComparison(ast::Location {
+            line: 2,
+            column: 29
+        }))
+    );
+
+    // A parameter is never one of itself, it's either a list or not a list at all
+    let ast = parser::parse("given parameters $a\n$a must be one of $a").unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::InvalidOperands(ast::Location {
+            line: 2,
+            column: 1
+        }))
+    );
+}
+
+#[test]
+fn it_fails_with_a_type_error() {
+    let type_error = |source: &str| {
+        let ast = parser::parse(source).unwrap();
+        match compiler::compile(&ast) {
+            Err(CompileErr::TypeError {
+                expected,
+                found,
+                location,
+            }) => Some((expected, found, location.line, location.column)),
+            _ => None,
+        }
+    };
+    // Literals
+    assert_eq!(
+        type_error("given parameters $a\n$a must be greater than \"hello\""),
+        Some((InputType::Numeric, InputType::StringLike, 2, 25))
+    );
+    assert_eq!(
+        type_error("given parameters $a\n\"hello\" must be less than $a"),
+        Some((InputType::Numeric, InputType::StringLike, 2, 1))
+    );
+    // Declared parameter types
+    assert_eq!(
+        type_error("given parameters $a: string, $b: numeric\n$a must be equal to $b"),
+        Some((InputType::StringLike, InputType::Numeric, 2, 21))
+    );
+    assert_eq!(
+        type_error("given parameters $a: list\n$a must be less than 5"),
+        Some((InputType::Numeric, InputType::List, 2, 1))
+    );
+    // Definitions and placeholders
+    assert_eq!(
+        type_error("given parameters $a\ndefine $b as true\n$a must be one of $b"),
+        Some((InputType::List, InputType::Bool, 3, 19))
+    );
+    assert_eq!(
+        type_error(
+            "given parameters $a\ndefine $b as placeholder string\n$a must be a subset of $b"
+        ),
+        Some((InputType::List, InputType::StringLike, 3, 24))
+    );
+    // Lists are never equal
+    assert_eq!(
+        type_error("given parameters $a\n$a must be equal to [1, 2]"),
+        Some((InputType::Any, InputType::List, 2, 21))
+    );
+    // Conjoined assertions are checked too
+    assert_eq!(
+        type_error("given parameters $a: string\nevery parameter must be greater than 5 or $a must be less than 3"),
+        Some((InputType::Numeric, InputType::StringLike, 2, 43))
+    );
+
+    // Subjects of unknown type are checked at runtime
+    for source in &[
+        "given parameters $a, $b\n$a must be greater than $b",
+        "given parameters $a\nevery parameter must be greater than 5",
+        "given parameters $a\ndefine $b as placeholder\n$a must be equal to $b",
+        "given parameters $a: numeric\n$a must be one of [1, 2]",
+        "given parameters $a: list\n$a must be intersecting with [1, 2]",
+    ] {
+        let ast = parser::parse(source).unwrap();
+        assert!(compiler::compile(&ast).is_ok(), "{}", source);
+    }
+}
+
+#[test]
+fn it_compiles_one_of_both_ways() {
+    let compile = |source: &str| {
+        let ast = parser::parse(source).unwrap();
+        let contract = compiler::compile(&ast).unwrap();
+        let bytecode = contract.bytecode().to_vec();
+        let verdict = |input: u64| {
+            interpreter::interpret(
+                &[
+                    PactType::Numeric(Numeric(input)),
+                    PactType::Numeric(Numeric(2)),
+                ],
+                contract.data_table().as_ref(),
+                contract.bytecode(),
+            )
+        };
+        (bytecode, verdict(1), verdict(3))
+    };
+
+    assert_eq!(
+        compile("given parameters $a, $b\n$a must be one of [1, 2]"),
+        (comp_in(input(0), user(0)), Ok(true), Ok(false))
+    );
+    assert_eq!(
+        compile("given parameters $a, $b\n$a must not be one of [1, 2]"),
+        (comp_nin(input(0), user(0)), Ok(false), Ok(true))
+    );
+    // Chained and quantified assertions are inverted the same way
+    assert_eq!(
+        compile("given parameters $a, $b\n$a must be less than 5 and must not be one of [3]"),
+        (
+            bytecode(&[
+                comp_lt(input(0), user(0)),
+                conj_and(),
+                comp_nin(input(0), user(1))
+            ]),
+            Ok(true),
+            Ok(false)
+        )
+    );
+    let none = Comparator::new(OpComp::IN).quantify(OpQuant::ANY).invert();
+    assert_eq!(
+        compile("given parameters $a, $b\nany parameter must not be one of [3]"),
+        (comp(none, input(0), user(0)), Ok(true), Ok(true))
+    );
+}
+
+#[test]
+fn it_compiles_set_comparators() {
+    let ast = parser::parse(
+        "given parameters $requested, $granted
+         $requested must be a subset of $granted
+         $requested must not be intersecting with [\"admin\", \"root\"]",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.bytecode(),
+        bytecode(&[
+            comp_subset(input(0), input(1)),
+            comp(
+                Comparator::new(OpComp::INTERSECTS).invert(),
+                input(0),
+                user(0)
+            ),
+        ])
+        .as_slice()
+    );
+    assert!(contract
+        .capabilities()
+        .contains(Capabilities::COMP_SUBSET | Capabilities::COMP_INTERSECTS));
+
+    let granted = PactType::List(vec![
+        PactType::StringLike(StringLike(b"read")),
+        PactType::StringLike(StringLike(b"write")),
+    ]);
+    let requested = PactType::List(vec![PactType::StringLike(StringLike(b"read"))]);
+    let result = interpreter::interpret(
+        &[requested, granted],
+        contract.data_table().as_ref(),
+        contract.bytecode(),
+    );
+    assert_eq!(result, Ok(true));
+}
+
+#[test]
+fn it_fails_with_a_user_defined_subset() {
+    let ast = parser::parse(
+        "given parameters $granted
+[1, 2] must be a subset of $granted",
+    )
+    .unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::UserDefinedSubset(ast::Location {
+            line: 2,
+            column: 1
+        }))
+    );
+}
+
+#[test]
+fn it_compiles_quantified_assertions() {
+    let ast = parser::parse(
+        "given parameters $a, $b, $c
+         every parameter must be less than 100
+         any parameter must be one of [1, 2] and every parameter must not be equal to $b",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.bytecode(),
+        bytecode(&[
+            comp(
+                Comparator::new(OpComp::GTE).invert().quantify(OpQuant::ALL),
+                input(0),
+                user(0)
+            ),
+            comp(
+                Comparator::new(OpComp::IN).quantify(OpQuant::ANY),
+                input(0),
+                user(1)
+            ),
+            conj_and(),
+            comp(
+                Comparator::new(OpComp::EQ).invert().quantify(OpQuant::ALL),
+                input(0),
+                input(1)
+            ),
+        ])
+        .as_slice()
+    );
+    assert!(contract.capabilities().contains(Capabilities::QUANTIFIERS));
+
+    let run = |inputs: &[u64]| {
+        let inputs: Vec<PactType> = inputs
+            .iter()
+            .map(|n| PactType::Numeric(Numeric(*n)))
+            .collect();
+        interpreter::interpret(&inputs, contract.data_table().as_ref(), contract.bytecode())
+    };
+    assert_eq!(run(&[2, 5, 99]), Ok(true));
+    assert_eq!(run(&[2, 5, 100]), Ok(false));
+    assert_eq!(run(&[3, 5, 99]), Ok(false));
+    assert_eq!(run(&[2, 5, 5]), Ok(false));
+}
+
+#[test]
+fn it_fails_with_a_misplaced_quantifier() {
+    let ast = parser::parse("given parameters $a\n$a must be equal to every parameter").unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::MisplacedQuantifier(ast::Location {
+            line: 2,
+            column: 21
+        }))
+    );
+}
+
+#[test]
+fn it_compiles_percentages() {
+    let ast = parser::parse(
+        "given parameters $fee, $amount
+         $fee must be less than 0.5 percent of $amount",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.bytecode(),
+        comp_ratio(
+            Comparator::new(OpComp::GTE).invert(),
+            input(0),
+            input(1),
+            user(0)
+        )
+        .as_slice()
+    );
+    assert_eq!(
+        contract.data_table().as_ref(),
+        &[PactType::List(vec![
+            PactType::Numeric(Numeric(5)),
+            PactType::Numeric(Numeric(1_000)),
+        ])]
+    );
+    assert!(contract.capabilities().contains(Capabilities::COMP_RATIO));
+
+    let run = |fee: u64, amount: u64| {
+        let inputs = [
+            PactType::Numeric(Numeric(fee)),
+            PactType::Numeric(Numeric(amount)),
+        ];
+        interpreter::interpret(&inputs, contract.data_table().as_ref(), contract.bytecode())
+    };
+    assert_eq!(run(4, 1_000), Ok(true));
+    assert_eq!(run(5, 1_000), Ok(false));
+    assert_eq!(run(u64::MAX / 201, u64::MAX), Ok(true));
+}
+
+#[test]
+fn it_fails_with_a_misplaced_percentage() {
+    let location = |line, column| ast::Location { line, column };
+    let ast = parser::parse("given parameters $a\n5 must be less than 1 percent of $a").unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::MisplacedPercentage(location(2, 21)))
+    );
+    let ast = parser::parse(
+        "given parameters $a, $b\n$a must be less than 0.000_000_000_000_000_01 percent of $b",
+    )
+    .unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::PercentageOutOfRange(location(2, 22)))
+    );
+    let ast =
+        parser::parse("given parameters $a, $b: string\n$a must be less than 1 percent of $b")
+            .unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::TypeError {
+            expected: ast::InputType::Numeric,
+            found: ast::InputType::StringLike,
+            location: location(2, 35)
+        })
+    );
+}
+
+#[test]
+fn it_compiles_arity_checks() {
+    let ast = parser::parse(
+        "given parameters $a, $b, $c
+         the number of parameters must be equal to 3
+         2 must be less than the number of parameters and $a must be equal to the number of parameters",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.bytecode(),
+        bytecode(&[
+            comp_eq(input(ARITY_INDEX), user(0)),
+            comp_gt(input(ARITY_INDEX), user(1)),
+            conj_and(),
+            comp_eq(input(0), input(ARITY_INDEX)),
+        ])
+        .as_slice()
+    );
+    assert!(contract.capabilities().contains(Capabilities::LOAD_ARITY));
+
+    let three = vec![PactType::Numeric(Numeric(3)); 3];
+    let run = |inputs: &[PactType]| {
+        interpreter::interpret(inputs, contract.data_table().as_ref(), contract.bytecode())
+    };
+    assert_eq!(run(&three), Ok(true));
+    assert_eq!(run(&[three.clone(), three].concat()), Ok(false));
+
+    let ast = parser::parse(
+        "given parameters $a\nthe number of parameters must not be equal to the number of parameters",
+    )
+    .unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::SelfComparison(ast::Location {
+            line: 2,
+            column: 1
+        }))
+    );
+
+    // The arity is never a list
+    for (source, column) in &[
+        (
+            "given parameters $a\n$a must be one of the number of parameters",
+            19,
+        ),
+        (
+            "given parameters $a\n$a must be a subset of the number of parameters",
+            24,
+        ),
+        (
+            "given parameters $a\nthe number of parameters must be intersecting with $a",
+            1,
+        ),
+    ] {
+        let ast = parser::parse(source).unwrap();
+        assert_eq!(
+            compiler::compile(&ast),
+            Err(CompileErr::TypeError {
+                expected: InputType::List,
+                found: InputType::Numeric,
+                location: ast::Location {
+                    line: 2,
+                    column: *column
+                }
+            })
+        );
+    }
+}
+
+#[test]
+fn it_compiles_named_clauses() {
+    let ast = parser::parse(
+        "given parameters $amount, $payee
+         $payee must not be equal to \"mallory\"
+         clause transfer_limit: $amount must be less than 100
+         clause payees: $payee must be one of [\"alice\", \"bob\"]",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.clause_names(),
+        Some(&[(1, "transfer_limit"), (2, "payees")][..])
+    );
+    assert!(contract.capabilities().contains(Capabilities::CLAUSE_NAMES));
+    let mut encoded = Vec::new();
+    contract.encode(&mut encoded).unwrap();
+    assert_eq!(Contract::decode(&encoded).unwrap(), contract);
+
+    let inputs = [
+        PactType::Numeric(Numeric(500)),
+        PactType::StringLike(StringLike(b"alice")),
+    ];
+    assert_eq!(
+        interpreter::interpret_clause(&contract, "payees", &inputs),
+        Ok(true)
+    );
+    assert_eq!(
+        interpreter::interpret_clause(&contract, "transfer_limit", &inputs),
+        Ok(false)
+    );
+    assert_eq!(
+        interpreter::interpret_clause(&contract, "kyc", &inputs),
+        Err(InterpErr::UnknownClause)
+    );
+    assert_eq!(
+        interpreter::interpret_clauses(&contract, &inputs),
+        Ok(vec![
+            (None, true),
+            (Some("transfer_limit"), false),
+            (Some("payees"), true)
+        ])
+    );
+
+    // Unnamed contracts don't carry clause names
+    let ast = parser::parse("given parameters $a\n$a must be less than 100").unwrap();
+    assert_eq!(compiler::compile(&ast).unwrap().clause_names(), None);
+}
+
+#[test]
+fn it_compiles_metadata() {
+    let ast = parser::parse(
+        "meta title \"Spending limit\"
+         meta author \"Treasury\"
+         given parameters $amount
+         meta ticket-id \"OPS-42\"
+         $amount must be less than 100",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    let metadata = contract.metadata().unwrap();
+    assert_eq!(metadata.title, Some("Spending limit"));
+    assert_eq!(metadata.author, Some("Treasury"));
+    assert_eq!(metadata.entries, vec![("ticket-id", "OPS-42")]);
+    assert!(contract.capabilities().contains(Capabilities::METADATA));
+    let mut encoded = Vec::new();
+    contract.encode(&mut encoded).unwrap();
+    assert_eq!(Contract::decode(&encoded).unwrap(), contract);
+
+    // Metadata is ignored by the interpreter
+    let plain = parser::parse("given parameters $amount\n$amount must be less than 100").unwrap();
+    let plain = compiler::compile(&plain).unwrap();
+    assert_eq!(plain.metadata(), None);
+    assert!(contract.equivalent(&plain));
+    assert_eq!(contract.bytecode(), plain.bytecode());
+
+    let ast = parser::parse(
+        "given parameters $amount
+         meta title \"Spending limit\"
+         meta title \"Limit\"
+         $amount must be less than 100",
+    )
+    .unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::DuplicateMetadata(
+            "title".into(),
+            ast::Location {
+                line: 3,
+                column: 10
+            }
+        ))
+    );
+}
+
+#[test]
+fn it_fails_with_a_duplicate_clause_name() {
+    let ast = parser::parse(
+        "given parameters $a
+clause limit: $a must be less than 100
+clause limit: $a must be greater than 1",
+    )
+    .unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::DuplicateClause(ast::Location {
+            line: 3,
+            column: 15
+        }))
+    );
+}
+
+#[test]
+fn it_recompiles_changed_definitions() {
+    let source = |payees: &str, clause: &str| {
+        format!(
+            "given parameters $payee\ndefine $payees as [{}]\n$payee {} $payees",
+            payees, clause
+        )
+    };
+    let original_source = source("\"alice\", \"bob\"", "must be one of");
+    let original_ast = parser::parse(&original_source).unwrap();
+    let original = compiler::compile(&original_ast).unwrap();
+
+    let rotated_source = source("\"carol\"", "must be one of");
+    let rotated_ast = parser::parse(&rotated_source).unwrap();
+    let rotated = compiler::recompile_data(&rotated_ast, &original).unwrap();
+    assert_eq!(rotated.bytecode(), original.bytecode());
+    assert_eq!(
+        rotated.data_table().as_ref(),
+        &[PactType::List(vec![PactType::StringLike(StringLike(
+            b"carol"
+        ))])]
+    );
+
+    let changed_source = source("\"carol\"", "must not be one of");
+    let changed_ast = parser::parse(&changed_source).unwrap();
+    assert_eq!(
+        compiler::recompile_data(&changed_ast, &original),
+        Err(CompileErr::StructureChanged)
+    );
+
+    let renamed_source = original_source
+        .replace("$payee ", "$recipient ")
+        .replace("$payee\n", "$recipient\n");
+    let renamed_ast = parser::parse(&renamed_source).unwrap();
+    assert_eq!(
+        compiler::recompile_data(&renamed_ast, &original),
+        Err(CompileErr::StructureChanged)
+    );
+}
+
+#[test]
+fn it_compiles_a_list_literal_subject() {
+    let ast = parser::parse(
+        "
+          given parameters $a
+          $a must be one of [1, 2, 3]
+        ",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+
+    let result = interpreter::interpret(
+        &[PactType::Numeric(Numeric(2))],
+        contract.data_table().as_ref(),
+        contract.bytecode(),
+    );
+    assert_eq!(result, Ok(true));
+}
+
+#[test]
+fn it_reports_the_location_of_an_undeclared_var() {
+    let ast = parser::parse(
+        "given parameters $a
+$a must be equal to 5 and
+    $a must be less than $b",
+    )
+    .unwrap();
+    let err = compiler::compile(&ast).unwrap_err();
+    assert_eq!(
+        err,
+        CompileErr::UndeclaredVar(
+            "$b".into(),
+            ast::Location {
+                line: 3,
+                column: 26
+            }
+        )
+    );
+    assert_eq!(
+        err.location(),
+        Some(ast::Location {
+            line: 3,
+            column: 26
+        })
+    );
+}
+
+#[test]
+fn it_reports_the_location_of_a_redeclared_var() {
+    let ast = parser::parse(
+        "given parameters $a
+  define $a as 5
+$a must be equal to 5",
+    )
+    .unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::Redeclared(
+            "$a".into(),
+            Some(ast::Location { line: 2, column: 3 })
+        ))
+    );
+}
+
+#[test]
+fn it_fails_with_a_duplicate_parameter() {
+    let ast = parser::parse(
+        "given parameters $a, $b, $a
+         $a must be equal to $b",
+    )
+    .unwrap();
+    let err = compiler::compile(&ast).unwrap_err();
+    assert_eq!(err, CompileErr::Redeclared("$a".into(), None));
+    assert_eq!(err.location(), None);
+}
+
+#[test]
+fn it_compiles_a_multi_line_input_declaration() {
+    let ast = parser::parse(
+        "given parameters $a,
+           $b,
+           $c
+         $c must be greater than $a",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.bytecode(),
+        &bytecode(&[comp_gt(input(2), input(0))])[..]
+    );
+}
+
+#[test]
+fn it_fails_with_a_duplicate_input_declaration() {
+    let ast = vec![
+        ast::Node::InputDeclaration(vec![("$a".into(), InputType::Any)]),
+        ast::Node::InputDeclaration(vec![("$b".into(), InputType::Any)]),
+    ];
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::DuplicateInputDeclaration)
+    );
+    assert!(parser::parse(
+        "given parameters $a
+         given parameters $b
+         $a must be equal to $b"
+    )
+    .is_err());
+}
+
+#[test]
+fn it_fails_with_a_parameter_declared_after_a_definition() {
+    let ast = vec![
+        ast::Node::Definition(
+            "$a".into(),
+            ast::Value::Numeric(5),
+            ast::Location { line: 1, column: 1 },
+        ),
+        ast::Node::InputDeclaration(vec![("$a".into(), InputType::Any)]),
+    ];
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::Redeclared("$a".into(), None))
+    );
+}
+
+#[test]
+fn it_warns_of_unused_declarations_and_shadowed_literals() {
+    let ast = parser::parse(
+        "given parameters $a, $b, $c
+         define $max as 10
+         define $min as 1
+         $a must be less than $max
+         $c must be greater than 1",
+    )
+    .unwrap();
+    let (contract, warnings) = compiler::compile_with_warnings(&ast).unwrap();
+    assert_eq!(contract, compiler::compile(&ast).unwrap());
+    assert_eq!(
+        warnings,
+        vec![
+            Warning::UnusedParameter("$b".into()),
+            Warning::UnusedDefinition(
+                "$min".into(),
+                ast::Location {
+                    line: 3,
+                    column: 10
+                }
+            ),
+            Warning::ShadowedLiteral(
+                "$min".into(),
+                ast::Location {
+                    line: 5,
+                    column: 34
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn it_does_not_warn_of_parameters_read_by_a_quantifier() {
+    let ast = parser::parse(
+        "given parameters $a, $b
+         every parameter must be less than 10",
+    )
+    .unwrap();
+    let (_, warnings) = compiler::compile_with_warnings(&ast).unwrap();
+    assert_eq!(warnings, vec![]);
+}
+
+#[test]
+fn it_compiles_aliased_definitions() {
+    let source = "given parameters $a, $b
+         define $limit as 100
+         define $ceiling as $limit
+         define $cap as $ceiling
+         $a must be less than $cap and $b must be less than $limit";
+    let ast = parser::parse(source).unwrap();
+    let (contract, warnings) = compiler::compile_with_warnings(&ast).unwrap();
+    // Aliases share the aliased data table entry
+    assert_eq!(
+        contract.data_table().as_ref(),
+        &[PactType::Numeric(Numeric(100))]
+    );
+    assert_eq!(
+        contract.bytecode(),
+        &bytecode(&[
+            comp_lt(input(0), user(0)),
+            conj_and(),
+            comp_lt(input(1), user(0))
+        ])[..]
+    );
+    assert_eq!(warnings, vec![]);
+
+    let json = ast::to_json(&ast);
+    assert!(json.contains(r#"{"Alias": ["$ceiling", "$limit", {"line": 3, "column": 10}]}"#));
+    assert_eq!(
+        compiler::compile(&ast::from_json(&json).unwrap()),
+        Ok(contract)
+    );
+}
+
+#[test]
+fn it_fails_with_an_invalid_alias() {
+    let compile = |source| compiler::compile(&parser::parse(source).unwrap()).unwrap_err();
+    let at = |line, column| ast::Location { line, column };
+    assert_eq!(
+        compile(
+            "given parameters $a
+             define $cap as $limit
+             define $limit as 100"
+        ),
+        CompileErr::UndefinedReference("$limit".into(), at(2, 14))
+    );
+    assert_eq!(
+        compile(
+            "given parameters $a
+             define $cap as $a"
+        ),
+        CompileErr::UndefinedReference("$a".into(), at(2, 14))
+    );
+    assert_eq!(
+        compile(
+            "given parameters $a
+             define $cap as $cap"
+        ),
+        CompileErr::CyclicDefinition("$cap".into(), at(2, 14))
+    );
+    assert_eq!(
+        compile(
+            "given parameters $a
+             define $limit as 100
+             define $a as $limit"
+        ),
+        CompileErr::Redeclared("$a".into(), Some(at(3, 14)))
+    );
+}
+
+/// Resolve the libraries shared by the include tests
+fn resolve_library(name: &str) -> Option<Vec<ast::Node>> {
+    let source = match name {
+        "common_lists" => {
+            "define $assets as [16001, 16010]
+             define $treasury as [\"alice\", \"bob\"]"
+        }
+        "limits" => "define $limit as 100\ndefine $daily as $limit",
+        _ => return None,
+    };
+    Some(parser::parse_library(source).unwrap())
+}
+
+#[test]
+fn it_compiles_included_definitions() {
+    let mut ast = parser::parse(
+        "given parameters $asset, $payee, $amount
+         include \"common_lists\"
+         include \"limits\"
+         $asset must be one of $assets and $payee must be one of $treasury
+         $amount must be less than $daily",
+    )
+    .unwrap();
+    let expected = parser::parse(
+        "given parameters $asset, $payee, $amount
+         define $assets as [16001, 16010]
+         define $treasury as [\"alice\", \"bob\"]
+         define $limit as 100
+         $asset must be one of $assets and $payee must be one of $treasury
+         $amount must be less than $limit",
+    )
+    .unwrap();
+    assert_eq!(
+        compiler::compile(&ast),
+        Err(CompileErr::UnresolvedInclude(
+            "common_lists".into(),
+            ast::Location {
+                line: 2,
+                column: 10
+            }
+        ))
+    );
+    assert_eq!(
+        compiler::compile_with_resolver(&mut ast, &resolve_library),
+        compiler::compile(&expected)
+    );
+}
+
+#[test]
+fn it_fails_with_an_invalid_include() {
+    let at = ast::Location {
+        line: 2,
+        column: 10,
+    };
+    let mut ast = parser::parse(
+        "given parameters $a
+         include \"missing\"",
+    )
+    .unwrap();
+    assert_eq!(
+        compiler::compile_with_resolver(&mut ast, &resolve_library),
+        Err(CompileErr::UnresolvedInclude("missing".into(), at))
+    );
+    assert!(ast.is_empty());
+
+    let mut ast = parser::parse(
+        "given parameters $a
+         include \"clauses\"",
+    )
+    .unwrap();
+    let resolver =
+        |_: &str| Some(parser::parse("given parameters $b $b must be equal to 1").unwrap());
+    assert_eq!(
+        compiler::compile_with_resolver(&mut ast, &resolver),
+        Err(CompileErr::InvalidInclude("clauses".into(), at))
+    );
+}
+
+#[test]
+fn it_binds_placeholders() {
+    let ast = parser::parse(
+        "given parameters $amount, $payee
+         define $limit as placeholder numeric
+         define $payees as placeholder
+         $amount must be less than $limit and $payee must be one of $payees",
+    )
+    .unwrap();
+    let template = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        template.placeholders(),
+        vec![("limit", InputType::Numeric), ("payees", InputType::Any)]
+    );
+    assert!(template.capabilities().contains(Capabilities::PLACEHOLDERS));
+    let inputs = [
+        PactType::Numeric(Numeric(5)),
+        PactType::StringLike(StringLike(b"bob")),
+    ];
+    assert_eq!(
+        interpreter::interpret(&inputs, template.data_table().as_ref(), template.bytecode()),
+        Err(InterpErr::UnboundPlaceholder(0))
+    );
+
+    // Templates survive encoding so they can be filled in at issuance
+    let mut encoded = Vec::new();
+    template.encode(&mut encoded).unwrap();
+    let template = Contract::decode(&encoded).unwrap();
+    let bytecode = template.bytecode().to_vec();
+    let payees = PactType::List(vec![PactType::StringLike(StringLike(b"bob"))]);
+    let contract = template
+        .bind(&[("limit", PactType::Numeric(Numeric(10)))])
+        .unwrap()
+        .bind(&[("payees", payees.clone())])
+        .unwrap();
+    assert_eq!(contract.bytecode(), &bytecode[..]);
+    assert!(contract.placeholders().is_empty());
+    assert_eq!(
+        interpreter::interpret(&inputs, contract.data_table().as_ref(), contract.bytecode()),
+        Ok(true)
+    );
+}
+
+#[test]
+fn it_fails_with_invalid_bindings() {
+    let ast = parser::parse(
+        "given parameters $amount
+         define $limit as placeholder numeric
+         $amount must be less than $limit",
+    )
+    .unwrap();
+    let bind = |bindings: &[(&'static str, PactType<'static>)]| {
+        compiler::compile(&ast).unwrap().bind(bindings).unwrap_err()
+    };
+    assert_eq!(
+        bind(&[("max", PactType::Numeric(Numeric(10)))]),
+        BindErr::UnknownPlaceholder("max")
+    );
+    assert_eq!(
+        bind(&[("limit", PactType::Bool(true))]),
+        BindErr::Mismatch {
+            name: "limit",
+            expected: InputType::Numeric
+        }
+    );
+    assert_eq!(
+        bind(&[
+            ("limit", PactType::Numeric(Numeric(10))),
+            ("limit", PactType::Numeric(Numeric(20)))
+        ]),
+        BindErr::UnknownPlaceholder("limit")
+    );
+}
+
+#[test]
+fn it_patches_the_data_table() {
+    let ast = parser::parse(
+        "given parameters $amount, $payee
+         define $payees as [\"alice\", \"bob\"]
+         $amount must be less than 10 and $payee must be one of $payees",
+    )
+    .unwrap();
+    let mut contract = compiler::compile(&ast).unwrap();
+    let inputs = [
+        PactType::Numeric(Numeric(15)),
+        PactType::StringLike(StringLike(b"carol")),
+    ];
+    assert_eq!(
+        interpreter::interpret(&inputs, contract.data_table().as_ref(), contract.bytecode()),
+        Ok(false)
+    );
+
+    assert_eq!(contract.data_table().get_numeric(1), Some(10));
+    assert_eq!(
+        contract.replace_data(1, PactType::Numeric(Numeric(20))),
+        Ok(PactType::Numeric(Numeric(10)))
+    );
+    let payees = PactType::List(vec![PactType::StringLike(StringLike(b"carol"))]);
+    assert!(contract.replace_data(0, payees).is_ok());
+    assert_eq!(
+        interpreter::interpret(&inputs, contract.data_table().as_ref(), contract.bytecode()),
+        Ok(true)
+    );
+
+    // Patches can't change the type of a value or grow the table
+    assert_eq!(
+        contract.replace_data(1, PactType::StringLike(StringLike(b"20"))),
+        Err(PatchErr::TypeMismatch(1))
+    );
+    assert_eq!(
+        contract.replace_data(2, PactType::Numeric(Numeric(20))),
+        Err(PatchErr::MissingIndex(2))
+    );
+    assert_eq!(contract.data_table().len(), 2);
+}
+
+#[test]
+fn it_compiles_equivalent_contracts() {
+    let ast_a = parser::parse(
+        "given parameters $a, $b
+         define $min as 5
+         define $max as 10
+         $a must be greater than $min and $a must be less than $max
+         $b must be greater than $a",
+    )
+    .unwrap();
+    let ast_b = parser::parse(
+        "given parameters $a, $b
+         define $max as 10
+         $a must be greater than 5 and $a must be less than $max
+         $a must be less than $b",
+    )
+    .unwrap();
+    let ast_c = parser::parse(
+        "given parameters $a, $b
+         $a must be greater than 5 and $a must be less than 10
+         $a must be less than or equal to $b",
+    )
+    .unwrap();
+    let a = compiler::compile(&ast_a).unwrap();
+    let b = compiler::compile(&ast_b).unwrap();
+    let c = compiler::compile(&ast_c).unwrap();
+
+    assert_ne!(a, b);
+    assert!(a.equivalent(&b));
+    assert!(!a.equivalent(&c));
+}
+
+#[test]
+fn it_merges_contracts() {
+    let ast_a = parser::parse(
+        "given parameters $amount, $payee
+         $amount must be less than 100
+         $payee must be one of [\"alice\", \"bob\"]",
+    )
+    .unwrap();
+    let ast_b = parser::parse(
+        "given parameters $amount, $payee
+         $payee must not be equal to \"bob\"",
+    )
+    .unwrap();
+    let ast_c = parser::parse(
+        "given parameters $amount, $payee
+         $amount must be equal to 500",
+    )
+    .unwrap();
+    let a = compiler::compile(&ast_a).unwrap();
+    let b = compiler::compile(&ast_b).unwrap();
+    let c = compiler::compile(&ast_c).unwrap();
+
+    let merged = a.merge(b, OpConj::AND).unwrap();
+    assert_eq!(merged.data_table().as_ref().len(), 3);
+    let verdict = |contract: &Contract, amount: u64, payee: &'static str| {
+        let inputs = [
+            PactType::Numeric(Numeric(amount)),
+            PactType::StringLike(StringLike(payee.as_bytes())),
+        ];
+        interpreter::interpret(&inputs, contract.data_table().as_ref(), contract.bytecode())
+            .unwrap()
+    };
+    assert!(verdict(&merged, 50, "alice"));
+    assert!(!verdict(&merged, 50, "bob"));
+    assert!(!verdict(&merged, 150, "alice"));
+
+    // `a` has multiple clauses so it can't be `OR`ed
+    let a = compiler::compile(&ast_a).unwrap();
+    assert_eq!(
+        a.merge(compiler::compile(&ast_c).unwrap(), OpConj::OR),
+        Err(MergeErr::Unrepresentable)
+    );
+
+    let b = compiler::compile(&ast_b).unwrap();
+    let merged = b.merge(c, OpConj::OR).unwrap();
+    assert!(verdict(&merged, 50, "alice"));
+    assert!(verdict(&merged, 500, "bob"));
+    assert!(!verdict(&merged, 50, "bob"));
+}
+
+#[test]
+fn it_interprets_named_inputs() {
+    let ast = parser::parse(
+        "given parameters $amount, $payee
+         $amount must be less than 100 and $payee must be one of [\"alice\", \"bob\"]",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(contract.input_names(), Some(&["amount", "payee"][..]));
+
+    // Names survive the binary format
+    let mut encoded = Vec::<u8>::default();
+    contract.encode(&mut encoded).unwrap();
+    let decoded = Contract::decode(&encoded).unwrap();
+    assert_eq!(decoded, contract);
+
+    let amount = |n| ("amount", PactType::Numeric(Numeric(n)));
+    let payee = || ("payee", PactType::StringLike(StringLike(b"bob")));
+    // Inputs are reordered by name
+    assert_eq!(
+        interpreter::interpret_named(&decoded, &[payee(), amount(99)]),
+        Ok(true)
+    );
+    assert_eq!(
+        interpreter::interpret_named(&decoded, &[amount(100), payee()]),
+        Ok(false)
+    );
+
+    assert_eq!(
+        interpreter::interpret_named(&decoded, &[payee()]),
+        Err(InterpErr::MissingInput(0))
+    );
+    assert_eq!(
+        interpreter::interpret_named(&decoded, &[amount(1), payee(), amount(2)]),
+        Err(InterpErr::UnexpectedInput(2))
+    );
+    assert_eq!(
+        interpreter::interpret_named(&decoded, &[("payer", PactType::Numeric(Numeric(1)))]),
+        Err(InterpErr::UnexpectedInput(0))
+    );
+
+    // Canonical contracts drop their names
+    let canonical = contract.canonicalize().unwrap();
+    assert_eq!(canonical.input_names(), None);
+    assert_eq!(
+        interpreter::interpret_named(&canonical, &[amount(99), payee()]),
+        Err(InterpErr::UnnamedInputs)
+    );
+}
+
+#[test]
+fn it_compiles_an_input_schema() {
+    let ast = parser::parse(
+        "given parameters $amount: numeric, $payee: string, $memo
+         $amount must be less than 100 and $payee must be one of [\"alice\", \"bob\"]",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.input_names(),
+        Some(&["amount", "payee", "memo"][..])
+    );
+    assert_eq!(
+        contract.input_types(),
+        Some(&[InputType::Numeric, InputType::StringLike, InputType::Any][..])
+    );
+
+    // The schema survives the binary format
+    let mut encoded = Vec::<u8>::default();
+    contract.encode(&mut encoded).unwrap();
+    let decoded = Contract::decode(&encoded).unwrap();
+    assert_eq!(decoded, contract);
+
+    let amount = PactType::Numeric(Numeric(99));
+    let payee = PactType::StringLike(StringLike(b"bob"));
+    let memo = PactType::List(vec![]);
+    assert_eq!(
+        decoded.check_inputs(&[amount.clone(), payee.clone(), memo.clone()]),
+        Ok(())
+    );
+    // Swapped inputs are caught before interpretation
+    assert_eq!(
+        decoded.check_inputs(&[payee.clone(), amount.clone(), memo]),
+        Err(SchemaErr::Mismatch {
+            index: 0,
+            expected: InputType::Numeric
+        })
+    );
+    assert_eq!(
+        decoded.check_inputs(&[amount.clone(), payee.clone()]),
+        Err(SchemaErr::InputCount {
+            expected: 3,
+            actual: 2
+        })
+    );
+
+    // Untyped contracts accept any inputs
+    let ast = parser::parse("given parameters $a $a must be equal to 1").unwrap();
+    let untyped = compiler::compile(&ast).unwrap();
+    assert_eq!(untyped.input_types(), None);
+    assert_eq!(untyped.check_inputs(&[payee, amount]), Ok(()));
+}
+
+#[test]
+fn it_compiles_unescaped_string_literals() {
+    let ast = parser::parse(
+        r#"given parameters $a
+           $a must be equal to "\"quoted\"\n\u{e9}""#,
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.data_table().as_ref(),
+        &[PactType::StringLike(StringLike("\"quoted\"\né".as_bytes()))]
+    );
+}
+
+#[test]
+fn it_compiles_bool_literals() {
+    let ast = parser::parse(
+        "given parameters $approved: bool, $trueish
+         define $yes as true
+         $approved must be equal to $yes and $trueish must not be equal to false",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.data_table().as_ref(),
+        &[PactType::Bool(true), PactType::Bool(false)]
+    );
+    assert_eq!(
+        contract.input_types(),
+        Some(&[InputType::Bool, InputType::Any][..])
+    );
+    let verdict = |approved, trueish| {
+        interpreter::interpret(
+            &[PactType::Bool(approved), PactType::Bool(trueish)],
+            contract.data_table().as_ref(),
+            contract.bytecode(),
+        )
+    };
+    assert_eq!(verdict(true, true), Ok(true));
+    assert_eq!(verdict(true, false), Ok(false));
+    assert_eq!(verdict(false, true), Ok(false));
+}
+
+#[test]
+fn it_compiles_a_json_ast() {
+    let source = "given parameters $a: numeric, $b
+         define $trusted as [\"alice\", \"bob\"]
+         clause limit: $a must be less than 100 or $a must be equal to 500
+         $b must be one of $trusted and every parameter must not be equal to 7
+         the number of parameters must be equal to 2";
+    let ast = parser::parse(source).unwrap();
+    let json = ast::to_json(&ast);
+    let decoded = ast::from_json(&json).unwrap();
+    assert_eq!(ast::to_json(&decoded), json);
+    assert_eq!(compiler::compile(&decoded), compiler::compile(&ast));
+
+    // A frontend may build the AST directly
+    let built = ast::from_json(
+        r#"[
+            {"InputDeclaration": [["$a", "Any"]]},
+            {"Clause": {
+                "lhs_subject": {"Identifier": ["$a", {"line": 0, "column": 0}]},
+                "imperative": "MustNotBe",
+                "comparator": "GreaterThan",
+                "rhs_subject": {"Value": [{"Numeric": 10}, {"line": 0, "column": 0}]},
+                "conjoined_assertion": null,
+                "location": {"line": 0, "column": 0}
+            }}
+        ]"#,
+    )
+    .unwrap();
+    assert_eq!(
+        compiler::compile(&built),
+        compiler::compile(
+            &parser::parse("given parameters $a\n$a must not be greater than 10").unwrap()
+        )
+    );
+}
+
+#[test]
+fn it_compiles_source_in_one_step() {
+    use pact::compiler::{CompiledContract, SourceErr};
+    use std::convert::TryFrom;
+
+    let source = "given parameters $a\n$a must be less than 10";
+    let compiled: CompiledContract = source.parse().unwrap();
+    let ast = parser::parse(source).unwrap();
+    let mut expected = Vec::new();
+    compiler::compile(&ast)
+        .unwrap()
+        .encode(&mut expected)
+        .unwrap();
+    assert_eq!(compiled.as_bytes(), &expected[..]);
+    assert_eq!(
+        interpreter::interpret(
+            &[PactType::Numeric(Numeric(5))],
+            compiled.contract().data_table().as_ref(),
+            compiled.contract().bytecode(),
+        ),
+        Ok(true)
+    );
+    assert_eq!(CompiledContract::try_from(source), Ok(compiled.clone()));
+    assert_eq!(CompiledContract::from_source(source), Ok(compiled));
+
+    assert!(matches!(
+        "given parameters".parse::<CompiledContract>(),
+        Err(SourceErr::Parse(_))
+    ));
+    assert_eq!(
+        CompiledContract::from_source("given parameters $a\n$a must be less than $b"),
+        Err(SourceErr::Compile(CompileErr::UndeclaredVar(
+            "$b".into(),
+            ast::Location {
+                line: 2,
+                column: 22
+            }
+        )))
+    );
+}
+
+#[test]
+fn it_reorders_clauses_by_cost() {
+    let source = "given parameters $payee: string, $memo: string, $amount: numeric
+         clause payees: $payee must be one of [\"alice\", \"bob\", \"carol\", \"dave\", \"erin\", \"frank\", \"grace\", \"heidi\"]
+         $memo must not be equal to \"a memo which is long enough to cost more to compare\"
+         clause limit: $amount must be less than 100 and $amount must be greater than 0";
+    let ast = parser::parse(source).unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    let optimised = compiler::compile_with_opts(&ast, compiler::OptLevel::ReorderClauses).unwrap();
+    assert_eq!(
+        optimised.clauses(),
+        vec![
+            (Some("limit"), contract.clauses()[2].1),
+            (None, contract.clauses()[1].1),
+            (Some("payees"), contract.clauses()[0].1),
+        ]
+    );
+    assert_eq!(optimised.data_table(), contract.data_table());
+    assert_eq!(optimised.input_types(), contract.input_types());
+    let verdict = |contract: &Contract, payee: &'static [u8], amount: u64| {
+        let inputs = [
+            PactType::StringLike(StringLike(payee)),
+            PactType::StringLike(StringLike(b"rent")),
+            PactType::Numeric(Numeric(amount)),
+        ];
+        interpreter::interpret(&inputs, contract.data_table().as_ref(), contract.bytecode())
+    };
+    for (payee, amount) in [(&b"alice"[..], 5), (b"alice", 500), (b"mallory", 5)].iter() {
+        assert_eq!(
+            verdict(&optimised, payee, *amount),
+            verdict(&contract, payee, *amount)
+        );
+    }
+    assert_eq!(
+        compiler::compile_with_opts(&ast, compiler::OptLevel::None).unwrap(),
+        contract
+    );
+
+    // Untyped parameters may fail at runtime so their clauses keep source order
+    let ast = parser::parse(
+        "given parameters $payee, $amount
+         $payee must be one of [\"alice\", \"bob\", \"carol\", \"dave\", \"erin\"]
+         $amount must be less than 100",
+    )
+    .unwrap();
+    assert_eq!(
+        compiler::compile_with_opts(&ast, compiler::OptLevel::ReorderClauses).unwrap(),
+        compiler::compile(&ast).unwrap()
+    );
+}
+
+#[test]
+fn it_reorders_conjoined_comparators_by_cost() {
+    let ast = parser::parse(
+        "given parameters $memo: string, $n: numeric
+         $memo must not be equal to \"a memo which is long enough to cost more to compare\" and $n must be equal to 1
+         $memo must be equal to \"a memo which is long enough to cost more to compare\" or $n must be equal to 2 and $n must be less than 9",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    let optimised = compiler::compile_with_opts(&ast, compiler::OptLevel::ReorderClauses).unwrap();
+    assert_eq!(
+        optimised.clauses()[0].1,
+        bytecode(&[
+            comp_eq(input(1), user(1)),
+            conj_and(),
+            comp_neq(input(0), user(0)),
+        ])
+        .as_slice()
+    );
+    // The `OR`ed comparator binds to the comparator before it, so neither moves
+    assert_eq!(optimised.clauses()[1].1, contract.clauses()[1].1);
+    let short_circuit = interpreter::Limits {
+        short_circuit: true,
+        ..Default::default()
+    };
+    for (memo, n) in [(&b"rent"[..], 1), (b"rent", 2), (b"rent", 9)].iter() {
+        let inputs = [
+            PactType::StringLike(StringLike(memo)),
+            PactType::Numeric(Numeric(*n)),
+        ];
+        let verdict = |contract: &Contract| {
+            interpreter::interpret_with_limits(
+                &inputs,
+                contract.data_table().as_ref(),
+                contract.bytecode(),
+                short_circuit,
+            )
+        };
+        assert_eq!(verdict(&optimised), verdict(&contract));
+    }
+}
+
+#[test]
+fn it_reorders_clauses_which_fail_under_limits() {
+    let ast = parser::parse(
+        "given parameters $memo: string, $b: numeric
+         $memo must not be equal to \"a memo which is long enough to cost more to compare\"
+         $b must be equal to 1",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    let optimised = compiler::compile_with_opts(&ast, compiler::OptLevel::ReorderClauses).unwrap();
+    let inputs = [
+        PactType::StringLike(StringLike(b"rent")),
+        PactType::Numeric(Numeric(2)),
+    ];
+    let verdict = |contract: &Contract, limits| {
+        interpreter::interpret_with_limits(
+            &inputs,
+            contract.data_table().as_ref(),
+            contract.bytecode(),
+            limits,
+        )
+    };
+    // The cheap clause rejects before the long literal is compared
+    let strings = interpreter::Limits {
+        max_string_bytes: Some(8),
+        ..Default::default()
+    };
+    assert_eq!(
+        verdict(&contract, strings),
+        Err(InterpErr::StringTooLong(51))
+    );
+    assert_eq!(verdict(&optimised, strings), Ok(false));
+    // The rejecting clause is reached before the OpCode limit
+    let opcodes = interpreter::Limits {
+        max_opcodes: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(
+        verdict(&contract, opcodes),
+        Err(InterpErr::LimitExceeded("max_opcodes"))
+    );
+    assert_eq!(verdict(&optimised, opcodes), Ok(false));
+}
+
+#[test]
+fn it_compiles_fixed_point_comparisons() {
+    let ast = parser::parse(
+        "
+          given parameters $amount: decimal(2), $fee: decimal(2)
+          define $limit as 1.5
+          $amount must be less than or equal to $limit
+          $fee must be less than 1 and must be less than $amount
+        ",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.input_types(),
+        Some(&[InputType::FixedPoint(2), InputType::FixedPoint(2)][..])
+    );
+    assert!(contract
+        .capabilities()
+        .contains(Capabilities::TYPE_FIXED_POINT));
+    let amount = |value| PactType::FixedPoint { value, decimals: 2 };
+    let verdict = |inputs: &[PactType]| {
+        interpreter::interpret(inputs, contract.data_table().as_ref(), contract.bytecode())
+    };
+    // `1.5` is compared as `1.50`
+    assert_eq!(verdict(&[amount(150), amount(99)]), Ok(true));
+    assert_eq!(verdict(&[amount(151), amount(99)]), Ok(false));
+    assert_eq!(verdict(&[amount(150), amount(100)]), Ok(false));
+
+    let scale_mismatch = |source: &str| {
+        let ast = parser::parse(source).unwrap();
+        match compiler::compile(&ast) {
+            Err(CompileErr::ScaleMismatch { decimals, location }) => {
+                Some((decimals, location.line, location.column))
+            }
+            _ => None,
+        }
+    };
+    // Constants are normalised where they're exactly representable
+    assert_eq!(
+        scale_mismatch("given parameters $a: decimal(2)\n$a must be equal to 1.500"),
+        None
+    );
+    assert_eq!(
+        scale_mismatch("given parameters $a: decimal(2)\n$a must be less than 1.125"),
+        Some((2, 2, 22))
+    );
+    assert_eq!(
+        scale_mismatch("given parameters $a: decimal(2)\n1.125 must be greater than $a"),
+        Some((2, 2, 1))
+    );
+    // Parameters must have the same decimals, numerics have none
+    assert_eq!(
+        scale_mismatch("given parameters $a: decimal(2), $b: decimal(6)\n$a must be less than $b"),
+        Some((2, 2, 22))
+    );
+    assert_eq!(
+        scale_mismatch("given parameters $a: decimal(2), $b: numeric\n$b must be equal to $a"),
+        Some((0, 2, 21))
+    );
+    assert!(parser::parse("given parameters $a: decimal(39)\n$a must be equal to 1.0").is_err());
+    assert!(parser::parse("given parameters $a\n$a must be equal to 1.").is_err());
+}
@@ -0,0 +1,170 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! Decompiler integration tests
+
+#![cfg(test)]
+use pact::compiler;
+use pact::decompiler::{self, DecompileErr};
+use pact::interpreter::{Comparator, Conjunction, OpCode, OpComp, OpConj};
+use pact::parser;
+use pact::types::{Contract, DataTable, Numeric, PactType, StringLike, ValidationErr};
+
+/// Assert `source` survives a compile -> decompile -> compile round trip
+fn assert_round_trip(source: &str) {
+    let ast = parser::parse(source).unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+
+    let decompiled = decompiler::decompile(&contract).unwrap();
+    let ast = parser::parse(&decompiled).unwrap();
+    let recompiled = compiler::compile(&ast).unwrap();
+
+    assert_eq!(recompiled, contract, "decompiled:\n{}", decompiled);
+}
+
+#[test]
+fn it_round_trips() {
+    assert_round_trip(
+        "given parameters $amount, $payee
+         define $payees as [\"alice\", \"bob\"]
+         $amount must be less than 100 and $payee must be one of $payees",
+    );
+    assert_round_trip(
+        "given parameters $a, $b, $user
+         define $trusted as [\"Rick Astley\", \"bob\"]
+         $a must be less than or equal to 123 and \"hello world\" must not be equal to $b
+         $user must be one of $trusted
+         $a must not be one of [1, 2, 3] or $a must be greater than or equal to $b",
+    );
+    assert_round_trip(
+        "given parameters $amount: numeric, $payee: string, $memo, $tags: list
+         $amount must be less than 100 and $payee must be equal to \"bob\"",
+    );
+    assert_round_trip(
+        "given parameters $approved: bool
+         $approved must be equal to true",
+    );
+    // Literal LHS subjects are flipped by the compiler
+    assert_round_trip(
+        "given parameters $a
+         5 must be greater than $a
+         10 must be less than $a",
+    );
+    assert_round_trip(
+        "given parameters $amount, $requested, $granted
+         $amount must be greater than 0
+         clause transfer_limit: $amount must be less than 100 or $amount must be equal to 500
+         clause scopes: $requested must be a subset of $granted",
+    );
+    assert_round_trip(
+        "given parameters $a, $b
+         the number of parameters must be equal to 2 and $a must be less than the number of parameters",
+    );
+    assert_round_trip(
+        r#"given parameters $memo
+           $memo must be one of ["say \"hi\"", "C:\\pact", "tab\tline\n", "bell\u{7}", "日本"]"#,
+    );
+    assert_round_trip(
+        "meta title \"Spending \\\"limit\\\"\"
+         given parameters $a
+         meta ticket-id \"OPS-42\"
+         $a must be less than 100",
+    );
+    assert_round_trip(
+        "given parameters $a, $b
+         every parameter must be less than 100 and any parameter must not be equal to $b",
+    );
+    assert_round_trip(
+        "given parameters $amount: decimal(6), $fee
+         $amount must be less than 0.000_150 and $fee must be greater than 12.50",
+    );
+    assert_round_trip(
+        "given parameters $fee, $amount, $tip
+         $fee must be less than 0.5 percent of $amount and $tip must be equal to 10 percent of $fee",
+    );
+}
+
+#[test]
+fn it_decompiles_to_readable_source() {
+    let ast = parser::parse(
+        "given parameters $amount, $payee
+         $amount must be less than 100 and $payee must not be equal to \"eve\"
+         $amount must be greater than 1",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        decompiler::decompile(&contract).unwrap(),
+        "given parameters $amount, $payee
+define $user0 as 100
+define $user1 as \"eve\"
+define $user2 as 1
+$amount must be less than $user0 and $payee must not be equal to $user1
+$amount must be greater than $user2
+"
+    );
+
+    // Without usable input names parameters are numbered
+    let (data_table, bytecode) = contract.into_parts();
+    let unnamed = Contract::from_parts_unchecked(data_table, bytecode);
+    let source = decompiler::decompile(&unnamed).unwrap();
+    assert!(source.starts_with("given parameters $input0, $input1\n"));
+    assert!(source.ends_with("$input0 must be greater than $user2\n"));
+    let clashing = unnamed.with_input_names(vec!["user1", "payee"]);
+    assert_eq!(decompiler::decompile(&clashing).unwrap(), source);
+}
+
+#[test]
+fn it_fails_on_unrepresentable_contracts() {
+    let eq: u8 = 0;
+    let xor: u8 = OpCode::CONJ(Conjunction::new(OpConj::XOR)).into();
+    let contract = Contract::from_parts_unchecked(
+        DataTable::new(vec![PactType::Numeric(Numeric(1))]),
+        vec![eq, 0x00, xor, eq, 0x00],
+    );
+    assert_eq!(
+        decompiler::decompile(&contract),
+        Err(DecompileErr::UnrepresentableConjunction(2))
+    );
+
+    let call: u8 = OpCode::COMP(Comparator::new(OpComp::CALL)).into();
+    let contract = Contract::from_parts_unchecked(
+        DataTable::new(vec![PactType::Numeric(Numeric(1))]),
+        vec![eq, 0x00, call, 0x00],
+    );
+    assert_eq!(
+        decompiler::decompile(&contract),
+        Err(DecompileErr::UnrepresentableComparator(2))
+    );
+
+    let contract = Contract::from_parts_unchecked(
+        DataTable::new(vec![PactType::StringLike(StringLike(b"not utf-8 \xff"))]),
+        vec![eq, 0x00],
+    );
+    assert_eq!(
+        decompiler::decompile(&contract),
+        Err(DecompileErr::UnrepresentableValue(0))
+    );
+
+    let contract = Contract::from_parts_unchecked(DataTable::new(vec![]), vec![eq, 0x00]);
+    assert_eq!(
+        decompiler::decompile(&contract),
+        Err(DecompileErr::Invalid(ValidationErr::MissingIndex {
+            offset: 0,
+            index: 0
+        }))
+    );
+}
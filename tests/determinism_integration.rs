@@ -0,0 +1,177 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! Cross-platform determinism tests
+//! Encoded and compiled bytes are asserted against golden values which must hold on every target
+//! regardless of pointer width (e.g. wasm32 vs. x86_64) or native endianness.
+
+#![cfg(test)]
+use pact::compiler;
+use pact::parser;
+use pact::types::{Contract, DataTable, Numeric, PactType, StringLike};
+
+const SOURCE: &str = "given parameters $amount, $payee
+define $payees as [\"alice\", \"bob\"]
+$amount must be less than 100 and $payee must be one of $payees";
+
+/// Bit-reverse each byte, converting between the wire format and readable header bytes
+fn swap_bits(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().map(|b| b.reverse_bits()).collect()
+}
+
+#[test]
+fn it_compiles_to_golden_bytes() {
+    let ast = parser::parse(SOURCE).unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    let mut encoded = Vec::<u8>::default();
+    contract.encode(&mut encoded).unwrap();
+
+    let mut expected = swap_bits(&[
+        1, // version
+        2, // input name count
+        6, // name length
+    ]);
+    expected.extend(b"amount");
+    expected.extend(swap_bits(&[5]));
+    expected.extend(b"payee");
+    expected.extend(swap_bits(&[
+        2, // data table length
+        2, 12, // List, 12 bytes
+        0, 5, // StringLike, 5 bytes
+    ]));
+    expected.extend(b"alice");
+    expected.extend(swap_bits(&[0, 3]));
+    expected.extend(b"bob");
+    // Numeric(100)
+    expected.extend(swap_bits(&[1, 8, 100, 0, 0, 0, 0, 0, 0, 0]));
+    // bytecode is not bit-reversed
+    expected.extend(&[0x12, 0x01, 0x20, 0x03, 0x10]);
+
+    assert_eq!(encoded, expected);
+}
+
+#[test]
+fn it_compiles_identically_on_repeat() {
+    // The compiler's identifier maps must not leak hash iteration order into the output
+    let encode = || {
+        let ast = parser::parse(SOURCE).unwrap();
+        let mut buf = Vec::<u8>::default();
+        compiler::compile(&ast).unwrap().encode(&mut buf).unwrap();
+        buf
+    };
+    let first = encode();
+    for _ in 0..16 {
+        assert_eq!(encode(), first);
+    }
+}
+
+#[test]
+fn it_encodes_numerics_independent_of_native_endianness() {
+    for n in &[
+        0,
+        1,
+        0xff,
+        0x100,
+        0x0123_4567_89ab_cdef,
+        u64::from(u32::MAX) + 1,
+        u64::MAX,
+    ] {
+        let mut encoded = Vec::<u8>::default();
+        PactType::Numeric(Numeric(*n)).encode(&mut encoded).unwrap();
+
+        // Build the little-endian payload with shifts only, so no native byte order is involved
+        let mut expected = swap_bits(&[1, 8]);
+        expected.extend((0..8).map(|i| ((n >> (8 * i)) as u8).reverse_bits()));
+        assert_eq!(encoded, expected, "numeric: {:#x}", n);
+
+        let (decoded, read) = PactType::decode(&encoded).unwrap();
+        assert_eq!(decoded, PactType::Numeric(Numeric(*n)));
+        assert_eq!(read, 10);
+    }
+}
+
+#[test]
+fn it_encodes_lengths_as_single_bytes() {
+    // Lengths are encoded in one byte on every target, never as a native `usize`
+    let long = [b'x'; 255];
+    let mut encoded = Vec::<u8>::default();
+    PactType::StringLike(StringLike(&long))
+        .encode(&mut encoded)
+        .unwrap();
+    assert_eq!(&encoded[..2], &swap_bits(&[0, 255])[..]);
+    assert_eq!(encoded.len(), 2 + 255);
+
+    let table = DataTable::new(vec![PactType::Numeric(Numeric(1)); 3]);
+    let mut encoded = Vec::<u8>::default();
+    table.encode(&mut encoded).unwrap();
+    assert_eq!(encoded[0], 3u8.reverse_bits());
+    assert_eq!(encoded.len(), 1 + 3 * 10);
+}
+
+#[test]
+fn it_round_trips_golden_bytes() {
+    let mut blob = swap_bits(&[0, 1, 1, 8, 0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0x23, 0x01]);
+    blob.extend(&[0x00, 0x00]);
+
+    let contract = Contract::decode(&blob).unwrap();
+    assert_eq!(
+        contract.data_table().as_ref(),
+        &[PactType::Numeric(Numeric(0x0123_4567_89ab_cdef))]
+    );
+    assert_eq!(contract.bytecode(), vec![0x00, 0x00]);
+
+    let mut encoded = Vec::<u8>::default();
+    contract.encode(&mut encoded).unwrap();
+    assert_eq!(encoded, blob);
+}
+
+#[test]
+fn it_orders_the_data_table_by_first_appearance() {
+    // Many identifiers exercise the compiler's identifier maps, interleaved definitions and
+    // literals fix the data table order
+    let source = "given parameters $e, $d, $c, $b, $a
+define $zulu as 1
+$a must be equal to 2
+define $alpha as 3
+define $mike as [\"x\", \"y\"]
+$b must be equal to $alpha and $c must be equal to $zulu
+$d must be one of $mike and $e must be greater than 4";
+    let ast = parser::parse(source).unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    assert_eq!(
+        contract.data_table().as_ref(),
+        &[
+            PactType::Numeric(Numeric(1)),
+            PactType::Numeric(Numeric(2)),
+            PactType::Numeric(Numeric(3)),
+            PactType::List(vec![
+                PactType::StringLike(StringLike(b"x")),
+                PactType::StringLike(StringLike(b"y")),
+            ]),
+            PactType::Numeric(Numeric(4)),
+        ]
+    );
+    let encode = || {
+        let ast = parser::parse(source).unwrap();
+        let mut buf = Vec::<u8>::default();
+        compiler::compile(&ast).unwrap().encode(&mut buf).unwrap();
+        buf
+    };
+    let first = encode();
+    for _ in 0..256 {
+        assert_eq!(encode(), first);
+    }
+}
@@ -0,0 +1,61 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! Differential tests against other pact implementations
+//! Every `tests/fixtures/*.json` file holds verdict fixtures (see `pact::json::Fixture`) which must
+//! agree with this crate. Fixtures exported by the JS SDK are dropped in alongside those exported
+//! by `pactc fixture`, so codec or comparator divergence fails here before release.
+
+#![cfg(test)]
+use pact::json::{self, Fixture, Verdict};
+use std::fs;
+use std::path::Path;
+
+/// Return this crate's verdict for `fixture`
+fn verdict(fixture: &Fixture) -> Verdict {
+    json::evaluate(&fixture.contract, &fixture.inputs).0
+}
+
+#[test]
+fn it_agrees_with_fixture_verdicts() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut checked = 0;
+    let mut divergent = Vec::new();
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let source = fs::read_to_string(&path).unwrap();
+        let fixtures = json::parse_fixtures(&source)
+            .unwrap_or_else(|err| panic!("{}: {:?}", path.display(), err));
+        for (index, fixture) in fixtures.iter().enumerate() {
+            let actual = verdict(fixture);
+            if actual != fixture.verdict {
+                divergent.push(format!(
+                    "{}[{}]: expected {:?}, got {:?}",
+                    path.display(),
+                    index,
+                    fixture.verdict,
+                    actual
+                ));
+            }
+            checked += 1;
+        }
+    }
+    assert!(checked > 0, "no fixtures found in {}", dir.display());
+    assert!(divergent.is_empty(), "\n{}", divergent.join("\n"));
+}
@@ -0,0 +1,247 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! Denial of service tests
+//! Adversarial programs are interpreted and their running time asserted against a linear weight
+//! model: a cost per OpCode plus a cost per list element scanned by `IN` comparators.
+//! The bounds are generous enough for unoptimized builds on slow CI machines, they catch
+//! super-linear behaviour rather than small regressions.
+//! Release build numbers for each named baseline are printed by
+//! `cargo test --release --test dos_integration -- --ignored --nocapture`
+
+#![cfg(test)]
+use pact::{
+    interpreter::{self, OpCodes},
+    testing::*,
+    types::{LengthWidth, Numeric, PactType, StringLike},
+};
+use std::time::{Duration, Instant};
+
+/// The maximum bytecode length considered, the largest 2 byte length
+const MAX_BYTECODE_LENGTH: usize = 0xffff;
+/// Modelled upper bound on the cost of interpreting one OpCode
+const NANOS_PER_OP: u64 = 20_000;
+/// Modelled upper bound on the cost of comparing one list element
+const NANOS_PER_ELEMENT: u64 = 2_000;
+/// The number of `IN` comparators in list scanning programs
+const LIST_SCANS: usize = 64;
+
+/// A pathological program and the data it runs against
+struct Baseline {
+    name: &'static str,
+    input: Vec<PactType<'static>>,
+    user: Vec<PactType<'static>>,
+    bytecode: Vec<u8>,
+    /// The number of list elements scanned by the program
+    elements: usize,
+}
+
+impl Baseline {
+    /// Return the number of OpCodes in the program
+    fn ops(&self) -> usize {
+        OpCodes::new(&self.bytecode).count()
+    }
+    /// Return the modelled upper bound on the program's running time
+    fn budget(&self) -> Duration {
+        Duration::from_nanos(
+            self.ops() as u64 * NANOS_PER_OP + self.elements as u64 * NANOS_PER_ELEMENT,
+        )
+    }
+    /// Interpret the program, returning its result and running time
+    fn run(&self) -> (bool, Duration) {
+        let start = Instant::now();
+        let result = interpreter::interpret(&self.input, &self.user, &self.bytecode).unwrap();
+        (result, start.elapsed())
+    }
+}
+
+/// One clause of `INPUT(0) == USER(0)` comparators joined by `AND`, of maximum length
+fn conjunction_chain() -> Baseline {
+    let mut ops = vec![comp_eq(input(0), user(0))];
+    while (ops.len() + 2) * 3 / 2 <= MAX_BYTECODE_LENGTH {
+        ops.push(conj_and());
+        ops.push(comp_eq(input(0), user(0)));
+    }
+    Baseline {
+        name: "conjunction_chain",
+        input: vec![PactType::Numeric(Numeric(1))],
+        user: vec![PactType::Numeric(Numeric(1))],
+        bytecode: bytecode(&ops),
+        elements: 0,
+    }
+}
+
+/// Alternating `OR` and `XOR` conjunctions over inverted and input vs. input comparators
+fn alternating_conjunctions() -> Baseline {
+    let mut ops = vec![comp_neq(input(0), input(1))];
+    let mut or = true;
+    while (ops.len() + 2) * 3 / 2 <= MAX_BYTECODE_LENGTH {
+        // `true OR true`, then `true XOR false` so the clause holds
+        if or {
+            ops.push(conj_or());
+            ops.push(comp_gte(input(1), input(0)));
+        } else {
+            ops.push(conj_xor());
+            ops.push(comp_gt(input(0), input(1)));
+        }
+        or = !or;
+    }
+    Baseline {
+        name: "alternating_conjunctions",
+        input: vec![PactType::Numeric(Numeric(1)), PactType::Numeric(Numeric(2))],
+        user: vec![],
+        bytecode: bytecode(&ops),
+        elements: 0,
+    }
+}
+
+/// Many single comparator clauses, each of which holds
+fn clause_chain() -> Baseline {
+    let ops = vec![comp_lte(input(0), user(0)); MAX_BYTECODE_LENGTH / 2];
+    Baseline {
+        name: "clause_chain",
+        input: vec![PactType::Numeric(Numeric(1))],
+        user: vec![PactType::Numeric(Numeric(1_000_000))],
+        bytecode: bytecode(&ops),
+        elements: 0,
+    }
+}
+
+/// `NOT IN` comparators against the largest list of numerics with 2 byte lengths, so every
+/// element is scanned
+fn list_scan() -> Baseline {
+    let list: Vec<PactType> = (0..MAX_BYTECODE_LENGTH as u64 / 11)
+        .map(|n| PactType::Numeric(Numeric(n)))
+        .collect();
+    let elements = list.len() * LIST_SCANS;
+    let list = PactType::List(list);
+    list.encode_as(&mut Vec::new(), LengthWidth::U16)
+        .expect("the list is encodable");
+    Baseline {
+        name: "list_scan",
+        input: vec![PactType::Numeric(Numeric(MAX_BYTECODE_LENGTH as u64))],
+        user: vec![list],
+        bytecode: bytecode(&vec![comp_nin(input(0), user(0)); LIST_SCANS]),
+        elements,
+    }
+}
+
+/// `IN` comparators of a list against a list of lists, where only the last element matches
+fn nested_list_scan() -> Baseline {
+    let needle = || {
+        PactType::List(vec![
+            PactType::StringLike(StringLike(b"needle")),
+            PactType::Numeric(Numeric(0)),
+        ])
+    };
+    let mut haystack: Vec<PactType> = (0..MAX_BYTECODE_LENGTH as u64 / 32)
+        .map(|n| {
+            PactType::List(vec![
+                PactType::StringLike(StringLike(b"needle")),
+                PactType::Numeric(Numeric(n + 1)),
+            ])
+        })
+        .collect();
+    haystack.push(needle());
+    let elements = haystack.len() * LIST_SCANS;
+    let haystack = PactType::List(haystack);
+    haystack
+        .encode_as(&mut Vec::new(), LengthWidth::U16)
+        .expect("the list is encodable");
+    Baseline {
+        name: "nested_list_scan",
+        input: vec![needle()],
+        user: vec![haystack],
+        bytecode: bytecode(&vec![comp_in(input(0), user(0)); LIST_SCANS]),
+        elements,
+    }
+}
+
+fn baselines() -> Vec<Baseline> {
+    vec![
+        conjunction_chain(),
+        alternating_conjunctions(),
+        clause_chain(),
+        list_scan(),
+        nested_list_scan(),
+    ]
+}
+
+/// Assert `baseline` holds and is interpreted within its modelled budget
+fn assert_within_budget(baseline: Baseline) {
+    let (result, elapsed) = baseline.run();
+    assert!(result, "{} should hold", baseline.name);
+    assert!(
+        elapsed <= baseline.budget(),
+        "{} took {:?}, over its budget of {:?}",
+        baseline.name,
+        elapsed,
+        baseline.budget()
+    );
+}
+
+#[test]
+fn it_interprets_a_maximum_length_conjunction_chain_within_budget() {
+    assert_within_budget(conjunction_chain());
+}
+
+#[test]
+fn it_interprets_alternating_conjunctions_within_budget() {
+    assert_within_budget(alternating_conjunctions());
+}
+
+#[test]
+fn it_interprets_a_maximum_length_clause_chain_within_budget() {
+    assert_within_budget(clause_chain());
+}
+
+#[test]
+fn it_scans_the_largest_list_within_budget() {
+    assert_within_budget(list_scan());
+}
+
+#[test]
+fn it_scans_the_largest_nested_list_within_budget() {
+    assert_within_budget(nested_list_scan());
+}
+
+#[test]
+fn baselines_fill_the_bytecode_limit() {
+    for baseline in baselines() {
+        assert!(baseline.bytecode.len() <= MAX_BYTECODE_LENGTH);
+    }
+    assert!(conjunction_chain().bytecode.len() > MAX_BYTECODE_LENGTH - 3);
+    assert!(clause_chain().bytecode.len() > MAX_BYTECODE_LENGTH - 2);
+}
+
+#[test]
+#[ignore]
+fn report_weight_model_baselines() {
+    const RUNS: u32 = 20;
+    for baseline in baselines() {
+        let total: Duration = (0..RUNS).map(|_| baseline.run().1).sum();
+        let mean = total / RUNS;
+        println!(
+            "{}: {:?} mean over {} runs, {} ops, {} list elements, {:.1} ns/op",
+            baseline.name,
+            mean,
+            RUNS,
+            baseline.ops(),
+            baseline.elements,
+            mean.as_nanos() as f64 / (baseline.ops() + baseline.elements) as f64
+        );
+    }
+}
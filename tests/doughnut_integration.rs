@@ -0,0 +1,116 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! Doughnut adapter integration tests
+
+#![cfg(all(test, feature = "doughnut"))]
+use pact::compiler;
+use pact::doughnut::{Doughnut, DoughnutErr, PactDomain};
+use pact::interpreter::InterpErr;
+use pact::parser;
+use pact::types::BinaryFormatErr;
+use pact::verifier::{Decision, PactErr, Verifier};
+
+/// A doughnut with named domains
+struct TestDoughnut(Vec<(&'static str, Vec<u8>)>);
+
+impl Doughnut for TestDoughnut {
+    fn get_domain(&self, domain: &str) -> Option<&[u8]> {
+        self.0
+            .iter()
+            .find(|(name, _)| *name == domain)
+            .map(|(_, payload)| &payload[..])
+    }
+}
+
+fn encoded(source: &str) -> Vec<u8> {
+    let ast = parser::parse(source).unwrap();
+    let mut blob = Vec::new();
+    compiler::compile(&ast).unwrap().encode(&mut blob).unwrap();
+    blob
+}
+
+#[test]
+fn it_verifies_a_domain_contract() {
+    let blob = encoded(
+        "given parameters $amount, $currency\n\
+         $amount must be less than 1000\n\
+         $currency must be equal to \"USD\"",
+    );
+    let doughnut = TestDoughnut(vec![("cennznet", vec![1, 2]), ("pact", blob.clone())]);
+    let domain = PactDomain::new("pact");
+    assert_eq!(domain.contract(&doughnut), Ok(&blob[..]));
+    assert_eq!(
+        domain.verify(&doughnut, (500_u64, "USD")),
+        Ok(Decision::Upheld)
+    );
+    assert!(matches!(
+        domain.verify(&doughnut, (5_000_u64, "USD")),
+        Ok(Decision::Rejected { .. })
+    ));
+    assert_eq!(
+        domain.verify(&doughnut, (-1_i64, "USD")),
+        Err(DoughnutErr::InvalidArguments)
+    );
+    assert_eq!(
+        domain.verify(&doughnut, (500_u64,)),
+        Err(DoughnutErr::Verify(PactErr::Interp(
+            InterpErr::MissingIndex(1)
+        )))
+    );
+}
+
+#[test]
+fn it_extracts_contracts_from_payloads() {
+    // A payload with a one byte tag before the contract
+    let mut payload = vec![7];
+    payload.extend(encoded("given parameters $a\n$a must be equal to 1"));
+    let doughnut = TestDoughnut(vec![("pact", payload)]);
+    let domain = PactDomain::new("pact").with_extractor(|payload| match payload.split_first() {
+        Some((7, blob)) => Some(blob),
+        _ => None,
+    });
+    assert_eq!(domain.verify(&doughnut, (1_u8,)), Ok(Decision::Upheld));
+
+    let untagged = TestDoughnut(vec![("pact", vec![0])]);
+    assert_eq!(
+        domain.verify(&untagged, (1_u8,)),
+        Err(DoughnutErr::MissingContract)
+    );
+    assert_eq!(
+        PactDomain::new("pact").verify(&untagged, (1_u8,)),
+        Err(DoughnutErr::Verify(PactErr::Decode(
+            BinaryFormatErr::TooShort
+        )))
+    );
+    assert_eq!(
+        PactDomain::new("other").verify(&doughnut, (1_u8,)),
+        Err(DoughnutErr::MissingDomain)
+    );
+}
+
+#[test]
+fn it_verifies_with_a_configured_verifier() {
+    let doughnut = TestDoughnut(vec![(
+        "pact",
+        encoded("given parameters $a\n$a must be equal to 1"),
+    )]);
+    let domain = PactDomain::new("pact").with_verifier(Verifier::new().with_max_contract_bytes(1));
+    assert!(matches!(
+        domain.verify(&doughnut, (1_u8,)),
+        Err(DoughnutErr::Verify(PactErr::TooLarge(_)))
+    ));
+}
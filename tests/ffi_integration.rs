@@ -0,0 +1,93 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! C API integration tests
+
+#![cfg(all(test, feature = "ffi"))]
+use pact::ffi::{
+    pact_compile, pact_interpret, pact_last_error, PACT_ERROR, PACT_OK, PACT_REJECTED,
+};
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+fn last_error() -> Option<String> {
+    let message = pact_last_error();
+    if message.is_null() {
+        None
+    } else {
+        Some(unsafe { CStr::from_ptr(message) }.to_string_lossy().into())
+    }
+}
+
+fn compile(source: &str) -> Vec<u8> {
+    let source = CString::new(source).unwrap();
+    let mut len = 0;
+    // Query the required length first
+    let code = unsafe { pact_compile(source.as_ptr(), ptr::null_mut(), 0, &mut len) };
+    assert_eq!(code, PACT_ERROR);
+    assert!(last_error().unwrap().contains("too small"));
+
+    let mut out = vec![0u8; len];
+    let code = unsafe { pact_compile(source.as_ptr(), out.as_mut_ptr(), out.len(), &mut len) };
+    assert_eq!(code, PACT_OK);
+    assert_eq!(last_error(), None);
+    out
+}
+
+fn interpret(contract: &[u8], inputs: &str) -> i32 {
+    let inputs = CString::new(inputs).unwrap();
+    unsafe { pact_interpret(contract.as_ptr(), contract.len(), inputs.as_ptr()) }
+}
+
+#[test]
+fn it_compiles_and_interprets() {
+    let contract = compile(
+        "given parameters $amount, $payee
+         $amount must be less than 100 and $payee must be one of [\"alice\", \"bob\"]",
+    );
+    assert_eq!(interpret(&contract, r#"[99, "bob"]"#), PACT_OK);
+    assert_eq!(interpret(&contract, r#"[100, "bob"]"#), PACT_REJECTED);
+    assert_eq!(last_error(), None);
+}
+
+#[test]
+fn it_reports_errors() {
+    let source = CString::new("given parameters $a $a must be equal to $b").unwrap();
+    let mut out = [0u8; 64];
+    let mut len = 0;
+    let code = unsafe { pact_compile(source.as_ptr(), out.as_mut_ptr(), out.len(), &mut len) };
+    assert_eq!(code, PACT_ERROR);
+    assert_eq!(last_error().unwrap(), "undeclared variable `$b`");
+
+    let contract = compile("given parameters $a $a must be equal to 5");
+    assert_eq!(interpret(&contract, "[\"five\"]"), PACT_ERROR);
+    assert_eq!(last_error().unwrap(), "TypeMismatch");
+    assert_eq!(interpret(&contract, "{}"), PACT_ERROR);
+    assert_eq!(last_error().unwrap(), "ExpectedArray");
+    assert_eq!(interpret(&[1, 0], "[]"), PACT_ERROR);
+    assert_eq!(last_error().unwrap(), "UnsupportedVersion");
+
+    let contract = compile("given parameters $a: numeric $a must be equal to 5");
+    assert_eq!(interpret(&contract, "[\"five\"]"), PACT_ERROR);
+    assert_eq!(
+        last_error().unwrap(),
+        "Mismatch { index: 0, expected: Numeric }"
+    );
+
+    let code = unsafe { pact_interpret(contract.as_ptr(), contract.len(), ptr::null()) };
+    assert_eq!(code, PACT_ERROR);
+    assert_eq!(last_error().unwrap(), "inputs is null");
+}
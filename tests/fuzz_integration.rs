@@ -0,0 +1,68 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! Fuzz regression tests
+//! Inputs under `fuzz/regressions/<target>` are malformed contracts which reach the edge of a
+//! decode path. They're replayed here so they're checked without a nightly toolchain, and can be
+//! passed to `cargo fuzz run <target> fuzz/regressions/<target>` as a seed corpus.
+
+#![cfg(test)]
+use pact::interpreter;
+use pact::types::{Capabilities, Contract, Numeric, PactType};
+use std::fs;
+use std::path::Path;
+
+/// Return the name and bytes of each regression input of fuzz target `target`
+fn regressions(target: &str) -> Vec<(String, Vec<u8>)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fuzz/regressions")
+        .join(target);
+    let mut inputs: Vec<(String, Vec<u8>)> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read(&path).unwrap())
+        })
+        .collect();
+    inputs.sort();
+    inputs
+}
+
+#[test]
+fn it_rejects_contract_regressions() {
+    let inputs = regressions("contract_v0_fuzz");
+    assert!(!inputs.is_empty());
+    let registers = [PactType::Numeric(Numeric(1)), PactType::Numeric(Numeric(2))];
+    for (name, bytes) in inputs {
+        let contract = match Contract::decode(&bytes) {
+            Ok(contract) => contract,
+            Err(_) => continue,
+        };
+        // Contracts which decode have malformed bytecode, which fails validation and evaluation
+        assert!(contract.validate(Capabilities::all()).is_err(), "{}", name);
+        assert!(
+            interpreter::interpret(
+                &registers,
+                contract.data_table().as_ref(),
+                contract.bytecode()
+            )
+            .is_err(),
+            "{}",
+            name
+        );
+    }
+}
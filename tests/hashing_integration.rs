@@ -0,0 +1,75 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! Contract hash tests
+//! The hashes below are pinned: hosts store them, so they must never change between versions.
+
+#![cfg(all(test, feature = "hashing"))]
+use pact::compiler;
+use pact::parser;
+use pact::types::{Contract, DataTable};
+
+fn hash(source: &str) -> String {
+    let ast = parser::parse(source).unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    contract
+        .hash()
+        .unwrap()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[test]
+fn it_hashes_to_pinned_values() {
+    assert_eq!(
+        hash(
+            "given parameters $a, $b
+             $a must be less than 100 and $b must be one of [\"alice\", \"bob\"]"
+        ),
+        "eb1da431d75c21731332664a9f7a6c8c8b2401984631d769b37cb03b5da3a5a0"
+    );
+}
+
+#[test]
+fn it_hashes_equivalent_contracts_equally() {
+    let expected = hash(
+        "given parameters $a, $b
+         $a must be less than 100 and $b must be one of [\"alice\", \"bob\"]",
+    );
+    assert_eq!(
+        hash(
+            "given parameters $a, $b
+             define $names as [\"alice\", \"bob\"]
+             define $limit as 100
+             $a must be less than $limit and $b must be one of $names"
+        ),
+        expected
+    );
+    assert_ne!(
+        hash(
+            "given parameters $a, $b
+             $a must be less than 101 and $b must be one of [\"alice\", \"bob\"]"
+        ),
+        expected
+    );
+}
+
+#[test]
+fn it_does_not_hash_invalid_contracts() {
+    let contract = Contract::from_parts_unchecked(DataTable::new(vec![]), vec![0x00, 0x00]);
+    assert!(contract.hash().is_err());
+}
@@ -18,10 +18,16 @@
 
 #![cfg(test)]
 use pact::{
-    interpreter::{self, InterpErr},
-    interpreter::{Comparator, Conjunction, OpCode, OpComp, OpConj, OpLoad},
-    types::{Numeric, PactType, StringLike},
+    interpreter::{
+        self, AssertionEvent, Executor, HostContext, InterpErr, Limits, ListScan, Observer,
+        Outcome, Program, State,
+    },
+    interpreter::{Comparator, Conjunction, OpCode, OpComp, OpConj, OpLoad, OpQuant, ARITY_INDEX},
+    testing::*,
+    types::{Capabilities, Contract, DataTable, Numeric, PactType, StringLike, Version},
 };
+use std::cmp::Ordering;
+use std::convert::TryFrom;
 
 #[test]
 fn it_does_an_eq_comparison() {
@@ -34,18 +40,37 @@ fn it_does_an_eq_comparison() {
             PactType::Numeric(Numeric(123)),
             PactType::StringLike(StringLike(b"hello world")),
         ],
-        &[
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x00,
-            // INPUT(1) == USER(1)
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x11,
-        ],
+        &bytecode(&[comp_eq(input(0), user(0)), comp_eq(input(1), user(1))]),
     );
 
     assert_eq!(result, Ok(true));
 }
 
+#[test]
+fn it_compares_bools() {
+    let input_data = [PactType::Bool(true)];
+    let user_data = [
+        PactType::Bool(true),
+        PactType::List(vec![PactType::Bool(false)]),
+        PactType::Numeric(Numeric(1)),
+    ];
+    let interpret = |op: OpComp, index: u8| {
+        interpreter::interpret(
+            &input_data,
+            &user_data,
+            &[OpCode::COMP(Comparator::new(op)).into(), index],
+        )
+    };
+    assert_eq!(interpret(OpComp::EQ, 0x00), Ok(true));
+    assert_eq!(interpret(OpComp::IN, 0x01), Ok(false));
+    // Bools are not ordered or interchangeable with numerics
+    assert_eq!(
+        interpret(OpComp::GT, 0x00),
+        Err(InterpErr::BadTypeOperation)
+    );
+    assert_eq!(interpret(OpComp::EQ, 0x02), Err(InterpErr::TypeMismatch));
+}
+
 #[test]
 fn it_does_a_not_eq_comparison() {
     let input = [
@@ -67,11 +92,7 @@ fn it_does_a_lt_comparison_ok() {
     let result = interpreter::interpret(
         &[PactType::Numeric(Numeric(99))],
         &[PactType::Numeric(Numeric(100))],
-        &[
-            // INPUT(1) < USER(1)
-            OpCode::COMP(Comparator::new(OpComp::GTE).invert()).into(),
-            0x00,
-        ],
+        &bytecode(&[comp_lt(input(0), user(0))]),
     );
 
     assert_eq!(result, Ok(true));
@@ -82,11 +103,7 @@ fn it_does_an_lte_comparison_ok() {
     let result = interpreter::interpret(
         &[PactType::Numeric(Numeric(100))],
         &[PactType::Numeric(Numeric(100))],
-        &[
-            // INPUT(1) <= USER(1)
-            OpCode::COMP(Comparator::new(OpComp::GT).invert()).into(),
-            0x00,
-        ],
+        &bytecode(&[comp_lte(input(0), user(0))]),
     );
 
     assert_eq!(result, Ok(true));
@@ -97,11 +114,7 @@ fn it_does_a_gt_comparison_ok() {
     let result = interpreter::interpret(
         &[PactType::Numeric(Numeric(101))],
         &[PactType::Numeric(Numeric(100))],
-        &[
-            // INPUT(1) > USER(1)
-            OpCode::COMP(Comparator::new(OpComp::GT)).into(),
-            0x00,
-        ],
+        &bytecode(&[comp_gt(input(0), user(0))]),
     );
 
     assert_eq!(result, Ok(true));
@@ -112,11 +125,7 @@ fn it_does_a_gte_comparison_ok() {
     let result = interpreter::interpret(
         &[PactType::Numeric(Numeric(100))],
         &[PactType::Numeric(Numeric(100))],
-        &[
-            // INPUT(1) < USER(1)
-            OpCode::COMP(Comparator::new(OpComp::GTE)).into(),
-            0x00,
-        ],
+        &bytecode(&[comp_gte(input(0), user(0))]),
     );
 
     assert_eq!(result, Ok(true));
@@ -162,6 +171,134 @@ fn it_fails_with_invalid_op_code() {
     assert_eq!(result, Err(InterpErr::InvalidOpCode(63)));
 }
 
+#[test]
+fn it_interprets_a_batch_of_input_sets() {
+    // Three clauses, then a malformed OpCode which is only reached when every clause holds
+    let mut source = bytecode(&[
+        comp_eq(input(0), user(0)),
+        comp_eq(input(0), user(0)),
+        comp_gt(input(1), user(1)),
+    ]);
+    source.push(63);
+    let user_data = [
+        PactType::Numeric(Numeric(1)),
+        PactType::Numeric(Numeric(10)),
+    ];
+    let upheld = [
+        PactType::Numeric(Numeric(1)),
+        PactType::Numeric(Numeric(11)),
+    ];
+    let rejected = [
+        PactType::Numeric(Numeric(2)),
+        PactType::Numeric(Numeric(11)),
+    ];
+    let last_rejected = [
+        PactType::Numeric(Numeric(1)),
+        PactType::Numeric(Numeric(10)),
+    ];
+    let missing = [PactType::Numeric(Numeric(1))];
+    let inputs: [&[PactType]; 5] = [&upheld, &rejected, &last_rejected, &missing, &[]];
+
+    let results = interpreter::interpret_batch(&inputs, &user_data, &source);
+    assert_eq!(
+        results,
+        vec![
+            Err(InterpErr::InvalidOpCode(63)),
+            Ok(false),
+            Err(InterpErr::InvalidOpCode(63)),
+            Err(InterpErr::MissingIndex(1)),
+            Err(InterpErr::MissingIndex(0)),
+        ]
+    );
+    for (input_data, result) in inputs.iter().zip(results) {
+        assert_eq!(
+            interpreter::interpret(input_data, &user_data, &source),
+            result
+        );
+    }
+    assert_eq!(
+        interpreter::interpret_batch(&inputs, &user_data, &source[..source.len() - 1]),
+        vec![
+            Ok(true),
+            Ok(false),
+            Ok(false),
+            Err(InterpErr::MissingIndex(1)),
+            Err(InterpErr::MissingIndex(0)),
+        ]
+    );
+}
+
+#[test]
+fn it_interprets_a_program() {
+    let source = bytecode(&[
+        comp_eq(input(0), user(0)),
+        conj_or(),
+        comp_gt(input(1), user(1)),
+    ]);
+    let program = Program::try_from(&source[..]).unwrap();
+    assert_eq!(program.opcodes().len(), 3);
+    let user_data = [
+        PactType::Numeric(Numeric(1)),
+        PactType::Numeric(Numeric(10)),
+    ];
+    for input_data in &[
+        vec![PactType::Numeric(Numeric(1)), PactType::Numeric(Numeric(0))],
+        vec![
+            PactType::Numeric(Numeric(2)),
+            PactType::Numeric(Numeric(11)),
+        ],
+        vec![
+            PactType::Numeric(Numeric(2)),
+            PactType::Numeric(Numeric(10)),
+        ],
+        vec![PactType::Numeric(Numeric(2))],
+    ] {
+        assert_eq!(
+            interpreter::interpret_program(&program, input_data, &user_data),
+            interpreter::interpret(input_data, &user_data, &source)
+        );
+    }
+}
+
+#[test]
+fn it_rejects_invalid_programs_when_loaded() {
+    // Malformed OpCodes are rejected even when execution would never reach them
+    let mut source = bytecode(&[comp_eq(input(0), user(0)), comp_eq(input(0), user(0))]);
+    source.push(63);
+    assert_eq!(
+        Program::try_from(&source[..]),
+        Err(InterpErr::InvalidOpCode(63))
+    );
+    assert_eq!(
+        Program::try_from(&[OpCode::COMP(Comparator::new(OpComp::EQ)).into()][..]),
+        Err(InterpErr::UnexpectedEOI("expected index"))
+    );
+    assert_eq!(
+        Program::try_from(&[][..]),
+        Err(InterpErr::UnexpectedEOI("incomplete operation"))
+    );
+    assert_eq!(
+        Program::try_from(&bytecode(&[comp_eq(input(0), user(0)), conj_and()])[..]),
+        Err(InterpErr::UnexpectedEOI("incomplete operation"))
+    );
+    let conjunction = OpCode::CONJ(Conjunction::new(OpConj::AND));
+    assert_eq!(
+        Program::try_from(&bytecode(&[conj_and(), comp_eq(input(0), user(0))])[..]),
+        Err(InterpErr::UnexpectedOpCode(conjunction.into()))
+    );
+    assert_eq!(
+        Program::try_from(
+            &bytecode(&[
+                comp_eq(input(0), user(0)),
+                conj_and(),
+                conj_and(),
+                comp_eq(input(0), user(0))
+            ])[..]
+        ),
+        Err(InterpErr::UnexpectedOpCode(conjunction.into()))
+    );
+}
+
 #[test]
 fn load_input_fails_with_unexpected_end_of_input() {
     let result = interpreter::interpret(
@@ -184,17 +321,13 @@ fn it_fails_when_comparator_is_not_followed_by_load_indexes() {
 
 #[test]
 fn load_fails_with_missing_index() {
-    let result = interpreter::interpret(
-        &[],
-        &[],
-        &[OpCode::COMP(Comparator::new(OpComp::EQ)).into(), 0x05],
-    );
+    let result = interpreter::interpret(&[], &[], &comp_eq(input(0), user(5)));
     assert_eq!(result, Err(InterpErr::MissingIndex(0)));
 
     let result = interpreter::interpret(
         &[PactType::Numeric(Numeric(101))],
         &[PactType::Numeric(Numeric(101))],
-        &[OpCode::COMP(Comparator::new(OpComp::EQ)).into(), 0x05],
+        &comp_eq(input(0), user(5)),
     );
     assert_eq!(result, Err(InterpErr::MissingIndex(5)));
 }
@@ -207,10 +340,7 @@ fn load_input_to_input_fails_with_missing_index_2() {
             PactType::Numeric(Numeric(123)),
             PactType::Numeric(Numeric(123)),
         ],
-        &[
-            OpCode::COMP(Comparator::new(OpComp::EQ).load(OpLoad::INPUT_VS_INPUT)).into(),
-            0x01,
-        ],
+        &bytecode(&[comp_eq(input(0), input(1))]),
     );
     assert_eq!(result, Err(InterpErr::MissingIndex(1)));
 }
@@ -241,13 +371,11 @@ fn it_does_an_and_conjunction_ok() {
             PactType::Numeric(Numeric(123)),
             PactType::StringLike(StringLike(b"hello world")),
         ],
-        &[
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x00,
-            OpCode::CONJ(Conjunction::new(OpConj::AND)).into(),
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x11,
-        ],
+        &bytecode(&[
+            comp_eq(input(0), user(0)),
+            conj_and(),
+            comp_eq(input(1), user(1)),
+        ]),
     );
     assert_eq!(result, Ok(true));
 }
@@ -263,13 +391,11 @@ fn it_does_an_or_conjunction_ok() {
             PactType::Numeric(Numeric(321)),
             PactType::StringLike(StringLike(b"hello world")),
         ],
-        &[
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x00,
-            OpCode::CONJ(Conjunction::new(OpConj::OR)).into(),
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x11,
-        ],
+        &bytecode(&[
+            comp_eq(input(0), user(0)),
+            conj_or(),
+            comp_eq(input(1), user(1)),
+        ]),
     );
     assert_eq!(result, Ok(true));
 
@@ -282,13 +408,11 @@ fn it_does_an_or_conjunction_ok() {
             PactType::Numeric(Numeric(123)),
             PactType::StringLike(StringLike(b"hello world")),
         ],
-        &[
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x00,
-            OpCode::CONJ(Conjunction::new(OpConj::OR)).into(),
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x11,
-        ],
+        &bytecode(&[
+            comp_eq(input(0), user(0)),
+            conj_or(),
+            comp_eq(input(1), user(1)),
+        ]),
     );
     assert_eq!(result, Ok(true));
 }
@@ -304,13 +428,11 @@ fn it_does_a_xor_conjunction_ok() {
             PactType::Numeric(Numeric(321)),
             PactType::StringLike(StringLike(b"hello world")),
         ],
-        &[
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x00,
-            OpCode::CONJ(Conjunction::new(OpConj::XOR)).into(),
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x11,
-        ],
+        &bytecode(&[
+            comp_eq(input(0), user(0)),
+            conj_xor(),
+            comp_eq(input(1), user(1)),
+        ]),
     );
     assert_eq!(result, Ok(true));
 }
@@ -326,13 +448,11 @@ fn it_does_an_and_conjunction_evaluates_false() {
             PactType::Numeric(Numeric(321)),
             PactType::StringLike(StringLike(b"hello world")),
         ],
-        &[
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x00,
-            OpCode::CONJ(Conjunction::new(OpConj::AND)).into(),
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x11,
-        ],
+        &bytecode(&[
+            comp_eq(input(0), user(0)),
+            conj_and(),
+            comp_eq(input(1), user(1)),
+        ]),
     );
     assert_eq!(result, Ok(false));
 }
@@ -348,13 +468,11 @@ fn it_does_an_or_conjunction_evaluates_false() {
             PactType::Numeric(Numeric(321)),
             PactType::StringLike(StringLike(b"world hello")),
         ],
-        &[
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x00,
-            OpCode::CONJ(Conjunction::new(OpConj::OR)).into(),
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x11,
-        ],
+        &bytecode(&[
+            comp_eq(input(0), user(0)),
+            conj_or(),
+            comp_eq(input(1), user(1)),
+        ]),
     );
     assert_eq!(result, Ok(false));
 }
@@ -370,13 +488,11 @@ fn it_does_a_xor_conjunction_evaluates_false() {
             PactType::Numeric(Numeric(123)),
             PactType::StringLike(StringLike(b"hello world")),
         ],
-        &[
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x00,
-            OpCode::CONJ(Conjunction::new(OpConj::XOR)).into(),
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x11,
-        ],
+        &bytecode(&[
+            comp_eq(input(0), user(0)),
+            conj_xor(),
+            comp_eq(input(1), user(1)),
+        ]),
     );
     assert_eq!(result, Ok(false));
 }
@@ -386,11 +502,7 @@ fn it_fails_with_unexpected_end_of_input_no_rhs_of_conjunction() {
     let result = interpreter::interpret(
         &[PactType::Numeric(Numeric(123))],
         &[PactType::Numeric(Numeric(123))],
-        &[
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x00,
-            OpCode::CONJ(Conjunction::new(OpConj::AND)).into(),
-        ],
+        &bytecode(&[comp_eq(input(0), user(0)), conj_and()]),
     );
     assert_eq!(
         result,
@@ -409,14 +521,12 @@ fn it_does_an_eq_comparison_evaluates_false() {
             PactType::Numeric(Numeric(321)),
             PactType::StringLike(StringLike(b"world hello")),
         ],
-        &[
+        &bytecode(&[
             // EQ LD_INPUT(0) LD_USER(0)
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x00,
+            comp_eq(input(0), user(0)),
             // EQ LD_INPUT(1) LD_USER(1)
-            OpCode::COMP(Comparator::new(OpComp::EQ)).into(),
-            0x11,
-        ],
+            comp_eq(input(1), user(1)),
+        ]),
     );
 
     assert_eq!(result, Ok(false));
@@ -427,10 +537,7 @@ fn it_does_an_lt_comparison_evaluates_false() {
     let result = interpreter::interpret(
         &[PactType::Numeric(Numeric(100))],
         &[PactType::Numeric(Numeric(99))],
-        &[
-            OpCode::COMP(Comparator::new(OpComp::GTE).invert()).into(),
-            0x00,
-        ],
+        &bytecode(&[comp_lt(input(0), user(0))]),
     );
 
     assert_eq!(result, Ok(false));
@@ -441,10 +548,7 @@ fn it_does_an_lte_comparison_evaluates_false() {
     let result = interpreter::interpret(
         &[PactType::Numeric(Numeric(101))],
         &[PactType::Numeric(Numeric(100))],
-        &[
-            OpCode::COMP(Comparator::new(OpComp::GT).invert()).into(),
-            0x00,
-        ],
+        &bytecode(&[comp_lte(input(0), user(0))]),
     );
 
     assert_eq!(result, Ok(false));
@@ -455,7 +559,7 @@ fn it_does_a_gt_comparison_evaluates_false() {
     let result = interpreter::interpret(
         &[PactType::Numeric(Numeric(100))],
         &[PactType::Numeric(Numeric(101))],
-        &[OpCode::COMP(Comparator::new(OpComp::GT)).into(), 0x00],
+        &comp_gt(input(0), user(0)),
     );
 
     assert_eq!(result, Ok(false));
@@ -466,7 +570,7 @@ fn it_does_a_gte_comparison_evaluates_false() {
     let result = interpreter::interpret(
         &[PactType::Numeric(Numeric(100))],
         &[PactType::Numeric(Numeric(101))],
-        &[OpCode::COMP(Comparator::new(OpComp::GTE)).into(), 0x00],
+        &comp_gte(input(0), user(0)),
     );
 
     assert_eq!(result, Ok(false));
@@ -480,18 +584,10 @@ fn it_does_a_numeric_in_comparison() {
         PactType::Numeric(Numeric(2)),
     ])];
 
-    let result = interpreter::interpret(
-        &input_data,
-        &user_data,
-        &[OpCode::COMP(Comparator::new(OpComp::IN)).into(), 0x00],
-    );
+    let result = interpreter::interpret(&input_data, &user_data, &comp_in(input(0), user(0)));
     assert_eq!(result, Ok(true));
 
-    let result = interpreter::interpret(
-        &input_data,
-        &user_data,
-        &[OpCode::COMP(Comparator::new(OpComp::IN)).into(), 0x10],
-    );
+    let result = interpreter::interpret(&input_data, &user_data, &comp_in(input(1), user(0)));
     assert_eq!(result, Ok(false));
 }
 
@@ -506,19 +602,182 @@ fn it_does_a_string_in_comparison() {
         PactType::StringLike(StringLike(b"let you down")),
     ])];
 
-    let result = interpreter::interpret(
+    let result = interpreter::interpret(&input_data, &user_data, &comp_in(input(0), user(0)));
+    assert_eq!(result, Ok(true));
+
+    let result = interpreter::interpret(&input_data, &user_data, &comp_in(input(1), user(0)));
+    assert_eq!(result, Ok(false));
+}
+
+#[test]
+fn it_short_circuits_decided_conjunctions() {
+    let input_data = [PactType::Numeric(Numeric(1))];
+    let user_data = [PactType::Numeric(Numeric(2))];
+    let short_circuit = Limits {
+        short_circuit: true,
+        ..Limits::default()
+    };
+
+    // `false AND ...` and `true OR ...` never load the missing input
+    let and = bytecode(&[
+        comp_eq(input(0), user(0)),
+        conj_and(),
+        comp_eq(input(5), user(0)),
+    ]);
+    let or = bytecode(&[
+        comp_lt(input(0), user(0)),
+        conj_or(),
+        comp_eq(input(5), user(0)),
+    ]);
+    let nand = bytecode(&[
+        comp_eq(input(0), user(0)),
+        conj(Conjunction::new(OpConj::AND).invert()),
+        comp_eq(input(5), user(0)),
+    ]);
+    for (code, expected) in &[(&and, false), (&or, true), (&nand, true)] {
+        assert_eq!(
+            interpreter::interpret_with_limits(&input_data, &user_data, code, short_circuit),
+            Ok(*expected)
+        );
+        assert_eq!(
+            interpreter::interpret(&input_data, &user_data, code),
+            Err(InterpErr::MissingIndex(5))
+        );
+    }
+
+    // Undecided conjunctions and malformed bytecode are still evaluated
+    let xor = bytecode(&[
+        comp_eq(input(0), user(0)),
+        conj_xor(),
+        comp_eq(input(5), user(0)),
+    ]);
+    assert_eq!(
+        interpreter::interpret_with_limits(&input_data, &user_data, &xor, short_circuit),
+        Err(InterpErr::MissingIndex(5))
+    );
+    let mut truncated = and.clone();
+    truncated.pop();
+    assert_eq!(
+        interpreter::interpret_with_limits(&input_data, &user_data, &truncated, short_circuit),
+        Err(InterpErr::UnexpectedEOI("expected index"))
+    );
+}
+
+#[test]
+fn it_compares_strings_in_constant_time() {
+    let constant_time = Limits {
+        constant_time_eq: true,
+        ..Limits::default()
+    };
+    let secret = [PactType::StringLike(StringLike(b"s3cr3t"))];
+    let guesses: [(&[u8], bool); 6] = [
+        (b"s3cr3t", true),
+        (b"s3cr3T", false),
+        (b"S3cr3t", false),
+        (b"s3cr3", false),
+        (b"s3cr3t!", false),
+        (b"", false),
+    ];
+    for (guess, expected) in guesses.iter() {
+        let input_data = [PactType::StringLike(StringLike(guess))];
+        let eq = comp_eq(input(0), user(0));
+        let neq = comp_neq(input(0), user(0));
+        for (code, expected) in &[(eq, *expected), (neq, !expected)] {
+            let result =
+                interpreter::interpret_with_limits(&input_data, &secret, code, constant_time);
+            assert_eq!(result, Ok(*expected));
+            assert_eq!(result, interpreter::interpret(&input_data, &secret, code));
+        }
+        // Input registers are compared the same way
+        let input_data = [secret[0].clone(), PactType::StringLike(StringLike(guess))];
+        let eq = comp_eq(input(0), input(1));
+        assert_eq!(
+            interpreter::interpret_with_limits(&input_data, &[], &eq, constant_time),
+            Ok(*expected)
+        );
+    }
+}
+
+#[test]
+fn it_limits_compared_string_lengths() {
+    let long = [b'x'; 1024];
+    let input_data = [
+        PactType::StringLike(StringLike(&long)),
+        PactType::List(vec![PactType::StringLike(StringLike(&long[..65]))]),
+        PactType::StringLike(StringLike(&long[..64])),
+    ];
+    let user_data = [
+        PactType::StringLike(StringLike(b"short")),
+        PactType::List(vec![PactType::StringLike(StringLike(b"short"))]),
+    ];
+    let limits = Limits {
+        max_string_bytes: Some(64),
+        ..Limits::default()
+    };
+
+    let result = interpreter::interpret_with_limits(
         &input_data,
         &user_data,
-        &[OpCode::COMP(Comparator::new(OpComp::IN)).into(), 0x00],
+        &comp_eq(input(0), user(0)),
+        limits,
     );
-    assert_eq!(result, Ok(true));
+    assert_eq!(result, Err(InterpErr::StringTooLong(1024)));
 
-    let result = interpreter::interpret(
+    let result = interpreter::interpret_with_limits(
+        &input_data,
+        &user_data,
+        &comp_eq(input(2), input(1)),
+        limits,
+    );
+    assert_eq!(result, Err(InterpErr::StringTooLong(65)));
+
+    let result = interpreter::interpret_with_limits(
         &input_data,
         &user_data,
-        &[OpCode::COMP(Comparator::new(OpComp::IN)).into(), 0x10],
+        &comp_in(input(2), user(1)),
+        limits,
     );
     assert_eq!(result, Ok(false));
+
+    // Unlimited by default
+    let result = interpreter::interpret(&input_data, &user_data, &comp_eq(input(0), user(0)));
+    assert_eq!(result, Ok(false));
+}
+
+#[test]
+fn it_limits_bytecode_length_and_opcodes() {
+    let input_data = [PactType::Numeric(Numeric(1))];
+    let user_data = [PactType::Numeric(Numeric(1))];
+    let source = bytecode(&[
+        comp_eq(input(0), user(0)),
+        conj_and(),
+        comp_eq(input(0), user(0)),
+    ]);
+    let limits = |max_bytecode_length, max_opcodes| Limits {
+        max_bytecode_length,
+        max_opcodes,
+        ..Limits::default()
+    };
+    let interpret =
+        |limits| interpreter::interpret_with_limits(&input_data, &user_data, &source, limits);
+
+    assert_eq!(interpret(limits(Some(source.len()), Some(3))), Ok(true));
+    assert_eq!(
+        interpret(limits(Some(source.len() - 1), None)),
+        Err(InterpErr::LimitExceeded("max_bytecode_length"))
+    );
+    assert_eq!(
+        interpret(limits(None, Some(2))),
+        Err(InterpErr::LimitExceeded("max_opcodes"))
+    );
+
+    // OpCodes after a failed clause are not executed, so not counted
+    let source = bytecode(&[comp_eq(input(0), user(0)), comp_gt(input(0), user(0))]);
+    let source = [source.clone(), source].concat();
+    assert_eq!(
+        interpreter::interpret_with_limits(&input_data, &user_data, &source, limits(None, Some(2))),
+        Ok(false)
+    );
 }
 
 #[test]
@@ -530,20 +789,417 @@ fn it_fails_with_lhs_list_for_in_comparison() {
     let user_data = [PactType::Numeric(Numeric(2)), PactType::Numeric(Numeric(5))];
 
     // List in Numeric
-    let result = interpreter::interpret(
+    let result = interpreter::interpret(&input_data, &user_data, &comp_in(input(0), user(0)));
+    assert_eq!(result, Err(InterpErr::BadTypeOperation));
+
+    // List in List is a membership test, not a subset test
+    let result = interpreter::interpret(&input_data, &input_data, &comp_in(input(0), user(0)));
+    assert_eq!(result, Ok(false));
+}
+
+#[test]
+fn it_does_an_in_comparison_with_nested_lists() {
+    let pair = |a, b| {
+        PactType::List(vec![
+            PactType::Numeric(Numeric(a)),
+            PactType::Numeric(Numeric(b)),
+        ])
+    };
+    let input_data = [pair(1, 2), pair(2, 1), PactType::Numeric(Numeric(1))];
+    let user_data = [PactType::List(vec![pair(1, 2), pair(3, 4)])];
+
+    let result = interpreter::interpret(&input_data, &user_data, &comp_in(input(0), user(0)));
+    assert_eq!(result, Ok(true));
+
+    let result = interpreter::interpret(&input_data, &user_data, &comp_in(input(1), user(0)));
+    assert_eq!(result, Ok(false));
+
+    // Elements of nested lists are not members of the outer list
+    let result = interpreter::interpret(&input_data, &user_data, &comp_in(input(2), user(0)));
+    assert_eq!(result, Ok(false));
+}
+
+#[test]
+fn it_does_an_input_vs_input_in_comparison() {
+    let numerics =
+        |ns: &[u64]| PactType::List(ns.iter().map(|n| PactType::Numeric(Numeric(*n))).collect());
+    let input_data = [
+        PactType::Numeric(Numeric(2)),
+        numerics(&[1, 2, 3]),
+        PactType::List(vec![numerics(&[1, 2, 3]), numerics(&[4])]),
+        PactType::Numeric(Numeric(5)),
+    ];
+    let run = |code: &[u8]| interpreter::interpret(&input_data, &[], code);
+
+    // Input vs input membership is the same as input vs user membership
+    assert_eq!(run(&comp_in(input(0), input(1))), Ok(true));
+    assert_eq!(run(&comp_nin(input(3), input(1))), Ok(true));
+    assert_eq!(run(&comp_in(input(3), input(1))), Ok(false));
+    // A list input is checked for membership of a list of lists
+    assert_eq!(run(&comp_in(input(1), input(2))), Ok(true));
+    assert_eq!(run(&comp_in(input(0), input(2))), Ok(false));
+    // The arity may be a member of a list
+    assert_eq!(run(&comp_nin(input(ARITY_INDEX), input(1))), Ok(true));
+    // The RHS must be a list
+    assert_eq!(
+        run(&comp_in(input(0), input(3))),
+        Err(InterpErr::BadTypeOperation)
+    );
+    assert_eq!(
+        run(&comp_in(input(1), input(3))),
+        Err(InterpErr::BadTypeOperation)
+    );
+    // Quantified comparators skip the RHS register
+    let any = Comparator::new(OpComp::IN).quantify(OpQuant::ANY);
+    assert_eq!(run(&comp(any, input(0), input(1))), Ok(true));
+    let all = Comparator::new(OpComp::IN).quantify(OpQuant::ALL);
+    assert_eq!(run(&comp(all, input(1), input(2))), Ok(false));
+}
+
+#[test]
+fn it_rejects_comparators_with_invalid_operands_when_parsed() {
+    let input_data = [
+        PactType::List(vec![PactType::Numeric(Numeric(1))]),
+        PactType::Numeric(Numeric(1)),
+    ];
+    for code in &[
+        // The arity is never a list
+        comp_in(input(1), input(ARITY_INDEX)),
+        comp_nin(input(1), input(ARITY_INDEX)),
+        comp_subset(input(0), input(ARITY_INDEX)),
+        comp_intersects(input(ARITY_INDEX), input(0)),
+        comp_intersects(input(ARITY_INDEX), user(0)),
+        // A list never contains itself
+        comp_in(input(0), input(0)),
+        comp_nin(input(1), input(1)),
+    ] {
+        assert_eq!(
+            interpreter::interpret(&input_data, &input_data, code),
+            Err(InterpErr::InvalidOperands(code[0], code[1]))
+        );
+    }
+    // The same registers are valid operands of other comparators, and arity loads of `IN`
+    for code in &[
+        comp_in(input(ARITY_INDEX), user(0)),
+        comp_in(input(ARITY_INDEX), input(0)),
+        comp_gt(input(1), input(ARITY_INDEX)),
+        comp_intersects(input(0), input(0)),
+    ] {
+        assert!(interpreter::interpret(&input_data, &input_data, code).is_ok());
+    }
+}
+
+#[test]
+fn it_does_an_in_comparison_with_a_sorted_list() {
+    let input_data = [
+        PactType::Numeric(Numeric(70)),
+        PactType::Numeric(Numeric(71)),
+        PactType::StringLike(StringLike(b"70")),
+    ];
+    let user_data = [PactType::sorted_list(
+        (0..100)
+            .map(|n| PactType::Numeric(Numeric(n * 10)))
+            .collect(),
+    )
+    .unwrap()];
+
+    let result =
+        interpreter::interpret_verbose(&input_data, &user_data, &comp_in(input(0), user(0)));
+    assert_eq!(result, Ok(Outcome::Upheld));
+    let result = interpreter::interpret(&input_data, &user_data, &comp_nin(input(1), user(0)));
+    assert_eq!(result, Ok(true));
+    // Other types are never members
+    let result = interpreter::interpret(&input_data, &user_data, &comp_in(input(2), user(0)));
+    assert_eq!(result, Ok(false));
+
+    // Binary search compares a logarithmic number of elements
+    struct Scans(Vec<ListScan>);
+    impl<'a> Observer<'a> for Scans {
+        fn assertion(&mut self, event: &AssertionEvent<'a>) {
+            self.0.extend(event.list_scan);
+        }
+    }
+    let mut scans = Scans(Vec::new());
+    interpreter::interpret_observed(
         &input_data,
         &user_data,
-        &[OpCode::COMP(Comparator::new(OpComp::IN)).into(), 0x00],
-    );
-    assert_eq!(result, Err(InterpErr::BadTypeOperation));
+        &bytecode(&[comp_in(input(0), user(0)), comp_nin(input(1), user(0))]),
+        &mut scans,
+    )
+    .unwrap();
+    assert_eq!(scans.0[0], ListScan::Matched(7));
+    match scans.0[1] {
+        ListScan::Missed(compared) => assert!(compared < 10),
+        scan => panic!("unexpected scan {:?}", scan),
+    }
+}
 
-    // List in List
+#[test]
+fn it_does_a_subset_comparison() {
+    let numerics =
+        |ns: &[u64]| PactType::List(ns.iter().map(|n| PactType::Numeric(Numeric(*n))).collect());
+    let input_data = [
+        numerics(&[3, 1]),
+        numerics(&[1, 4]),
+        numerics(&[]),
+        numerics(&[1]),
+    ];
+    let granted = (1..=3)
+        .map(|n| PactType::Numeric(Numeric(n)))
+        .collect::<Vec<_>>();
+    let user_data = [
+        PactType::List(granted.clone()),
+        PactType::sorted_list(granted).unwrap(),
+    ];
+
+    for list in 0..2 {
+        let result =
+            interpreter::interpret(&input_data, &user_data, &comp_subset(input(0), user(list)));
+        assert_eq!(result, Ok(true));
+        let result =
+            interpreter::interpret(&input_data, &user_data, &comp_subset(input(1), user(list)));
+        assert_eq!(result, Ok(false));
+        // The empty list is a subset of every list
+        let result =
+            interpreter::interpret(&input_data, &user_data, &comp_subset(input(2), user(list)));
+        assert_eq!(result, Ok(true));
+    }
+    let result = interpreter::interpret(&input_data, &user_data, &comp_subset(input(3), input(0)));
+    assert_eq!(result, Ok(true));
+    let result = interpreter::interpret(&input_data, &user_data, &comp_subset(input(0), input(3)));
+    assert_eq!(result, Ok(false));
+}
+
+#[test]
+fn it_does_an_intersects_comparison() {
+    let strings = |ss: &[&'static [u8]]| {
+        PactType::List(
+            ss.iter()
+                .map(|s| PactType::StringLike(StringLike(s)))
+                .collect(),
+        )
+    };
+    let input_data = [
+        strings(&[b"read", b"admin"]),
+        strings(&[b"write"]),
+        strings(&[]),
+    ];
+    let user_data = [
+        strings(&[b"admin", b"root"]),
+        PactType::sorted_list(vec![
+            PactType::StringLike(StringLike(b"root")),
+            PactType::StringLike(StringLike(b"admin")),
+        ])
+        .unwrap(),
+    ];
+
+    for list in 0..2 {
+        let result = interpreter::interpret(
+            &input_data,
+            &user_data,
+            &comp_intersects(input(0), user(list)),
+        );
+        assert_eq!(result, Ok(true));
+        let result = interpreter::interpret(
+            &input_data,
+            &user_data,
+            &comp_intersects(input(1), user(list)),
+        );
+        assert_eq!(result, Ok(false));
+        // The empty list intersects no list
+        let result = interpreter::interpret(
+            &input_data,
+            &user_data,
+            &comp_intersects(input(2), user(list)),
+        );
+        assert_eq!(result, Ok(false));
+    }
     let result = interpreter::interpret(
         &input_data,
-        &input_data,
-        &[OpCode::COMP(Comparator::new(OpComp::IN)).into(), 0x00],
+        &user_data,
+        &comp_intersects(input(0), input(1)),
     );
+    assert_eq!(result, Ok(false));
+}
+
+#[test]
+fn it_fails_set_comparisons_of_non_lists() {
+    let input_data = [
+        PactType::Numeric(Numeric(1)),
+        PactType::List(vec![PactType::Numeric(Numeric(1))]),
+    ];
+    let user_data = [
+        PactType::List(vec![PactType::Numeric(Numeric(1))]),
+        PactType::Numeric(Numeric(1)),
+    ];
+
+    let result = interpreter::interpret(&input_data, &user_data, &comp_subset(input(0), user(0)));
+    assert_eq!(result, Err(InterpErr::BadTypeOperation));
+    let result =
+        interpreter::interpret(&input_data, &user_data, &comp_intersects(input(1), user(1)));
     assert_eq!(result, Err(InterpErr::BadTypeOperation));
+    // Lists may only be checked for membership with `IN`
+    let result = interpreter::interpret(&input_data, &user_data, &comp_gt(input(1), user(0)));
+    assert_eq!(result, Err(InterpErr::BadTypeOperation));
+}
+
+#[test]
+fn it_evaluates_quantified_comparators() {
+    let inputs = [
+        PactType::Numeric(Numeric(1)),
+        PactType::Numeric(Numeric(50)),
+        PactType::Numeric(Numeric(99)),
+    ];
+    let user_data = [
+        PactType::Numeric(Numeric(100)),
+        PactType::Numeric(Numeric(50)),
+    ];
+    let every =
+        |comparator: Comparator, rhs| comp(comparator.quantify(OpQuant::ALL), input(0), user(rhs));
+    let any =
+        |comparator: Comparator, rhs| comp(comparator.quantify(OpQuant::ANY), input(0), user(rhs));
+
+    // every parameter must be less than 100
+    let lt = Comparator::new(OpComp::GTE).invert();
+    assert_eq!(
+        interpreter::interpret(&inputs, &user_data, &every(lt, 0)),
+        Ok(true)
+    );
+    assert_eq!(
+        interpreter::interpret(&inputs, &user_data, &every(lt, 1)),
+        Ok(false)
+    );
+    // any parameter must be equal to 50, and none may be
+    let eq = Comparator::new(OpComp::EQ);
+    assert_eq!(
+        interpreter::interpret(&inputs, &user_data, &any(eq, 1)),
+        Ok(true)
+    );
+    assert_eq!(
+        interpreter::interpret(&inputs, &user_data, &every(eq.invert(), 1)),
+        Ok(false)
+    );
+    assert_eq!(
+        interpreter::interpret(&inputs, &user_data, &any(eq, 0)),
+        Ok(false)
+    );
+    // Without inputs `ALL` holds vacuously and `ANY` fails
+    assert_eq!(
+        interpreter::interpret(&[], &user_data, &every(eq, 0)),
+        Ok(true)
+    );
+    assert_eq!(
+        interpreter::interpret(&[], &user_data, &any(eq, 0)),
+        Ok(false)
+    );
+}
+
+#[test]
+fn it_reports_the_deciding_quantified_register() {
+    let inputs = [
+        PactType::Numeric(Numeric(1)),
+        PactType::Numeric(Numeric(150)),
+        PactType::Numeric(Numeric(200)),
+    ];
+    let user_data = [PactType::Numeric(Numeric(100))];
+    let comparator = Comparator::new(OpComp::GTE).invert().quantify(OpQuant::ALL);
+    match interpreter::interpret_verbose(&inputs, &user_data, &comp(comparator, input(0), user(0)))
+    {
+        Ok(Outcome::Rejected { lhs, rhs, .. }) => {
+            assert_eq!(lhs, &inputs[1]);
+            assert_eq!(rhs, &user_data[0]);
+        }
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+}
+
+#[test]
+fn it_skips_the_rhs_register_of_quantified_comparators() {
+    // every other parameter must be less than or equal to input 1
+    let at_most_max = comp(
+        Comparator::new(OpComp::GT).invert().quantify(OpQuant::ALL),
+        input(0),
+        input(1),
+    );
+    let inputs = [
+        PactType::Numeric(Numeric(3)),
+        PactType::Numeric(Numeric(7)),
+        PactType::Numeric(Numeric(5)),
+    ];
+    assert_eq!(interpreter::interpret(&inputs, &[], &at_most_max), Ok(true));
+    // Quantifiers may start after leading parameters
+    let tail = comp(
+        Comparator::new(OpComp::GT).quantify(OpQuant::ALL),
+        input(1),
+        input(0),
+    );
+    assert_eq!(interpreter::interpret(&inputs, &[], &tail), Ok(true));
+    assert_eq!(interpreter::interpret(&inputs[..1], &[], &tail), Ok(true));
+}
+
+#[test]
+fn it_bounds_quantified_comparators() {
+    let inputs = vec![PactType::Numeric(Numeric(1)); 16];
+    let user_data = [PactType::Numeric(Numeric(1))];
+    let every = comp(
+        Comparator::new(OpComp::EQ).quantify(OpQuant::ALL),
+        input(0),
+        user(0),
+    );
+    assert_eq!(
+        interpreter::interpret(&inputs[..15], &user_data, &every),
+        Ok(true)
+    );
+    assert_eq!(
+        interpreter::interpret(&inputs, &user_data, &every),
+        Err(InterpErr::TooManyInputs(16))
+    );
+}
+
+#[test]
+fn it_loads_the_arity_register() {
+    let inputs = vec![PactType::Numeric(Numeric(3)); 20];
+    let user_data = [
+        PactType::Numeric(Numeric(3)),
+        PactType::Numeric(Numeric(16)),
+    ];
+
+    let exactly_three = comp_eq(input(ARITY_INDEX), user(0));
+    assert_eq!(
+        interpreter::interpret(&inputs[..3], &user_data, &exactly_three),
+        Ok(true)
+    );
+    assert_eq!(
+        interpreter::interpret(&inputs[..2], &user_data, &exactly_three),
+        Ok(false)
+    );
+    assert_eq!(
+        interpreter::interpret(&[], &user_data, &comp_lt(input(ARITY_INDEX), user(0))),
+        Ok(true)
+    );
+    // The arity can be compared with other inputs
+    assert_eq!(
+        interpreter::interpret(
+            &inputs[..3],
+            &user_data,
+            &comp_eq(input(0), input(ARITY_INDEX))
+        ),
+        Ok(true)
+    );
+    // Arities beyond any declared parameter count saturate
+    let at_most_sixteen = comp_gte(input(ARITY_INDEX), user(1));
+    assert_eq!(
+        interpreter::interpret(&inputs[..15], &user_data, &at_most_sixteen),
+        Ok(false)
+    );
+    assert_eq!(
+        interpreter::interpret(&inputs, &user_data, &at_most_sixteen),
+        Ok(true)
+    );
+    assert_eq!(
+        interpreter::interpret(&inputs, &user_data, &comp_eq(input(ARITY_INDEX), user(1))),
+        Ok(true)
+    );
 }
 
 #[test]
@@ -554,11 +1210,7 @@ fn it_does_an_in_comparison_with_a_mixed_list() {
         PactType::Numeric(Numeric(1931)),
     ])];
 
-    let result = interpreter::interpret(
-        &input_data,
-        &user_data,
-        &[OpCode::COMP(Comparator::new(OpComp::IN)).into(), 0x00],
-    );
+    let result = interpreter::interpret(&input_data, &user_data, &comp_in(input(0), user(0)));
     assert_eq!(result, Ok(true));
 }
 
@@ -573,20 +1225,14 @@ fn it_does_a_not_in_comparison() {
     let result = interpreter::interpret(
         &input_data,
         &user_data,
-        &[
-            OpCode::COMP(Comparator::new(OpComp::IN).invert()).into(),
-            0x00,
-        ],
+        &bytecode(&[comp_nin(input(0), user(0))]),
     );
     assert_eq!(result, Ok(false));
 
     let result = interpreter::interpret(
         &input_data,
         &user_data,
-        &[
-            OpCode::COMP(Comparator::new(OpComp::IN).invert()).into(),
-            0x10,
-        ],
+        &bytecode(&[comp_nin(input(1), user(0))]),
     );
     assert_eq!(result, Ok(true));
 }
@@ -608,11 +1254,444 @@ fn it_fails_for_invalid_list_operators() {
     ];
 
     for invalid_code in &invalid_code_set {
-        let result = interpreter::interpret(
+        let result =
+            interpreter::interpret(&input_data, &user_data, &[(*invalid_code).into(), 0x00]);
+        assert_eq!(result, Err(InterpErr::BadTypeOperation));
+    }
+}
+
+#[test]
+fn it_reports_the_rejecting_assertion() {
+    let input_data = [
+        PactType::Numeric(Numeric(123)),
+        PactType::StringLike(StringLike(b"hello world")),
+    ];
+    let user_data = [
+        PactType::Numeric(Numeric(123)),
+        PactType::StringLike(StringLike(b"goodbye")),
+    ];
+    let result = interpreter::interpret_verbose(
+        &input_data,
+        &user_data,
+        &bytecode(&[
+            // Clause 1: INPUT(0) == USER(0)
+            comp_eq(input(0), user(0)),
+            // Clause 2: INPUT(1) == USER(1) AND INPUT(0) == USER(0)
+            comp_eq(input(1), user(1)),
+            conj_and(),
+            comp_eq(input(0), user(0)),
+        ]),
+    );
+    assert_eq!(
+        result,
+        Ok(Outcome::Rejected {
+            opcode_offset: 2,
+            comparator: Comparator::new(OpComp::EQ).indices(1, 1),
+            lhs: &input_data[1],
+            rhs: &user_data[1],
+        })
+    );
+}
+
+#[test]
+fn it_reports_the_last_assertion_of_a_clause_without_a_false_assertion() {
+    let input_data = [PactType::Numeric(Numeric(123))];
+    let user_data = [PactType::Numeric(Numeric(123))];
+    let result = interpreter::interpret_verbose(
+        &input_data,
+        &user_data,
+        &bytecode(&[
+            comp_eq(input(0), user(0)),
+            conj_xor(),
+            comp_gte(input(0), user(0)),
+        ]),
+    );
+    assert_eq!(
+        result,
+        Ok(Outcome::Rejected {
+            opcode_offset: 3,
+            comparator: Comparator::new(OpComp::GTE),
+            lhs: &input_data[0],
+            rhs: &user_data[0],
+        })
+    );
+
+    let result =
+        interpreter::interpret_verbose(&input_data, &user_data, &comp_eq(input(0), user(0)));
+    assert_eq!(result, Ok(Outcome::Upheld));
+}
+
+#[test]
+fn it_reports_decisions_to_an_observer() {
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Assertion(usize, bool),
+        Clause(usize, bool),
+    }
+    struct Recorder(Vec<Event>);
+    impl<'a> Observer<'a> for Recorder {
+        fn assertion(&mut self, event: &AssertionEvent<'a>) {
+            self.0
+                .push(Event::Assertion(event.opcode_offset, event.result));
+        }
+        fn clause(&mut self, opcode_offset: usize, upheld: bool) {
+            self.0.push(Event::Clause(opcode_offset, upheld));
+        }
+    }
+
+    let input_data = [
+        PactType::Numeric(Numeric(123)),
+        PactType::StringLike(StringLike(b"hello world")),
+    ];
+    let user_data = [
+        PactType::Numeric(Numeric(123)),
+        PactType::StringLike(StringLike(b"goodbye")),
+    ];
+    let mut recorder = Recorder(Vec::new());
+    let result = interpreter::interpret_observed(
+        &input_data,
+        &user_data,
+        &bytecode(&[
+            // Clause 1: INPUT(0) == USER(0)
+            comp_eq(input(0), user(0)),
+            // Clause 2: INPUT(1) == USER(1) AND INPUT(0) == USER(0)
+            comp_eq(input(1), user(1)),
+            conj_and(),
+            comp_eq(input(0), user(0)),
+            // Clause 3 is never evaluated
+            comp_eq(input(0), user(0)),
+        ]),
+        &mut recorder,
+    );
+    assert!(matches!(
+        result,
+        Ok(Outcome::Rejected {
+            opcode_offset: 2,
+            ..
+        })
+    ));
+    assert_eq!(
+        recorder.0,
+        vec![
+            Event::Assertion(0, true),
+            Event::Clause(0, true),
+            Event::Assertion(2, false),
+            Event::Assertion(5, true),
+            Event::Clause(2, false),
+        ]
+    );
+}
+
+#[test]
+fn it_reports_list_scans_to_an_observer() {
+    struct Scans(Vec<Option<ListScan>>);
+    impl<'a> Observer<'a> for Scans {
+        fn assertion(&mut self, event: &AssertionEvent<'a>) {
+            self.0.push(event.list_scan);
+        }
+    }
+
+    let input_data = [
+        PactType::StringLike(StringLike(b"bob")),
+        PactType::StringLike(StringLike(b"eve")),
+    ];
+    let user_data = [PactType::List(vec![
+        PactType::StringLike(StringLike(b"alice")),
+        PactType::StringLike(StringLike(b"bob")),
+        PactType::StringLike(StringLike(b"carol")),
+    ])];
+    let mut scans = Scans(Vec::new());
+    let result = interpreter::interpret_observed(
+        &input_data,
+        &user_data,
+        &bytecode(&[
+            // INPUT(0) IN USER(0) AND INPUT(1) NOT IN USER(0) AND INPUT(0) == INPUT(0)
+            comp_in(input(0), user(0)),
+            conj_and(),
+            comp_nin(input(1), user(0)),
+            conj_and(),
+            comp_eq(input(0), input(0)),
+        ]),
+        &mut scans,
+    );
+    assert_eq!(result, Ok(Outcome::Upheld));
+    assert_eq!(
+        scans.0,
+        vec![Some(ListScan::Matched(1)), Some(ListScan::Missed(3)), None]
+    );
+}
+
+/// Host predicates for tests, `0` is "is a council member"
+struct Council(&'static [&'static [u8]]);
+
+impl HostContext for Council {
+    fn call(&self, index: u64, arg: &PactType) -> Result<bool, InterpErr> {
+        match (index, arg) {
+            (0, PactType::StringLike(s)) => Ok(self.0.contains(&s.0)),
+            (0, _) => Err(InterpErr::TypeMismatch),
+            _ => Err(InterpErr::UnknownHostFn(index)),
+        }
+    }
+}
+
+#[test]
+fn it_calls_host_predicates() {
+    let council = Council(&[b"alice", b"bob"]);
+    let user_data = [PactType::Numeric(Numeric(0)), PactType::Numeric(Numeric(7))];
+    let mut bytecode = Vec::new();
+    // INPUT(0) CALL USER(0) AND INPUT(1) NOT CALL USER(0)
+    OpCode::COMP(Comparator::new(OpComp::CALL))
+        .compile(&mut bytecode)
+        .unwrap();
+    OpCode::CONJ(Conjunction::new(OpConj::AND))
+        .compile(&mut bytecode)
+        .unwrap();
+    OpCode::COMP(Comparator::new(OpComp::CALL).invert().indices(1, 0))
+        .compile(&mut bytecode)
+        .unwrap();
+    assert_eq!(bytecode, vec![0x04, 0x00, 0x20, 0x14, 0x10]);
+
+    let verdict = |signer: &'static [u8], payee: &'static [u8]| {
+        let input_data = [
+            PactType::StringLike(StringLike(signer)),
+            PactType::StringLike(StringLike(payee)),
+        ];
+        interpreter::interpret_with_context(&input_data, &user_data, &bytecode, &council)
+    };
+    assert_eq!(verdict(b"alice", b"carol"), Ok(true));
+    assert_eq!(verdict(b"alice", b"bob"), Ok(false));
+    assert_eq!(verdict(b"carol", b"dave"), Ok(false));
+
+    // Predicate errors and unregistered predicates fail the contract
+    let input_data = [PactType::Numeric(Numeric(1))];
+    assert_eq!(
+        interpreter::interpret_with_context(
             &input_data,
             &user_data,
-            &[invalid_code.clone().into(), 0x00],
-        );
-        assert_eq!(result, Err(InterpErr::BadTypeOperation));
+            &comp_call(input(0), user(0)),
+            &council
+        ),
+        Err(InterpErr::TypeMismatch)
+    );
+    assert_eq!(
+        interpreter::interpret_with_context(
+            &input_data,
+            &user_data,
+            &comp_call(input(0), user(1)),
+            &council
+        ),
+        Err(InterpErr::UnknownHostFn(7))
+    );
+    // The predicate number must be numeric
+    let user_data = [PactType::StringLike(StringLike(b"0"))];
+    assert_eq!(
+        interpreter::interpret_with_context(
+            &input_data,
+            &user_data,
+            &comp_call(input(0), user(0)),
+            &council
+        ),
+        Err(InterpErr::BadTypeOperation)
+    );
+    // Without a host context nothing is registered
+    assert_eq!(
+        interpreter::interpret(
+            &input_data,
+            &[PactType::Numeric(Numeric(0))],
+            &comp_call(input(0), user(0))
+        ),
+        Err(InterpErr::UnknownHostFn(0))
+    );
+}
+
+#[test]
+fn it_executes_step_wise() {
+    let input_data = [PactType::Numeric(Numeric(5))];
+    let user_data = [
+        PactType::Numeric(Numeric(1)),
+        PactType::Numeric(Numeric(10)),
+    ];
+    let source = bytecode(&[
+        comp_gt(input(0), user(0)),
+        conj_and(),
+        comp_lt(input(0), user(1)),
+    ]);
+    let mut executor = Executor::new(&input_data, &user_data, &source);
+    assert!(matches!(executor.state(), State::Initial));
+
+    let mut gas = 0;
+    while let Some((offset, _op)) = executor.step().expect("it steps") {
+        gas += 1;
+        if gas == 2 {
+            assert_eq!(offset, 2);
+            assert!(matches!(executor.state(), State::Conjunctive { .. }));
+        }
     }
+    assert_eq!(gas, 3);
+    assert!(executor.is_finished());
+    assert_eq!(executor.step(), Ok(None));
+    assert_eq!(executor.verdict(), Ok(true));
+}
+
+#[test]
+fn it_stops_stepping_after_a_failed_clause() {
+    let input_data = [PactType::Numeric(Numeric(5))];
+    let user_data = [PactType::Numeric(Numeric(10))];
+    let source = bytecode(&[comp_gt(input(0), user(0)), comp_lt(input(0), user(0))]);
+    let mut executor = Executor::new(&input_data, &user_data, &source);
+    executor.step().expect("it steps");
+    assert!(matches!(executor.state(), State::AssertionFalse));
+    // The next clause fails the contract without being evaluated
+    assert_eq!(
+        executor.step().expect("it steps").map(|(offset, _)| offset),
+        Some(2)
+    );
+    assert!(matches!(executor.state(), State::Failed));
+    assert_eq!(executor.step(), Ok(None));
+    assert_eq!(executor.verdict(), Ok(false));
+}
+
+#[test]
+fn it_stops_stepping_on_an_error() {
+    let user_data = [PactType::Numeric(Numeric(1))];
+    let source = bytecode(&[comp_eq(input(0), user(0))]);
+    let mut executor = Executor::new(&[], &user_data, &source);
+    assert_eq!(executor.step(), Err(InterpErr::MissingIndex(0)));
+    // Errors are final
+    assert_eq!(executor.step(), Err(InterpErr::MissingIndex(0)));
+    assert_eq!(executor.verdict(), Err(InterpErr::MissingIndex(0)));
+    assert!(executor.is_finished());
+}
+
+#[test]
+fn it_refuses_unsupported_features() {
+    let input_data = [
+        PactType::List(vec![PactType::Numeric(Numeric(1))]),
+        PactType::Numeric(Numeric(1)),
+    ];
+    let user_data = [
+        PactType::Numeric(Numeric(1)),
+        PactType::List(vec![PactType::Numeric(Numeric(1))]),
+    ];
+    let source = bytecode(&[
+        comp_gte(input(1), user(0)),
+        conj_or(),
+        comp_subset(input(0), user(1)),
+    ]);
+    // A v0.1 interpreter doesn't support `SUBSET`, which is only reached at the third OpCode
+    let v0_1 = Capabilities::supported_by(Version::new(0, 1, 0));
+    let mut executor = Executor::new(&input_data, &user_data, &source).with_features(v0_1);
+    executor.step().expect("it steps");
+    executor.step().expect("it steps");
+    assert_eq!(
+        executor.step(),
+        Err(InterpErr::UnsupportedFeature(Capabilities::COMP_SUBSET))
+    );
+
+    let contract = Contract::new(DataTable::new(user_data.to_vec()), source).unwrap();
+    assert_eq!(
+        interpreter::interpret_with_features(&contract, &input_data, v0_1),
+        Err(InterpErr::UnsupportedFeature(Capabilities::COMP_SUBSET))
+    );
+    assert_eq!(
+        interpreter::interpret_with_features(&contract, &input_data, Capabilities::all()),
+        Ok(true)
+    );
+}
+
+#[test]
+fn it_refuses_contracts_declaring_unknown_features() {
+    let contract = Contract::new(
+        DataTable::new(vec![PactType::Numeric(Numeric(1))]),
+        bytecode(&[comp_eq(input(0), user(0))]),
+    )
+    .unwrap()
+    .with_declared_features();
+    let mut encoded = Vec::new();
+    contract.encode(&mut encoded).unwrap();
+    assert_eq!(Contract::decode(&encoded).unwrap(), contract);
+
+    // A later crate version declares a feature this build doesn't know
+    let unknown = Capabilities::from_bits(1 << 31);
+    encoded[4] |= 1;
+    let contract = Contract::decode(&encoded).unwrap();
+    assert!(contract.required_features().contains(unknown));
+    let inputs = [PactType::Numeric(Numeric(1))];
+    assert_eq!(
+        interpreter::interpret_with_features(&contract, &inputs, Capabilities::all()),
+        Err(InterpErr::UnsupportedFeature(unknown))
+    );
+    assert_eq!(
+        interpreter::interpret_clauses(&contract, &inputs),
+        Err(InterpErr::UnsupportedFeature(unknown))
+    );
+}
+
+#[test]
+fn it_has_no_verdict_part_way_through_an_assertion() {
+    let input_data = [PactType::Numeric(Numeric(5))];
+    let user_data = [PactType::Numeric(Numeric(1))];
+    let source = bytecode(&[comp_gt(input(0), user(0)), conj_or()]);
+    let mut executor = Executor::new(&input_data, &user_data, &source);
+    executor.step().expect("it steps");
+    assert_eq!(executor.verdict(), Ok(true));
+    executor.step().expect("it steps");
+    assert_eq!(
+        executor.verdict(),
+        Err(InterpErr::UnexpectedEOI("incomplete operation"))
+    );
+}
+
+#[test]
+fn it_compares_fixed_points_by_value() {
+    let fixed_point = |value, decimals| PactType::FixedPoint { value, decimals };
+    let verdict =
+        |lhs: PactType, rhs: PactType, code: Vec<u8>| interpreter::interpret(&[lhs], &[rhs], &code);
+    // Scales are normalised, numerics have no decimals
+    let cases = [
+        (fixed_point(15, 1), fixed_point(150, 2), Ordering::Equal),
+        (fixed_point(151, 2), fixed_point(15, 1), Ordering::Greater),
+        (
+            PactType::Numeric(Numeric(2)),
+            fixed_point(1999, 3),
+            Ordering::Greater,
+        ),
+        (
+            fixed_point(2000, 3),
+            PactType::Numeric(Numeric(2)),
+            Ordering::Equal,
+        ),
+        (
+            fixed_point(u128::MAX, 0),
+            fixed_point(1, 38),
+            Ordering::Greater,
+        ),
+    ];
+    for (lhs, rhs, order) in cases.iter() {
+        let eq = verdict(lhs.clone(), rhs.clone(), comp_eq(input(0), user(0)));
+        let gt = verdict(lhs.clone(), rhs.clone(), comp_gt(input(0), user(0)));
+        let lt = verdict(lhs.clone(), rhs.clone(), comp_lt(input(0), user(0)));
+        assert_eq!(eq, Ok(*order == Ordering::Equal));
+        assert_eq!(gt, Ok(*order == Ordering::Greater));
+        assert_eq!(lt, Ok(*order == Ordering::Less));
+    }
+
+    assert_eq!(
+        verdict(
+            fixed_point(1, 0),
+            PactType::StringLike(StringLike(b"1")),
+            comp_eq(input(0), user(0))
+        ),
+        Err(InterpErr::TypeMismatch)
+    );
+    // List membership compares values exactly
+    let list = PactType::List(vec![fixed_point(15, 1), PactType::Numeric(Numeric(2))]);
+    assert_eq!(
+        verdict(fixed_point(15, 1), list.clone(), comp_in(input(0), user(0))),
+        Ok(true)
+    );
+    assert_eq!(
+        verdict(fixed_point(150, 2), list, comp_in(input(0), user(0))),
+        Ok(false)
+    );
 }
@@ -0,0 +1,52 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! Parallel batch interpretation tests
+
+#![cfg(all(test, feature = "parallel"))]
+use pact::interpreter::{self, InterpErr};
+use pact::testing::*;
+use pact::types::{Numeric, PactType};
+
+#[test]
+fn it_interprets_a_batch_in_parallel() {
+    let source = bytecode(&[
+        comp_gte(input(0), user(0)),
+        conj_and(),
+        comp_lt(input(1), user(1)),
+    ]);
+    let user_data = [
+        PactType::Numeric(Numeric(10)),
+        PactType::Numeric(Numeric(20)),
+    ];
+    let input_sets: Vec<Vec<PactType>> = (0..1000)
+        .map(|i| {
+            (0..i % 3)
+                .map(|j| PactType::Numeric(Numeric(i / 10 + j)))
+                .collect()
+        })
+        .collect();
+    let inputs: Vec<&[PactType]> = input_sets.iter().map(Vec::as_slice).collect();
+
+    let results = interpreter::interpret_batch_par(&inputs, &user_data, &source);
+    assert_eq!(
+        results,
+        interpreter::interpret_batch(&inputs, &user_data, &source)
+    );
+    assert!(results.contains(&Ok(true)));
+    assert!(results.contains(&Ok(false)));
+    assert!(results.contains(&Err(InterpErr::MissingIndex(1))));
+}
@@ -17,7 +17,8 @@
 //! Parser integration tests
 
 #![cfg(test)]
-use pact::parser;
+use pact::compiler;
+use pact::parser::{self, ast};
 
 #[test]
 fn it_parses() {
@@ -51,6 +52,162 @@ fn it_parses() {
     println!("{:?}", ast);
 }
 
+#[test]
+fn it_skips_comments() {
+    let commented = parser::parse(
+        "// a heavily commented contract
+        given parameters /* the payee */ $a, // the amount
+          $b
+        /* the
+           treasury */
+        define $treasury as /* members */ [\"alice\", /* former */ \"bob\"] // end
+        clause limit: $b must be less than /* cents */ 100 and // or else
+          $a must be one of $treasury
+        // trailing comment without a newline",
+    )
+    .unwrap();
+    let bare = parser::parse(
+        "given parameters $a, $b
+        define $treasury as [\"alice\", \"bob\"]
+        clause limit: $b must be less than 100 and $a must be one of $treasury",
+    )
+    .unwrap();
+    assert_eq!(commented.len(), bare.len());
+    assert_eq!(compiler::compile(&commented), compiler::compile(&bare));
+}
+
+#[test]
+fn it_fails_with_an_unterminated_block_comment() {
+    assert!(parser::parse("given parameters $a /* $a must be less than 5").is_err());
+}
+
+#[test]
+fn it_parses_chained_assertions() {
+    let chained = parser::parse(
+        "given parameters $a, $b
+         $a must be greater than 5 and must be less than 100
+           or must be equal to 500
+         clause limit: $b must not be one of [1, 2] and
+           $a must be less than $b and must not be equal to 7",
+    )
+    .unwrap();
+    let explicit = parser::parse(
+        "given parameters $a, $b
+         $a must be greater than 5 and $a must be less than 100 or $a must be equal to 500
+         clause limit: $b must not be one of [1, 2] and
+           $a must be less than $b and $a must not be equal to 7",
+    )
+    .unwrap();
+    assert_eq!(compiler::compile(&chained), compiler::compile(&explicit));
+    match &chained[1] {
+        ast::Node::Clause(assertion) => {
+            let (_, conjoined) = assertion.conjoined_assertion.as_ref().unwrap();
+            assert!(matches!(
+                &conjoined.lhs_subject,
+                ast::Subject::Identifier(ident, _) if ident == "$a"
+            ));
+            assert_eq!(
+                conjoined.location,
+                ast::Location {
+                    line: 2,
+                    column: 40
+                }
+            );
+        }
+        node => panic!("unexpected node: {:?}", node),
+    }
+}
+
+#[test]
+fn it_parses_one_of_both_ways() {
+    let ast = parser::parse(
+        "given parameters $a
+         $a must be one of [1, 2]
+         $a must not be one of [3, 4] and must not be one of [5]",
+    )
+    .unwrap();
+    let imperatives: Vec<&ast::Imperative> = ast[1..]
+        .iter()
+        .flat_map(|node| match node {
+            ast::Node::Clause(assertion) => {
+                assert!(matches!(assertion.comparator, ast::Comparator::OneOf));
+                let mut imperatives = vec![&assertion.imperative];
+                if let Some((_, conjoined)) = &assertion.conjoined_assertion {
+                    assert!(matches!(conjoined.comparator, ast::Comparator::OneOf));
+                    imperatives.push(&conjoined.imperative);
+                }
+                imperatives
+            }
+            node => panic!("unexpected node: {:?}", node),
+        })
+        .collect();
+    assert!(matches!(
+        imperatives[..],
+        [
+            ast::Imperative::MustBe,
+            ast::Imperative::MustNotBe,
+            ast::Imperative::MustNotBe
+        ]
+    ));
+    assert!(parser::parse("given parameters $a\n$a must not one of [1]").is_err());
+    assert!(parser::parse("given parameters $a\n$a is not one of [1]").is_err());
+}
+
+#[test]
+fn it_fails_with_a_chained_assertion_without_a_subject() {
+    assert!(parser::parse("given parameters $a must be less than 5").is_err());
+    assert!(
+        parser::parse("given parameters $a\nmust be less than 5 and $a must be less than 6")
+            .is_err()
+    );
+}
+
+#[test]
+fn it_parses_includes_and_libraries() {
+    let ast = parser::parse(
+        "given parameters $a
+         include \"common_lists\"
+         $a must be one of $assets",
+    )
+    .unwrap();
+    assert!(matches!(
+        &ast[1],
+        ast::Node::Include(name, ast::Location { line: 2, column: 10 }) if name == "common_lists"
+    ));
+
+    let library = parser::parse_library(
+        "// shared lists
+         define $assets as [16001, 16010]
+         define $currencies as $assets",
+    )
+    .unwrap();
+    assert!(matches!(library[0], ast::Node::Definition(..)));
+    assert!(matches!(library[1], ast::Node::Alias(..)));
+    assert!(parser::parse_library("").unwrap().is_empty());
+    assert!(parser::parse_library("given parameters $a").is_err());
+    assert!(parser::parse_library("define $a as 1\n$a must be equal to 1").is_err());
+    assert!(parser::parse_library("include \"other\"").is_err());
+}
+
+#[test]
+fn it_parses_metadata() {
+    let ast = parser::parse(
+        "meta title \"Spending limit\"
+         given parameters $a
+         meta review_by \"2020-01-01\"
+         $a must be less than 5",
+    )
+    .unwrap();
+    assert!(matches!(
+        &ast[0],
+        ast::Node::Meta(key, value, ast::Location { line: 1, column: 1 })
+            if key == "title" && value == "Spending limit"
+    ));
+    assert!(matches!(&ast[2], ast::Node::Meta(key, ..) if key == "review_by"));
+    assert!(parser::parse("meta title\ngiven parameters $a").is_err());
+    assert!(parser::parse("meta \"title\" \"a\"\ngiven parameters $a").is_err());
+}
+
 #[test]
 fn it_parses_an_integer_list() {
     let _ = parser::parse(
@@ -94,3 +251,229 @@ fn it_fails_when_parsing_a_mixed_list() {
     )
     .unwrap();
 }
+
+#[test]
+fn it_fails_when_an_integer_literal_overflows() {
+    assert!(parser::parse("given parameters $a $a must be equal to 18446744073709551616").is_err());
+    assert!(
+        parser::parse("given parameters $a $a must be one of [1, 18446744073709551616]").is_err()
+    );
+    assert!(parser::parse("given parameters $a $a must be equal to 18446744073709551615").is_ok());
+}
+
+#[test]
+fn it_parses_separated_and_hex_integers() {
+    let value = |literal: &str| {
+        let ast = parser::parse(&format!(
+            "given parameters $a $a must be equal to {}",
+            literal
+        ))
+        .unwrap();
+        match &ast[1] {
+            ast::Node::Clause(ast::Assertion {
+                rhs_subject: ast::Subject::Value(ast::Value::Numeric(n), _),
+                ..
+            }) => *n,
+            node => panic!("unexpected node: {:?}", node),
+        }
+    };
+    assert_eq!(value("1_000_000"), 1_000_000);
+    assert_eq!(value("1_0_0"), 100);
+    assert_eq!(value("0x0"), 0);
+    assert_eq!(value("0xff"), 255);
+    assert_eq!(value("0xDEAD_beef"), 0xdead_beef);
+    assert_eq!(value("0xffff_ffff_ffff_ffff"), u64::MAX);
+    assert!(parser::parse("given parameters $a $a must be one of [0x10, 1_6]").is_ok());
+}
+
+#[test]
+fn it_fails_with_malformed_integers() {
+    for literal in &[
+        "1__000",
+        "1_000_",
+        "_1000",
+        "0x",
+        "0x_ff",
+        "0xff__ff",
+        "0xfg",
+        "0x1_0000_0000_0000_0000",
+    ] {
+        assert!(
+            parser::parse(&format!(
+                "given parameters $a $a must be equal to {}",
+                literal
+            ))
+            .is_err(),
+            "parsed {}",
+            literal
+        );
+    }
+}
+
+#[test]
+fn it_unescapes_string_literals() {
+    let ast = parser::parse(
+        r#"given parameters $a
+           define $s as "say \"hi\"\\ \n\r\t \u{1F600} \u{e9} ünïcødé - #$%"
+           define $empty as ""
+           define $list as ["a\"b", "c\nd"]"#,
+    )
+    .unwrap();
+    let values: Vec<String> = ast[1..]
+        .iter()
+        .map(|node| match node {
+            ast::Node::Definition(_, value, _) => format!("{:?}", value),
+            node => panic!("unexpected node: {:?}", node),
+        })
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            format!(
+                "StringLike({:?})",
+                "say \"hi\"\\ \n\r\t \u{1F600} \u{e9} ünïcødé - #$%"
+            ),
+            "StringLike(\"\")".to_string(),
+            "List([StringLike(\"a\\\"b\"), StringLike(\"c\\nd\")])".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn it_fails_with_malformed_string_literals() {
+    for literal in &[
+        r#""unterminated"#,
+        r#""bad \q escape""#,
+        r#""trailing \""#,
+        r#""\u{}""#,
+        r#""\u{1234567}""#,
+        r#""\u{d800}""#,
+        r#""\u{110000}""#,
+        "\"raw\nnewline\"",
+    ] {
+        assert!(
+            parser::parse(&format!(
+                "given parameters $a $a must be equal to {}",
+                literal
+            ))
+            .is_err(),
+            "parsed {}",
+            literal
+        );
+    }
+}
+
+#[test]
+fn it_tracks_source_locations() {
+    let ast = parser::parse(
+        "given parameters $a
+define $b as 5
+$a must be equal to $b and
+  7 must not be equal to $a",
+    )
+    .unwrap();
+
+    match &ast[1] {
+        ast::Node::Definition(_, _, location) => {
+            assert_eq!(*location, ast::Location { line: 2, column: 1 })
+        }
+        node => panic!("expected definition, got: {:?}", node),
+    }
+    match &ast[2] {
+        ast::Node::Clause(assertion) => {
+            assert_eq!(assertion.location, ast::Location { line: 3, column: 1 });
+            assert_eq!(
+                assertion.rhs_subject.location(),
+                ast::Location {
+                    line: 3,
+                    column: 21
+                }
+            );
+            let (_, conjoined) = assertion.conjoined_assertion.as_ref().unwrap();
+            assert_eq!(conjoined.location, ast::Location { line: 4, column: 3 });
+            assert_eq!(
+                conjoined.lhs_subject.location(),
+                ast::Location { line: 4, column: 3 }
+            );
+        }
+        node => panic!("expected clause, got: {:?}", node),
+    }
+}
+
+#[test]
+fn it_parses_named_clauses() {
+    let ast = parser::parse(
+        "given parameters $a
+         clause transfer_limit2: $a must be less than 5
+         $a must be greater than 1",
+    )
+    .unwrap();
+    match &ast[1] {
+        ast::Node::NamedClause(name, assertion) => {
+            assert_eq!(name, "transfer_limit2");
+            assert_eq!(
+                assertion.location,
+                ast::Location {
+                    line: 2,
+                    column: 34
+                }
+            );
+        }
+        node => panic!("expected named clause, got: {:?}", node),
+    }
+    assert!(matches!(ast[2], ast::Node::Clause(_)));
+
+    assert!(parser::parse("given parameters $a clause : $a must be less than 5").is_err());
+    assert!(parser::parse("given parameters $a clause 2fa: $a must be less than 5").is_err());
+    assert!(parser::parse("given parameters $a clause limit $a must be less than 5").is_err());
+}
+
+#[test]
+fn it_parses_typed_parameters() {
+    let ast = parser::parse(
+        "given parameters $a: numeric, $b:string, $c, $d: list
+         $a must be less than 5",
+    )
+    .unwrap();
+    match &ast[0] {
+        ast::Node::InputDeclaration(params) => assert_eq!(
+            params,
+            &vec![
+                ("$a".to_string(), ast::InputType::Numeric),
+                ("$b".to_string(), ast::InputType::StringLike),
+                ("$c".to_string(), ast::InputType::Any),
+                ("$d".to_string(), ast::InputType::List),
+            ]
+        ),
+        node => panic!("expected an input declaration, got: {:?}", node),
+    }
+
+    assert!(parser::parse("given parameters $a: boolean $a must be equal to 1").is_err());
+}
+
+#[test]
+fn it_exports_the_grammar() {
+    let grammar = parser::grammar();
+    assert!(grammar.contains("contract = _{ SOI ~ meta* ~ input_declaration ~ statement* ~ EOI }"));
+    assert!(grammar.contains("named_clause = {"));
+    assert_eq!(
+        grammar,
+        std::fs::read_to_string("src/parser/grammar.pest").unwrap()
+    );
+}
+
+#[test]
+fn it_parses_to_ast() {
+    let ast = parser::parse_to_ast("given parameters $a\n$a must be less than 5").unwrap();
+    assert!(matches!(ast[0], ast::Node::InputDeclaration(_)));
+    match &ast[1] {
+        ast::Node::Clause(assertion) => {
+            assert!(matches!(assertion.comparator, ast::Comparator::LessThan));
+            assert!(matches!(
+                assertion.rhs_subject,
+                ast::Subject::Value(ast::Value::Numeric(5), _)
+            ));
+        }
+        node => panic!("expected clause, got: {:?}", node),
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! Substrate adapter integration tests
+
+#![cfg(all(test, feature = "substrate"))]
+use pact::compiler;
+use pact::parser;
+use pact::substrate::{FilterErr, PactFilter, INVALID_ARGUMENTS, REJECTED, UNVERIFIABLE};
+use pact::testing::*;
+use pact::types::traits::{IntoPactTable, ToPactInputs};
+use pact::types::{
+    BinaryFormatErr, Capabilities, Contract, DataTable, PactType, StringLike, ValidationErr,
+};
+use pact::verifier::{Decision, PactErr};
+
+/// A runtime's dispatchable calls
+enum Call {
+    Transfer { dest: Vec<u8>, amount: u128 },
+    Remark(Vec<u8>),
+}
+
+impl ToPactInputs for Call {
+    fn to_pact_inputs(&self) -> Result<Vec<PactType<'_>>, ()> {
+        match self {
+            Call::Transfer { dest, amount } => (dest, *amount).into_pact_table(),
+            Call::Remark(remark) => (remark,).into_pact_table(),
+        }
+    }
+}
+
+/// Gates transfers with a contract, and allows remarks
+struct Contracts(Vec<u8>);
+
+impl PactFilter<Call> for Contracts {
+    fn contract(&self, call: &Call) -> Option<Vec<u8>> {
+        match call {
+            Call::Transfer { .. } => Some(self.0.clone()),
+            Call::Remark(_) => None,
+        }
+    }
+}
+
+fn encoded(source: &str) -> Vec<u8> {
+    let ast = parser::parse(source).unwrap();
+    let mut blob = Vec::new();
+    compiler::compile(&ast).unwrap().encode(&mut blob).unwrap();
+    blob
+}
+
+fn transfer(dest: &[u8], amount: u128) -> Call {
+    Call::Transfer {
+        dest: dest.to_vec(),
+        amount,
+    }
+}
+
+#[test]
+fn it_filters_calls() {
+    let contracts = Contracts(encoded(
+        "given parameters $dest, $amount\n\
+         $dest must be one of [\"alice\", \"bob\"]\n\
+         $amount must be less than 100",
+    ));
+    assert_eq!(contracts.filter(&transfer(b"alice", 10)), Ok(()));
+    assert_eq!(contracts.filter(&Call::Remark(b"hi".to_vec())), Ok(()));
+
+    let err = contracts.filter(&transfer(b"eve", 10)).unwrap_err();
+    assert!(matches!(
+        err,
+        FilterErr::Rejected(Decision::Rejected { .. })
+    ));
+    assert_eq!(err.code(), REJECTED);
+
+    let err = contracts.filter(&transfer(b"bob", u128::MAX)).unwrap_err();
+    assert_eq!(err, FilterErr::InvalidArguments);
+    assert_eq!(err.code(), INVALID_ARGUMENTS);
+
+    let err = Contracts(vec![0]).filter(&transfer(b"bob", 1)).unwrap_err();
+    assert_eq!(
+        err,
+        FilterErr::Verify(PactErr::Decode(BinaryFormatErr::TooShort))
+    );
+    assert_eq!(err.code(), UNVERIFIABLE);
+}
+
+#[test]
+fn it_refuses_host_calls_by_default() {
+    let mut blob = Vec::new();
+    Contract::from_parts_unchecked(
+        DataTable::new(vec![PactType::StringLike(StringLike(b"is_member"))]),
+        comp_call(input(0), user(0)),
+    )
+    .encode(&mut blob)
+    .unwrap();
+    assert_eq!(
+        Contracts(blob).filter(&transfer(b"alice", 1)),
+        Err(FilterErr::Verify(PactErr::Invalid(
+            ValidationErr::Disallowed {
+                offset: 0,
+                required: Capabilities::COMP_CALL
+            }
+        )))
+    );
+}
@@ -0,0 +1,151 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! Compiler and interpreter tracing integration tests
+
+#![cfg(all(test, feature = "trace"))]
+use pact::compiler::{self, CompileErr};
+use pact::interpreter;
+use pact::parser;
+use pact::types::{Numeric, PactType};
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// Records entered spans and events as lines of `name field=value ...`
+#[derive(Clone, Default)]
+struct Recorder {
+    spans: Arc<Mutex<Vec<String>>>,
+    lines: Arc<Mutex<Vec<String>>>,
+}
+
+/// Formats recorded fields, with the message first
+struct Line(String);
+
+impl Visit for Line {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}{}", value, self.0);
+        } else {
+            write!(self.0, " {}={:?}", field.name(), value).unwrap();
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+    fn new_span(&self, span: &Attributes) -> Id {
+        let mut line = Line(String::new());
+        span.record(&mut line);
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(format!("{}{}", span.metadata().name(), line.0));
+        Id::from_u64(spans.len() as u64)
+    }
+    fn record(&self, _span: &Id, _values: &Record) {}
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+    fn event(&self, event: &Event) {
+        let mut line = Line(String::new());
+        event.record(&mut line);
+        self.lines.lock().unwrap().push(line.0);
+    }
+    fn enter(&self, span: &Id) {
+        let name = self.spans.lock().unwrap()[span.into_u64() as usize - 1].clone();
+        self.lines.lock().unwrap().push(format!("enter {}", name));
+    }
+    fn exit(&self, _span: &Id) {}
+}
+
+/// Parse and compile `source`, returning the compile result and recorded trace lines
+fn traced(source: &str) -> (Result<(), CompileErr>, Vec<String>) {
+    let recorder = Recorder::default();
+    let result = tracing::subscriber::with_default(recorder.clone(), || {
+        let ast = parser::parse_to_ast(source).unwrap();
+        compiler::compile(&ast).map(|_| ())
+    });
+    let lines = recorder.lines.lock().unwrap().clone();
+    (result, lines)
+}
+
+#[test]
+fn it_traces_compiler_passes() {
+    let (result, lines) = traced(
+        "given parameters $a
+         define $limit as 100
+         clause limit: $a must be less than $limit",
+    );
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        lines,
+        vec![
+            "enter parse bytes=100",
+            "parsed statement rule=input_declaration line=1",
+            "parsed statement rule=definition line=2",
+            "parsed statement rule=named_clause line=3",
+            "parsed statement rule=EOI line=3",
+            "enter compile nodes=3",
+            "declared input parameter identifier=$a index=0 input_type=Any",
+            "allocated data table entry index=0 value=Numeric(Numeric(100))",
+            "defined user variable identifier=$limit index=0",
+            "enter clause index=0 name=limit",
+            "enter assertion line=3 column=24",
+            "resolved identifier identifier=$a input=0",
+            "resolved identifier identifier=$limit user=0",
+            "emitted comparator offset=0 opcode=18 lhs=0 rhs=0",
+        ]
+    );
+}
+
+#[test]
+fn it_traces_a_full_data_table() {
+    let literals: Vec<String> = (0..17)
+        .map(|n| format!("$a must be equal to {}", n))
+        .collect();
+    let (result, lines) = traced(&format!("given parameters $a\n{}", literals.join("\n")));
+    assert_eq!(result, Err(CompileErr::DataTableFull));
+    assert_eq!(lines.last().unwrap(), "data table is full entries=16");
+}
+
+#[test]
+fn it_traces_interpreted_clauses() {
+    let ast = parser::parse(
+        "given parameters $a, $b
+         $a must be less than 100 and $b must be equal to 1
+         $a must be greater than 5",
+    )
+    .unwrap();
+    let contract = compiler::compile(&ast).unwrap();
+    let inputs = [PactType::Numeric(Numeric(7)), PactType::Numeric(Numeric(1))];
+    let recorder = Recorder::default();
+    let result = tracing::subscriber::with_default(recorder.clone(), || {
+        interpreter::interpret(&inputs, contract.data_table().as_ref(), contract.bytecode())
+    });
+    assert_eq!(result, Ok(true));
+    assert_eq!(
+        *recorder.lines.lock().unwrap(),
+        vec![
+            "enter clause offset=0",
+            "evaluated assertion offset=0 opcode=18 lhs=0 rhs=0 result=true",
+            "enter clause offset=0",
+            "evaluated assertion offset=3 opcode=0 lhs=1 rhs=1 result=true",
+            "enter clause offset=5",
+            "evaluated assertion offset=5 opcode=1 lhs=0 rhs=2 result=true",
+        ]
+    );
+}
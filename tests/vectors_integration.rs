@@ -0,0 +1,61 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! Interpreter conformance vectors
+//! `tests/vectors/conformance.json` is generated by `pactc vectors` from `pact::json::vectors` and
+//! consumed verbatim by other pact implementations. It must replay here and stay in sync with the
+//! generator, regenerate it with `cargo run --bin pactc -- vectors -o tests/vectors/conformance.json`.
+
+#![cfg(test)]
+use pact::json::{self, vectors};
+use std::fs;
+use std::path::Path;
+
+fn conformance_path() -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vectors/conformance.json")
+}
+
+#[test]
+fn it_replays_conformance_vectors() {
+    let source = fs::read_to_string(conformance_path()).unwrap();
+    let fixtures = json::parse_fixtures(&source).unwrap();
+    assert!(!fixtures.is_empty());
+    let mut divergent = Vec::new();
+    for fixture in &fixtures {
+        let (verdict, error) = json::evaluate(&fixture.contract, &fixture.inputs);
+        if verdict != fixture.verdict || error != fixture.error.as_deref() {
+            divergent.push(format!(
+                "{}: expected {:?} {:?}, got {:?} {:?}",
+                fixture.name.as_deref().unwrap_or("<unnamed>"),
+                fixture.verdict,
+                fixture.error,
+                verdict,
+                error
+            ));
+        }
+    }
+    assert!(divergent.is_empty(), "\n{}", divergent.join("\n"));
+}
+
+#[test]
+fn it_matches_the_vector_generator() {
+    let source = fs::read_to_string(conformance_path()).unwrap();
+    let generated = json::fixtures_to_json(&vectors::conformance()).unwrap();
+    assert!(
+        source == generated,
+        "tests/vectors/conformance.json is stale, regenerate it with `pactc vectors`"
+    );
+}
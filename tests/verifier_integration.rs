@@ -0,0 +1,204 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! Verifier integration tests
+
+#![cfg(test)]
+use pact::{
+    interpreter::{Comparator, InterpErr, Limits, OpComp, OpLoad},
+    testing::*,
+    types::{
+        BinaryFormatErr, Capabilities, Contract, DataTable, InputType, Numeric, PactType,
+        SchemaErr, StringLike, ValidationErr,
+    },
+    verifier::{Decision, MissingInputPolicy, PactErr, Verifier},
+};
+
+/// Encode a contract with a single numeric data table entry, 100
+fn encoded(bytecode: Vec<u8>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    Contract::from_parts_unchecked(
+        DataTable::new(vec![PactType::Numeric(Numeric(100))]),
+        bytecode,
+    )
+    .encode(&mut buf)
+    .unwrap();
+    buf
+}
+
+#[test]
+fn it_verifies_a_contract() {
+    // INPUT(0) < 100 AND INPUT(1) >= INPUT(0)
+    let blob = encoded(bytecode(&[
+        comp_lt(input(0), user(0)),
+        conj_and(),
+        comp_gte(input(1), input(0)),
+    ]));
+    let verifier = Verifier::new();
+
+    assert_eq!(
+        verifier.verify(
+            &blob,
+            &[PactType::Numeric(Numeric(5)), PactType::Numeric(Numeric(5))]
+        ),
+        Ok(Decision::Upheld)
+    );
+    assert_eq!(
+        verifier.verify(
+            &blob,
+            &[PactType::Numeric(Numeric(5)), PactType::Numeric(Numeric(4))]
+        ),
+        Ok(Decision::Rejected {
+            opcode_offset: 3,
+            comparator: Comparator::new(OpComp::GTE)
+                .load(OpLoad::INPUT_VS_INPUT)
+                .indices(1, 0),
+        })
+    );
+}
+
+#[test]
+fn it_refuses_contracts_before_interpreting_them() {
+    let blob = encoded(comp_gt(input(0), user(0)));
+    let inputs = [PactType::Numeric(Numeric(5))];
+
+    assert_eq!(
+        Verifier::new()
+            .with_max_contract_bytes(blob.len() - 1)
+            .verify(&blob, &inputs),
+        Err(PactErr::TooLarge(blob.len()))
+    );
+    assert_eq!(
+        Verifier::new().verify(&blob[..1], &inputs),
+        Err(PactErr::Decode(BinaryFormatErr::TooShort))
+    );
+    assert_eq!(
+        Verifier::new()
+            .with_capabilities(Capabilities::COMP_EQ)
+            .verify(&blob, &inputs),
+        Err(PactErr::Invalid(ValidationErr::Disallowed {
+            offset: 0,
+            required: Capabilities::COMP_GT,
+        }))
+    );
+
+    let mut typed = Vec::new();
+    Contract::from_parts_unchecked(
+        DataTable::new(vec![PactType::Numeric(Numeric(100))]),
+        comp_gt(input(0), user(0)),
+    )
+    .with_input_schema(vec![("amount", InputType::Numeric)])
+    .encode(&mut typed)
+    .unwrap();
+    assert_eq!(
+        Verifier::new().verify(&typed, &[PactType::StringLike(StringLike(b"5"))]),
+        Err(PactErr::Schema(SchemaErr::Mismatch {
+            index: 0,
+            expected: InputType::Numeric,
+        }))
+    );
+}
+
+#[test]
+fn it_applies_interpreter_limits() {
+    let blob = encoded(comp_eq(input(0), input(1)));
+    let verifier = Verifier::new().with_limits(Limits {
+        max_string_bytes: Some(4),
+        ..Limits::default()
+    });
+    assert_eq!(
+        verifier.verify(
+            &blob,
+            &[
+                PactType::StringLike(StringLike(b"hello")),
+                PactType::StringLike(StringLike(b"hi")),
+            ]
+        ),
+        Err(PactErr::Interp(InterpErr::StringTooLong(5)))
+    );
+}
+
+#[test]
+fn it_applies_the_missing_input_policy() {
+    // INPUT(0) == 100 OR INPUT(1) == 100
+    let blob = encoded(bytecode(&[
+        comp_eq(input(0), user(0)),
+        conj_or(),
+        comp_eq(input(1), user(0)),
+    ]));
+    let inputs = [PactType::Numeric(Numeric(5))];
+
+    assert_eq!(
+        Verifier::new().verify(&blob, &inputs),
+        Err(PactErr::Interp(InterpErr::MissingIndex(1)))
+    );
+    assert_eq!(
+        Verifier::new()
+            .with_missing_input_policy(MissingInputPolicy::Reject)
+            .verify(&blob, &inputs),
+        Ok(Decision::Rejected {
+            opcode_offset: 3,
+            comparator: Comparator::new(OpComp::EQ).indices(1, 0),
+        })
+    );
+    // A short-circuited comparator never loads the missing input
+    assert_eq!(
+        Verifier::new()
+            .with_missing_input_policy(MissingInputPolicy::Reject)
+            .with_limits(Limits {
+                short_circuit: true,
+                ..Limits::default()
+            })
+            .verify(&blob, &[PactType::Numeric(Numeric(100))]),
+        Ok(Decision::Upheld)
+    );
+}
+
+#[test]
+fn it_chains_error_sources() {
+    use std::error::Error;
+
+    // A comparator without indices
+    let mut blob = encoded(comp_gt(input(0), user(0)));
+    blob.pop();
+    let err = Verifier::new()
+        .verify(&blob, &[PactType::Numeric(Numeric(5))])
+        .unwrap_err();
+    let mut messages = vec![err.to_string()];
+    let mut source = err.source();
+    while let Some(err) = source {
+        messages.push(err.to_string());
+        source = err.source();
+    }
+    assert_eq!(
+        messages,
+        vec![
+            "contract is invalid",
+            "bytecode is malformed",
+            "unexpected end of input: expected index",
+        ]
+    );
+    assert_eq!(
+        PactErr::Schema(SchemaErr::Mismatch {
+            index: 1,
+            expected: InputType::StringLike
+        })
+        .source()
+        .unwrap()
+        .to_string(),
+        "input 1 is not of type string"
+    );
+}
@@ -0,0 +1,326 @@
+// Copyright 2019 Centrality Investments Limited
+// This file is part of Pact.
+//
+// Licensed under the LGPL, Version 3.0 (the "License");
+// you may not use this file except in compliance with the License.
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// You should have received a copy of the GNU General Public License
+// along with Pact. If not, see:
+//   <https://centrality.ai/licenses/gplv3.txt>
+//   <https://centrality.ai/licenses/lgplv3.txt>
+
+//! WASM transpiler integration tests
+//! Golden tests asserting the transpiled module agrees with the interpreter
+
+#![cfg(all(test, feature = "wasm"))]
+use pact::interpreter::{self, Comparator, Conjunction, OpCode, OpComp, OpConj, OpLoad};
+use pact::types::{Numeric, PactType, StringLike};
+use pact::wasm;
+use std::cell::Cell;
+use std::panic;
+
+/// Read an unsigned LEB128 value from `code` at `pc`
+fn read_uleb(code: &[u8], pc: &mut usize) -> u32 {
+    let (mut value, mut shift) = (0u32, 0);
+    loop {
+        let byte = code[*pc];
+        *pc += 1;
+        value |= u32::from(byte & 0x7f) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+    }
+}
+
+/// Read a signed LEB128 value from `code` at `pc`
+fn read_sleb(code: &[u8], pc: &mut usize) -> i32 {
+    let (mut value, mut shift) = (0i32, 0);
+    loop {
+        let byte = code[*pc];
+        *pc += 1;
+        value |= i32::from(byte & 0x7f) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            if shift < 32 && byte & 0x40 != 0 {
+                value |= -1 << shift;
+            }
+            return value;
+        }
+    }
+}
+
+/// A minimal executor for the instruction subset emitted by `wasm::transpile`
+/// Locates the `verdict` body in the code section and runs it against `compare`
+fn run_verdict(module: &[u8], compare: &dyn Fn(i32, i32) -> i32) -> i32 {
+    assert_eq!(
+        &module[..8],
+        &[0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]
+    );
+    let mut pc = 8;
+    let code = loop {
+        let id = module[pc];
+        pc += 1;
+        let size = read_uleb(module, &mut pc) as usize;
+        if id == 10 {
+            break &module[pc..pc + size];
+        }
+        pc += size;
+    };
+
+    let mut pc = 0;
+    assert_eq!(read_uleb(code, &mut pc), 1, "one function body");
+    let body_len = read_uleb(code, &mut pc) as usize;
+    assert_eq!(pc + body_len, code.len());
+    assert_eq!(&code[pc..pc + 3], &[1, 2, 0x7f], "locals: 2 x i32");
+    pc += 3;
+
+    let mut locals = [0i32; 2];
+    let mut stack: Vec<i32> = Vec::new();
+    loop {
+        let op = code[pc];
+        pc += 1;
+        match op {
+            // if (blocktype void); skip to the matching end when false
+            0x04 => {
+                assert_eq!(code[pc], 0x40);
+                pc += 1;
+                if stack.pop().unwrap() == 0 {
+                    while code[pc] != 0x0b {
+                        pc += 1;
+                    }
+                    pc += 1;
+                }
+            }
+            // end
+            0x0b => {
+                if pc == code.len() {
+                    return stack.pop().unwrap();
+                }
+            }
+            0x0f => return stack.pop().unwrap(),
+            0x10 => {
+                assert_eq!(read_uleb(code, &mut pc), 0, "call pact.compare");
+                let indices = stack.pop().unwrap();
+                let opcode = stack.pop().unwrap();
+                stack.push(compare(opcode, indices));
+            }
+            0x20 => stack.push(locals[read_uleb(code, &mut pc) as usize]),
+            0x21 => locals[read_uleb(code, &mut pc) as usize] = stack.pop().unwrap(),
+            0x41 => stack.push(read_sleb(code, &mut pc)),
+            0x45 => {
+                let a = stack.pop().unwrap();
+                stack.push((a == 0) as i32);
+            }
+            0x48 | 0x71 | 0x72 | 0x73 => {
+                let b = stack.pop().unwrap();
+                let a = stack.pop().unwrap();
+                stack.push(match op {
+                    0x48 => (a < b) as i32,
+                    0x71 => a & b,
+                    0x72 => a | b,
+                    _ => a ^ b,
+                });
+            }
+            _ => panic!("unexpected instruction: {:#x}", op),
+        }
+    }
+}
+
+/// Assert the transpiled module and the interpreter agree on `bytecode`
+fn assert_equivalent(input_data: &[PactType], user_data: &[PactType], bytecode: &[u8]) {
+    let module = wasm::transpile(bytecode).expect("it transpiles");
+    let verdict = run_verdict(&module, &|opcode, indices| {
+        wasm::host_compare(input_data, user_data, opcode, indices)
+    });
+    let expected = match interpreter::interpret(input_data, user_data, bytecode) {
+        Ok(true) => 1,
+        Ok(false) => 0,
+        Err(_) => wasm::COMPARE_ERR,
+    };
+    assert_eq!(verdict, expected, "bytecode: {:x?}", bytecode);
+}
+
+fn comp(op: OpComp) -> u8 {
+    OpCode::COMP(Comparator::new(op)).into()
+}
+
+fn conj(op: OpConj) -> u8 {
+    OpCode::CONJ(Conjunction::new(op)).into()
+}
+
+#[test]
+fn it_agrees_with_the_interpreter() {
+    let input_data = [
+        PactType::Numeric(Numeric(123)),
+        PactType::StringLike(StringLike(b"hello world")),
+        PactType::Numeric(Numeric(5)),
+    ];
+    let user_data = [
+        PactType::Numeric(Numeric(123)),
+        PactType::StringLike(StringLike(b"hello world")),
+        PactType::List(vec![
+            PactType::Numeric(Numeric(4)),
+            PactType::Numeric(Numeric(5)),
+        ]),
+        PactType::Numeric(Numeric(200)),
+    ];
+    let not_eq: u8 = OpCode::COMP(Comparator::new(OpComp::EQ).invert()).into();
+    let lt: u8 = OpCode::COMP(Comparator::new(OpComp::GTE).invert()).into();
+    let nand: u8 = OpCode::CONJ(Conjunction::new(OpConj::AND).invert()).into();
+    let input_eq: u8 =
+        OpCode::COMP(Comparator::new(OpComp::EQ).load(OpLoad::INPUT_VS_INPUT)).into();
+
+    let programs: Vec<Vec<u8>> = vec![
+        vec![comp(OpComp::EQ), 0x00],
+        vec![not_eq, 0x00],
+        vec![comp(OpComp::EQ), 0x00, comp(OpComp::EQ), 0x11],
+        vec![not_eq, 0x00, comp(OpComp::EQ), 0x11],
+        vec![comp(OpComp::EQ), 0x00, not_eq, 0x11],
+        vec![lt, 0x03, comp(OpComp::IN), 0x22],
+        vec![not_eq, 0x00, conj(OpConj::OR), comp(OpComp::EQ), 0x11],
+        vec![
+            comp(OpComp::EQ),
+            0x00,
+            conj(OpConj::XOR),
+            comp(OpComp::EQ),
+            0x11,
+        ],
+        vec![comp(OpComp::EQ), 0x00, nand, comp(OpComp::EQ), 0x11],
+        vec![
+            not_eq,
+            0x00,
+            conj(OpConj::AND),
+            comp(OpComp::EQ),
+            0x11,
+            conj(OpConj::OR),
+            lt,
+            0x23,
+        ],
+        vec![input_eq, 0x00, comp(OpComp::GT), 0x23],
+        // comparator errors propagate
+        vec![comp(OpComp::GT), 0x11],
+        vec![comp(OpComp::EQ), 0x0f],
+        // a false clause fails fast before a later erroring clause
+        vec![not_eq, 0x00, comp(OpComp::GT), 0x11],
+    ];
+    for bytecode in programs {
+        assert_equivalent(&input_data, &user_data, &bytecode);
+    }
+}
+
+#[test]
+fn it_transpiles_a_compiled_contract() {
+    let ast = pact::parser::parse(
+        "
+          given parameters $amount, $payee
+          define $payees as [\"alice\", \"bob\"]
+          $amount must be less than 100 and $payee must be one of $payees
+        ",
+    )
+    .unwrap();
+    let contract = pact::compiler::compile(&ast).unwrap();
+    for input_data in &[
+        [
+            PactType::Numeric(Numeric(99)),
+            PactType::StringLike(StringLike(b"bob")),
+        ],
+        [
+            PactType::Numeric(Numeric(100)),
+            PactType::StringLike(StringLike(b"bob")),
+        ],
+        [
+            PactType::Numeric(Numeric(1)),
+            PactType::StringLike(StringLike(b"eve")),
+        ],
+    ] {
+        assert_equivalent(
+            input_data,
+            contract.data_table().as_ref(),
+            contract.bytecode(),
+        );
+    }
+}
+
+/// A fault to inject into the host `compare` callback
+#[derive(Clone, Copy, Debug)]
+enum Fault {
+    /// Return an error code
+    Error,
+    /// Panic inside the callback
+    Panic,
+    /// Return a value outside of the callback contract
+    OutOfRange,
+}
+
+/// Run `bytecode` with a `compare` callback which injects `fault` on call number `fail_at`
+/// Every other call is answered by the reference `host_compare`
+fn run_with_fault(
+    input_data: &[PactType],
+    user_data: &[PactType],
+    bytecode: &[u8],
+    fault: Fault,
+    fail_at: usize,
+) -> i32 {
+    let module = wasm::transpile(bytecode).expect("it transpiles");
+    let calls = Cell::new(0);
+    run_verdict(&module, &|opcode, indices| {
+        let call = calls.get();
+        calls.set(call + 1);
+        wasm::guard_compare(|| {
+            if call != fail_at {
+                return wasm::host_compare(input_data, user_data, opcode, indices);
+            }
+            match fault {
+                Fault::Error => -7,
+                Fault::Panic => panic!("injected host failure"),
+                Fault::OutOfRange => 2,
+            }
+        })
+    })
+}
+
+#[test]
+fn it_converts_faulty_host_callbacks_into_errors() {
+    let input_data = [PactType::Numeric(Numeric(5)), PactType::Numeric(Numeric(7))];
+    let user_data = [PactType::Numeric(Numeric(5)), PactType::Numeric(Numeric(7))];
+    // INPUT(0) == USER(0) AND INPUT(1) == USER(1), then INPUT(1) > USER(0)
+    let bytecode = [
+        comp(OpComp::EQ),
+        0x00,
+        conj(OpConj::AND),
+        comp(OpComp::EQ),
+        0x11,
+        comp(OpComp::GT),
+        0x10,
+    ];
+    assert_equivalent(&input_data, &user_data, &bytecode);
+
+    // Silence the injected panics
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    for fail_at in 0..3 {
+        for (fault, expected) in &[
+            (Fault::Error, -7),
+            (Fault::Panic, wasm::CALLBACK_ERR),
+            (Fault::OutOfRange, wasm::CALLBACK_ERR),
+        ] {
+            let verdict = run_with_fault(&input_data, &user_data, &bytecode, *fault, fail_at);
+            assert_eq!(
+                verdict, *expected,
+                "fault: {:?} at call: {}",
+                fault, fail_at
+            );
+        }
+    }
+    panic::set_hook(hook);
+
+    // A fault after the verdict is decided is never observed
+    let verdict = run_with_fault(&input_data, &user_data, &bytecode, Fault::Panic, 3);
+    assert_eq!(verdict, 1);
+}